@@ -1,4 +1,15 @@
 from .rs import *
 from .base import DecisionTree
-from .supervised import LGDTCLassifier, DL85Classifier
+from .rules import (
+    LeafRule,
+    PurityRule,
+    SupportRule,
+    DepthRule,
+    ErrorRule,
+    AndRule,
+    OrRule,
+    NotRule,
+)
+from .supervised import LGDTCLassifier, DL85Classifier, DL85Regressor
 from .unsupervised import DL85Cluster
+from .persistence import load
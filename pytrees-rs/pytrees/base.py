@@ -1,5 +1,13 @@
+import json
+import logging
 import uuid
-from sklearn.utils import check_array, check_X_y, assert_all_finite
+import warnings
+import numpy as np
+from scipy.sparse import issparse
+from sklearn.exceptions import NotFittedError
+from sklearn.utils import check_array, check_X_y, assert_all_finite, check_random_state
+from .exceptions import TreeNotFoundError, SearchFailedError
+from .rs import ExposedMissingBranch
 
 
 class DecisionTree:
@@ -14,30 +22,236 @@ class DecisionTree:
     def predict(self):
         pass
 
+    def save(self, path):
+        """Writes the fitted estimator to a JSON file: its parameters, its tree and what
+        prediction needs. ``pytrees.load(path)`` restores it without pickle. Callable parameters
+        cannot be saved and are restored as ``None``."""
+
+        from .persistence import save
+
+        save(self, path)
+
+    def prune(self, alpha=0.0):
+        """Cost-complexity pruning: going up from the leaves, collapses into a leaf each subtree
+        which does not reduce the training error by more than ``alpha`` per extra leaf. Returns
+        the number of collapsed subtrees. Only classification trees are pruned."""
+
+        self.check_is_fitted()
+        collapsed = self.results.prune(alpha)
+        self.refresh_tree()
+        return collapsed
+
+    def prune_with_validation(self, X_val, y_val, threshold=0.0):
+        """Reduced error pruning: going up from the leaves, collapses into a leaf each subtree
+        which does not make more than ``threshold`` fewer errors on the validation set than the
+        leaf predicting its majority class. Returns the number of collapsed subtrees."""
+
+        self.check_is_fitted()
+        X_val, y_val = check_X_y(X_val, y_val, force_all_finite="allow-nan")
+        collapsed = self.results.prune_with_validation(
+            X_val.astype(float).tolist(), y_val.astype(int).tolist(), threshold
+        )
+        self.refresh_tree()
+        return collapsed
+
+    def set_leaf_output(self, id, out, X=None, y=None):
+        """Sets the output of the leaf with the given id, as given by ``apply``. The errors of
+        the tree are recomputed on (X, y) when given, on the training samples otherwise."""
+
+        self.check_is_fitted()
+        self.results.set_leaf_output(id, out)
+        self.refresh_edited_tree(X, y)
+        return self
+
+    def collapse(self, id, X=None, y=None):
+        """Turns the node with the given id into a leaf predicting its majority class. The
+        errors of the tree are recomputed on (X, y) when given, on the training samples
+        otherwise."""
+
+        self.check_is_fitted()
+        self.results.collapse(id)
+        self.refresh_edited_tree(X, y)
+        return self
+
+    def graft(self, id, tree, X=None, y=None):
+        """Replaces the subtree of the node with the given id by another tree, laid out like
+        ``tree_`` or taken from a fitted estimator. Pass (X, y) to recompute the errors of the
+        grafted nodes, they otherwise keep the ones of their tree."""
+
+        self.check_is_fitted()
+        if hasattr(tree, "tree_"):
+            tree = tree.tree_
+        self.results.graft(id, json.dumps(tree) if isinstance(tree, dict) else tree)
+        self.refresh_edited_tree(X, y)
+        return self
+
+    def refresh_edited_tree(self, X, y):
+        # Recounts the classes support of the nodes on (X, y), if given, before reading the tree
+        if X is not None:
+            X, y = check_X_y(X, y, force_all_finite="allow-nan")
+            self.results.evaluate(X.astype(float).tolist(), y.astype(int).tolist())
+        self.refresh_tree()
+
+    def structural_eq(self, other):
+        """Whether the fitted trees of both estimators test the same features at the same places
+        and predict the same outputs, whatever their errors or node indices."""
+
+        self.check_is_fitted()
+        other.check_is_fitted()
+        return self.results.structural_eq(other.results)
+
+    def diff(self, other):
+        """Nodes where the fitted trees of both estimators differ. Each one is a dict with the
+        ``path`` of branches (0 or 1) from the root and the ``first`` and ``second`` nodes, given
+        as ``{"split": feature}`` or ``{"leaf": output}``. Subtrees below a difference are not
+        compared."""
+
+        self.check_is_fitted()
+        other.check_is_fitted()
+        return self.results.diff(other.results)
+
+    @property
+    def anytime_curve_(self):
+        """``(elapsed_time, error)`` pairs, in seconds, of the best tree each time the search
+        improved it. Only recorded when fitting with ``record_anytime=True``, the curve is empty
+        otherwise."""
+
+        self.check_is_fitted()
+        return self.results.anytime_curve
+
+    @property
+    def stop_reason_(self):
+        """Why the last search stopped, an ``ExposedStopReason``: ``Done`` once it completed,
+        ``TimeLimitReached``, ``CacheLimitReached`` when the cache was full under the ``Stop``
+        policy, or ``Interrupted`` by the callback or a keyboard interrupt."""
+
+        self.check_is_fitted()
+        return self.results.stop_reason
+
+    @property
+    def leaf_reasons_(self):
+        """Number of leaves of the fitted tree stopped for each reason, such as
+        ``LeafRuleHolds`` or ``PurityThresholdReached``, to see which rules turned nodes into
+        leaves. The reasons are only recorded when fitting with ``explain_leaves=True``, the dict
+        is empty otherwise."""
+
+        self.check_is_fitted()
+        return self.results.leaf_reasons
+
+    @property
+    def leaf_samples_(self):
+        """Indices of the training samples captured by each leaf, by leaf index in
+        ``tree_["tree"]``, when fitted with ``leaf_samples=True``. Their number of samples of each
+        class is the ``classes_support`` of the leaf."""
+
+        self.check_is_fitted()
+        return {
+            index: node["value"]["samples"]
+            for index, node in enumerate(self.tree_["tree"])
+            if DecisionTree.is_leaf_node(node) and "samples" in node["value"]
+        }
+
+    def node(self, id):
+        """Node of the fitted tree with the given id, holding its ``test``, ``feature_name``,
+        ``error``, ``out``, ``support``, ``classes_support`` and the ids of its ``left`` and
+        ``right`` children, None for a leaf. The ids are the ones given by ``apply``, the root
+        being 0, and stay valid until the tree is modified."""
+
+        self.check_is_fitted()
+        return self.results.node(id)
+
+    def nodes(self):
+        """Iterates over the nodes of the fitted tree in pre-order, each node before its left then
+        right subtree."""
+
+        self.check_is_fitted()
+        return iter(self.results.nodes())
+
+    def refresh_tree(self):
+        # Reads back the tree and its error after the Rust side changed them
+        self.set_leaf_probabilities()
+        self.tree_ = json.loads(self.results.tree)
+        self.tree_error_ = self.results.error
+        if self.accuracy_ is not None:
+            self.set_accuracy()
+
+    def set_leaf_probabilities(self):
+        # Classifiers store the smoothed class probabilities of the leaves in their tree
+        if getattr(self, "smoothing", None) is not None:
+            self.results.set_leaf_probabilities(self.smoothing)
+
     def set_accuracy(self):
         self.accuracy_ = round(
             1 - self.results.error / self.statistics["num_samples"], 5
         )
 
     @staticmethod
-    def is_leaf_node(node):
-        return (node["left"] == 0) and (node["right"] == 0)
+    def is_dataframe(X):
+        return hasattr(X, "columns") and hasattr(X, "to_numpy")
 
-    def predict(self, X):
-        """Implements the standard predict function for a DL8.5 classifier.
+    def set_feature_names(self):
+        # Column names are only known when fitting on a DataFrame
+        if self.results.feature_names is not None:
+            self.feature_names_in_ = np.asarray(self.results.feature_names, dtype=object)
 
-        Parameters
-        ----------
-        X : array-like, shape (n_samples, n_features)
-            The input samples.
+    def warn_if_interrupted(self):
+        if self.statistics.get("interrupted", False):
+            warnings.warn(
+                "The search was interrupted, the returned tree may not be optimal.",
+                RuntimeWarning,
+            )
 
-        Returns
-        -------
-        y : ndarray, shape (n_samples,)
-            The label for each sample is the label of the closest sample
-            seen during fit.
-        """
+    @staticmethod
+    def configure_logging(verbose):
+        # The search logs to the "pytrees" logger: 1 gives the info messages, 2 the debug ones and
+        # more everything. Without any handler configured, the messages go to stderr
+        if not verbose:
+            return
+        logger = logging.getLogger("pytrees")
+        logger.setLevel({1: logging.INFO, 2: logging.DEBUG}.get(verbose, 5))
+        if not logger.hasHandlers():
+            handler = logging.StreamHandler()
+            handler.setFormatter(logging.Formatter("[%(levelname)s] %(message)s"))
+            logger.addHandler(handler)
+
+    @staticmethod
+    def split_beam_width(beam_width):
+        # A list gives the width of each depth, the deeper nodes using its last one
+        if isinstance(beam_width, (list, tuple)):
+            return 0, list(beam_width)
+        return beam_width, None
+
+    @staticmethod
+    def split_purity_threshold(purity_threshold, max_depth):
+        # One threshold for all the depths, one per depth or a function of the depth, starting at
+        # the children of the root
+        if purity_threshold is None:
+            return None
+        if callable(purity_threshold):
+            return [float(purity_threshold(depth)) for depth in range(1, max_depth + 1)]
+        if isinstance(purity_threshold, (list, tuple)):
+            return [float(threshold) for threshold in purity_threshold]
+        return [float(purity_threshold)]
+
+    @staticmethod
+    def seed(random_state):
+        # Seed of the random heuristic, drawn from numpy as in scikit-learn
+        if isinstance(random_state, (int, np.integer)):
+            return int(random_state)
+        return int(check_random_state(random_state).randint(np.iinfo(np.int32).max))
+
+    @staticmethod
+    def row(X, i):
+        # Sparse rows are densified one at a time
+        if issparse(X):
+            return X[i].toarray().ravel()
+        return X[i, :]
+
+    @staticmethod
+    def is_leaf_node(node):
+        return (node["left"] == 0) and (node["right"] == 0)
 
+    def check_is_fitted(self):
         # Check is fit is called
         # check_is_fitted(self, attributes='tree_') # use of attributes is deprecated. alternative solution is below
 
@@ -61,24 +275,153 @@ class DecisionTree:
                 "if the problem is in the scope supported by the tool.",
             )
 
+    def predict(self, X):
+        """Implements the standard predict function for a DL8.5 classifier.
+
+        Parameters
+        ----------
+        X : array-like, shape (n_samples, n_features)
+            The input samples.
+
+        Returns
+        -------
+        y : ndarray, shape (n_samples,)
+            The label for each sample is the label of the closest sample
+            seen during fit.
+        """
+
+        self.check_is_fitted()
+
         # Input validation
-        X = check_array(X)
+        X = check_array(X, accept_sparse="csr", force_all_finite="allow-nan")
 
         pred = []
 
         for i in range(X.shape[0]):
-            pred.append(self.pred_value_on_dict(X[i, :]))
+            leaves = self.weighted_leaves(DecisionTree.row(X, i))
+            if len(leaves) == 1:
+                pred.append(leaves[0][0]["value"]["out"])
+            else:
+                pred.append(self.combine_outputs(leaves))
 
         return pred
 
-    def pred_value_on_dict(self, instance, tree=None):
+    def predict_proba(self, X):
+        """Predict class probabilities of the input samples. The probabilities of a sample are
+        the class distribution of the training samples reaching the same leaf, with Laplace
+        smoothing when the estimator has a ``smoothing`` parameter.
+
+        Parameters
+        ----------
+        X : array-like, shape (n_samples, n_features)
+            The input samples.
+
+        Returns
+        -------
+        proba : ndarray, shape (n_samples, n_classes)
+            The class probabilities of each sample, ordered by class label.
+        """
+
+        self.check_is_fitted()
+
+        # Input validation
+        X = check_array(X, accept_sparse="csr", force_all_finite="allow-nan")
+
+        proba = []
+
+        for i in range(X.shape[0]):
+            distribution = None
+            for leaf, weight in self.weighted_leaves(DecisionTree.row(X, i)):
+                supports = leaf["value"].get("classes_support")
+                probabilities = leaf["value"].get("probabilities")
+                if probabilities is not None:
+                    leaf_proba = np.array(probabilities)
+                elif supports is None:
+                    raise SearchFailedError(
+                        "predict_proba(): ",
+                        "The tree does not store the class distributions of its leaves.",
+                    )
+                elif sum(supports) == 0:
+                    leaf_proba = np.full(len(supports), 1 / len(supports))
+                else:
+                    leaf_proba = np.array(supports) / sum(supports)
+                distribution = weight * leaf_proba + (
+                    0 if distribution is None else distribution
+                )
+            proba.append(distribution)
+
+        return np.array(proba)
+
+    def apply(self, X):
+        """Index in ``tree_["tree"]`` of the leaf reached by each sample. Samples with missing
+        values follow ``missing_branch``, and with ``Both`` the branch holding the most training
+        samples."""
+
+        return np.array([leaf for leaf, _ in self.paths(X)], dtype=np.intp)
+
+    def decision_path(self, X):
+        """Tests taken by each sample from the root to its leaf, as lists of (feature, branch)
+        pairs where the branch is 0 or 1."""
+
+        return [tests for _, tests in self.paths(X)]
+
+    def explain_counterfactual(self, x):
+        """Fewest binary features to flip in the sample ``x`` for the tree to predict another
+        output, as ``(features, leaf)`` with the sorted flipped features and the index in
+        ``tree_["tree"]`` of the leaf then reached. A missing value is flipped when set to either
+        value. Returns None when all the leaves predict the same output."""
+
+        self.check_is_fitted()
+        branch = getattr(self, "missing_branch", ExposedMissingBranch.Left)
+        counterfactual = self.results.counterfactual([float(value) for value in x], branch)
+        if counterfactual is None:
+            return None
+        leaf, features = counterfactual
+        return features, leaf
+
+    def paths(self, X):
+        self.check_is_fitted()
+        X = check_array(X, accept_sparse="csr", force_all_finite="allow-nan")
+        branch = getattr(self, "missing_branch", ExposedMissingBranch.Left)
+        instances = [
+            [float(value) for value in DecisionTree.row(X, i)] for i in range(X.shape[0])
+        ]
+        return self.results.decision_path(instances, branch)
+
+    def weighted_leaves(self, instance):
+        """Leaves reached by an instance with their weights. Samples with missing values (NaN)
+        follow ``missing_branch`` and can reach several leaves when it is ``Both``."""
+
+        branch = getattr(self, "missing_branch", ExposedMissingBranch.Left)
+        return [
+            (self.tree_["tree"][index], weight)
+            for index, weight in self.results.weighted_leaves(
+                [float(value) for value in instance], branch
+            )
+        ]
+
+    def combine_outputs(self, leaves):
+        # Weighted mean for regression, weighted vote otherwise
+        if getattr(self, "_estimator_type", None) == "regressor":
+            return sum(leaf["value"]["out"] * weight for leaf, weight in leaves) / sum(
+                weight for _, weight in leaves
+            )
+        votes = {}
+        for leaf, weight in leaves:
+            votes[leaf["value"]["out"]] = votes.get(leaf["value"]["out"], 0) + weight
+        return max(votes, key=votes.get)
+
+    def leaf_on_dict(self, instance, tree=None):
         node = tree if tree is not None else self.tree_["tree"][0]
         while not DecisionTree.is_leaf_node(node):
             if instance[node["value"]["test"]] == 1:
                 node = self.tree_["tree"][node["right"]]
             else:
                 node = self.tree_["tree"][node["left"]]
-        return node["value"]["out"]
+        return node
+
+    def pred_value_on_dict(self, instance, tree=None):
+        return self.leaf_on_dict(instance, tree)["value"]["out"]
 
     def get_dot_body_rec(self, node, parent=None, left=0):
         gstring = ""
@@ -123,6 +466,82 @@ class DecisionTree:
             )
         return gstring
 
+    def export_graphviz(self, feature_names=None, class_names=None):
+        """Export the fitted tree in the Graphviz DOT format. Features and classes are shown by
+        index unless ``feature_names`` and ``class_names`` are given."""
+
+        self.check_is_fitted()
+        return self.results.export_graphviz(feature_names, class_names)
+
+    def export_text(self, feature_names=None, class_names=None):
+        """Export the fitted tree as indented text, one line per branch with the support, error
+        and class distribution of the node it leads to."""
+
+        self.check_is_fitted()
+        return self.results.export_text(feature_names, class_names)
+
+    def to_sklearn_arrays(self):
+        """The fitted tree as the arrays of scikit-learn's ``tree_`` attribute, with nodes
+        numbered depth first from the root: ``children_left``, ``children_right``, ``feature``,
+        ``threshold``, ``value``, ``impurity``, ``n_node_samples`` and
+        ``weighted_n_node_samples``. Leaves have -1 children and feature and threshold -2, as in
+        scikit-learn. Features are binary, so every threshold is 0.5 and the left child holds the
+        samples at 0. ``value`` has shape (n_nodes, 1, n_classes) and holds the classes support of
+        each node, or (n_nodes, 1, 1) with the outputs of a regression tree. ``impurity`` is the
+        Gini impurity of the classes support, and it stays 0 like ``n_node_samples`` when the nodes
+        do not store their classes support."""
+
+        self.check_is_fitted()
+        nodes = self.tree_["tree"]
+        regression = getattr(self, "_estimator_type", None) == "regressor"
+        order = []
+        stack = [0]
+        while stack:
+            index = stack.pop()
+            order.append(index)
+            node = nodes[index]
+            if not DecisionTree.is_leaf_node(node):
+                stack.extend([node["right"], node["left"]])
+        position = {index: rank for rank, index in enumerate(order)}
+
+        arrays = {
+            name: np.full(len(order), default, dtype=dtype)
+            for name, default, dtype in [
+                ("children_left", -1, np.intp),
+                ("children_right", -1, np.intp),
+                ("feature", -2, np.intp),
+                ("threshold", -2.0, np.float64),
+                ("impurity", 0.0, np.float64),
+                ("n_node_samples", 0, np.intp),
+            ]
+        }
+        values = []
+        for rank, index in enumerate(order):
+            node = nodes[index]
+            supports = node["value"].get("classes_support")
+            if not DecisionTree.is_leaf_node(node):
+                arrays["children_left"][rank] = position[node["left"]]
+                arrays["children_right"][rank] = position[node["right"]]
+                arrays["feature"][rank] = node["value"]["test"]
+                arrays["threshold"][rank] = 0.5
+            if supports is not None:
+                total = sum(supports)
+                arrays["n_node_samples"][rank] = total
+                if total > 0:
+                    arrays["impurity"][rank] = 1 - sum((s / total) ** 2 for s in supports)
+            if regression:
+                out = node["value"].get("out")
+                values.append([0.0 if out is None else out])
+            else:
+                values.append(supports if supports is not None else [])
+        width = max(len(value) for value in values)
+        arrays["value"] = np.array(
+            [value + [0] * (width - len(value)) for value in values], dtype=np.float64
+        ).reshape(len(order), 1, width)
+        arrays["weighted_n_node_samples"] = arrays["n_node_samples"].astype(np.float64)
+        arrays["node_count"] = len(order)
+        return arrays
+
     def export_to_graphviz_dot(self):
         gstring = "digraph Tree { \n" "graph [ranksep=0]; \n" "node [shape=record]; \n"
         id = str(uuid.uuid4())
@@ -0,0 +1,79 @@
+import json
+import warnings
+from . import rs
+from .rules import LeafRule
+from pytreesrs.odt import Result
+
+FORMAT_VERSION = 1
+
+
+def encode_param(value):
+    # Enums are saved by name and rules by their JSON form. Callables cannot be saved
+    if isinstance(value, LeafRule):
+        return {"__leaf_rule__": value.to_dict()}
+    if type(value).__module__ == "pytreesrs.enums":
+        return {"__enum__": repr(value)}
+    if isinstance(value, (list, tuple)):
+        return [encode_param(item) for item in value]
+    if hasattr(value, "tolist"):
+        return value.tolist()
+    if callable(value):
+        warnings.warn(
+            "Callable parameters cannot be saved, they are restored as None.", UserWarning
+        )
+        return None
+    return value
+
+
+def decode_param(value):
+    if isinstance(value, dict) and "__enum__" in value:
+        enum, variant = value["__enum__"].rsplit(".", 1)
+        return getattr(getattr(rs, enum.rsplit(".", 1)[-1]), variant)
+    if isinstance(value, dict) and "__leaf_rule__" in value:
+        return LeafRule.from_dict(value["__leaf_rule__"])
+    if isinstance(value, list):
+        return [decode_param(item) for item in value]
+    return value
+
+
+def save(estimator, path):
+    estimator.check_is_fitted()
+    state = {
+        "version": FORMAT_VERSION,
+        "estimator": type(estimator).__name__,
+        "params": {
+            name: encode_param(value)
+            for name, value in estimator.get_params(deep=False).items()
+        },
+        "results": json.loads(estimator.results.__getstate__()),
+        "tree": estimator.tree_,
+        "tree_error": estimator.tree_error_,
+        "accuracy": estimator.accuracy_,
+        "statistics": estimator.statistics,
+    }
+    with open(path, "w") as file:
+        json.dump(state, file)
+
+
+def load(path):
+    """Restores an estimator saved with ``save``, ready to predict."""
+
+    import pytrees
+
+    with open(path) as file:
+        state = json.load(file)
+    if state.get("version") != FORMAT_VERSION:
+        raise ValueError("Unsupported model file version: {}".format(state.get("version")))
+
+    estimator = getattr(pytrees, state["estimator"])(
+        **{name: decode_param(value) for name, value in state["params"].items()}
+    )
+    estimator.results = Result()
+    estimator.results.__setstate__(json.dumps(state["results"]))
+    estimator.tree_ = state["tree"]
+    estimator.tree_error_ = state["tree_error"]
+    estimator.accuracy_ = state["accuracy"]
+    estimator.statistics = state["statistics"]
+    estimator.is_fitted_ = True
+    estimator.set_feature_names()
+    return estimator
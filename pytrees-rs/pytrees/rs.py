@@ -1,10 +1,23 @@
 from pytreesrs.enums import (
+    ExposedBinarizationStrategy,
     ExposedBranchingStrategy,
+    ExposedCacheFullPolicy,
     ExposedCacheInitStrategy,
     ExposedCacheType,
+    ExposedCategoricalEncoding,
     ExposedDataFormat,
+    ExposedLowerBoundPolicy,
     ExposedLowerBoundStrategy,
+    ExposedMissingBranch,
+    ExposedMissingValues,
+    ExposedObjective,
+    ExposedRestartSchedule,
     ExposedSearchHeuristic,
     ExposedSearchStrategy,
+    ExposedSortPolicy,
     ExposedSpecialization,
+    ExposedStopReason,
+    ExposedTieBreak,
 )
+from pytreesrs import Cover
+from pytreesrs.preprocessing import Binarizer, CategoricalEncoder
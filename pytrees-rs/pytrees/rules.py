@@ -0,0 +1,115 @@
+import json
+
+
+class LeafRule:
+    """Condition turning a node into a leaf without trying to split it. Rules are combined with
+    ``&``, ``|`` and ``~``, or with ``AndRule``, ``OrRule`` and ``NotRule``."""
+
+    def to_dict(self):
+        raise NotImplementedError
+
+    def to_json(self):
+        return json.dumps(self.to_dict())
+
+    @staticmethod
+    def from_dict(rule):
+        (kind, value), = rule.items()
+        if kind in ("And", "Or"):
+            rules = [LeafRule.from_dict(item) for item in value]
+            return AndRule(*rules) if kind == "And" else OrRule(*rules)
+        if kind == "Not":
+            return NotRule(LeafRule.from_dict(value))
+        return {
+            "Purity": PurityRule,
+            "MaxSupport": SupportRule,
+            "MinDepth": DepthRule,
+            "MaxLeafError": ErrorRule,
+        }[kind](value)
+
+    def __and__(self, other):
+        return AndRule(self, other)
+
+    def __or__(self, other):
+        return OrRule(self, other)
+
+    def __invert__(self):
+        return NotRule(self)
+
+
+class PurityRule(LeafRule):
+    """Holds when the majority class has at least ``threshold`` of the samples of the node."""
+
+    def __init__(self, threshold):
+        self.threshold = threshold
+
+    def to_dict(self):
+        return {"Purity": float(self.threshold)}
+
+
+class SupportRule(LeafRule):
+    """Holds when the node has at most ``max_support`` samples."""
+
+    def __init__(self, max_support):
+        self.max_support = max_support
+
+    def to_dict(self):
+        return {"MaxSupport": int(self.max_support)}
+
+
+class DepthRule(LeafRule):
+    """Holds when the node is at least ``min_depth`` deep."""
+
+    def __init__(self, min_depth):
+        self.min_depth = min_depth
+
+    def to_dict(self):
+        return {"MinDepth": int(self.min_depth)}
+
+
+class ErrorRule(LeafRule):
+    """Holds when the node makes at most ``max_error`` errors as a leaf."""
+
+    def __init__(self, max_error):
+        self.max_error = max_error
+
+    def to_dict(self):
+        return {"MaxLeafError": float(self.max_error)}
+
+
+class ComposedRule(LeafRule):
+    """Rule combining other ones. Rules can be added and removed between fits, the estimator
+    reading the rule again at each fit."""
+
+    def __init__(self, *rules):
+        self.rules = list(rules)
+
+    def add(self, rule):
+        self.rules.append(rule)
+        return self
+
+    def remove(self, rule):
+        """Removes the first rule equal to ``rule``, raises a ``ValueError`` when there is none."""
+
+        for index, other in enumerate(self.rules):
+            if other is rule or other.to_dict() == rule.to_dict():
+                del self.rules[index]
+                return self
+        raise ValueError("The rule is not part of this one")
+
+
+class AndRule(ComposedRule):
+    def to_dict(self):
+        return {"And": [rule.to_dict() for rule in self.rules]}
+
+
+class OrRule(ComposedRule):
+    def to_dict(self):
+        return {"Or": [rule.to_dict() for rule in self.rules]}
+
+
+class NotRule(LeafRule):
+    def __init__(self, rule):
+        self.rule = rule
+
+    def to_dict(self):
+        return {"Not": self.rule.to_dict()}
@@ -1,2 +1,3 @@
 from .lgdt import LGDTCLassifier
 from .dl85_classifier import DL85Classifier
+from .dl85_regressor import DL85Regressor
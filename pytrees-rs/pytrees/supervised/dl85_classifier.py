@@ -1,8 +1,13 @@
 import json
+import math
+import numpy as np
 from .. import *
 from sklearn.base import BaseEstimator, ClassifierMixin
 from sklearn.utils import check_array, check_X_y, assert_all_finite
-from pytreesrs.odt import dl85
+from pytreesrs import Cover
+from pytreesrs.odt import DL85, dl85, restart_budget
+from pytreesrs.model_selection import depth_front
+from ..persistence import encode_param, decode_param
 
 
 class DL85Classifier(BaseEstimator, ClassifierMixin, DecisionTree):
@@ -13,14 +18,54 @@ class DL85Classifier(BaseEstimator, ClassifierMixin, DecisionTree):
         max_error=1e10,
         max_time=600,
         cache_init_size=0,
-        one_time_sort=True,
+        sort_policy=ExposedSortPolicy.RootOnly,
         data_format=ExposedDataFormat.ClassSupports,
         specialization=ExposedSpecialization.Murtree,
         lower_bound=ExposedLowerBoundStrategy.Similarity,
+        lower_bound_policy=ExposedLowerBoundPolicy.TwoCovers,
         branching_type=ExposedBranchingStrategy.Dynamic,
         heuristic=ExposedSearchHeuristic.None_,
         cache_init_strategy=ExposedCacheInitStrategy.None_,
         error_function=None,
+        explain_leaves=False,
+        candidates_filter=None,
+        callback=None,
+        callback_interval=1000,
+        cache_type=ExposedCacheType.Trie,
+        max_cache_size=0,
+        max_cache_bytes=0,
+        cache_full_policy=ExposedCacheFullPolicy.Evict,
+        cover_keys=False,
+        random_state=None,
+        max_leaves=0,
+        beam_width=0,
+        min_heuristic_score=0.0,
+        min_relative_score=0.0,
+        tie_break=ExposedTieBreak.Index,
+        auto_depth=False,
+        minimize_leaves=False,
+        leaf_samples=False,
+        sparse_threshold=0.0,
+        discrepancy_budget=None,
+        purity_threshold=None,
+        leaf_rule=None,
+        feature_costs=None,
+        max_cost=float("inf"),
+        include_features=None,
+        exclude_features=None,
+        root_feature=None,
+        monotonic_cst=None,
+        missing_values=ExposedMissingValues.Zero,
+        missing_branch=ExposedMissingBranch.Left,
+        record_anytime=False,
+        trace=None,
+        verbose=0,
+        objective=ExposedObjective.Error,
+        class_weight=None,
+        constraints=None,
+        error_function_numpy=False,
+        error_function_cache=False,
+        smoothing=0.0,
     ):
         super().__init__()
         self.min_sup = min_sup
@@ -28,7 +73,7 @@ class DL85Classifier(BaseEstimator, ClassifierMixin, DecisionTree):
         self.max_error = max_error
         self.max_time = max_time
         self.cache_init_size = cache_init_size
-        self.one_time_sort = one_time_sort
+        self.sort_policy = sort_policy
         self.data_format = data_format
         self.specialization = specialization
         self.lower_bound = lower_bound
@@ -36,32 +81,82 @@ class DL85Classifier(BaseEstimator, ClassifierMixin, DecisionTree):
         self.heuristic = heuristic
         self.cache_init_strategy = cache_init_strategy
         self.error_function = error_function
+        self.explain_leaves = explain_leaves
+        self.candidates_filter = candidates_filter
+        self.callback = callback
+        self.callback_interval = callback_interval
+        self.cache_type = cache_type
+        self.max_cache_size = max_cache_size
+        self.max_cache_bytes = max_cache_bytes
+        self.cache_full_policy = cache_full_policy
+        self.cover_keys = cover_keys
+        self.lower_bound_policy = lower_bound_policy
+        self.random_state = random_state
+        self.max_leaves = max_leaves
+        self.beam_width = beam_width
+        self.min_heuristic_score = min_heuristic_score
+        self.min_relative_score = min_relative_score
+        self.tie_break = tie_break
+        self.auto_depth = auto_depth
+        self.minimize_leaves = minimize_leaves
+        self.leaf_samples = leaf_samples
+        self.sparse_threshold = sparse_threshold
+        self.discrepancy_budget = discrepancy_budget
+        self.purity_threshold = purity_threshold
+        self.leaf_rule = leaf_rule
+        self.feature_costs = feature_costs
+        self.max_cost = max_cost
+        self.include_features = include_features
+        self.exclude_features = exclude_features
+        self.root_feature = root_feature
+        self.monotonic_cst = monotonic_cst
+        self.missing_values = missing_values
+        self.missing_branch = missing_branch
+        self.record_anytime = record_anytime
+        self.trace = trace
+        self.verbose = verbose
+        self.objective = objective
+        self.class_weight = class_weight
+        self.constraints = constraints
+        self.error_function_numpy = error_function_numpy
+        self.error_function_cache = error_function_cache
+        self.smoothing = smoothing
 
         self.results = None
 
-    def fit(self, X, y=None):
+    def fit(self, X, y=None, warm_start=None, subset=None, template=None):
+        """Fit the tree. ``subset`` optionally restricts the fit to the given row indices, see
+        ``fit_subset``. ``warm_start`` is an optional tree, laid out like ``tree_``, used as
+        initial solution: its leaf outputs and errors are recomputed on (X, y) and it is kept if
+        the search cannot find a better tree. Editing a fitted ``tree_`` and passing it back
+        allows refining a tree by hand. A fitted estimator, such as a greedy ``LGDT``, can also be
+        given to start from its tree, and ``True`` starts from the greedy tree learned under the
+        same constraints. ``template`` is a partial tree, laid out like ``tree_``, whose splits are
+        fixed: the nodes without a test are left open and optimally completed by the search."""
 
-        target_is_need = True if y is not None else False
+        self.fit_learner(X, y, warm_start, subset, template, None)
 
-        if target_is_need:  # target-needed tasks (eg: classification, regression, etc.)
-            # Check that X and y have correct shape and raise ValueError if not
-            X, y = check_X_y(X, y, dtype="float64")
-            # if opt_func is None and opt_pred_func is None:
-            #     print("No optimization criterion defined. Misclassification error is used by default.")
-        else:  # target-less tasks (clustering, etc.)
-            # Check that X has correct shape and raise ValueError if not
-            assert_all_finite(X)
-            X = check_array(X, dtype="float64")
+    def fit_learner(self, X, y, warm_start, subset, template, learner):
+        # Fit keeping the DL85 learner in the given pytreesrs.odt.DL85 handle, when not None
+        self.learner_ = learner
+        if hasattr(warm_start, "tree_"):
+            warm_start = warm_start.tree_
+        elif hasattr(warm_start, "tree"):
+            warm_start = warm_start.tree
+
+        data, X, y = self.check_input(X, y)
+        beam_width, depth_beam_widths = self.split_beam_width(self.beam_width)
 
+        self.configure_logging(self.verbose)
         self.results = dl85(
-            X,
+            data,
             y,
             self.min_sup,
             self.max_depth,
             self.max_time,
             self.cache_init_size,
             self.max_error,
-            self.one_time_sort,
+            self.sort_policy,
             self.data_format,
             self.specialization,
             self.lower_bound,
@@ -69,10 +164,86 @@ class DL85Classifier(BaseEstimator, ClassifierMixin, DecisionTree):
             self.heuristic,
             self.cache_init_strategy,
             self.error_function,
+            json.dumps(warm_start) if isinstance(warm_start, dict) else warm_start,
+            self.explain_leaves,
+            self.candidates_filter,
+            self.callback,
+            self.callback_interval,
+            "classification",
+            self.cache_type,
+            self.max_cache_size,
+            self.max_leaves,
+            beam_width,
+            self.feature_costs,
+            self.max_cost,
+            self.include_features,
+            self.exclude_features,
+            self.root_feature,
+            self.monotonic_cst,
+            self.missing_values,
+            self.discrepancy_budget,
+            depth_beam_widths,
+            self.split_purity_threshold(self.purity_threshold, self.max_depth),
+            self.leaf_rule.to_json() if self.leaf_rule is not None else None,
+            self.max_cache_bytes,
+            self.cache_full_policy,
+            self.seed(self.random_state),
+            self.record_anytime,
+            self.trace,
+            self.verbose,
+            self.objective,
+            self.class_weight,
+            self.constraints,
+            self.error_function_numpy,
+            self.error_function_cache,
+            subset,
+            self.cover_keys,
+            self.lower_bound_policy,
+            self.min_heuristic_score,
+            self.min_relative_score,
+            self.tie_break,
+            self.auto_depth,
+            self.minimize_leaves,
+            json.dumps(template) if isinstance(template, dict) else template,
+            self.leaf_samples,
+            self.sparse_threshold,
+            learner,
         )
+        self.read_results()
+
+    def check_input(self, X, y):
+        # DataFrames and Covers are given as is, a Cover holding its classes. Returns the data
+        # given to the search with the checked X and y
+        data = X
+
+        if isinstance(X, Cover):
+            pass
+        elif y is not None:  # target-needed tasks (eg: classification, regression, etc.)
+            # Check that X and y have correct shape and raise ValueError if not
+            X, y = check_X_y(
+                X,
+                y,
+                accept_sparse=["csr", "csc"],
+                dtype="float64",
+                force_all_finite="allow-nan",
+            )
+            # if opt_func is None and opt_pred_func is None:
+            #     print("No optimization criterion defined. Misclassification error is used by default.")
+        else:  # target-less tasks (clustering, etc.)
+            # Check that X has correct shape and raise ValueError if not
+            assert_all_finite(X)
+            X = check_array(X, accept_sparse=["csr", "csc"], dtype="float64")
+
+        if not self.is_dataframe(data) and not isinstance(data, Cover):
+            data = X
+        return data, X, y
 
+    def read_results(self):
+        self.set_leaf_probabilities()
         tree = json.loads(self.results.tree)
         self.statistics = json.loads(self.results.statistics)
+        self.set_feature_names()
+        self.warn_if_interrupted()
         if len(tree["tree"]) == 1 and tree["tree"][0]["value"]["out"] not in [0, 1]:
             self.tree_ = None
         else:
@@ -80,3 +251,191 @@ class DL85Classifier(BaseEstimator, ClassifierMixin, DecisionTree):
             self.is_fitted_ = True
             self.tree_error_ = self.results.error
             self.set_accuracy()
+
+    def fit_cover(self, cover, warm_start=None, subset=None):
+        """Fit the tree on a ``pytreesrs.Cover``, built once from (X, y) and shared by many fits
+        so the data is not read again for each of them."""
+
+        self.fit(cover, warm_start=warm_start, subset=subset)
+        return self
+
+    def fit_subset(self, X, y, subset, warm_start=None):
+        """Fit the tree on the rows of (X, y) selected by ``subset``, either row indices or a
+        boolean mask. The selected rows are not copied into a new dataset, the search runs on the
+        full data with the other rows masked out. Cross-validation folds and subsampled bags can
+        then all be fitted from the same X and y."""
+
+        subset = np.asarray(subset)
+        if subset.dtype == bool:
+            if subset.shape != (len(y),):
+                raise ValueError("A boolean subset must have one entry per row")
+            subset = np.flatnonzero(subset)
+        self.fit(X, y, warm_start=warm_start, subset=[int(i) for i in subset])
+        return self
+
+    def pareto_front(self, X, y):
+        """Optimal training error of every depth from 1 to ``max_depth`` under ``min_sup``, from
+        one search whose cache is shared by all the depths. Only the depths improving on all the
+        shallower ones are kept, as dicts holding ``max_depth``, ``error``, ``leaves``,
+        ``is_optimal`` and the JSON ``tree``. ``max_time`` bounds each depth, the estimator is not
+        fitted."""
+
+        X, y = check_X_y(X, y, dtype="float64", force_all_finite="allow-nan")
+        return depth_front(X, y, self.max_depth, self.min_sup, self.max_time)
+
+    def partial_fit(self, X, y, time_budget=None):
+        """Continues the search for ``time_budget`` more seconds, ``max_time`` when None. The
+        search is warm started from the fitted tree, if any, so each call can only improve it.
+        Calling ``partial_fit(X, y, time_budget=10)`` until ``stop_reason_`` is no longer
+        ``TimeLimitReached`` searches interactively, ten seconds at a time. ``max_depth`` can be
+        raised and ``min_sup`` lowered between calls, the fitted tree then remains a valid
+        starting point. The learner of the first call is kept in ``learner_`` and the next calls
+        go on from its cache, relaxed when the constraints are, with the other parameters of that
+        first call. Tighter constraints or a call to ``fit`` restart the search from scratch, as
+        the data must be the same between the calls. After ``fit_lds`` stopped before its end, the
+        limited discrepancy search goes on from the step it reached, kept in
+        ``statistics["lds"]``."""
+
+        if time_budget is not None and time_budget <= 0:
+            raise ValueError("time_budget must be positive")
+        warm_start = None
+        if getattr(self, "is_fitted_", False):
+            constraints = self.statistics["constraints"]
+            if (
+                self.max_depth >= constraints["max_depth"]
+                and self.min_sup <= constraints["min_sup"]
+            ):
+                warm_start = self.tree_
+        # A limited discrepancy search stopped before its end goes on from the step it reached
+        lds = self.statistics.get("lds") if warm_start is not None else None
+        if lds is not None:
+            time_limit = self.max_time if time_budget is None else max(1, math.ceil(time_budget))
+            for _ in self.lds_steps(X, y, lds, warm_start, time_limit):
+                pass
+            return self
+        max_time = self.max_time
+        learner = getattr(self, "learner_", None)
+        try:
+            if time_budget is not None:
+                # The time limit of the search is in whole seconds
+                self.max_time = max(1, math.ceil(time_budget))
+            if warm_start is not None and learner is not None and learner.is_fitted:
+                data, X, y = self.check_input(X, y)
+                self.configure_logging(self.verbose)
+                self.results = learner.partial_fit(
+                    data, y, self.max_depth, self.min_sup, self.max_time
+                )
+                self.read_results()
+            else:
+                self.fit_learner(X, y, warm_start, None, None, DL85())
+        finally:
+            self.max_time = max_time
+        return self
+
+    def fit_iter(
+        self,
+        X,
+        y,
+        initial_time=1,
+        schedule=ExposedRestartSchedule.Exponential,
+        growth_factor=2.0,
+    ):
+        """Anytime fit. Each step restarts the search, warm started from the best tree found so
+        far, with a time limit following the restart ``schedule``: it doubles at each step with
+        ``Exponential``, is multiplied by ``growth_factor`` with ``Geometric``, grows by one second
+        with ``Linear`` and follows the Luby sequence of powers of ``growth_factor`` with
+        ``Luby``. Limits start at ``initial_time`` seconds and are bounded by ``max_time``. Each
+        step yields ``(tree, error, is_optimal, reason)``, where reason is the
+        ``ExposedStopReason`` of the step. The iteration stops once a step is not stopped by its
+        time limit or uses the whole time budget."""
+
+        if schedule != ExposedRestartSchedule.Linear and growth_factor <= 1:
+            raise ValueError("growth_factor must be greater than 1")
+        max_time = self.max_time
+        warm_start = None
+        iteration = 0
+        try:
+            while True:
+                budget = restart_budget(schedule, iteration, initial_time, growth_factor)
+                time_limit = max(1, min(round(budget), max_time))
+                self.max_time = time_limit
+                self.fit(X, y, warm_start=warm_start)
+                # With a beam, a completed search is not proven optimal
+                reason = self.results.stop_reason
+                yield self.tree_, self.results.error, self.results.is_optimal, reason
+
+                if reason != ExposedStopReason.TimeLimitReached or time_limit >= max_time:
+                    return
+                warm_start = self.tree_
+                iteration += 1
+        finally:
+            self.max_time = max_time
+
+    def fit_lds(
+        self,
+        X,
+        y,
+        initial_discrepancy=0,
+        schedule=ExposedRestartSchedule.Linear,
+        growth_factor=2.0,
+    ):
+        """Limited discrepancy search. Each step restarts the search, warm started from the best
+        tree found so far, allowing each path to stray a few more times from the heuristic order.
+        The discrepancy budget follows the restart ``schedule``, starting at
+        ``initial_discrepancy``: it grows by one at each step with ``Linear``, doubles with
+        ``Exponential``, is multiplied by ``growth_factor`` with ``Geometric`` and follows the
+        Luby sequence of powers of ``growth_factor`` with ``Luby``. Each step yields
+        ``(tree, error, is_optimal, discrepancy_budget)``. The iteration stops once the budget
+        no longer limits the search or ``max_time`` is spent over all the steps. When it stops
+        before, ``statistics["lds"]`` keeps the step it reached and ``partial_fit`` resumes it."""
+
+        if schedule != ExposedRestartSchedule.Linear and growth_factor <= 1:
+            raise ValueError("growth_factor must be greater than 1")
+        state = {
+            "iteration": 0,
+            "initial_discrepancy": initial_discrepancy,
+            "schedule": encode_param(schedule),
+            "growth_factor": growth_factor,
+        }
+        yield from self.lds_steps(X, y, state, None, self.max_time)
+
+    def lds_steps(self, X, y, state, warm_start, time_limit):
+        # Steps of the limited discrepancy search from the one of the state, within time_limit
+        max_time = self.max_time
+        discrepancy_budget = self.discrepancy_budget
+        schedule = decode_param(state["schedule"])
+        iteration = state["iteration"]
+        spent = 0
+        try:
+            while spent < time_limit:
+                budget = restart_budget(
+                    schedule, iteration, state["initial_discrepancy"], state["growth_factor"]
+                )
+                # Exponential budgets starting at 0 would never grow
+                self.discrepancy_budget = max(iteration, round(budget))
+                self.max_time = time_limit - spent
+                self.fit(X, y, warm_start=warm_start)
+                spent += self.statistics["duration"]["secs"]
+                limited = self.statistics["discrepancy_limited"]
+                completed = self.results.stop_reason == ExposedStopReason.Done
+                if limited or not completed:
+                    # A step stopped by the time or an interruption is run again when resuming
+                    self.statistics["lds"] = dict(
+                        state,
+                        iteration=iteration + int(completed),
+                        discrepancy_budget=self.discrepancy_budget,
+                    )
+                yield (
+                    self.tree_,
+                    self.results.error,
+                    self.results.is_optimal,
+                    self.discrepancy_budget,
+                )
+
+                if not limited or self.statistics.get("interrupted", False):
+                    return
+                warm_start = self.tree_
+                iteration += 1
+        finally:
+            self.max_time = max_time
+            self.discrepancy_budget = discrepancy_budget
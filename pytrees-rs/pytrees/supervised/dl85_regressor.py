@@ -0,0 +1,171 @@
+import json
+from .. import *
+from sklearn.base import BaseEstimator, RegressorMixin
+from sklearn.utils import check_X_y
+from pytreesrs.odt import dl85
+
+
+class DL85Regressor(BaseEstimator, RegressorMixin, DecisionTree):
+    """Optimal regression tree. Leaves predict the mean of their targets and the search minimizes
+    the sum of squared errors."""
+
+    def __init__(
+        self,
+        min_sup=1,
+        max_depth=1,
+        max_error=1e10,
+        max_time=600,
+        cache_init_size=0,
+        sort_policy=ExposedSortPolicy.RootOnly,
+        lower_bound=ExposedLowerBoundStrategy.Similarity,
+        lower_bound_policy=ExposedLowerBoundPolicy.TwoCovers,
+        branching_type=ExposedBranchingStrategy.Dynamic,
+        heuristic=ExposedSearchHeuristic.None_,
+        cache_init_strategy=ExposedCacheInitStrategy.None_,
+        explain_leaves=False,
+        candidates_filter=None,
+        callback=None,
+        callback_interval=1000,
+        cache_type=ExposedCacheType.Trie,
+        max_cache_size=0,
+        max_cache_bytes=0,
+        cache_full_policy=ExposedCacheFullPolicy.Evict,
+        cover_keys=False,
+        random_state=None,
+        max_leaves=0,
+        beam_width=0,
+        min_heuristic_score=0.0,
+        min_relative_score=0.0,
+        tie_break=ExposedTieBreak.Index,
+        auto_depth=False,
+        minimize_leaves=False,
+        leaf_samples=False,
+        sparse_threshold=0.0,
+        leaf_rule=None,
+        feature_costs=None,
+        max_cost=float("inf"),
+        include_features=None,
+        exclude_features=None,
+        root_feature=None,
+        missing_values=ExposedMissingValues.Zero,
+        missing_branch=ExposedMissingBranch.Left,
+        record_anytime=False,
+        trace=None,
+        verbose=0,
+    ):
+        super().__init__()
+        self.min_sup = min_sup
+        self.max_depth = max_depth
+        self.max_error = max_error
+        self.max_time = max_time
+        self.cache_init_size = cache_init_size
+        self.sort_policy = sort_policy
+        self.lower_bound = lower_bound
+        self.branching_type = branching_type
+        self.heuristic = heuristic
+        self.cache_init_strategy = cache_init_strategy
+        self.explain_leaves = explain_leaves
+        self.candidates_filter = candidates_filter
+        self.callback = callback
+        self.callback_interval = callback_interval
+        self.cache_type = cache_type
+        self.max_cache_size = max_cache_size
+        self.max_cache_bytes = max_cache_bytes
+        self.cache_full_policy = cache_full_policy
+        self.cover_keys = cover_keys
+        self.lower_bound_policy = lower_bound_policy
+        self.random_state = random_state
+        self.max_leaves = max_leaves
+        self.beam_width = beam_width
+        self.min_heuristic_score = min_heuristic_score
+        self.min_relative_score = min_relative_score
+        self.tie_break = tie_break
+        self.auto_depth = auto_depth
+        self.minimize_leaves = minimize_leaves
+        self.leaf_samples = leaf_samples
+        self.sparse_threshold = sparse_threshold
+        self.leaf_rule = leaf_rule
+        self.feature_costs = feature_costs
+        self.max_cost = max_cost
+        self.include_features = include_features
+        self.exclude_features = exclude_features
+        self.root_feature = root_feature
+        self.missing_values = missing_values
+        self.missing_branch = missing_branch
+        self.record_anytime = record_anytime
+        self.trace = trace
+        self.verbose = verbose
+
+        self.results = None
+
+    def fit(self, X, y):
+        # DataFrames are given as is to keep their column names
+        data = X
+        X, y = check_X_y(
+            X,
+            y,
+            accept_sparse=["csr", "csc"],
+            dtype="float64",
+            force_all_finite="allow-nan",
+            y_numeric=True,
+        )
+
+        if not self.is_dataframe(data):
+            data = X
+
+        beam_width, depth_beam_widths = self.split_beam_width(self.beam_width)
+        self.configure_logging(self.verbose)
+        self.results = dl85(
+            data,
+            y.astype("float64"),
+            self.min_sup,
+            self.max_depth,
+            self.max_time,
+            self.cache_init_size,
+            self.max_error,
+            self.sort_policy,
+            lower_bound=self.lower_bound,
+            branching_type=self.branching_type,
+            heuristic=self.heuristic,
+            cache_init_strategy=self.cache_init_strategy,
+            explain_leaves=self.explain_leaves,
+            candidates_filter=self.candidates_filter,
+            callback=self.callback,
+            callback_interval=self.callback_interval,
+            cache_type=self.cache_type,
+            max_cache_size=self.max_cache_size,
+            max_cache_bytes=self.max_cache_bytes,
+            cache_full_policy=self.cache_full_policy,
+            cover_keys=self.cover_keys,
+            lower_bound_policy=self.lower_bound_policy,
+            min_heuristic_score=self.min_heuristic_score,
+            min_relative_score=self.min_relative_score,
+            tie_break=self.tie_break,
+            auto_depth=self.auto_depth,
+            minimize_leaves=self.minimize_leaves,
+            leaf_samples=self.leaf_samples,
+            sparse_threshold=self.sparse_threshold,
+            seed=self.seed(self.random_state),
+            record_anytime=self.record_anytime,
+            trace=self.trace,
+            verbose=self.verbose,
+            max_leaves=self.max_leaves,
+            beam_width=beam_width,
+            depth_beam_widths=depth_beam_widths,
+            leaf_rule=self.leaf_rule.to_json() if self.leaf_rule is not None else None,
+            feature_costs=self.feature_costs,
+            max_cost=self.max_cost,
+            include_features=self.include_features,
+            exclude_features=self.exclude_features,
+            root_feature=self.root_feature,
+            missing_values=self.missing_values,
+            task="regression",
+        )
+
+        self.tree_ = json.loads(self.results.tree)
+        self.statistics = json.loads(self.results.statistics)
+        self.set_feature_names()
+        self.warn_if_interrupted()
+        self.is_fitted_ = True
+        self.tree_error_ = self.results.error
+        return self
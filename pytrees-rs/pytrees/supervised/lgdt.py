@@ -2,8 +2,14 @@ import json
 
 from sklearn.base import BaseEstimator, ClassifierMixin
 from sklearn.utils import check_array, check_X_y, assert_all_finite
+from pytreesrs import Cover
 from pytreesrs.greedy import lgdt
-from .. import ExposedSearchStrategy, DecisionTree
+from .. import (
+    ExposedMissingBranch,
+    ExposedMissingValues,
+    ExposedSearchStrategy,
+    DecisionTree,
+)
 
 
 class LGDTCLassifier(BaseEstimator, ClassifierMixin, DecisionTree):
@@ -12,24 +18,57 @@ class LGDTCLassifier(BaseEstimator, ClassifierMixin, DecisionTree):
         min_sup=1,
         max_depth=2,
         search_strategy=ExposedSearchStrategy.LessGreedyMurtree,
+        missing_values=ExposedMissingValues.Zero,
+        missing_branch=ExposedMissingBranch.Left,
+        monotonic_cst=None,
+        smoothing=0.0,
     ):
         super().__init__()
         self.min_sup = min_sup
         self.max_depth = max_depth
         self.search_strategy = search_strategy
+        self.missing_values = missing_values
+        self.missing_branch = missing_branch
+        self.monotonic_cst = monotonic_cst
+        self.smoothing = smoothing
 
     def fit(self, X, y):
-        X, y = check_X_y(X, y, dtype="float64")
-        self.results = lgdt(
+        # DataFrames are given as is to keep their column names
+        data = X
+        if isinstance(X, Cover):
+            return self.fit_cover(X)
+        X, y = check_X_y(
             X,
             y,
+            accept_sparse=["csr", "csc"],
+            dtype="float64",
+            force_all_finite="allow-nan",
+        )
+        if not self.is_dataframe(data):
+            data = X
+
+        return self.fit_results(data, y)
+
+    def fit_cover(self, cover):
+        """Fit the tree on a ``pytreesrs.Cover`` built with its classes, shared by many fits so
+        the data is not read again for each of them."""
+        return self.fit_results(cover, None)
+
+    def fit_results(self, data, y):
+        self.results = lgdt(
+            data,
+            y,
             self.search_strategy,
             self.min_sup,
             self.max_depth,
+            self.missing_values,
+            self.monotonic_cst,
         )
 
+        self.set_leaf_probabilities()
         tree = json.loads(self.results.tree)
         self.statistics = json.loads(self.results.statistics)
+        self.set_feature_names()
         if len(tree["tree"]) == 1 and tree["tree"][0]["value"]["out"] not in [0, 1]:
             self.tree_ = None
         else:
@@ -37,3 +76,4 @@ class LGDTCLassifier(BaseEstimator, ClassifierMixin, DecisionTree):
             self.is_fitted_ = True
             self.tree_error_ = self.results.error
             self.set_accuracy()
+        return self
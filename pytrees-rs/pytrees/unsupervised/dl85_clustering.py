@@ -16,12 +16,20 @@ class DL85Cluster(BaseEstimator, ClusterMixin, DecisionTree):
         max_error=1e10,
         max_time=600,
         cache_init_size=0,
-        one_time_sort=True,
+        sort_policy=ExposedSortPolicy.RootOnly,
         lower_bound=ExposedLowerBoundStrategy.None_,
         branching_type=ExposedBranchingStrategy.Dynamic,
         heuristic=ExposedSearchHeuristic.None_,
         cache_init_strategy=ExposedCacheInitStrategy.None_,
         error_function=None,
+        explain_leaves=False,
+        candidates_filter=None,
+        callback=None,
+        callback_interval=1000,
+        cache_type=ExposedCacheType.Trie,
+        max_cache_size=0,
+        error_function_numpy=False,
+        error_function_cache=False,
     ):
         super().__init__()
         self.min_sup = min_sup
@@ -29,7 +37,7 @@ class DL85Cluster(BaseEstimator, ClusterMixin, DecisionTree):
         self.max_error = max_error
         self.max_time = max_time
         self.cache_init_size = cache_init_size
-        self.one_time_sort = one_time_sort
+        self.sort_policy = sort_policy
         self.data_format = ExposedDataFormat.Tids
         self.specialization = ExposedSpecialization.None_
         self.lower_bound = lower_bound
@@ -37,6 +45,14 @@ class DL85Cluster(BaseEstimator, ClusterMixin, DecisionTree):
         self.heuristic = heuristic
         self.cache_init_strategy = cache_init_strategy
         self.error_function = error_function
+        self.explain_leaves = explain_leaves
+        self.candidates_filter = candidates_filter
+        self.callback = callback
+        self.callback_interval = callback_interval
+        self.cache_type = cache_type
+        self.max_cache_size = max_cache_size
+        self.error_function_numpy = error_function_numpy
+        self.error_function_cache = error_function_cache
 
         self.results = None
 
@@ -79,7 +95,7 @@ class DL85Cluster(BaseEstimator, ClusterMixin, DecisionTree):
             self.max_time,
             self.cache_init_size,
             self.max_error,
-            self.one_time_sort,
+            self.sort_policy,
             self.data_format,
             self.specialization,
             self.lower_bound,
@@ -87,10 +103,19 @@ class DL85Cluster(BaseEstimator, ClusterMixin, DecisionTree):
             self.heuristic,
             self.cache_init_strategy,
             self.error_function,
+            explain_leaves=self.explain_leaves,
+            candidates_filter=self.candidates_filter,
+            callback=self.callback,
+            callback_interval=self.callback_interval,
+            cache_type=self.cache_type,
+            max_cache_size=self.max_cache_size,
+            error_function_numpy=self.error_function_numpy,
+            error_function_cache=self.error_function_cache,
         )
 
         tree = json.loads(self.results.tree)
         self.statistics = json.loads(self.results.statistics)
+        self.warn_if_interrupted()
         if len(tree["tree"]) == 1 and tree["tree"][0]["value"]["out"] not in [0, 1]:
             self.tree_ = None
         else:
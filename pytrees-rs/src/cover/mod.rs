@@ -0,0 +1,123 @@
+use crate::utils::{read_dataset, ExposedMissingValues};
+use dtrees_rs::structures::{RevBitset, Structure};
+use numpy::{PyReadonlyArray2, PyReadonlyArrayDyn};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+// Bitsets of a dataset, built once and given to many fits in place of the input. Each fit starts
+// from the full dataset and leaves it that way
+#[pyclass(name = "Cover", module = "pytreesrs")]
+pub(crate) struct PyCover {
+    pub(crate) structure: RevBitset,
+    // Column names of a DataFrame input
+    pub(crate) feature_names: Option<Vec<String>>,
+    // Columns having an extra missing value attribute
+    pub(crate) missing_columns: Vec<usize>,
+    pub(crate) labelled: bool,
+}
+
+#[pymethods]
+impl PyCover {
+    #[new]
+    #[pyo3(signature = (input, target=None, missing_values=ExposedMissingValues::Zero))]
+    fn new(
+        input: &PyAny,
+        target: Option<PyReadonlyArrayDyn<f64>>,
+        missing_values: ExposedMissingValues,
+    ) -> PyResult<Self> {
+        let target = target.map(|target| target.as_array().map(|a| *a as usize));
+        let input = read_dataset(input, target.as_ref(), missing_values.into())?;
+        Ok(Self {
+            structure: input.dataset.structure(),
+            feature_names: input.feature_names,
+            missing_columns: input.missing_columns,
+            labelled: target.is_some(),
+        })
+    }
+
+    // Built from binarized columns already packed as uint64 words, one row of `n_samples / 64`
+    // words, rounded up, per feature. Sample t is bit t % 64 of word t / 64 of each row, as given
+    // by `np.packbits(X.T, axis=1, bitorder="little")` viewed as uint64 after padding the rows
+    // to a multiple of 8 bytes
+    #[staticmethod]
+    #[pyo3(signature = (bits, n_samples, target=None, feature_names=None))]
+    fn from_packed(
+        bits: PyReadonlyArray2<u64>,
+        n_samples: usize,
+        target: Option<PyReadonlyArrayDyn<f64>>,
+        feature_names: Option<Vec<String>>,
+    ) -> PyResult<Self> {
+        let columns = bits
+            .as_array()
+            .rows()
+            .into_iter()
+            .map(|row| row.to_vec())
+            .collect::<Vec<Vec<u64>>>();
+        if feature_names
+            .as_ref()
+            .is_some_and(|names| names.len() != columns.len())
+        {
+            return Err(PyValueError::new_err(
+                "feature_names must have one name per row of bits",
+            ));
+        }
+        let labels = target.map(|target| {
+            target
+                .as_array()
+                .iter()
+                .map(|a| *a as usize)
+                .collect::<Vec<usize>>()
+        });
+        let structure = RevBitset::from_packed(columns, labels.as_deref(), n_samples)
+            .map_err(PyValueError::new_err)?;
+        Ok(Self {
+            structure,
+            feature_names,
+            missing_columns: vec![],
+            labelled: labels.is_some(),
+        })
+    }
+
+    #[getter]
+    fn n_samples(&self) -> usize {
+        self.structure.num_transactions()
+    }
+
+    // The missing value attributes are not counted, they are not columns of the input
+    #[getter]
+    fn n_features(&self) -> usize {
+        self.structure.num_attributes() - self.missing_columns.len()
+    }
+
+    #[getter]
+    fn class_counts(&mut self) -> Vec<usize> {
+        self.structure.labels_support().to_vec()
+    }
+
+    #[getter]
+    fn feature_names(&self) -> Option<Vec<String>> {
+        self.feature_names.clone()
+    }
+
+    #[getter]
+    fn missing_columns(&self) -> Vec<usize> {
+        self.missing_columns.clone()
+    }
+
+    // Appends samples, given as class and binary row over all the attributes, missing value ones
+    // included, after the current ones
+    fn add_samples(&mut self, samples: Vec<(usize, Vec<usize>)>) -> PyResult<()> {
+        self.structure
+            .add_samples(&samples)
+            .map_err(PyValueError::new_err)
+    }
+
+    // Removes the samples of the given transactions, the next ones are renumbered in order. The
+    // removed samples are returned as class and binary row, to be given to
+    // DL85.invalidate_samples before the next partial fit
+    fn remove_samples(&mut self, tids: Vec<usize>) -> PyResult<Vec<(usize, Vec<usize>)>> {
+        self.structure
+            .remove_samples(&tids)
+            .map_err(PyValueError::new_err)
+    }
+}
@@ -0,0 +1,256 @@
+use crate::utils::{read_dataset, ExposedMissingValues, ExposedSearchStrategy};
+use dtrees_rs::ensemble::{BoostedODT, LGDTForest};
+use dtrees_rs::searches::SearchStrategy;
+use dtrees_rs::tree::MissingBranch;
+use numpy::{PyReadonlyArray2, PyReadonlyArrayDyn};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+// Rows of the input, followed by the missing value attributes of the columns having one
+fn instances(input: &PyReadonlyArray2<f64>, missing_columns: &[usize]) -> Vec<Vec<f64>> {
+    input
+        .as_array()
+        .rows()
+        .into_iter()
+        .map(|row| {
+            let mut instance = row.to_vec();
+            let indicators = missing_columns
+                .iter()
+                .map(|column| instance[*column].is_nan() as usize as f64)
+                .collect::<Vec<f64>>();
+            instance.extend(indicators);
+            instance
+        })
+        .collect()
+}
+
+// Random forest of LGDT trees, each fitted on a bootstrap sample and a random subset of the
+// features. Trees are fitted in parallel and predict by majority vote
+#[pyclass(name = "LGDTForest", module = "pytreesrs.ensemble")]
+pub(crate) struct PyLGDTForest {
+    #[pyo3(get, set)]
+    n_trees: usize,
+    #[pyo3(get, set)]
+    max_features: Option<usize>,
+    #[pyo3(get, set)]
+    seed: u64,
+    #[pyo3(get, set)]
+    min_sup: usize,
+    #[pyo3(get, set)]
+    max_depth: usize,
+    #[pyo3(get, set)]
+    search_strategy: ExposedSearchStrategy,
+    #[pyo3(get, set)]
+    missing_values: ExposedMissingValues,
+    forest: Option<LGDTForest>,
+    missing_columns: Vec<usize>,
+}
+
+impl PyLGDTForest {
+    fn fitted_forest(&self) -> PyResult<&LGDTForest> {
+        self.forest
+            .as_ref()
+            .ok_or_else(|| PyValueError::new_err("Call fit method first"))
+    }
+}
+
+#[pymethods]
+impl PyLGDTForest {
+    #[new]
+    #[pyo3(signature = (n_trees=10, max_features=None, seed=0, min_sup=1, max_depth=2, search_strategy=ExposedSearchStrategy::LessGreedyMurtree, missing_values=ExposedMissingValues::Zero))]
+    fn new(
+        n_trees: usize,
+        max_features: Option<usize>,
+        seed: u64,
+        min_sup: usize,
+        max_depth: usize,
+        search_strategy: ExposedSearchStrategy,
+        missing_values: ExposedMissingValues,
+    ) -> Self {
+        Self {
+            n_trees,
+            max_features,
+            seed,
+            min_sup,
+            max_depth,
+            search_strategy,
+            missing_values,
+            forest: None,
+            missing_columns: vec![],
+        }
+    }
+
+    fn fit<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        input: &PyAny,
+        target: PyReadonlyArrayDyn<f64>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        let strategy = match slf.search_strategy {
+            ExposedSearchStrategy::LessGreedyInfoGain => SearchStrategy::LessGreedyInfoGain,
+            ExposedSearchStrategy::LessGreedyMurtree => SearchStrategy::LessGreedyMurtree,
+            _ => return Err(PyValueError::new_err("Invalid strategy for this approach")),
+        };
+        if slf.n_trees == 0 {
+            return Err(PyValueError::new_err("n_trees must be at least 1"));
+        }
+
+        let target = target.as_array().map(|a| *a as usize);
+        let input = read_dataset(input, Some(&target), slf.missing_values.into())?;
+        let structure = input.dataset.structure();
+
+        let mut forest = LGDTForest::new(slf.n_trees, slf.min_sup, slf.max_depth, strategy);
+        forest.max_features = slf.max_features;
+        forest.seed = slf.seed;
+        let py = slf.py();
+        py.allow_threads(|| forest.fit(&structure));
+
+        slf.forest = Some(forest);
+        slf.missing_columns = input.missing_columns;
+        Ok(slf)
+    }
+
+    fn predict(&self, input: PyReadonlyArray2<f64>) -> PyResult<Vec<f64>> {
+        let forest = self.fitted_forest()?;
+        instances(&input, &self.missing_columns)
+            .iter()
+            .map(|instance| {
+                forest
+                    .predict(instance, MissingBranch::Left)
+                    .ok_or_else(|| PyValueError::new_err("A tree has a leaf without output"))
+            })
+            .collect()
+    }
+
+    // Trees of the forest as JSON, tested on the features of the whole data
+    #[getter]
+    fn trees(&self) -> PyResult<Vec<String>> {
+        Ok(self
+            .fitted_forest()?
+            .trees
+            .iter()
+            .map(|tree| serde_json::to_string_pretty(tree).unwrap())
+            .collect())
+    }
+}
+
+// AdaBoost over optimal trees of depth 1 or 2, each fitted on the data reweighted by the previous
+// stages. Predictions can be followed stage by stage
+#[pyclass(name = "BoostedODT", module = "pytreesrs.ensemble")]
+pub(crate) struct PyBoostedODT {
+    #[pyo3(get, set)]
+    n_estimators: usize,
+    #[pyo3(get, set)]
+    max_depth: usize,
+    #[pyo3(get, set)]
+    min_sup: usize,
+    #[pyo3(get, set)]
+    learning_rate: f64,
+    #[pyo3(get, set)]
+    missing_values: ExposedMissingValues,
+    booster: Option<BoostedODT>,
+    missing_columns: Vec<usize>,
+}
+
+impl PyBoostedODT {
+    fn fitted_booster(&self) -> PyResult<&BoostedODT> {
+        self.booster
+            .as_ref()
+            .ok_or_else(|| PyValueError::new_err("Call fit method first"))
+    }
+}
+
+#[pymethods]
+impl PyBoostedODT {
+    #[new]
+    #[pyo3(signature = (n_estimators=50, max_depth=2, min_sup=1, learning_rate=1.0, missing_values=ExposedMissingValues::Zero))]
+    fn new(
+        n_estimators: usize,
+        max_depth: usize,
+        min_sup: usize,
+        learning_rate: f64,
+        missing_values: ExposedMissingValues,
+    ) -> Self {
+        Self {
+            n_estimators,
+            max_depth,
+            min_sup,
+            learning_rate,
+            missing_values,
+            booster: None,
+            missing_columns: vec![],
+        }
+    }
+
+    fn fit<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        input: &PyAny,
+        target: PyReadonlyArrayDyn<f64>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        if !(1..=2).contains(&slf.max_depth) {
+            return Err(PyValueError::new_err("max_depth must be 1 or 2"));
+        }
+        if slf.learning_rate <= 0.0 {
+            return Err(PyValueError::new_err("learning_rate must be positive"));
+        }
+
+        let target = target.as_array().map(|a| *a as usize);
+        let input = read_dataset(input, Some(&target), slf.missing_values.into())?;
+        let mut structure = input.dataset.structure();
+
+        let mut booster = BoostedODT::new(
+            slf.n_estimators,
+            slf.max_depth,
+            slf.min_sup,
+            slf.learning_rate,
+        );
+        let py = slf.py();
+        py.allow_threads(|| booster.fit(&mut structure));
+
+        slf.booster = Some(booster);
+        slf.missing_columns = input.missing_columns;
+        Ok(slf)
+    }
+
+    fn predict(&self, input: PyReadonlyArray2<f64>) -> PyResult<Vec<f64>> {
+        let booster = self.fitted_booster()?;
+        instances(&input, &self.missing_columns)
+            .iter()
+            .map(|instance| {
+                booster
+                    .predict(instance, MissingBranch::Left)
+                    .ok_or_else(|| PyValueError::new_err("A tree has a leaf without output"))
+            })
+            .collect()
+    }
+
+    // Predictions after each stage, one list per stage
+    fn staged_predict(&self, input: PyReadonlyArray2<f64>) -> PyResult<Vec<Vec<f64>>> {
+        let booster = self.fitted_booster()?;
+        let mut stages = vec![vec![]; booster.estimators.len()];
+        for instance in instances(&input, &self.missing_columns) {
+            let predictions = booster
+                .staged_predict(&instance, MissingBranch::Left)
+                .ok_or_else(|| PyValueError::new_err("A tree has a leaf without output"))?;
+            for (stage, prediction) in stages.iter_mut().zip(predictions) {
+                stage.push(prediction);
+            }
+        }
+        Ok(stages)
+    }
+
+    #[getter]
+    fn estimator_weights(&self) -> PyResult<Vec<f64>> {
+        Ok(self.fitted_booster()?.estimator_weights.clone())
+    }
+
+    // Trees of each stage as JSON
+    #[getter]
+    fn trees(&self) -> PyResult<Vec<String>> {
+        Ok(self
+            .fitted_booster()?
+            .estimators
+            .iter()
+            .map(|tree| serde_json::to_string_pretty(tree).unwrap())
+            .collect())
+    }
+}
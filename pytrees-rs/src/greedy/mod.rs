@@ -1,39 +1,204 @@
-use crate::utils::{ExposedSearchStrategy, LearningResult};
-use dtrees_rs::data::{BinaryData, FileReader};
+use crate::cover::PyCover;
+use crate::utils::{
+    monotonic_features, read_dataset, ExposedMissingValues, ExposedSearchStrategy, LearningResult,
+};
 use dtrees_rs::searches::greedy::LGDT;
 use dtrees_rs::searches::SearchStrategy;
-use dtrees_rs::structures::RevBitset;
-use numpy::PyReadonlyArrayDyn;
+use dtrees_rs::structures::Structure;
+use dtrees_rs::tree::MissingBranch;
+use numpy::{PyReadonlyArray2, PyReadonlyArrayDyn};
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 
 #[pyfunction]
 #[pyo3(name = "lgdt")]
+#[pyo3(signature = (input, target, search_strategy, min_sup, max_depth, missing_values=ExposedMissingValues::Zero, monotonic_cst=None))]
 pub(crate) fn search_lgdt(
-    input: PyReadonlyArrayDyn<f64>,
-    target: PyReadonlyArrayDyn<f64>,
+    input: &PyAny,
+    target: Option<PyReadonlyArrayDyn<f64>>,
     search_strategy: ExposedSearchStrategy,
     min_sup: usize,
     max_depth: usize,
-) -> LearningResult {
+    missing_values: ExposedMissingValues,
+    monotonic_cst: Option<Vec<i8>>,
+) -> PyResult<LearningResult> {
     let search_strategy = match search_strategy {
         ExposedSearchStrategy::LessGreedyInfoGain => SearchStrategy::LessGreedyInfoGain,
         ExposedSearchStrategy::LessGreedyMurtree => SearchStrategy::LessGreedyMurtree,
         _ => panic!("Invalid strategy for this approach"),
     };
 
-    let input = input.as_array().map(|a| *a as usize);
-    let target = target.as_array().map(|a| *a as usize);
-    let dataset = BinaryData::read_from_numpy(&input, Some(&target));
-    let mut structure = RevBitset::new(&dataset);
+    // A Cover already holds the data and its classes
+    let mut cover = input.extract::<PyRefMut<PyCover>>().ok();
+    let mut read_structure;
+    let (feature_names, missing_columns, structure) = match (cover.as_deref_mut(), target) {
+        (Some(cover), None) if cover.labelled => {
+            cover.structure.unmask();
+            (
+                cover.feature_names.clone(),
+                cover.missing_columns.clone(),
+                &mut cover.structure,
+            )
+        }
+        (Some(_), _) => {
+            return Err(PyValueError::new_err(
+                "LGDT requires a Cover built with its classes and no target",
+            ))
+        }
+        (None, Some(target)) => {
+            let target = target.as_array().map(|a| *a as usize);
+            let input = read_dataset(input, Some(&target), missing_values.into())?;
+            read_structure = input.dataset.structure();
+            (
+                input.feature_names,
+                input.missing_columns,
+                &mut read_structure,
+            )
+        }
+        (None, None) => return Err(PyValueError::new_err("LGDT requires a target")),
+    };
 
     let mut learner = LGDT::new(min_sup, max_depth, search_strategy);
+    learner.monotonic_features = monotonic_features(
+        monotonic_cst,
+        structure.num_attributes(),
+        structure.num_labels(),
+        &missing_columns,
+    )?;
 
-    learner.fit(&mut structure);
+    learner.fit(structure);
 
-    LearningResult {
+    Ok(LearningResult {
         error: learner.error,
         tree: learner.tree.clone(),
+        optimal_tree: None,
         constraints: learner.constraints,
         statistics: learner.statistics,
+        feature_names,
+        missing_columns,
+    })
+}
+
+// Estimator form of `lgdt`, keeping the learned tree to predict or to warm start DL85
+#[pyclass(name = "LGDT", module = "pytreesrs.greedy")]
+pub(crate) struct PyLGDT {
+    #[pyo3(get, set)]
+    min_sup: usize,
+    #[pyo3(get, set)]
+    max_depth: usize,
+    #[pyo3(get, set)]
+    search_strategy: ExposedSearchStrategy,
+    #[pyo3(get, set)]
+    missing_values: ExposedMissingValues,
+    #[pyo3(get, set)]
+    monotonic_cst: Option<Vec<i8>>,
+    result: Option<LearningResult>,
+}
+
+impl PyLGDT {
+    fn fitted_result(&self) -> PyResult<&LearningResult> {
+        self.result
+            .as_ref()
+            .ok_or_else(|| PyValueError::new_err("Call fit method first"))
+    }
+}
+
+#[pymethods]
+impl PyLGDT {
+    #[new]
+    #[pyo3(signature = (min_sup=1, max_depth=2, search_strategy=ExposedSearchStrategy::LessGreedyMurtree, missing_values=ExposedMissingValues::Zero, monotonic_cst=None))]
+    fn new(
+        min_sup: usize,
+        max_depth: usize,
+        search_strategy: ExposedSearchStrategy,
+        missing_values: ExposedMissingValues,
+        monotonic_cst: Option<Vec<i8>>,
+    ) -> Self {
+        Self {
+            min_sup,
+            max_depth,
+            search_strategy,
+            missing_values,
+            monotonic_cst,
+            result: None,
+        }
+    }
+
+    fn fit<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        input: &PyAny,
+        target: PyReadonlyArrayDyn<f64>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        let result = search_lgdt(
+            input,
+            Some(target),
+            slf.search_strategy,
+            slf.min_sup,
+            slf.max_depth,
+            slf.missing_values,
+            slf.monotonic_cst.clone(),
+        )?;
+        slf.result = Some(result);
+        Ok(slf)
+    }
+
+    // Fits on the data of a Cover, built with its classes, without reading it again
+    fn fit_cover<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        cover: &PyCell<PyCover>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        let result = search_lgdt(
+            cover,
+            None,
+            slf.search_strategy,
+            slf.min_sup,
+            slf.max_depth,
+            slf.missing_values,
+            slf.monotonic_cst.clone(),
+        )?;
+        slf.result = Some(result);
+        Ok(slf)
+    }
+
+    fn predict(&self, input: PyReadonlyArray2<f64>) -> PyResult<Vec<f64>> {
+        let result = self.fitted_result()?;
+        input
+            .as_array()
+            .rows()
+            .into_iter()
+            .map(|row| {
+                let mut instance = row.to_vec();
+                let indicators = result
+                    .missing_columns
+                    .iter()
+                    .map(|column| instance[*column].is_nan() as usize as f64)
+                    .collect::<Vec<f64>>();
+                instance.extend(indicators);
+                result
+                    .tree
+                    .predict(&instance, MissingBranch::Left)
+                    .ok_or_else(|| PyValueError::new_err("The tree has a leaf without output"))
+            })
+            .collect()
+    }
+
+    #[getter]
+    fn result(&self) -> PyResult<LearningResult> {
+        self.fitted_result().cloned()
+    }
+
+    #[getter]
+    fn statistics(&self) -> PyResult<String> {
+        self.fitted_result()?.statistics()
+    }
+
+    #[getter]
+    fn constraints(&self) -> PyResult<String> {
+        self.fitted_result()?.constraints()
+    }
+
+    #[getter]
+    fn tree(&self) -> PyResult<String> {
+        self.fitted_result()?.tree()
     }
 }
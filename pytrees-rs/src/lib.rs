@@ -1,21 +1,40 @@
-use crate::greedy::search_lgdt;
+use crate::cover::PyCover;
+use crate::ensemble::{PyBoostedODT, PyLGDTForest};
+use crate::greedy::{search_lgdt, PyLGDT};
+use crate::model_selection::{cross_validate, pareto_depths, tune};
+use crate::optimal::d2::optimal_search_d2;
+use crate::optimal::learner::PyDL85;
 use crate::optimal::optimal_search_dl85;
+use crate::optimal::schedule::restart_budget;
+use crate::preprocessing::{PyBinarizer, PyCategoricalEncoder};
 use crate::utils::{
-    ExposedBranchingStrategy, ExposedCacheInitStrategy, ExposedCacheType, ExposedDataFormat,
-    ExposedLowerBoundStrategy, ExposedSearchHeuristic, ExposedSearchStrategy,
-    ExposedSpecialization,
+    ExposedBinarizationStrategy, ExposedBranchingStrategy, ExposedCacheFullPolicy,
+    ExposedCacheInitStrategy, ExposedCacheType, ExposedCategoricalEncoding, ExposedDataFormat,
+    ExposedLowerBoundPolicy, ExposedLowerBoundStrategy, ExposedMissingBranch, ExposedMissingValues,
+    ExposedObjective, ExposedRestartSchedule, ExposedSearchHeuristic, ExposedSearchStrategy,
+    ExposedSortPolicy, ExposedSpecialization, ExposedStopReason, ExposedTieBreak, LearningResult,
+    PyNode, PythonLogger,
 };
 use numpy::pyo3::{pymodule, PyResult, Python};
 use pyo3::prelude::PyModule;
 use pyo3::wrap_pyfunction;
+mod cover;
+mod ensemble;
 mod greedy;
+mod model_selection;
 mod optimal;
+mod preprocessing;
 mod utils;
 
 #[pymodule]
 fn pytreesrs(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    PythonLogger::init();
+    m.add_class::<PyCover>()?;
     odt(py, m)?;
     greed(py, m)?;
+    ensembles(py, m)?;
+    selection(py, m)?;
+    preprocess(py, m)?;
     enums(py, m)?;
     Ok(())
 }
@@ -32,6 +51,17 @@ fn enums(py: Python<'_>, parent_module: &PyModule) -> PyResult<()> {
     module.add_class::<ExposedBranchingStrategy>()?;
     module.add_class::<ExposedCacheInitStrategy>()?;
     module.add_class::<ExposedSearchStrategy>()?;
+    module.add_class::<ExposedBinarizationStrategy>()?;
+    module.add_class::<ExposedCategoricalEncoding>()?;
+    module.add_class::<ExposedMissingValues>()?;
+    module.add_class::<ExposedMissingBranch>()?;
+    module.add_class::<ExposedRestartSchedule>()?;
+    module.add_class::<ExposedCacheFullPolicy>()?;
+    module.add_class::<ExposedLowerBoundPolicy>()?;
+    module.add_class::<ExposedTieBreak>()?;
+    module.add_class::<ExposedSortPolicy>()?;
+    module.add_class::<ExposedStopReason>()?;
+    module.add_class::<ExposedObjective>()?;
 
     parent_module.add_submodule(module)?;
     py.import("sys")?
@@ -45,6 +75,11 @@ fn enums(py: Python<'_>, parent_module: &PyModule) -> PyResult<()> {
 fn odt(py: Python<'_>, parent_module: &PyModule) -> PyResult<()> {
     let module = PyModule::new(py, "odt")?;
     module.add_function(wrap_pyfunction!(optimal_search_dl85, module)?)?;
+    module.add_function(wrap_pyfunction!(optimal_search_d2, module)?)?;
+    module.add_function(wrap_pyfunction!(restart_budget, module)?)?;
+    module.add_class::<PyDL85>()?;
+    module.add_class::<LearningResult>()?;
+    module.add_class::<PyNode>()?;
 
     parent_module.add_submodule(module)?;
     py.import("sys")?
@@ -59,6 +94,7 @@ fn odt(py: Python<'_>, parent_module: &PyModule) -> PyResult<()> {
 fn greed(py: Python<'_>, parent_module: &PyModule) -> PyResult<()> {
     let module = PyModule::new(py, "greedy")?;
     module.add_function(wrap_pyfunction!(search_lgdt, module)?)?;
+    module.add_class::<PyLGDT>()?;
 
     parent_module.add_submodule(module)?;
     py.import("sys")?
@@ -67,3 +103,49 @@ fn greed(py: Python<'_>, parent_module: &PyModule) -> PyResult<()> {
 
     Ok(())
 }
+
+#[pymodule]
+#[pyo3(name = "ensemble")]
+fn ensembles(py: Python<'_>, parent_module: &PyModule) -> PyResult<()> {
+    let module = PyModule::new(py, "ensemble")?;
+    module.add_class::<PyLGDTForest>()?;
+    module.add_class::<PyBoostedODT>()?;
+
+    parent_module.add_submodule(module)?;
+    py.import("sys")?
+        .getattr("modules")?
+        .set_item("pytreesrs.ensemble", module)?;
+
+    Ok(())
+}
+
+#[pymodule]
+#[pyo3(name = "model_selection")]
+fn selection(py: Python<'_>, parent_module: &PyModule) -> PyResult<()> {
+    let module = PyModule::new(py, "model_selection")?;
+    module.add_function(wrap_pyfunction!(cross_validate, module)?)?;
+    module.add_function(wrap_pyfunction!(tune, module)?)?;
+    module.add_function(wrap_pyfunction!(pareto_depths, module)?)?;
+
+    parent_module.add_submodule(module)?;
+    py.import("sys")?
+        .getattr("modules")?
+        .set_item("pytreesrs.model_selection", module)?;
+
+    Ok(())
+}
+
+#[pymodule]
+#[pyo3(name = "preprocessing")]
+fn preprocess(py: Python<'_>, parent_module: &PyModule) -> PyResult<()> {
+    let module = PyModule::new(py, "preprocessing")?;
+    module.add_class::<PyBinarizer>()?;
+    module.add_class::<PyCategoricalEncoder>()?;
+
+    parent_module.add_submodule(module)?;
+    py.import("sys")?
+        .getattr("modules")?
+        .set_item("pytreesrs.preprocessing", module)?;
+
+    Ok(())
+}
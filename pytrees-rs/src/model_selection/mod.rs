@@ -0,0 +1,296 @@
+use crate::utils::{read_dataset, ExposedMissingValues, ExposedSearchStrategy};
+use dtrees_rs::cache::trie::Trie;
+use dtrees_rs::heuristics::NoHeuristic;
+use dtrees_rs::searches::errors::NativeError;
+use dtrees_rs::searches::greedy::LGDT;
+use dtrees_rs::searches::optimal::DL85;
+use dtrees_rs::searches::tuning::{depth_front, tune_depth_and_support, TuningResult};
+use dtrees_rs::searches::{
+    BranchingStrategy, CacheInitStrategy, LowerBoundStrategy, NodeExposedData, SearchStrategy,
+    SortPolicy, Specialization,
+};
+use dtrees_rs::structures::{RevBitset, Structure};
+use dtrees_rs::tree::{MissingBranch, Tree};
+use numpy::PyReadonlyArrayDyn;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use rayon::prelude::*;
+
+// DL85 with the defaults of the dl85 function
+fn default_dl85(
+    min_sup: usize,
+    max_depth: usize,
+    time: usize,
+) -> DL85<Trie, NativeError, NoHeuristic> {
+    DL85::new(
+        min_sup,
+        max_depth,
+        <f64>::INFINITY,
+        time,
+        SortPolicy::RootOnly,
+        0,
+        CacheInitStrategy::None_,
+        Specialization::Murtree,
+        LowerBoundStrategy::Similarity,
+        BranchingStrategy::Dynamic,
+        NodeExposedData::ClassesSupport,
+        Box::<Trie>::default(),
+        Box::<NativeError>::default(),
+        Box::<NoHeuristic>::default(),
+    )
+}
+
+// Learner fitted on each fold, read from the estimator configuration
+#[derive(Clone, Copy)]
+enum FoldLearner {
+    Optimal {
+        min_sup: usize,
+        max_depth: usize,
+        time: usize,
+    },
+    Greedy {
+        min_sup: usize,
+        max_depth: usize,
+        strategy: SearchStrategy,
+    },
+}
+
+impl FoldLearner {
+    // The configuration holds the "algorithm", "dl85" by default or "lgdt", with its "min_sup",
+    // "max_depth" and, for DL85, the "time" limit of each fold or, for LGDT, its "search_strategy"
+    fn from_config(config: &PyDict) -> PyResult<Self> {
+        let mut algorithm = "dl85".to_string();
+        let mut min_sup = 1;
+        let mut max_depth = 2;
+        let mut time = 600;
+        let mut strategy = SearchStrategy::LessGreedyMurtree;
+        for (key, value) in config.iter() {
+            match key.extract::<&str>()? {
+                "algorithm" => algorithm = value.extract()?,
+                "min_sup" => min_sup = value.extract()?,
+                "max_depth" => max_depth = value.extract()?,
+                "time" => time = value.extract()?,
+                "search_strategy" => {
+                    strategy = match value.extract::<ExposedSearchStrategy>()? {
+                        ExposedSearchStrategy::LessGreedyMurtree => {
+                            SearchStrategy::LessGreedyMurtree
+                        }
+                        ExposedSearchStrategy::LessGreedyInfoGain => {
+                            SearchStrategy::LessGreedyInfoGain
+                        }
+                        _ => return Err(PyValueError::new_err("Invalid strategy for LGDT")),
+                    }
+                }
+                key => {
+                    return Err(PyValueError::new_err(format!(
+                        "Unknown estimator parameter {}",
+                        key
+                    )))
+                }
+            }
+        }
+        match algorithm.as_str() {
+            "dl85" => Ok(FoldLearner::Optimal {
+                min_sup,
+                max_depth,
+                time,
+            }),
+            "lgdt" => Ok(FoldLearner::Greedy {
+                min_sup,
+                max_depth,
+                strategy,
+            }),
+            _ => Err(PyValueError::new_err(
+                "algorithm must be either \"dl85\" or \"lgdt\"",
+            )),
+        }
+    }
+
+    // Tree learned on the structure with its training error
+    fn fit(&self, structure: &mut RevBitset) -> (f64, Tree) {
+        match *self {
+            FoldLearner::Optimal {
+                min_sup,
+                max_depth,
+                time,
+            } => {
+                let mut learner = default_dl85(min_sup, max_depth, time);
+                learner
+                    .fit(structure)
+                    .expect("The default learner has no monotonicity or rate constraints");
+                (learner.statistics.tree_error, learner.tree)
+            }
+            FoldLearner::Greedy {
+                min_sup,
+                max_depth,
+                strategy,
+            } => {
+                let mut learner = LGDT::new(min_sup, max_depth, strategy);
+                learner.fit(structure);
+                (learner.error, learner.tree)
+            }
+        }
+    }
+}
+
+/// Cross-validation of a DL85 or LGDT configuration. The data is read once, the folds are
+/// consecutive blocks of samples and are fitted in parallel on `n_jobs` threads, all the available
+/// ones by default, with the GIL released. The errors are numbers of misclassified samples
+#[pyfunction]
+#[pyo3(name = "cross_validate")]
+#[pyo3(signature = (estimator_config, input, target, folds=5, n_jobs=None, missing_values=ExposedMissingValues::Zero))]
+pub(crate) fn cross_validate(
+    py: Python<'_>,
+    estimator_config: &PyDict,
+    input: &PyAny,
+    target: PyReadonlyArrayDyn<f64>,
+    folds: usize,
+    n_jobs: Option<usize>,
+    missing_values: ExposedMissingValues,
+) -> PyResult<PyObject> {
+    let learner = FoldLearner::from_config(estimator_config)?;
+    let target = target.as_array().map(|a| *a as usize);
+    let input = read_dataset(input, Some(&target), missing_values.into())?;
+    let structure = input.dataset.structure();
+    let size = structure.num_transactions();
+    if folds < 2 || folds > size {
+        return Err(PyValueError::new_err(format!(
+            "folds must be between 2 and the number of samples, {}",
+            size
+        )));
+    }
+    let labels = input
+        .dataset
+        .targets()
+        .ok_or_else(|| PyValueError::new_err("Cross-validation needs a target"))?;
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(n_jobs.unwrap_or(0))
+        .build()
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    let attributes = (0..structure.num_attributes()).collect::<Vec<usize>>();
+    let results = py.allow_threads(|| {
+        pool.install(|| {
+            (0..folds)
+                .into_par_iter()
+                .map(|fold| {
+                    let test = fold * size / folds..(fold + 1) * size / folds;
+                    let train = (0..size)
+                        .filter(|tid| !test.contains(tid))
+                        .collect::<Vec<usize>>();
+                    let mut train_structure = structure
+                        .sample(&train, &attributes)
+                        .expect("Folds are drawn from the structure");
+                    let (train_error, tree) = learner.fit(&mut train_structure);
+                    let test_error = test
+                        .filter(|tid| {
+                            let instance = input.dataset.instance(*tid);
+                            tree.predict(&instance, MissingBranch::Left)
+                                != Some(labels[*tid] as f64)
+                        })
+                        .count();
+                    (train_error, test_error as f64, tree)
+                })
+                .collect::<Vec<(f64, f64, Tree)>>()
+        })
+    });
+
+    let summary = PyDict::new(py);
+    summary.set_item(
+        "train_error",
+        results.iter().map(|r| r.0).collect::<Vec<f64>>(),
+    )?;
+    summary.set_item(
+        "test_error",
+        results.iter().map(|r| r.1).collect::<Vec<f64>>(),
+    )?;
+    summary.set_item(
+        "trees",
+        results
+            .iter()
+            .map(|r| serde_json::to_string_pretty(&r.2).unwrap())
+            .collect::<Vec<String>>(),
+    )?;
+    Ok(summary.into())
+}
+
+/// DL85 fitted for every pair of `depths` and `min_sups`, shallow depths first, each search reusing
+/// the cache and the best valid tree of the previous ones. Returns the Pareto set of the number of
+/// leaves and the training error, from the simplest tree, as dicts holding the configuration
+#[pyfunction]
+#[pyo3(name = "tune")]
+#[pyo3(signature = (input, target, depths, min_sups=vec![1], time=600, missing_values=ExposedMissingValues::Zero))]
+pub(crate) fn tune(
+    py: Python<'_>,
+    input: &PyAny,
+    target: PyReadonlyArrayDyn<f64>,
+    depths: Vec<usize>,
+    min_sups: Vec<usize>,
+    time: usize,
+    missing_values: ExposedMissingValues,
+) -> PyResult<PyObject> {
+    if depths.is_empty() || min_sups.is_empty() {
+        return Err(PyValueError::new_err(
+            "depths and min_sups must not be empty",
+        ));
+    }
+    let target = target.as_array().map(|a| *a as usize);
+    let input = read_dataset(input, Some(&target), missing_values.into())?;
+    let mut structure = input.dataset.structure();
+    let front = py
+        .allow_threads(|| {
+            tune_depth_and_support(&mut structure, &depths, &min_sups, || {
+                default_dl85(1, 1, time)
+            })
+        })
+        .map_err(PyValueError::new_err)?;
+
+    front_to_list(py, front)
+}
+
+/// DL85 fitted for every depth from 1 to `max_depth`, with one learner whose cache is shared by
+/// all the depths, the tree of each depth warm starting the next one. Returns the depths whose
+/// training error is lower than the one of all the shallower depths, as the dicts of `tune`
+#[pyfunction]
+#[pyo3(name = "depth_front")]
+#[pyo3(signature = (input, target, max_depth, min_sup=1, time=600, missing_values=ExposedMissingValues::Zero))]
+pub(crate) fn pareto_depths(
+    py: Python<'_>,
+    input: &PyAny,
+    target: PyReadonlyArrayDyn<f64>,
+    max_depth: usize,
+    min_sup: usize,
+    time: usize,
+    missing_values: ExposedMissingValues,
+) -> PyResult<PyObject> {
+    if max_depth == 0 {
+        return Err(PyValueError::new_err("max_depth must be at least 1"));
+    }
+    let target = target.as_array().map(|a| *a as usize);
+    let input = read_dataset(input, Some(&target), missing_values.into())?;
+    let mut structure = input.dataset.structure();
+    let front = py
+        .allow_threads(|| {
+            let mut learner = default_dl85(min_sup, 1, time);
+            depth_front(&mut structure, max_depth, min_sup, &mut learner)
+        })
+        .map_err(PyValueError::new_err)?;
+    front_to_list(py, front)
+}
+
+// One dict per tuning result, holding its configuration, error, size and tree
+fn front_to_list(py: Python<'_>, front: Vec<TuningResult>) -> PyResult<PyObject> {
+    let results = PyList::empty(py);
+    for result in front {
+        let entry = PyDict::new(py);
+        entry.set_item("max_depth", result.max_depth)?;
+        entry.set_item("min_sup", result.min_sup)?;
+        entry.set_item("error", result.error)?;
+        entry.set_item("leaves", result.leaves)?;
+        entry.set_item("is_optimal", result.is_optimal)?;
+        entry.set_item("tree", serde_json::to_string_pretty(&result.tree).unwrap())?;
+        results.append(entry)?;
+    }
+    Ok(results.into())
+}
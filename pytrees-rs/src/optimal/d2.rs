@@ -0,0 +1,76 @@
+use crate::utils::{read_dataset, ExposedMissingValues, ExposedSearchStrategy, LearningResult};
+use dtrees_rs::globals::{fill_classes_support, get_tree_root_error};
+use dtrees_rs::searches::optimal::d2::GenericDepth2;
+use dtrees_rs::searches::{Constraints, SearchStrategy, Statistics};
+use dtrees_rs::structures::Structure;
+use numpy::PyReadonlyArrayDyn;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::time::Instant;
+
+// Exact trees of depth 1 or 2, without the DL85 search
+#[pyfunction]
+#[pyo3(name = "depth2")]
+#[pyo3(signature = (input, target, min_sup=1, max_depth=2, objective=ExposedSearchStrategy::LessGreedyMurtree, missing_values=ExposedMissingValues::Zero))]
+pub(crate) fn optimal_search_d2(
+    input: &PyAny,
+    target: PyReadonlyArrayDyn<f64>,
+    min_sup: usize,
+    max_depth: usize,
+    objective: ExposedSearchStrategy,
+    missing_values: ExposedMissingValues,
+) -> PyResult<LearningResult> {
+    if !(1..=2).contains(&max_depth) {
+        return Err(PyValueError::new_err("max_depth must be 1 or 2"));
+    }
+    let strategy = match objective {
+        ExposedSearchStrategy::LessGreedyMurtree => SearchStrategy::LessGreedyMurtree,
+        ExposedSearchStrategy::LessGreedyInfoGain => SearchStrategy::LessGreedyInfoGain,
+        _ => {
+            return Err(PyValueError::new_err(
+                "objective must be LessGreedyMurtree (error) or LessGreedyInfoGain",
+            ))
+        }
+    };
+
+    let target = target.as_array().map(|a| *a as usize);
+    let input = read_dataset(input, Some(&target), missing_values.into())?;
+    let mut structure = input.dataset.structure();
+
+    let start = Instant::now();
+    let mut tree = GenericDepth2::new(strategy).fit(min_sup, max_depth, &mut structure);
+    fill_classes_support(&mut tree, &mut structure);
+
+    let constraints = Constraints {
+        max_depth,
+        min_sup,
+        search_strategy: strategy,
+        ..Default::default()
+    };
+    let error = get_tree_root_error(&tree);
+    // The information gain objective does not minimize the error
+    let best_lower_bound = match strategy {
+        SearchStrategy::LessGreedyMurtree => error,
+        _ => 0.0,
+    };
+    let statistics = Statistics {
+        tree_error: error,
+        best_lower_bound,
+        gap: error - best_lower_bound,
+        duration: start.elapsed(),
+        num_attributes: structure.num_attributes(),
+        num_samples: structure.support(),
+        constraints,
+        ..Statistics::default()
+    };
+
+    Ok(LearningResult {
+        error,
+        tree,
+        optimal_tree: None,
+        constraints,
+        statistics,
+        feature_names: input.feature_names,
+        missing_columns: input.missing_columns,
+    })
+}
@@ -0,0 +1,125 @@
+use crate::cover::PyCover;
+use crate::optimal::learning_result;
+use crate::utils::{
+    read_dataset, CallbackError, ExposedMissingValues, LearningResult, PythonLogger,
+};
+use dtrees_rs::cache::Caching;
+use dtrees_rs::globals::fill_samples;
+use dtrees_rs::heuristics::Heuristic;
+use dtrees_rs::searches::errors::ErrorWrapper;
+use dtrees_rs::searches::optimal::DL85;
+use numpy::PyReadonlyArrayDyn;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+pub(crate) type Learner = DL85<dyn Caching, dyn ErrorWrapper, dyn Heuristic>;
+
+// Learner of a fit, with what its next searches need
+pub(crate) struct KeptLearner {
+    pub(crate) learner: Learner,
+    pub(crate) raised: CallbackError,
+    pub(crate) missing_values: ExposedMissingValues,
+    pub(crate) leaf_samples: bool,
+    pub(crate) verbose: usize,
+}
+
+// Keeps the DL85 learner of a fit, given as `learner` to `dl85`, alive between the calls so that
+// its next searches go on from its cache instead of starting from scratch. The other parameters
+// of the learner stay those of that fit
+#[pyclass(name = "DL85", module = "pytreesrs.odt", unsendable)]
+pub(crate) struct PyDL85 {
+    pub(crate) learner: Option<KeptLearner>,
+}
+
+impl PyDL85 {
+    fn kept(&mut self) -> PyResult<&mut KeptLearner> {
+        self.learner
+            .as_mut()
+            .ok_or_else(|| PyValueError::new_err("The learner has not been fitted by dl85"))
+    }
+}
+
+#[pymethods]
+impl PyDL85 {
+    #[new]
+    fn new() -> Self {
+        Self { learner: None }
+    }
+
+    #[getter]
+    fn is_fitted(&self) -> bool {
+        self.learner.is_some()
+    }
+
+    // Searches again on the same data, a Cover or the input given to the first fit, for `time`
+    // seconds, warm started by the last tree. The maximum depth can be raised and the minimum
+    // support lowered, the cache is relaxed instead of being cleared. Under the same constraints,
+    // the search restarts from the similarity covers and the cover merges of the last one
+    #[pyo3(signature = (input, target=None, max_depth=2, min_sup=1, time=600))]
+    fn partial_fit(
+        &mut self,
+        input: &PyAny,
+        target: Option<PyReadonlyArrayDyn<f64>>,
+        max_depth: usize,
+        min_sup: usize,
+        time: usize,
+    ) -> PyResult<LearningResult> {
+        let kept = self.kept()?;
+        let mut cover = input.extract::<PyRefMut<PyCover>>().ok();
+        let target = target.map(|target| target.as_array().map(|a| *a as usize));
+        let mut read_structure;
+        let (feature_names, missing_columns, structure) = match cover.as_deref_mut() {
+            Some(cover) => {
+                cover.structure.unmask();
+                (
+                    cover.feature_names.clone(),
+                    cover.missing_columns.clone(),
+                    &mut cover.structure,
+                )
+            }
+            None => {
+                let input = read_dataset(input, target.as_ref(), kept.missing_values.into())?;
+                read_structure = input.dataset.structure();
+                (
+                    input.feature_names,
+                    input.missing_columns,
+                    &mut read_structure,
+                )
+            }
+        };
+
+        let learner = &mut kept.learner;
+        let constraints = learner.constraints;
+        match (max_depth, min_sup) == (constraints.max_depth, constraints.min_sup) {
+            true => learner.prepare_restart(),
+            false => learner
+                .relax(max_depth, min_sup)
+                .map_err(PyValueError::new_err)?,
+        }
+        learner.constraints.max_time = time;
+        let tree = learner.tree.clone();
+        PythonLogger::set_verbosity(kept.verbose);
+        let fitted = match tree.is_empty() {
+            true => learner.fit(structure),
+            false => learner.fit_with_warm_start(structure, &tree),
+        };
+        if fitted.is_ok() && kept.leaf_samples {
+            fill_samples(&mut learner.tree, structure);
+        }
+        structure.unmask();
+        PythonLogger::set_verbosity(0);
+        fitted.map_err(PyValueError::new_err)?;
+        if let Some(error) = kept.raised.take() {
+            return Err(error);
+        }
+        learning_result(learner, None, feature_names, missing_columns)
+    }
+
+    // Forgets the cache entries reached by samples added to or removed from the data since the
+    // last search, given as class and binary row as returned by Cover.remove_samples. The next
+    // search only solves again the parts of the tree these samples reach
+    fn invalidate_samples(&mut self, samples: Vec<(usize, Vec<usize>)>) -> PyResult<()> {
+        self.kept()?.learner.invalidate_samples(&samples);
+        Ok(())
+    }
+}
@@ -1,51 +1,159 @@
+use crate::cover::PyCover;
+use crate::optimal::learner::{KeptLearner, Learner, PyDL85};
 use crate::utils::{
-    ExposedBranchingStrategy, ExposedCacheInitStrategy, ExposedDataFormat,
-    ExposedLowerBoundStrategy, ExposedSearchHeuristic, ExposedSpecialization, LearningResult,
-    PythonError,
+    monotonic_features, read_dataset, CallbackError, ExposedBranchingStrategy,
+    ExposedCacheFullPolicy, ExposedCacheInitStrategy, ExposedCacheType, ExposedDataFormat,
+    ExposedLowerBoundPolicy, ExposedLowerBoundStrategy, ExposedMissingValues, ExposedObjective,
+    ExposedSearchHeuristic, ExposedSortPolicy, ExposedSpecialization, ExposedTieBreak,
+    LearningResult, PythonError, PythonFilter, PythonHeuristic, PythonLogger, PythonObserver,
+    SignalsInterrupter,
 };
+use dtrees_rs::cache::hashmap::HashMapCache;
 use dtrees_rs::cache::trie::Trie;
-use dtrees_rs::data::{BinaryData, FileReader};
+use dtrees_rs::cache::Caching;
+use dtrees_rs::globals::fill_samples;
 use dtrees_rs::heuristics::{
-    GiniIndex, Heuristic, InformationGain, InformationGainRatio, NoHeuristic,
+    CompositeHeuristic, GiniIndex, Heuristic, InformationGain, InformationGainRatio, NoHeuristic,
+    RandomHeuristic, WeightOfEvidence,
+};
+use dtrees_rs::searches::errors::{ErrorWrapper, NativeError, SquaredError};
+use dtrees_rs::searches::filters::{
+    CandidatesFilter, FeatureCostFilter, FeatureSetFilter, TemplateFilter,
 };
-use dtrees_rs::searches::errors::{ErrorWrapper, NativeError};
 use dtrees_rs::searches::optimal::DL85;
+use dtrees_rs::searches::progress::ProgressObserver;
+use dtrees_rs::searches::rates::RateConstraints;
+use dtrees_rs::searches::rules::LeafRule;
+use dtrees_rs::searches::trace::JsonlTracer;
 use dtrees_rs::searches::{
-    BranchingStrategy, CacheInitStrategy, LowerBoundStrategy, NodeExposedData, Specialization,
+    BranchingStrategy, CacheInitStrategy, LowerBoundStrategy, NodeExposedData, Objective,
+    SearchStrategy, SortPolicy, Specialization,
 };
-use dtrees_rs::structures::RevBitset;
+use dtrees_rs::structures::{RevBitset, Structure};
+use dtrees_rs::tree::Tree;
 use numpy::PyReadonlyArrayDyn;
+use pyo3::exceptions::{PyOSError, PyValueError};
 use pyo3::prelude::*;
+use pyo3::types::PyBool;
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+
+pub(crate) mod d2;
+pub(crate) mod learner;
+pub(crate) mod schedule;
 
 #[pyfunction]
 #[pyo3(name = "dl85")]
-#[pyo3(signature = (input, target=None, min_sup=1, max_depth=2, time=600, cache_init_size=0, error=<f64>::INFINITY, one_time_sort=true, exposed_data_format=ExposedDataFormat::ClassSupports, specialization=ExposedSpecialization::Murtree, lower_bound=ExposedLowerBoundStrategy::Similarity, branching_type=ExposedBranchingStrategy::Dynamic, heuristic=ExposedSearchHeuristic::None_, cache_init_strategy=ExposedCacheInitStrategy::None_, error_function=None,))]
+#[pyo3(signature = (input, target=None, min_sup=1, max_depth=2, time=600, cache_init_size=0, error=<f64>::INFINITY, sort_policy=None, exposed_data_format=ExposedDataFormat::ClassSupports, specialization=ExposedSpecialization::Murtree, lower_bound=ExposedLowerBoundStrategy::Similarity, branching_type=ExposedBranchingStrategy::Dynamic, heuristic=None, cache_init_strategy=ExposedCacheInitStrategy::None_, error_function=None, warm_start=None, explain_leaves=false, candidates_filter=None, callback=None, callback_interval=1000, task="classification", cache_type=ExposedCacheType::Trie, max_cache_size=0, max_leaves=0, beam_width=0, feature_costs=None, max_cost=<f64>::INFINITY, include_features=None, exclude_features=None, root_feature=None, monotonic_cst=None, missing_values=ExposedMissingValues::Zero, discrepancy_budget=None, depth_beam_widths=None, purity_thresholds=None, leaf_rule=None, max_cache_bytes=0, cache_full_policy=ExposedCacheFullPolicy::Evict, seed=0, record_anytime=false, trace=None, verbose=0, objective=ExposedObjective::Error, class_weight=None, constraints=None, error_function_numpy=false, error_function_cache=false, subset=None, cover_keys=false, lower_bound_policy=ExposedLowerBoundPolicy::TwoCovers, min_heuristic_score=0.0, min_relative_score=0.0, tie_break=ExposedTieBreak::Index, auto_depth=false, minimize_leaves=false, template=None, leaf_samples=false, sparse_threshold=0.0, learner=None,))]
 pub(crate) fn optimal_search_dl85(
-    input: PyReadonlyArrayDyn<f64>,
+    input: &PyAny,
     target: Option<PyReadonlyArrayDyn<f64>>,
     min_sup: usize,
     max_depth: usize,
     time: usize,
     cache_init_size: usize,
     error: f64,
-    one_time_sort: bool,
+    sort_policy: Option<&PyAny>,
     exposed_data_format: ExposedDataFormat,
     specialization: ExposedSpecialization,
     lower_bound: ExposedLowerBoundStrategy,
     branching_type: ExposedBranchingStrategy,
-    heuristic: ExposedSearchHeuristic,
+    heuristic: Option<&PyAny>,
     cache_init_strategy: ExposedCacheInitStrategy,
     error_function: Option<PyObject>,
-) -> LearningResult {
-    if target.is_none() {
+    warm_start: Option<&PyAny>,
+    explain_leaves: bool,
+    candidates_filter: Option<PyObject>,
+    callback: Option<PyObject>,
+    callback_interval: usize,
+    task: &str,
+    cache_type: ExposedCacheType,
+    max_cache_size: usize,
+    max_leaves: usize,
+    beam_width: usize,
+    feature_costs: Option<Vec<f64>>,
+    max_cost: f64,
+    include_features: Option<Vec<usize>>,
+    exclude_features: Option<Vec<usize>>,
+    root_feature: Option<usize>,
+    monotonic_cst: Option<Vec<i8>>,
+    missing_values: ExposedMissingValues,
+    discrepancy_budget: Option<usize>,
+    depth_beam_widths: Option<Vec<usize>>,
+    purity_thresholds: Option<Vec<f64>>,
+    leaf_rule: Option<&str>,
+    max_cache_bytes: usize,
+    cache_full_policy: ExposedCacheFullPolicy,
+    seed: u64,
+    record_anytime: bool,
+    trace: Option<&str>,
+    verbose: usize,
+    objective: ExposedObjective,
+    class_weight: Option<&PyAny>,
+    constraints: Option<HashMap<String, f64>>,
+    error_function_numpy: bool,
+    error_function_cache: bool,
+    subset: Option<Vec<usize>>,
+    cover_keys: bool,
+    lower_bound_policy: ExposedLowerBoundPolicy,
+    min_heuristic_score: f64,
+    min_relative_score: f64,
+    tie_break: ExposedTieBreak,
+    auto_depth: bool,
+    minimize_leaves: bool,
+    template: Option<&str>,
+    leaf_samples: bool,
+    sparse_threshold: f64,
+    learner: Option<&PyCell<PyDL85>>,
+) -> PyResult<LearningResult> {
+    // A kept learner goes on with the cache of the whole data, see PyDL85
+    let handle = learner;
+    if handle.is_some() && (task != "classification" || subset.is_some()) {
+        return Err(PyValueError::new_err(
+            "Only classification learners fitted on the whole data can be kept",
+        ));
+    }
+    // Regression targets are continuous, they are given to the error function and not to the data
+    let (target, regression_targets) = match task {
+        "classification" => (target, None),
+        "regression" => match target {
+            Some(target) => (
+                None,
+                Some(target.as_array().iter().copied().collect::<Vec<f64>>()),
+            ),
+            None => return Err(PyValueError::new_err("Regression requires a target")),
+        },
+        _ => {
+            return Err(PyValueError::new_err(
+                "task must be either \"classification\" or \"regression\"",
+            ))
+        }
+    };
+    let exposed_data_format = match regression_targets {
+        Some(_) => ExposedDataFormat::Tids,
+        None => exposed_data_format,
+    };
+
+    // A Cover already holds the data and its classes
+    let mut cover = input.extract::<PyRefMut<PyCover>>().ok();
+    if cover.is_some() && target.is_some() {
+        return Err(PyValueError::new_err(
+            "The classes of a Cover are given when building it",
+        ));
+    }
+    let labelled = target.is_some() || cover.as_ref().is_some_and(|cover| cover.labelled);
+
+    if !labelled {
         if let ExposedDataFormat::ClassSupports = exposed_data_format {
             panic!("When target (y) is not specified cover (with tids) must be used for error computation")
         }
     }
 
-    let data_format = match exposed_data_format {
-        ExposedDataFormat::Tids => NodeExposedData::Tids,
-        ExposedDataFormat::ClassSupports => NodeExposedData::ClassesSupport,
+    // The squared error of a regression reads the target values of the nodes
+    let data_format = match (exposed_data_format, &regression_targets, &error_function) {
+        (_, Some(_), None) => NodeExposedData::Targets,
+        (ExposedDataFormat::Tids, _, _) => NodeExposedData::Tids,
+        (ExposedDataFormat::ClassSupports, _, _) => NodeExposedData::ClassesSupport,
     };
 
     let cache_init_strategy = match cache_init_strategy {
@@ -69,39 +177,222 @@ pub(crate) fn optimal_search_dl85(
         ExposedBranchingStrategy::None_ => BranchingStrategy::None_,
     };
 
-    let heuristic: Box<dyn Heuristic> = match heuristic {
-        ExposedSearchHeuristic::InformationGain => Box::<InformationGain>::default(),
-        ExposedSearchHeuristic::InformationGainRatio => Box::<InformationGainRatio>::default(),
-        ExposedSearchHeuristic::GiniIndex => Box::<GiniIndex>::default(),
-        ExposedSearchHeuristic::None_ => Box::<NoHeuristic>::default(),
+    // Shared by the Python callables, the first error they raise stops the search
+    let raised = CallbackError::default();
+    let heuristic = match heuristic {
+        Some(object) => search_heuristic(object, seed, &raised)?,
+        None => Box::<NoHeuristic>::default(),
+    };
+
+    let sort_policy = match sort_policy {
+        Some(object) => search_sort_policy(object)?,
+        None => SortPolicy::RootOnly,
     };
 
     // Objects initialization start
-    let input = input.as_array().map(|a| *a as usize);
     let target = match target.is_some() {
         true => Some(target.unwrap().as_array().map(|a| *a as usize)),
         false => None,
     };
-    let dataset = BinaryData::read_from_numpy(&input, target.as_ref());
-    let mut structure = RevBitset::new(&dataset);
+    let mut read_structure;
+    let (feature_names, missing_columns, structure) = match cover.as_deref_mut() {
+        Some(cover) => {
+            cover.structure.unmask();
+            (
+                cover.feature_names.clone(),
+                cover.missing_columns.clone(),
+                &mut cover.structure,
+            )
+        }
+        None => {
+            let input = read_dataset(input, target.as_ref(), missing_values.into())?;
+            read_structure = input.dataset.structure();
+            (
+                input.feature_names,
+                input.missing_columns,
+                &mut read_structure,
+            )
+        }
+    };
+    // The structure is unmasked on every return, the cover staying usable after an error
+    let mut structure = UnmaskOnDrop(structure);
+    if let Some(subset) = subset {
+        structure.mask(&subset).map_err(PyValueError::new_err)?;
+    }
+    structure
+        .set_sparse_threshold(sparse_threshold)
+        .map_err(PyValueError::new_err)?;
+    let monotonic_features = monotonic_features(
+        monotonic_cst,
+        structure.num_attributes(),
+        structure.num_labels(),
+        &missing_columns,
+    )?;
+    let objective = Objective::from(objective);
+    let balanced = matches!(objective, Objective::BalancedAccuracy);
+    if !monotonic_features.is_empty() && (error_function.is_some() || balanced) {
+        return Err(PyValueError::new_err(
+            "Monotonicity constraints require the classification error",
+        ));
+    }
+    if balanced && (error_function.is_some() || !labelled || task != "classification") {
+        return Err(PyValueError::new_err(
+            "The balanced accuracy objective requires a classification target and no error function",
+        ));
+    }
+    if class_weight.is_some()
+        && (balanced
+            || !monotonic_features.is_empty()
+            || error_function.is_some()
+            || !labelled
+            || task != "classification")
+    {
+        return Err(PyValueError::new_err(
+            "class_weight requires a classification target, no error function, no monotonicity constraints and the Error objective",
+        ));
+    }
+
+    let rate_constraints = rate_constraints(constraints)?;
+    if !rate_constraints.is_empty()
+        && (structure.num_labels() != 2
+            || !monotonic_features.is_empty()
+            || error_function.is_some()
+            || task != "classification")
+    {
+        return Err(PyValueError::new_err(
+            "Rate constraints require binary classes, no error function and no monotonicity constraints",
+        ));
+    }
 
     let external_error: Box<dyn ErrorWrapper> = match error_function {
         Some(function) => {
             specialization = Specialization::None_;
-            Box::new(PythonError::new(function))
+            Box::new(Python::with_gil(|py| {
+                PythonError::with_options(
+                    py,
+                    function,
+                    error_function_numpy,
+                    error_function_cache,
+                    raised.clone(),
+                )
+            })?)
         }
-        None => Box::<NativeError>::default(),
+        None => match (regression_targets, objective) {
+            (Some(targets), _) => {
+                specialization = Specialization::None_;
+                Box::new(SquaredError::new(targets))
+            }
+            (None, Objective::BalancedAccuracy) => {
+                Box::new(NativeError::balanced(structure.labels_support()))
+            }
+            (None, Objective::Error) => match class_weight {
+                Some(class_weight) => Box::new(native_class_weights(class_weight, &mut structure)?),
+                None => Box::<NativeError>::default(),
+            },
+        },
     };
 
-    // TODO : Allow multiple caching strategy
-    let cache = Box::<Trie>::default();
+    // The missing value attributes cost as much as their column
+    let cost_filter = match feature_costs {
+        Some(mut costs) => {
+            if costs.len() + missing_columns.len() != structure.num_attributes() {
+                return Err(PyValueError::new_err(
+                    "feature_costs must have one cost per feature",
+                ));
+            }
+            let missing_costs = missing_columns
+                .iter()
+                .map(|column| costs[*column])
+                .collect::<Vec<f64>>();
+            costs.extend(missing_costs);
+            Some(FeatureCostFilter::new(costs, max_cost))
+        }
+        None => None,
+    };
+
+    // The missing value attributes follow their column
+    let with_missing_attributes = |features: Vec<usize>| {
+        let num_features = structure.num_attributes() - missing_columns.len();
+        let mut attributes = features.clone();
+        for (index, column) in missing_columns.iter().enumerate() {
+            if features.contains(column) {
+                attributes.push(num_features + index);
+            }
+        }
+        attributes
+    };
+    let set_filter = match (&include_features, &exclude_features, root_feature) {
+        (None, None, None) => None,
+        _ => Some(FeatureSetFilter::new(
+            include_features.map(with_missing_attributes),
+            exclude_features.map_or(vec![], with_missing_attributes),
+            root_feature,
+        )),
+    };
+
+    // The template fixes the top splits, the search completes the tree below its open nodes
+    let template_filter = match template {
+        Some(template) => {
+            let template = serde_json::from_str::<Tree>(template)
+                .map_err(|e| PyValueError::new_err(format!("Invalid template tree: {}", e)))?;
+            let filter = TemplateFilter::new(&template);
+            let num_features = structure.num_attributes() - missing_columns.len();
+            if let Some(feature) = filter.features().find(|feature| *feature >= num_features) {
+                return Err(PyValueError::new_err(format!(
+                    "The template tests the unknown feature {}",
+                    feature
+                )));
+            }
+            Some(filter)
+        }
+        None => None,
+    };
+
+    // The depth 2 specialization does not know about the filters
+    if candidates_filter.is_some()
+        || cost_filter.is_some()
+        || set_filter.is_some()
+        || template_filter.is_some()
+    {
+        specialization = Specialization::None_;
+    }
+
+    // The warm start is either a tree, as JSON, or True to start from the greedy LGDT tree
+    let mut greedy_warm_start = false;
+    let warm_start = match warm_start {
+        Some(object) if object.is_instance_of::<PyBool>() => {
+            greedy_warm_start = object.is_true()?;
+            None
+        }
+        Some(object) => Some(
+            serde_json::from_str::<Tree>(object.extract::<&str>()?)
+                .map_err(|e| PyValueError::new_err(format!("Invalid warm start tree: {}", e)))?,
+        ),
+        None => None,
+    };
+    if auto_depth && (warm_start.is_some() || greedy_warm_start) {
+        return Err(PyValueError::new_err(
+            "auto_depth cannot be combined with a warm start",
+        ));
+    }
+    if let Some(tree) = &warm_start {
+        tree.check_attributes(structure.num_attributes())
+            .map_err(|e| PyValueError::new_err(format!("Invalid warm start tree: {}", e)))?;
+    }
+
+    let cache: Box<dyn Caching> = match cache_type {
+        ExposedCacheType::Trie => Box::<Trie>::default(),
+        ExposedCacheType::CompressedTrie => Box::new(Trie::compressed()),
+        ExposedCacheType::Hashmap => Box::<HashMapCache>::default(),
+        ExposedCacheType::None_ => return Err(PyValueError::new_err("DL85 requires a cache")),
+    };
 
     let mut learner = DL85::new(
         min_sup,
         max_depth,
         error,
         time,
-        one_time_sort,
+        sort_policy,
         cache_init_size,
         cache_init_strategy,
         specialization,
@@ -112,13 +403,234 @@ pub(crate) fn optimal_search_dl85(
         external_error,
         heuristic,
     );
+    learner.constraints.explain_leaves = explain_leaves;
+    learner.constraints.record_anytime = record_anytime;
+    learner.constraints.max_cache_size = max_cache_size;
+    learner.constraints.max_cache_bytes = max_cache_bytes;
+    learner.constraints.cache_full_policy = cache_full_policy.into();
+    learner.constraints.cover_keys = cover_keys;
+    learner.constraints.lower_bound_policy = lower_bound_policy.into();
+    learner.constraints.max_leaves = max_leaves;
+    learner.constraints.beam_width = beam_width;
+    learner.depth_beam_widths = depth_beam_widths.unwrap_or_default();
+    learner.constraints.min_heuristic_score = min_heuristic_score;
+    learner.constraints.min_relative_score = min_relative_score;
+    learner.constraints.tie_break = tie_break.into();
+    learner.constraints.seed = seed;
+    learner.constraints.minimize_leaves = minimize_leaves;
+    learner.stop_conditions.purity_thresholds = purity_thresholds.unwrap_or_default();
+    learner.stop_conditions.leaf_rule = leaf_rule
+        .map(serde_json::from_str::<LeafRule>)
+        .transpose()
+        .map_err(|e| PyValueError::new_err(format!("Invalid leaf rule: {}", e)))?;
+    if let Some(budget) = discrepancy_budget {
+        learner.constraints.search_strategy = SearchStrategy::DiscrepancySearch;
+        learner.constraints.discrepancy_budget = budget;
+    }
+    learner.monotonic_features = monotonic_features;
+    learner.rate_constraints = rate_constraints;
+    let mut filters: Vec<Box<dyn CandidatesFilter>> = vec![];
+    if let Some(filter) = cost_filter {
+        filters.push(Box::new(filter));
+    }
+    if let Some(filter) = set_filter {
+        filters.push(Box::new(filter));
+    }
+    if let Some(filter) = template_filter {
+        filters.push(Box::new(filter));
+    }
+    if let Some(function) = candidates_filter {
+        filters.push(Box::new(PythonFilter::new(function, raised.clone())));
+    }
+    if !filters.is_empty() {
+        learner.candidates_filter = Some(Box::new(filters));
+    }
+    learner.interrupter = Some(Box::new(SignalsInterrupter::new(raised.clone())));
+    learner.progress_observer = callback.map(|function| {
+        Box::new(PythonObserver::new(function, raised.clone())) as Box<dyn ProgressObserver>
+    });
+    learner.progress_interval = callback_interval;
+    if let Some(path) = trace {
+        let tracer = JsonlTracer::create(path).map_err(|e| PyOSError::new_err(e.to_string()))?;
+        learner.tracer = Some(Box::new(tracer));
+    }
 
-    learner.fit(&mut structure);
+    PythonLogger::set_verbosity(verbose);
+    let mut optimal_tree = None;
+    match warm_start {
+        _ if auto_depth => learner
+            .fit_increasing_depths(&mut *structure)
+            .map(|tree| optimal_tree = tree),
+        Some(tree) => learner.fit_with_warm_start(&mut *structure, &tree),
+        None if greedy_warm_start => learner.fit_with_greedy_warm_start(&mut *structure),
+        None => learner.fit(&mut *structure),
+    }
+    .map_err(PyValueError::new_err)?;
+    if leaf_samples {
+        fill_samples(&mut learner.tree, &mut *structure);
+    }
+    drop(structure);
+    PythonLogger::set_verbosity(0);
+    // The search stopped at the first error raised by one of the Python callables
+    if let Some(error) = raised.take() {
+        return Err(error);
+    }
+    if let Some(tracer) = learner.tracer.as_mut() {
+        tracer
+            .finish()
+            .map_err(|e| PyOSError::new_err(e.to_string()))?;
+    }
+    let result = learning_result(&mut learner, optimal_tree, feature_names, missing_columns)?;
+    if let Some(handle) = handle {
+        // Only the first fit is traced, its trace file is complete
+        learner.tracer = None;
+        handle.borrow_mut().learner = Some(KeptLearner {
+            learner,
+            raised,
+            missing_values,
+            leaf_samples,
+            verbose,
+        });
+    }
+    Ok(result)
+}
 
-    LearningResult {
+// Result of a fitted learner, whose tree is compacted since a warm start tree may hold nodes no
+// longer reachable from its root
+fn learning_result(
+    learner: &mut Learner,
+    optimal_tree: Option<Tree>,
+    feature_names: Option<Vec<String>>,
+    missing_columns: Vec<usize>,
+) -> PyResult<LearningResult> {
+    learner.tree.compact().map_err(PyValueError::new_err)?;
+    Ok(LearningResult {
         error: learner.statistics.tree_error,
-        tree: learner.tree,
+        tree: learner.tree.clone(),
+        optimal_tree,
         constraints: learner.statistics.constraints,
-        statistics: learner.statistics,
+        statistics: learner.statistics.clone(),
+        feature_names,
+        missing_columns,
+    })
+}
+
+// Unmasks the wrapped structure when dropped
+struct UnmaskOnDrop<'a>(&'a mut RevBitset);
+
+impl Deref for UnmaskOnDrop<'_> {
+    type Target = RevBitset;
+
+    fn deref(&self) -> &RevBitset {
+        self.0
+    }
+}
+
+impl DerefMut for UnmaskOnDrop<'_> {
+    fn deref_mut(&mut self) -> &mut RevBitset {
+        self.0
+    }
+}
+
+impl Drop for UnmaskOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.unmask();
+    }
+}
+
+// The heuristic is either one of the native heuristics, a Python callable or a list of them, the
+// next ones breaking the ties of the previous ones
+fn search_heuristic(
+    object: &PyAny,
+    seed: u64,
+    raised: &CallbackError,
+) -> PyResult<Box<dyn Heuristic>> {
+    Ok(match object.extract::<ExposedSearchHeuristic>() {
+        Ok(ExposedSearchHeuristic::InformationGain) => Box::<InformationGain>::default(),
+        Ok(ExposedSearchHeuristic::InformationGainRatio) => Box::<InformationGainRatio>::default(),
+        Ok(ExposedSearchHeuristic::GiniIndex) => Box::<GiniIndex>::default(),
+        Ok(ExposedSearchHeuristic::WeightOfEvidence) => Box::<WeightOfEvidence>::default(),
+        Ok(ExposedSearchHeuristic::Random) => Box::new(RandomHeuristic::new(seed)),
+        Ok(ExposedSearchHeuristic::None_) => Box::<NoHeuristic>::default(),
+        Err(_) if object.is_callable() => {
+            Box::new(PythonHeuristic::new(object.into(), raised.clone()))
+        }
+        Err(_) => match object.extract::<Vec<&PyAny>>() {
+            Ok(objects) if !objects.is_empty() => Box::new(CompositeHeuristic::new(
+                objects
+                    .into_iter()
+                    .map(|object| search_heuristic(object, seed, raised))
+                    .collect::<PyResult<Vec<Box<dyn Heuristic>>>>()?,
+            )),
+            _ => {
+                return Err(PyValueError::new_err(
+                    "heuristic must be an ExposedSearchHeuristic, a callable or a list of them",
+                ))
+            }
+        },
+    })
+}
+
+// The sort policy is either an ExposedSortPolicy or the depth up to which the candidates are sorted
+fn search_sort_policy(object: &PyAny) -> PyResult<SortPolicy> {
+    match object.extract::<ExposedSortPolicy>() {
+        Ok(policy) => Ok(policy.into()),
+        Err(_) => match object.extract::<usize>() {
+            Ok(depth) => Ok(SortPolicy::UpToDepth(depth)),
+            Err(_) => Err(PyValueError::new_err(
+                "sort_policy must be an ExposedSortPolicy or a depth",
+            )),
+        },
+    }
+}
+
+// The class weights are either "balanced" or a dict of the weight of each class. As in
+// scikit-learn, the classes missing from the dict weigh 1
+fn native_class_weights(class_weight: &PyAny, structure: &mut RevBitset) -> PyResult<NativeError> {
+    if let Ok(mode) = class_weight.extract::<&str>() {
+        return match mode {
+            "balanced" => Ok(NativeError::balanced(structure.labels_support())),
+            _ => Err(PyValueError::new_err(
+                "class_weight must be either \"balanced\" or a dict",
+            )),
+        };
+    }
+    let weights = class_weight
+        .extract::<HashMap<usize, f64>>()
+        .map_err(|_| PyValueError::new_err("class_weight must be either \"balanced\" or a dict"))?;
+    let mut native = vec![1.0; structure.num_labels()];
+    for (class, weight) in weights {
+        if class >= native.len() || !weight.is_finite() || weight < 0.0 {
+            return Err(PyValueError::new_err(format!(
+                "Invalid weight {} of class {}",
+                weight, class
+            )));
+        }
+        native[class] = weight;
+    }
+    Ok(NativeError::weighted(native))
+}
+
+// The constraints bound the false_negative_rate and false_positive_rate of the tree
+fn rate_constraints(constraints: Option<HashMap<String, f64>>) -> PyResult<RateConstraints> {
+    let mut rate_constraints = RateConstraints::default();
+    for (name, rate) in constraints.unwrap_or_default() {
+        if !(0.0..=1.0).contains(&rate) {
+            return Err(PyValueError::new_err(format!(
+                "The {} must be between 0 and 1",
+                name
+            )));
+        }
+        match name.as_str() {
+            "false_negative_rate" => rate_constraints.max_false_negative_rate = Some(rate),
+            "false_positive_rate" => rate_constraints.max_false_positive_rate = Some(rate),
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Unknown constraint {}, expected false_negative_rate or false_positive_rate",
+                    name
+                )))
+            }
+        }
     }
+    Ok(rate_constraints)
 }
@@ -0,0 +1,15 @@
+use crate::utils::ExposedRestartSchedule;
+use dtrees_rs::searches::schedule::RestartSchedule;
+use pyo3::prelude::*;
+
+// Budget of a restart of an anytime search, the first one being the iteration 0
+#[pyfunction]
+#[pyo3(signature = (schedule, iteration, initial, growth_factor=2.0))]
+pub(crate) fn restart_budget(
+    schedule: ExposedRestartSchedule,
+    iteration: usize,
+    initial: f64,
+    growth_factor: f64,
+) -> f64 {
+    RestartSchedule::from(schedule).budget(iteration, initial, growth_factor)
+}
@@ -0,0 +1,230 @@
+use crate::utils::{ExposedBinarizationStrategy, ExposedCategoricalEncoding};
+use dtrees_rs::data::binarizer::{BinarizationStrategy, Binarizer};
+use dtrees_rs::data::encoder::{CategoricalEncoder, CategoricalEncoding};
+use ndarray::Array2;
+use numpy::{IntoPyArray, PyArray2, PyReadonlyArray1, PyReadonlyArray2};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+#[pyclass(name = "Binarizer", module = "pytreesrs.preprocessing")]
+pub(crate) struct PyBinarizer {
+    binarizer: Binarizer,
+}
+
+fn to_rows(input: &PyReadonlyArray2<f64>) -> Vec<Vec<f64>> {
+    input
+        .as_array()
+        .rows()
+        .into_iter()
+        .map(|row| row.to_vec())
+        .collect()
+}
+
+#[pymethods]
+impl PyBinarizer {
+    #[new]
+    #[pyo3(signature = (strategy=ExposedBinarizationStrategy::Quantile, n_bins=4))]
+    fn new(strategy: ExposedBinarizationStrategy, n_bins: usize) -> Self {
+        let strategy = match strategy {
+            ExposedBinarizationStrategy::Quantile => BinarizationStrategy::Quantile,
+            ExposedBinarizationStrategy::EqualWidth => BinarizationStrategy::EqualWidth,
+            ExposedBinarizationStrategy::Mdlp => BinarizationStrategy::Mdlp,
+        };
+        Self {
+            binarizer: Binarizer::new(strategy, n_bins),
+        }
+    }
+
+    #[pyo3(signature = (input, target=None))]
+    fn fit<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        input: PyReadonlyArray2<f64>,
+        target: Option<PyReadonlyArray1<f64>>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        let target = target.map(|target| {
+            target
+                .as_array()
+                .iter()
+                .map(|a| *a as usize)
+                .collect::<Vec<usize>>()
+        });
+        slf.binarizer
+            .fit(&to_rows(&input), target.as_deref())
+            .map_err(PyValueError::new_err)?;
+        Ok(slf)
+    }
+
+    fn transform<'py>(
+        &self,
+        py: Python<'py>,
+        input: PyReadonlyArray2<f64>,
+    ) -> PyResult<&'py PyArray2<f64>> {
+        let rows = to_rows(&input);
+        if rows.first().map_or(0, |row| row.len()) != self.binarizer.thresholds().len() {
+            return Err(PyValueError::new_err(
+                "The number of columns differs from the one seen during fit",
+            ));
+        }
+        let values = self
+            .binarizer
+            .transform(&rows)
+            .into_iter()
+            .flatten()
+            .map(|value| value as f64)
+            .collect::<Vec<f64>>();
+        let array = Array2::from_shape_vec((rows.len(), self.binarizer.num_features()), values)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(array.into_pyarray(py))
+    }
+
+    #[pyo3(signature = (input, target=None))]
+    fn fit_transform<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        py: Python<'py>,
+        input: PyReadonlyArray2<f64>,
+        target: Option<PyReadonlyArray1<f64>>,
+    ) -> PyResult<&'py PyArray2<f64>> {
+        let rows = to_rows(&input);
+        let target = target.map(|target| {
+            target
+                .as_array()
+                .iter()
+                .map(|a| *a as usize)
+                .collect::<Vec<usize>>()
+        });
+        slf.binarizer
+            .fit(&rows, target.as_deref())
+            .map_err(PyValueError::new_err)?;
+        slf.transform(py, input)
+    }
+
+    #[getter]
+    fn thresholds(&self) -> Vec<Vec<f64>> {
+        self.binarizer.thresholds().to_vec()
+    }
+
+    // Column and threshold of each binary feature
+    #[getter]
+    fn feature_mapping(&self) -> Vec<(usize, f64)> {
+        self.binarizer.feature_mapping()
+    }
+
+    // Readable names of the binary features, such as "age > 42.5", to print the trees
+    #[pyo3(signature = (columns=None))]
+    fn feature_names(&self, columns: Option<Vec<String>>) -> PyResult<Vec<String>> {
+        self.binarizer
+            .feature_mapping()
+            .into_iter()
+            .map(|(column, threshold)| {
+                let name = match &columns {
+                    Some(columns) => columns.get(column).cloned().ok_or_else(|| {
+                        PyValueError::new_err("Not enough column names were given")
+                    })?,
+                    None => format!("x{}", column),
+                };
+                Ok(format!("{} > {}", name, threshold))
+            })
+            .collect()
+    }
+}
+
+#[pyclass(name = "CategoricalEncoder", module = "pytreesrs.preprocessing")]
+pub(crate) struct PyCategoricalEncoder {
+    encoder: CategoricalEncoder,
+    // Column names of the DataFrame seen during fit
+    columns: Option<Vec<String>>,
+}
+
+// Rows of the input as strings and, for DataFrames, the column names
+type StringRows = (Vec<Vec<String>>, Option<Vec<String>>);
+
+fn to_string_rows(input: &PyAny) -> PyResult<StringRows> {
+    let (input, columns) = match input.hasattr("columns")? && input.hasattr("to_numpy")? {
+        true => {
+            let columns = input
+                .getattr("columns")?
+                .iter()?
+                .map(|name| name.and_then(|name| name.str()?.extract::<String>()))
+                .collect::<PyResult<Vec<String>>>()?;
+            (input.call_method0("to_numpy")?, Some(columns))
+        }
+        false => (input, None),
+    };
+    let rows = input
+        .iter()?
+        .map(|row| {
+            row?.iter()?
+                .map(|value| value.and_then(|value| value.str()?.extract::<String>()))
+                .collect::<PyResult<Vec<String>>>()
+        })
+        .collect::<PyResult<Vec<Vec<String>>>>()?;
+    Ok((rows, columns))
+}
+
+#[pymethods]
+impl PyCategoricalEncoder {
+    #[new]
+    #[pyo3(signature = (encoding=ExposedCategoricalEncoding::OneHot))]
+    fn new(encoding: ExposedCategoricalEncoding) -> Self {
+        let encoding = match encoding {
+            ExposedCategoricalEncoding::OneHot => CategoricalEncoding::OneHot,
+            ExposedCategoricalEncoding::Ordinal => CategoricalEncoding::Ordinal,
+        };
+        Self {
+            encoder: CategoricalEncoder::new(encoding),
+            columns: None,
+        }
+    }
+
+    fn fit<'py>(mut slf: PyRefMut<'py, Self>, input: &PyAny) -> PyResult<PyRefMut<'py, Self>> {
+        let (rows, columns) = to_string_rows(input)?;
+        slf.encoder.fit(&rows);
+        slf.columns = columns;
+        Ok(slf)
+    }
+
+    fn transform<'py>(&self, py: Python<'py>, input: &PyAny) -> PyResult<&'py PyArray2<f64>> {
+        let (rows, _) = to_string_rows(input)?;
+        let values = self
+            .encoder
+            .transform(&rows)
+            .map_err(PyValueError::new_err)?
+            .into_iter()
+            .flatten()
+            .map(|value| value as f64)
+            .collect::<Vec<f64>>();
+        let array = Array2::from_shape_vec((rows.len(), self.encoder.num_features()), values)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(array.into_pyarray(py))
+    }
+
+    fn fit_transform<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        py: Python<'py>,
+        input: &PyAny,
+    ) -> PyResult<&'py PyArray2<f64>> {
+        let (rows, columns) = to_string_rows(input)?;
+        slf.encoder.fit(&rows);
+        slf.columns = columns;
+        slf.transform(py, input)
+    }
+
+    #[getter]
+    fn categories(&self) -> Vec<Vec<String>> {
+        self.encoder.categories().to_vec()
+    }
+
+    // Column and category of each binary feature
+    #[getter]
+    fn feature_mapping(&self) -> Vec<(usize, String)> {
+        self.encoder.feature_mapping()
+    }
+
+    // Readable names of the binary features, such as `color == "red"`, to print the trees. The
+    // column names of a fitted DataFrame are used by default
+    #[pyo3(signature = (columns=None))]
+    fn feature_names(&self, columns: Option<Vec<String>>) -> Vec<String> {
+        let columns = columns.or_else(|| self.columns.clone());
+        self.encoder.feature_names(columns.as_deref())
+    }
+}
@@ -1,54 +1,82 @@
+use dtrees_rs::data::binary_data::{encode_missing_values, MissingValuesPolicy};
+use dtrees_rs::data::libsvm::SparseData;
+use dtrees_rs::data::{BinaryData, FileReader};
+use dtrees_rs::globals::{deserialize_infinity, get_tree_root_error, item};
+use dtrees_rs::heuristics::Heuristic;
 use dtrees_rs::searches::errors::ErrorWrapper;
-use dtrees_rs::searches::{Constraints, Statistics};
-use dtrees_rs::tree::Tree;
-use pyo3::{pyclass, pymethods, PyObject, PyResult, Python};
+use dtrees_rs::searches::filters::CandidatesFilter;
+use dtrees_rs::searches::interrupt::SearchInterrupter;
+use dtrees_rs::searches::monotonic::{MonotonicFeatures, Monotonicity};
+use dtrees_rs::searches::progress::{ProgressObserver, SearchProgress};
+use dtrees_rs::searches::schedule::RestartSchedule;
+use dtrees_rs::searches::{
+    CacheFullPolicy, Constraints, LowerBoundPolicy, Objective, SortPolicy, Statistics, StopReason,
+    TieBreak,
+};
+use dtrees_rs::structures::{RevBitset, Structure};
+use dtrees_rs::tree::{MissingBranch, Tree};
+use ndarray::{Array, IxDyn};
+use numpy::{PyArray1, PyReadonlyArray1, PyReadonlyArrayDyn};
+use pyo3::exceptions::PyValueError;
+use pyo3::types::{PyCFunction, PyDict, PyFunction, PyList, PySlice};
+use pyo3::{
+    pyclass, pymethods, IntoPy, Py, PyAny, PyCell, PyErr, PyObject, PyResult, Python, ToPyObject,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
 
-#[pyclass]
+#[pyclass(module = "pytreesrs.enums")]
 #[derive(Copy, Clone)]
 pub(crate) enum ExposedSearchHeuristic {
     InformationGain,
     InformationGainRatio,
     GiniIndex,
+    WeightOfEvidence,
+    Random,
     None_,
 }
 
-#[pyclass]
+#[pyclass(module = "pytreesrs.enums")]
 #[derive(Copy, Clone)]
 pub(crate) enum ExposedDataFormat {
     ClassSupports,
     Tids,
 }
 
-#[pyclass]
+#[pyclass(module = "pytreesrs.enums")]
 #[derive(Copy, Clone)]
 pub(crate) enum ExposedCacheType {
     Trie,
+    CompressedTrie,
     Hashmap,
     None_,
 }
 
-#[pyclass]
+#[pyclass(module = "pytreesrs.enums")]
 #[derive(Copy, Clone)]
 pub(crate) enum ExposedSpecialization {
     Murtree,
     None_,
 }
 
-#[pyclass]
+#[pyclass(module = "pytreesrs.enums")]
 #[derive(Copy, Clone)]
 pub enum ExposedLowerBoundStrategy {
     Similarity,
     None_,
 }
 
-#[pyclass]
+#[pyclass(module = "pytreesrs.enums")]
 #[derive(Copy, Clone)]
 pub enum ExposedBranchingStrategy {
     Dynamic,
     None_,
 }
 
-#[pyclass]
+#[pyclass(module = "pytreesrs.enums")]
 #[derive(Copy, Clone)]
 pub enum ExposedCacheInitStrategy {
     DynamicAllocation,
@@ -56,7 +84,7 @@ pub enum ExposedCacheInitStrategy {
     None_,
 }
 
-#[pyclass]
+#[pyclass(module = "pytreesrs.enums")]
 #[derive(Copy, Clone)]
 pub enum ExposedSearchStrategy {
     DiscrepancySearch,
@@ -65,43 +93,812 @@ pub enum ExposedSearchStrategy {
     None_,
 }
 
+#[pyclass(module = "pytreesrs.enums")]
+#[derive(Copy, Clone)]
+pub enum ExposedBinarizationStrategy {
+    Quantile,
+    EqualWidth,
+    Mdlp,
+}
+
+#[pyclass(module = "pytreesrs.enums")]
+#[derive(Copy, Clone)]
+pub enum ExposedCategoricalEncoding {
+    OneHot,
+    Ordinal,
+}
+
+#[pyclass(module = "pytreesrs.enums")]
+#[derive(Copy, Clone)]
+pub enum ExposedMissingValues {
+    Zero,
+    SeparateAttribute,
+}
+
+#[pyclass(module = "pytreesrs.enums")]
+#[derive(Copy, Clone)]
+pub enum ExposedMissingBranch {
+    Left,
+    Right,
+    Both,
+}
+
+impl From<ExposedMissingBranch> for MissingBranch {
+    fn from(branch: ExposedMissingBranch) -> Self {
+        match branch {
+            ExposedMissingBranch::Left => MissingBranch::Left,
+            ExposedMissingBranch::Right => MissingBranch::Right,
+            ExposedMissingBranch::Both => MissingBranch::Both,
+        }
+    }
+}
+
+#[pyclass(module = "pytreesrs.enums")]
+#[derive(Copy, Clone)]
+pub enum ExposedCacheFullPolicy {
+    Evict,
+    Stop,
+}
+
+impl From<ExposedCacheFullPolicy> for CacheFullPolicy {
+    fn from(policy: ExposedCacheFullPolicy) -> Self {
+        match policy {
+            ExposedCacheFullPolicy::Evict => CacheFullPolicy::Evict,
+            ExposedCacheFullPolicy::Stop => CacheFullPolicy::Stop,
+        }
+    }
+}
+
+#[pyclass(module = "pytreesrs.enums")]
+#[derive(Copy, Clone)]
+pub enum ExposedLowerBoundPolicy {
+    LastCover,
+    TwoCovers,
+}
+
+impl From<ExposedLowerBoundPolicy> for LowerBoundPolicy {
+    fn from(policy: ExposedLowerBoundPolicy) -> Self {
+        match policy {
+            ExposedLowerBoundPolicy::LastCover => LowerBoundPolicy::LastCover,
+            ExposedLowerBoundPolicy::TwoCovers => LowerBoundPolicy::TwoCovers,
+        }
+    }
+}
+
+#[pyclass(module = "pytreesrs.enums")]
+#[derive(Copy, Clone)]
+pub enum ExposedTieBreak {
+    Index,
+    Random,
+}
+
+impl From<ExposedTieBreak> for TieBreak {
+    fn from(tie_break: ExposedTieBreak) -> Self {
+        match tie_break {
+            ExposedTieBreak::Index => TieBreak::Index,
+            ExposedTieBreak::Random => TieBreak::Random,
+        }
+    }
+}
+
+#[pyclass(module = "pytreesrs.enums")]
+#[derive(Copy, Clone)]
+pub enum ExposedSortPolicy {
+    RootOnly,
+    Always,
+}
+
+impl From<ExposedSortPolicy> for SortPolicy {
+    fn from(sort_policy: ExposedSortPolicy) -> Self {
+        match sort_policy {
+            ExposedSortPolicy::RootOnly => SortPolicy::RootOnly,
+            ExposedSortPolicy::Always => SortPolicy::Always,
+        }
+    }
+}
+
+#[pyclass(module = "pytreesrs.enums")]
+#[derive(Copy, Clone)]
+pub enum ExposedRestartSchedule {
+    Luby,
+    Exponential,
+    Geometric,
+    Linear,
+}
+
+impl From<ExposedRestartSchedule> for RestartSchedule {
+    fn from(schedule: ExposedRestartSchedule) -> Self {
+        match schedule {
+            ExposedRestartSchedule::Luby => RestartSchedule::Luby,
+            ExposedRestartSchedule::Exponential => RestartSchedule::Exponential,
+            ExposedRestartSchedule::Geometric => RestartSchedule::Geometric,
+            ExposedRestartSchedule::Linear => RestartSchedule::Linear,
+        }
+    }
+}
+
+#[pyclass(module = "pytreesrs.enums")]
+#[derive(Copy, Clone)]
+pub enum ExposedObjective {
+    Error,
+    BalancedAccuracy,
+}
+
+impl From<ExposedObjective> for Objective {
+    fn from(objective: ExposedObjective) -> Self {
+        match objective {
+            ExposedObjective::Error => Objective::Error,
+            ExposedObjective::BalancedAccuracy => Objective::BalancedAccuracy,
+        }
+    }
+}
+
+#[pyclass(module = "pytreesrs.enums")]
+#[derive(Copy, Clone)]
+pub enum ExposedStopReason {
+    Done,
+    TimeLimitReached,
+    LowerBoundConstrained,
+    MaxDepthReached,
+    NotEnoughSupport,
+    PureNode,
+    PurityThresholdReached,
+    CacheLimitReached,
+    LeafRuleHolds,
+    NoCandidates,
+    Interrupted,
+    FromSpecializedAlgorithm,
+    None_,
+}
+
+impl From<StopReason> for ExposedStopReason {
+    fn from(reason: StopReason) -> Self {
+        match reason {
+            StopReason::Done => ExposedStopReason::Done,
+            StopReason::TimeLimitReached => ExposedStopReason::TimeLimitReached,
+            StopReason::LowerBoundConstrained => ExposedStopReason::LowerBoundConstrained,
+            StopReason::MaxDepthReached => ExposedStopReason::MaxDepthReached,
+            StopReason::NotEnoughSupport => ExposedStopReason::NotEnoughSupport,
+            StopReason::PureNode => ExposedStopReason::PureNode,
+            StopReason::PurityThresholdReached => ExposedStopReason::PurityThresholdReached,
+            StopReason::CacheLimitReached => ExposedStopReason::CacheLimitReached,
+            StopReason::LeafRuleHolds => ExposedStopReason::LeafRuleHolds,
+            StopReason::NoCandidates => ExposedStopReason::NoCandidates,
+            StopReason::Interrupted => ExposedStopReason::Interrupted,
+            StopReason::FromSpecializedAlgorithm => ExposedStopReason::FromSpecializedAlgorithm,
+            StopReason::None => ExposedStopReason::None_,
+        }
+    }
+}
+
+impl From<ExposedMissingValues> for MissingValuesPolicy {
+    fn from(policy: ExposedMissingValues) -> Self {
+        match policy {
+            ExposedMissingValues::Zero => MissingValuesPolicy::Zero,
+            ExposedMissingValues::SeparateAttribute => MissingValuesPolicy::SeparateAttribute,
+        }
+    }
+}
+
+// Enums are pickled by name, as `getattr(enum_class, variant)`, since they have no constructor
+macro_rules! picklable_enums {
+    ($($name:ident),*) => {
+        $(
+            #[pymethods]
+            impl $name {
+                fn __reduce__(slf: &PyCell<Self>) -> PyResult<(PyObject, (PyObject, String))> {
+                    let py = slf.py();
+                    let repr = slf.repr()?.to_string();
+                    let variant = repr.rsplit('.').next().unwrap_or_default().to_string();
+                    let getattr = py.import("builtins")?.getattr("getattr")?.into_py(py);
+                    Ok((getattr, (slf.get_type().into_py(py), variant)))
+                }
+            }
+        )*
+    };
+}
+
+picklable_enums!(
+    ExposedSearchHeuristic,
+    ExposedDataFormat,
+    ExposedCacheType,
+    ExposedSpecialization,
+    ExposedLowerBoundStrategy,
+    ExposedBranchingStrategy,
+    ExposedCacheInitStrategy,
+    ExposedSearchStrategy,
+    ExposedBinarizationStrategy,
+    ExposedCategoricalEncoding,
+    ExposedMissingValues,
+    ExposedMissingBranch,
+    ExposedRestartSchedule,
+    ExposedCacheFullPolicy,
+    ExposedLowerBoundPolicy,
+    ExposedTieBreak,
+    ExposedSortPolicy,
+    ExposedStopReason,
+    ExposedObjective
+);
+
+// Samples of an input, kept sparse for the scipy sparse matrices
+pub(crate) enum Dataset {
+    Dense(BinaryData),
+    Sparse(SparseData),
+}
+
+impl Dataset {
+    pub(crate) fn structure(&self) -> RevBitset {
+        match self {
+            Dataset::Dense(data) => RevBitset::new(data),
+            Dataset::Sparse(data) => RevBitset::from_sparse(data),
+        }
+    }
+
+    pub(crate) fn targets(&self) -> Option<&[usize]> {
+        match self {
+            Dataset::Dense(data) => data.get_train().0.as_deref(),
+            Dataset::Sparse(data) => {
+                Some(data.targets.as_slice()).filter(|targets| !targets.is_empty())
+            }
+        }
+    }
+
+    // Feature values of a sample, as given to the trees
+    pub(crate) fn instance(&self, tid: usize) -> Vec<f64> {
+        match self {
+            Dataset::Dense(data) => data.get_train().1[tid]
+                .iter()
+                .map(|value| *value as f64)
+                .collect(),
+            Dataset::Sparse(data) => {
+                let mut instance = vec![0.0; data.num_attributes];
+                for attribute in &data.rows[tid] {
+                    instance[*attribute] = 1.0;
+                }
+                instance
+            }
+        }
+    }
+}
+
+pub(crate) struct InputData {
+    pub(crate) dataset: Dataset,
+    // Column names of a DataFrame input
+    pub(crate) feature_names: Option<Vec<String>>,
+    // Columns having an extra missing value attribute
+    pub(crate) missing_columns: Vec<usize>,
+}
+
+// Constraints given as in scikit-learn, one value per feature: 1 when the positive class must
+// increase with the feature, -1 when it must decrease and 0 otherwise
+pub(crate) fn monotonic_features(
+    monotonic_cst: Option<Vec<i8>>,
+    num_attributes: usize,
+    num_labels: usize,
+    missing_columns: &[usize],
+) -> PyResult<MonotonicFeatures> {
+    let monotonic_cst = match monotonic_cst {
+        Some(monotonic_cst) => monotonic_cst,
+        None => return Ok(MonotonicFeatures::new()),
+    };
+    if monotonic_cst.len() + missing_columns.len() != num_attributes {
+        return Err(PyValueError::new_err(
+            "monotonic_cst must have one value per feature",
+        ));
+    }
+    if num_labels != 2 && monotonic_cst.iter().any(|cst| *cst != 0) {
+        return Err(PyValueError::new_err(
+            "Monotonicity constraints require binary classes",
+        ));
+    }
+    monotonic_cst
+        .iter()
+        .enumerate()
+        .filter(|(_, cst)| **cst != 0)
+        .map(|(feature, cst)| match cst {
+            1 => Ok((feature, Monotonicity::Increasing)),
+            -1 => Ok((feature, Monotonicity::Decreasing)),
+            _ => Err(PyValueError::new_err(
+                "monotonic_cst values must be -1, 0 or 1",
+            )),
+        })
+        .collect()
+}
+
+// Builds the dataset from a dense numpy array, a scipy CSR/CSC matrix or a DataFrame. Missing
+// values (NaN) of dense inputs are read according to the given policy.
+pub(crate) fn read_dataset(
+    input: &PyAny,
+    target: Option<&Array<usize, IxDyn>>,
+    missing_values: MissingValuesPolicy,
+) -> PyResult<InputData> {
+    if input.hasattr("columns")? && input.hasattr("to_numpy")? {
+        let feature_names = input
+            .getattr("columns")?
+            .iter()?
+            .map(|name| name.and_then(|name| name.str()?.extract::<String>()))
+            .collect::<PyResult<Vec<String>>>()?;
+        let values = input.call_method1("to_numpy", ("float64",))?;
+        let data = read_dataset(values, target, missing_values)?;
+        return Ok(InputData {
+            feature_names: Some(feature_names),
+            ..data
+        });
+    }
+
+    if !input.hasattr("indptr")? {
+        let input = input.extract::<PyReadonlyArrayDyn<f64>>()?;
+        let invalid = input
+            .as_array()
+            .indexed_iter()
+            .find(|(_, a)| !a.is_nan() && **a != 0.0 && **a != 1.0)
+            .map(|(position, a)| (position[0], position[1], *a));
+        if let Some((row, column, value)) = invalid {
+            return Err(PyValueError::new_err(format!(
+                "Input value {} at row {}, column {} is not binary",
+                value, row, column
+            )));
+        }
+        let (input, missing_columns) = encode_missing_values(&input.as_array(), missing_values);
+        return Ok(InputData {
+            dataset: Dataset::Dense(BinaryData::read_from_numpy(&input, target)),
+            feature_names: None,
+            missing_columns,
+        });
+    }
+
+    let by_rows = match input.getattr("format")?.extract::<&str>()? {
+        "csr" => true,
+        "csc" => false,
+        _ => {
+            return Err(PyValueError::new_err(
+                "Sparse input must be a CSR or CSC matrix",
+            ))
+        }
+    };
+    let sparse_array = |name: &str| -> PyResult<Vec<usize>> {
+        let array = input
+            .getattr(name)?
+            .call_method1("astype", ("int64",))?
+            .extract::<PyReadonlyArray1<i64>>()?;
+        Ok(array.as_array().iter().map(|a| *a as usize).collect())
+    };
+    let indptr = sparse_array("indptr")?;
+    let indices = sparse_array("indices")?;
+    let values = sparse_array("data")?;
+    let shape = input.getattr("shape")?.extract::<(usize, usize)>()?;
+    let target = target.map(|target| target.iter().copied().collect::<Vec<usize>>());
+
+    let dataset = SparseData::from_compressed(
+        &indptr,
+        &indices,
+        &values,
+        shape,
+        by_rows,
+        target.as_deref(),
+    )
+    .map_err(|e| PyValueError::new_err(format!("Invalid sparse input: {}", e)))?;
+    Ok(InputData {
+        dataset: Dataset::Sparse(dataset),
+        feature_names: None,
+        missing_columns: vec![],
+    })
+}
+
+// Error and leaf value of each node data
+type ErrorsCache = RefCell<HashMap<Vec<usize>, (f64, f64)>>;
+
 pub struct PythonError {
     function: PyObject,
+    // Gives the node data as a NumPy array instead of a list
+    numpy: bool,
+    // Array reused by the NumPy calls, the callable gets a view of its first values
+    buffer: RefCell<Option<Py<PyArray1<usize>>>>,
+    // Errors already computed for each node data
+    cache: Option<ErrorsCache>,
+    raised: CallbackError,
 }
 
 impl PythonError {
-    pub fn new(function: PyObject) -> PythonError {
-        PythonError { function }
+    // Callable objects are called through their bound __call__ method, looked up once instead of
+    // at each node
+    pub fn with_options(
+        py: Python,
+        function: PyObject,
+        numpy: bool,
+        cache: bool,
+        raised: CallbackError,
+    ) -> PyResult<PythonError> {
+        let callable = function.as_ref(py);
+        let function = match callable.is_instance_of::<PyFunction>()
+            || callable.is_instance_of::<PyCFunction>()
+        {
+            true => function,
+            false => callable.getattr("__call__")?.into(),
+        };
+        Ok(PythonError {
+            function,
+            numpy,
+            buffer: RefCell::default(),
+            cache: cache.then(RefCell::default),
+            raised,
+        })
+    }
+
+    // Once a call failed, the next nodes get an infinite error until the search stops
+    fn call(&self, data: &[usize]) -> Option<(f64, f64)> {
+        if self.raised.is_set() {
+            return None;
+        }
+        Python::with_gil(|py| {
+            let result = match self.numpy {
+                true => self.function.call1(py, (self.numpy_view(py, data),)),
+                false => self.function.call1(py, (data.to_vec(),)),
+            };
+            self.raised
+                .catch(result.and_then(|result| result.extract(py)))
+        })
+    }
+}
+
+impl PythonError {
+    // Copies the data in the buffer, grown when too small, and returns a view of it. The view is
+    // only valid during the call, the next node overwrites it
+    fn numpy_view<'py>(&self, py: Python<'py>, data: &[usize]) -> &'py PyAny {
+        let mut buffer = self.buffer.borrow_mut();
+        let array = match buffer
+            .as_ref()
+            .map(|array| array.clone_ref(py).into_ref(py))
+        {
+            Some(array) if array.len() >= data.len() => array,
+            _ => {
+                let array = PyArray1::<usize>::zeros(py, data.len(), false);
+                *buffer = Some(array.into());
+                array
+            }
+        };
+        array.readwrite().as_slice_mut().unwrap()[..data.len()].copy_from_slice(data);
+        array
+            .get_item(PySlice::new(py, 0, data.len() as isize, 1))
+            .unwrap()
     }
 }
 
 impl ErrorWrapper for PythonError {
     fn compute(&self, data: &[usize]) -> (f64, f64) {
-        let mut error = (0., 0.);
-        let send_data = data.to_vec();
+        let failed = (f64::INFINITY, 0.0);
+        let cache = match &self.cache {
+            Some(cache) => cache,
+            None => return self.call(data).unwrap_or(failed),
+        };
+        if let Some(error) = cache.borrow().get(data) {
+            return *error;
+        }
+        match self.call(data) {
+            Some(error) => {
+                cache.borrow_mut().insert(data.to_vec(), error);
+                error
+            }
+            None => failed,
+        }
+    }
+}
+
+// First error raised by a Python callable during a search. The callables are not called anymore
+// once it is set, the signals interrupter stops the search and the binding raises the error
+#[derive(Clone, Default)]
+pub struct CallbackError(Arc<Mutex<Option<PyErr>>>);
+
+impl CallbackError {
+    pub fn take(&self) -> Option<PyErr> {
+        self.0.lock().unwrap().take()
+    }
+
+    fn is_set(&self) -> bool {
+        self.0.lock().unwrap().is_some()
+    }
+
+    // Keeps the error of a failed call, the first one only
+    fn catch<T>(&self, result: PyResult<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.0.lock().unwrap().get_or_insert(error);
+                None
+            }
+        }
+    }
+}
+
+pub struct PythonHeuristic {
+    function: PyObject,
+    raised: CallbackError,
+}
+
+impl PythonHeuristic {
+    pub fn new(function: PyObject, raised: CallbackError) -> PythonHeuristic {
+        PythonHeuristic { function, raised }
+    }
+}
+
+impl Heuristic for PythonHeuristic {
+    // The callable scores each candidate from the class distributions of the node and of its two
+    // children. Candidates with the highest scores are explored first. After a failed call the
+    // candidates keep their order
+    fn compute(&self, structure: &mut dyn Structure, candidates: &mut Vec<usize>) {
+        let parent = structure.labels_support().to_vec();
+        let mut scores = Vec::with_capacity(candidates.len());
+        for attribute in candidates.iter() {
+            let _ = structure.push(item(*attribute, 0));
+            let left = structure.labels_support().to_vec();
+            structure.backtrack();
+            let right = parent
+                .iter()
+                .zip(left.iter())
+                .map(|(parent, left)| parent - left)
+                .collect::<Vec<usize>>();
+
+            if self.raised.is_set() {
+                return;
+            }
+            let score = Python::with_gil(|py| {
+                self.raised.catch(
+                    self.function
+                        .call1(py, (parent.clone(), left, right))
+                        .and_then(|score| score.extract::<f64>(py)),
+                )
+            });
+            let score = match score {
+                Some(score) => score,
+                None => return,
+            };
+            // A NaN score ranks the candidate last
+            let score = match score.is_nan() {
+                true => f64::NEG_INFINITY,
+                false => score,
+            };
+            scores.push((*attribute, score));
+        }
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+        *candidates = scores.iter().map(|(a, _)| *a).collect::<Vec<usize>>();
+    }
+}
+
+pub struct PythonFilter {
+    function: PyObject,
+    raised: CallbackError,
+}
+
+impl PythonFilter {
+    pub fn new(function: PyObject, raised: CallbackError) -> PythonFilter {
+        PythonFilter { function, raised }
+    }
+}
+
+impl CandidatesFilter for PythonFilter {
+    // After a failed call the candidates are left as they are
+    fn filter(&self, path: &[usize], depth: usize, support: usize, candidates: &mut Vec<usize>) {
+        if self.raised.is_set() {
+            return;
+        }
+        let filtered = Python::with_gil(|py| {
+            self.raised.catch(
+                self.function
+                    .call1(py, (path.to_vec(), depth, support, candidates.clone()))
+                    .and_then(|filtered| filtered.extract::<Vec<usize>>(py)),
+            )
+        });
+        let mut filtered = match filtered {
+            Some(filtered) => filtered,
+            None => return,
+        };
+        // Only candidates valid for the node can be kept
+        filtered.retain(|candidate| candidates.contains(candidate));
+        *candidates = filtered;
+    }
+}
+
+pub struct PythonObserver {
+    function: PyObject,
+    raised: CallbackError,
+}
+
+impl PythonObserver {
+    pub fn new(function: PyObject, raised: CallbackError) -> PythonObserver {
+        PythonObserver { function, raised }
+    }
+}
+
+impl ProgressObserver for PythonObserver {
+    // A failed call stops the search
+    fn on_progress(&mut self, progress: &SearchProgress) -> bool {
+        if self.raised.is_set() {
+            return true;
+        }
         Python::with_gil(|py| {
-            error = self
-                .function
-                .call1(py, (send_data,))
-                .unwrap()
-                .extract(py)
-                .unwrap();
+            let stop = || -> PyResult<bool> {
+                let event = PyDict::new(py);
+                event.set_item("explored_nodes", progress.explored_nodes)?;
+                event.set_item("upper_bound", progress.upper_bound)?;
+                event.set_item("elapsed", progress.elapsed.as_secs_f64())?;
+                event.set_item("cache_size", progress.cache_size)?;
+                // Any truthy value returned by the callback stops the search
+                self.function.call1(py, (event,))?.is_true(py)
+            };
+            self.raised.catch(stop()).unwrap_or(true)
+        })
+    }
+}
+
+// Stops the search on Ctrl+C or once a Python callable failed. Python signals are only checked every
+// POLLING_INTERVAL nodes
+pub struct SignalsInterrupter {
+    calls: usize,
+    raised: CallbackError,
+}
+
+impl SignalsInterrupter {
+    const POLLING_INTERVAL: usize = 1000;
+
+    pub fn new(raised: CallbackError) -> SignalsInterrupter {
+        SignalsInterrupter { calls: 0, raised }
+    }
+}
+
+impl SearchInterrupter for SignalsInterrupter {
+    fn should_stop(&mut self) -> bool {
+        if self.raised.is_set() {
+            return true;
+        }
+        self.calls += 1;
+        if self.calls < Self::POLLING_INTERVAL {
+            return false;
+        }
+        self.calls = 0;
+        // The KeyboardInterrupt is dropped, the interruption is reported in the statistics
+        Python::with_gil(|py| py.check_signals().is_err())
+    }
+}
+
+// Forwards the records of the searches to the "pytrees" logger of the Python logging module
+pub struct PythonLogger;
+
+impl PythonLogger {
+    const LOGGER_NAME: &'static str = "pytrees";
+
+    // Installs the logger, nothing is logged until a verbosity is set
+    pub fn init() {
+        static LOGGER: PythonLogger = PythonLogger;
+        if log::set_logger(&LOGGER).is_ok() {
+            log::set_max_level(log::LevelFilter::Off);
+        }
+    }
+
+    // 0 logs nothing, 1 the info messages, 2 the debug ones and more everything
+    pub fn set_verbosity(verbose: usize) {
+        log::set_max_level(match verbose {
+            0 => log::LevelFilter::Off,
+            1 => log::LevelFilter::Info,
+            2 => log::LevelFilter::Debug,
+            _ => log::LevelFilter::Trace,
+        });
+    }
+}
+
+impl log::Log for PythonLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        // Levels of the logging module, trace being below its DEBUG
+        let level = match record.level() {
+            log::Level::Error => 40,
+            log::Level::Warn => 30,
+            log::Level::Info => 20,
+            log::Level::Debug => 10,
+            log::Level::Trace => 5,
+        };
+        // Failures of the logging module are not worth stopping the search
+        let _ = Python::with_gil(|py| -> PyResult<()> {
+            py.import("logging")?
+                .call_method1("getLogger", (Self::LOGGER_NAME,))?
+                .call_method1("log", (level, record.args().to_string()))?;
+            Ok(())
         });
-        error
     }
+
+    fn flush(&self) {}
+}
+
+// Node of a fitted tree. Its id and the ids of its children are the indices given by apply, they
+// stay valid until the tree is modified
+#[pyclass(name = "Node", module = "pytreesrs.odt")]
+#[derive(Clone)]
+pub struct PyNode {
+    #[pyo3(get)]
+    id: usize,
+    #[pyo3(get)]
+    test: Option<usize>,
+    #[pyo3(get)]
+    feature_name: Option<String>,
+    #[pyo3(get)]
+    error: f64,
+    #[pyo3(get)]
+    out: Option<f64>,
+    #[pyo3(get)]
+    support: Option<usize>,
+    #[pyo3(get)]
+    classes_support: Option<Vec<usize>>,
+    #[pyo3(get)]
+    left: Option<usize>,
+    #[pyo3(get)]
+    right: Option<usize>,
 }
 
-#[pyclass(name = "Result")]
+#[pymethods]
+impl PyNode {
+    #[getter]
+    pub fn is_leaf(&self) -> bool {
+        self.left.is_none()
+    }
+
+    pub fn __repr__(&self) -> String {
+        match (self.test, self.left, self.right) {
+            (Some(test), Some(left), Some(right)) => format!(
+                "Node(id={}, test={}, left={}, right={}, error={})",
+                self.id, test, left, right, self.error
+            ),
+            _ => format!(
+                "Node(id={}, out={:?}, error={})",
+                self.id, self.out, self.error
+            ),
+        }
+    }
+}
+
+#[pyclass(name = "Result", module = "pytreesrs.odt")]
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct LearningResult {
     #[pyo3(get, set)]
+    #[serde(deserialize_with = "deserialize_infinity")]
     pub(crate) error: f64,
     pub(crate) tree: Tree,
+    // With the automatic depth, tree of the deepest depth proven optimal
+    #[serde(default)]
+    pub(crate) optimal_tree: Option<Tree>,
     pub(crate) constraints: Constraints,
     pub(crate) statistics: Statistics,
+    // Column names of the DataFrame the tree was learned on
+    #[pyo3(get)]
+    #[serde(default)]
+    pub(crate) feature_names: Option<Vec<String>>,
+    // Columns which got a missing value attribute, appended after the original features
+    #[pyo3(get)]
+    #[serde(default)]
+    pub(crate) missing_columns: Vec<usize>,
 }
 
 #[pymethods]
 impl LearningResult {
+    // Empty result, only used to rebuild pickled results
+    #[new]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn __getstate__(&self) -> PyResult<String> {
+        serde_json::to_string(self).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    pub fn __setstate__(&mut self, state: &str) -> PyResult<()> {
+        *self = serde_json::from_str(state).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(())
+    }
+
     // Could be done with paste!
 
     #[getter]
@@ -121,4 +918,364 @@ impl LearningResult {
         let json = serde_json::to_string_pretty(&self.tree).unwrap();
         Ok(json)
     }
+
+    // With the automatic depth, tree of the deepest depth proven optimal and this depth. The tree
+    // of the result is then the best one found at the next depth, when it was not solved
+    #[getter]
+    pub fn optimal_tree(&self) -> Option<String> {
+        self.optimal_tree
+            .as_ref()
+            .map(|tree| serde_json::to_string_pretty(tree).unwrap())
+    }
+
+    #[getter]
+    pub fn optimal_depth(&self) -> Option<usize> {
+        self.statistics.optimal_depth
+    }
+
+    // The search ran to completion, it was neither interrupted, stopped by the time limit nor
+    // restricted by the beam, the discrepancy budget or the full cache
+    #[getter]
+    pub fn is_optimal(&self) -> bool {
+        self.statistics.is_optimal()
+    }
+
+    // Why the search stopped: Done, TimeLimitReached, CacheLimitReached or Interrupted
+    #[getter]
+    pub fn stop_reason(&self) -> ExposedStopReason {
+        self.statistics.stop_reason().into()
+    }
+
+    // Elapsed seconds and error of the best tree each time it improved, empty unless recorded
+    #[getter]
+    pub fn anytime_curve(&self) -> Vec<(f64, f64)> {
+        self.statistics.anytime_curve.clone()
+    }
+
+    // Best proven lower bound of the error, equal to the error once it is proven optimal
+    #[getter]
+    pub fn best_lower_bound(&self) -> f64 {
+        self.statistics.best_lower_bound
+    }
+
+    // Difference between the error and its best lower bound, 0 when optimality is proven
+    #[getter]
+    pub fn gap(&self) -> f64 {
+        self.statistics.gap
+    }
+
+    #[getter]
+    pub fn depth(&self) -> usize {
+        self.tree.depth()
+    }
+
+    #[getter]
+    pub fn num_leaves(&self) -> usize {
+        self.tree.num_leaves()
+    }
+
+    // Number of internal nodes testing each feature, by feature index
+    #[getter]
+    pub fn feature_counts(&self) -> BTreeMap<usize, usize> {
+        self.tree.feature_counts()
+    }
+
+    // Node of the tree with the given id, the index given by apply
+    pub fn node(&self, id: usize) -> PyResult<PyNode> {
+        if !self.tree.pre_order().contains(&id) {
+            return Err(PyValueError::new_err(format!("Unknown node {}", id)));
+        }
+        Ok(self.py_node(id))
+    }
+
+    // Nodes of the tree in pre-order, each node before its left then right subtree
+    pub fn nodes(&self) -> Vec<PyNode> {
+        self.tree
+            .pre_order()
+            .into_iter()
+            .map(|id| self.py_node(id))
+            .collect()
+    }
+
+    // Number of leaves stopped for each reason, empty unless the leaves were explained
+    #[getter]
+    pub fn leaf_reasons(&self) -> BTreeMap<String, usize> {
+        self.tree.leaf_reasons()
+    }
+
+    // Cost-complexity pruning of the tree on the training error, returns the number of collapsed
+    // subtrees
+    #[pyo3(signature = (alpha=0.0))]
+    pub fn prune(&mut self, alpha: f64) -> PyResult<usize> {
+        let collapsed = self.tree.prune(alpha);
+        self.tree.compact().map_err(PyValueError::new_err)?;
+        self.error = get_tree_root_error(&self.tree);
+        Ok(collapsed)
+    }
+
+    // Reduced error pruning of the tree on a validation set, returns the number of collapsed
+    // subtrees. The error stays the training error of the pruned tree
+    #[pyo3(signature = (instances, labels, threshold=0.0))]
+    pub fn prune_with_validation(
+        &mut self,
+        instances: Vec<Vec<f64>>,
+        labels: Vec<usize>,
+        threshold: f64,
+    ) -> PyResult<usize> {
+        let collapsed = self
+            .tree
+            .prune_with_validation(&instances, &labels, threshold);
+        self.tree.compact().map_err(PyValueError::new_err)?;
+        self.error = get_tree_root_error(&self.tree);
+        Ok(collapsed)
+    }
+
+    // Sets the output of a leaf and recomputes the misclassification errors of the tree
+    pub fn set_leaf_output(&mut self, id: usize, out: f64) -> PyResult<()> {
+        self.tree
+            .set_leaf_output(id, out)
+            .map_err(PyValueError::new_err)?;
+        self.error = get_tree_root_error(&self.tree);
+        Ok(())
+    }
+
+    // Turns a node into a leaf predicting its majority class and recomputes the errors. The tree
+    // is then compacted, its nodes being numbered again in pre-order
+    pub fn collapse(&mut self, id: usize) -> PyResult<()> {
+        self.tree
+            .collapse_to_leaf(id)
+            .map_err(PyValueError::new_err)?;
+        self.tree.compact().map_err(PyValueError::new_err)?;
+        self.error = get_tree_root_error(&self.tree);
+        Ok(())
+    }
+
+    // Replaces the subtree of a node by a tree given as JSON, compacts the tree and recomputes
+    // the errors. The grafted nodes keep the classes support they had in their tree until evaluate
+    // is called
+    pub fn graft(&mut self, id: usize, tree: &str) -> PyResult<()> {
+        let subtree = serde_json::from_str::<Tree>(tree)
+            .map_err(|e| PyValueError::new_err(format!("Invalid grafted tree: {}", e)))?;
+        self.tree
+            .graft(id, &subtree)
+            .map_err(PyValueError::new_err)?;
+        self.tree.compact().map_err(PyValueError::new_err)?;
+        self.error = get_tree_root_error(&self.tree);
+        Ok(())
+    }
+
+    // Recounts the classes support of the nodes on a dataset and recomputes the misclassification
+    // errors, returns the error of the tree
+    pub fn evaluate(&mut self, instances: Vec<Vec<f64>>, labels: Vec<usize>) -> PyResult<f64> {
+        self.tree
+            .set_classes_support(&instances, &labels)
+            .map_err(PyValueError::new_err)?;
+        self.error = self.tree.update_errors();
+        Ok(self.error)
+    }
+
+    // Class probabilities of the leaves, with Laplace smoothing
+    pub fn set_leaf_probabilities(&mut self, smoothing: f64) -> PyResult<()> {
+        if !(smoothing >= 0.0 && smoothing.is_finite()) {
+            return Err(PyValueError::new_err(
+                "smoothing must be a non negative number",
+            ));
+        }
+        self.tree.set_leaf_probabilities(smoothing);
+        Ok(())
+    }
+
+    // Same splits and same leaf outputs as the tree of the other result
+    pub fn structural_eq(&self, other: &LearningResult) -> bool {
+        self.tree.structural_eq(&other.tree)
+    }
+
+    // Nodes where the trees differ, as dicts holding the "path" of branches from the root and the
+    // "first" and "second" nodes, {"split": feature} or {"leaf": output}
+    pub fn diff(&self, py: Python<'_>, other: &LearningResult) -> PyResult<PyObject> {
+        let value = serde_json::to_value(self.tree.diff(&other.tree))
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        json_to_py(py, &value)
+    }
+
+    // Nested representation of the tree. Each node is a dict holding its values and, for internal
+    // nodes, its "left" and "right" children
+    pub fn tree_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let value = serde_json::to_value(&self.tree).unwrap();
+        let nodes = value["tree"].as_array().cloned().unwrap_or_default();
+        if nodes.is_empty() {
+            return Ok(py.None());
+        }
+        nested_node(py, &nodes, 0, self.feature_names.as_deref())
+    }
+
+    // Leaves reached by an instance, as (node index, weight) pairs. Only samples with missing
+    // values (NaN) can reach several leaves
+    #[pyo3(signature = (instance, missing_branch=ExposedMissingBranch::Left))]
+    pub fn weighted_leaves(
+        &self,
+        mut instance: Vec<f64>,
+        missing_branch: ExposedMissingBranch,
+    ) -> Vec<(usize, f64)> {
+        self.add_missing_indicators(&mut instance);
+        self.tree.weighted_leaves(&instance, missing_branch.into())
+    }
+
+    // Index of the leaf reached by each instance. Missing values follow missing_branch, and with
+    // Both the branch holding the most training samples
+    #[pyo3(signature = (instances, missing_branch=ExposedMissingBranch::Left))]
+    pub fn apply(
+        &self,
+        instances: Vec<Vec<f64>>,
+        missing_branch: ExposedMissingBranch,
+    ) -> Vec<usize> {
+        self.decision_path(instances, missing_branch)
+            .into_iter()
+            .map(|(leaf, _)| leaf)
+            .collect()
+    }
+
+    // Leaf reached by each instance, with the (feature, branch) tests taken from the root
+    #[pyo3(signature = (instances, missing_branch=ExposedMissingBranch::Left))]
+    pub fn decision_path(
+        &self,
+        instances: Vec<Vec<f64>>,
+        missing_branch: ExposedMissingBranch,
+    ) -> Vec<(usize, Vec<(usize, usize)>)> {
+        instances
+            .into_iter()
+            .map(|mut instance| {
+                self.add_missing_indicators(&mut instance);
+                self.tree.decision_path(&instance, missing_branch.into())
+            })
+            .collect()
+    }
+
+    // Fewest features to flip for the instance to change its prediction, with the leaf then
+    // reached. None when all the leaves predict the same output
+    #[pyo3(signature = (instance, missing_branch=ExposedMissingBranch::Left))]
+    pub fn counterfactual(
+        &self,
+        mut instance: Vec<f64>,
+        missing_branch: ExposedMissingBranch,
+    ) -> Option<(usize, Vec<usize>)> {
+        self.add_missing_indicators(&mut instance);
+        self.tree.counterfactual(&instance, missing_branch.into())
+    }
+
+    #[pyo3(signature = (feature_names=None, class_names=None))]
+    pub fn export_graphviz(
+        &self,
+        feature_names: Option<Vec<String>>,
+        class_names: Option<Vec<String>>,
+    ) -> String {
+        let feature_names = feature_names.or_else(|| self.feature_names.clone());
+        self.tree
+            .to_dot(feature_names.as_deref(), class_names.as_deref())
+    }
+
+    #[pyo3(signature = (feature_names=None, class_names=None))]
+    pub fn export_text(
+        &self,
+        feature_names: Option<Vec<String>>,
+        class_names: Option<Vec<String>>,
+    ) -> String {
+        let feature_names = feature_names.or_else(|| self.feature_names.clone());
+        self.tree
+            .to_text(feature_names.as_deref(), class_names.as_deref())
+    }
+}
+
+impl LearningResult {
+    fn py_node(&self, id: usize) -> PyNode {
+        let node = self.tree.get_node(id).expect("Node ids come from the tree");
+        let value = &node.value;
+        let children = node.children();
+        PyNode {
+            id,
+            test: value.test(),
+            feature_name: value.test().and_then(|test| {
+                self.feature_names
+                    .as_ref()
+                    .and_then(|names| names.get(test).cloned())
+            }),
+            error: value.error(),
+            out: value.out(),
+            support: value.support(),
+            classes_support: value.classes_support().map(|supports| supports.to_vec()),
+            left: children.map(|(left, _)| left),
+            right: children.map(|(_, right)| right),
+        }
+    }
+
+    // The missing value attributes are 1 when their column is missing
+    fn add_missing_indicators(&self, instance: &mut Vec<f64>) {
+        let indicators = self
+            .missing_columns
+            .iter()
+            .map(|column| {
+                instance
+                    .get(*column)
+                    .map_or(0.0, |a| a.is_nan() as usize as f64)
+            })
+            .collect::<Vec<f64>>();
+        instance.extend(indicators);
+    }
+}
+
+fn nested_node(
+    py: Python<'_>,
+    nodes: &[Value],
+    index: usize,
+    feature_names: Option<&[String]>,
+) -> PyResult<PyObject> {
+    let node = &nodes[index];
+    let dict = json_to_py(py, &node["value"])?;
+    let (left, right) = (node["left"].as_u64(), node["right"].as_u64());
+    if let (Some(left), Some(right)) = (left, right) {
+        if left != right {
+            let dict: &PyDict = dict.downcast(py)?;
+            let test = node["value"]["test"].as_u64().map(|test| test as usize);
+            if let (Some(names), Some(test)) = (feature_names, test) {
+                if let Some(name) = names.get(test) {
+                    dict.set_item("feature_name", name)?;
+                }
+            }
+            dict.set_item(
+                "left",
+                nested_node(py, nodes, left as usize, feature_names)?,
+            )?;
+            dict.set_item(
+                "right",
+                nested_node(py, nodes, right as usize, feature_names)?,
+            )?;
+        }
+    }
+    Ok(dict)
+}
+
+fn json_to_py(py: Python<'_>, value: &Value) -> PyResult<PyObject> {
+    Ok(match value {
+        Value::Null => py.None(),
+        Value::Bool(value) => value.to_object(py),
+        Value::Number(value) => match value.as_u64() {
+            Some(value) => value.to_object(py),
+            None => value.as_f64().to_object(py),
+        },
+        Value::String(value) => value.to_object(py),
+        Value::Array(values) => {
+            let list = PyList::empty(py);
+            for value in values {
+                list.append(json_to_py(py, value)?)?;
+            }
+            list.to_object(py)
+        }
+        Value::Object(values) => {
+            let dict = PyDict::new(py);
+            for (key, value) in values {
+                dict.set_item(key, json_to_py(py, value)?)?;
+            }
+            dict.to_object(py)
+        }
+    })
 }
@@ -0,0 +1,191 @@
+use crate::cache::hashmap::HashMapCache;
+use crate::cache::trie::Trie;
+use crate::cache::Caching;
+use crate::data::libsvm::{is_sparse_file, SparseData};
+use crate::data::{BinaryData, FileReader};
+use crate::globals::get_tree_root_error;
+use crate::heuristics::NoHeuristic;
+use crate::parser::BenchAlgorithm;
+use crate::searches::errors::NativeError;
+use crate::searches::greedy::LGDT;
+use crate::searches::optimal::d2::GenericDepth2;
+use crate::searches::optimal::DL85;
+use crate::searches::{
+    BranchingStrategy, CacheInitStrategy, CacheType, LowerBoundStrategy, NodeExposedData,
+    SearchStrategy, SortPolicy, Specialization,
+};
+use crate::structures::RevBitset;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+// Dataset read from a file, kept sparse for the libsvm format
+pub(crate) enum Dataset {
+    Dense(BinaryData),
+    Sparse(SparseData),
+}
+
+impl Dataset {
+    pub(crate) fn read(file: &str) -> Dataset {
+        match is_sparse_file(file) {
+            true => Dataset::Sparse(SparseData::read(file).unwrap_or_else(|e| panic!("{}", e))),
+            false => Dataset::Dense(BinaryData::read(file, false, 0.0)),
+        }
+    }
+
+    pub(crate) fn structure(&self) -> RevBitset {
+        match self {
+            Dataset::Dense(data) => RevBitset::new(data),
+            Dataset::Sparse(data) => RevBitset::from_sparse(data),
+        }
+    }
+}
+
+// One run of an algorithm on a dataset. The duration is in seconds
+#[derive(Debug, Serialize)]
+pub(crate) struct BenchRecord {
+    pub(crate) dataset: String,
+    pub(crate) algorithm: BenchAlgorithm,
+    // Cache of the DL8.5 runs
+    pub(crate) cache: Option<CacheType>,
+    pub(crate) depth: usize,
+    pub(crate) duration: f64,
+    pub(crate) error: f64,
+    pub(crate) cache_size: usize,
+    pub(crate) cache_bytes: usize,
+    pub(crate) optimal: bool,
+}
+
+// Runs each algorithm for each depth on the files of the directory with the given extension, in
+// the order of their names. DL8.5 is run once with each cache
+pub(crate) fn run(
+    dir: &Path,
+    extension: &str,
+    depths: &[usize],
+    algorithms: &[BenchAlgorithm],
+    caches: &[CacheType],
+    support: usize,
+    timeout: Option<usize>,
+) -> std::io::Result<Vec<BenchRecord>> {
+    let runs = algorithms
+        .iter()
+        .flat_map(|algorithm| match algorithm {
+            BenchAlgorithm::Dl85 => caches
+                .iter()
+                .map(|cache| (*algorithm, Some(*cache)))
+                .collect(),
+            _ => vec![(*algorithm, None)],
+        })
+        .collect::<Vec<(BenchAlgorithm, Option<CacheType>)>>();
+
+    let mut files = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<PathBuf>>>()?;
+    files.retain(|file| file.is_file() && file.extension().map_or(false, |e| e == extension));
+    files.sort();
+
+    let mut records = vec![];
+    for file in files {
+        let data = Dataset::read(file.to_str().unwrap());
+        let dataset = file
+            .file_name()
+            .map_or(String::new(), |name| name.to_string_lossy().to_string());
+        for &depth in depths {
+            for &(algorithm, cache) in runs.iter() {
+                // Depth 2 optimal trees cannot be deeper
+                if algorithm == BenchAlgorithm::D2Odt && (depth == 0 || depth > 2) {
+                    continue;
+                }
+                let mut structure = data.structure();
+                let start = Instant::now();
+                let (tree, cache_size, cache_bytes, optimal) = match algorithm {
+                    BenchAlgorithm::Dl85 => {
+                        let cache: Box<dyn Caching> = match cache {
+                            Some(CacheType::CompressedTrie) => Box::new(Trie::compressed()),
+                            Some(CacheType::Hashmap) => Box::<HashMapCache>::default(),
+                            _ => Box::<Trie>::default(),
+                        };
+                        // Same configuration as the default one of the Python bindings
+                        let mut learner = DL85::new(
+                            support,
+                            depth,
+                            <f64>::INFINITY,
+                            timeout.unwrap_or(<usize>::MAX),
+                            SortPolicy::RootOnly,
+                            0,
+                            CacheInitStrategy::None_,
+                            Specialization::Murtree,
+                            LowerBoundStrategy::Similarity,
+                            BranchingStrategy::Dynamic,
+                            NodeExposedData::ClassesSupport,
+                            cache,
+                            Box::<NativeError>::default(),
+                            Box::<NoHeuristic>::default(),
+                        );
+                        learner
+                            .fit(&mut structure)
+                            .expect("The benchmarks use no monotonicity or rate constraints");
+                        let statistics = &learner.statistics;
+                        let optimal = statistics.is_optimal();
+                        let (cache_size, cache_bytes) =
+                            (statistics.cache_size, statistics.cache.estimated_bytes);
+                        (learner.tree, cache_size, cache_bytes, optimal)
+                    }
+                    BenchAlgorithm::D2Odt => {
+                        let mut learner = GenericDepth2::new(SearchStrategy::LessGreedyMurtree);
+                        (learner.fit(support, depth, &mut structure), 0, 0, true)
+                    }
+                    BenchAlgorithm::Lgdt => {
+                        let mut learner =
+                            LGDT::new(support, depth, SearchStrategy::LessGreedyMurtree);
+                        learner.fit(&mut structure);
+                        // Up to depth 2 the tree is learned by the optimal depth 2 search
+                        (learner.tree, 0, 0, depth <= 2)
+                    }
+                };
+                records.push(BenchRecord {
+                    dataset: dataset.clone(),
+                    algorithm,
+                    cache,
+                    depth,
+                    duration: start.elapsed().as_secs_f64(),
+                    error: get_tree_root_error(&tree),
+                    cache_size,
+                    cache_bytes,
+                    optimal,
+                });
+            }
+        }
+    }
+    Ok(records)
+}
+
+pub(crate) fn to_csv(records: &[BenchRecord]) -> String {
+    let mut csv = String::from(
+        "dataset,algorithm,cache,depth,duration,error,cache_size,cache_bytes,optimal\n",
+    );
+    for record in records {
+        let algorithm = record
+            .algorithm
+            .to_possible_value()
+            .map_or(String::new(), |value| value.get_name().to_string());
+        let cache = record
+            .cache
+            .and_then(|cache| cache.to_possible_value())
+            .map_or(String::new(), |value| value.get_name().to_string());
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{}\n",
+            record.dataset,
+            algorithm,
+            cache,
+            record.depth,
+            record.duration,
+            record.error,
+            record.cache_size,
+            record.cache_bytes,
+            record.optimal
+        ));
+    }
+    csv
+}
@@ -0,0 +1,86 @@
+use std::collections::VecDeque;
+use std::mem::size_of;
+
+// What an eviction sweep does with a position of the queue
+pub(crate) enum Candidate {
+    Evict,
+    // The entry cannot be evicted yet, it goes back to the end of the queue
+    Keep,
+    // The entry was already removed, its position is dropped
+    Stale,
+}
+
+// Positions of the cache entries in insertion order, the oldest being evicted first. A sweep stops
+// at the first entry which can be evicted. When a sweep finds none, the next ones wait for as many
+// insertions as the positions it went through, so an insertion costs a constant time on average
+#[derive(Default)]
+pub(crate) struct EvictionQueue {
+    positions: VecDeque<usize>,
+    wait: usize,
+}
+
+impl EvictionQueue {
+    pub(crate) fn push(&mut self, position: usize) {
+        self.positions.push_back(position);
+    }
+
+    // Removes and returns the oldest position which can be evicted, if any
+    pub(crate) fn pop(&mut self, mut candidate: impl FnMut(usize) -> Candidate) -> Option<usize> {
+        if self.wait > 0 {
+            self.wait -= 1;
+            return None;
+        }
+        let mut swept = 0;
+        for _ in 0..self.positions.len() {
+            let position = self.positions.pop_front()?;
+            swept += 1;
+            match candidate(position) {
+                Candidate::Evict => return Some(position),
+                Candidate::Keep => self.positions.push_back(position),
+                Candidate::Stale => {}
+            }
+        }
+        self.wait = swept;
+        None
+    }
+
+    pub(crate) fn estimated_bytes(&self) -> usize {
+        self.positions.capacity() * size_of::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod eviction_test {
+    use crate::cache::eviction::{Candidate, EvictionQueue};
+
+    #[test]
+    fn oldest_evictable_position_comes_first() {
+        let mut queue = EvictionQueue::default();
+        for position in 1..6 {
+            queue.push(position);
+        }
+        let evicted = queue.pop(|position| match position {
+            1 => Candidate::Stale,
+            2 => Candidate::Keep,
+            _ => Candidate::Evict,
+        });
+        assert_eq!(evicted, Some(3));
+        // The kept position is now after the remaining ones
+        assert_eq!(queue.pop(|_| Candidate::Evict), Some(4));
+        assert_eq!(queue.pop(|_| Candidate::Evict), Some(5));
+        assert_eq!(queue.pop(|_| Candidate::Evict), Some(2));
+    }
+
+    #[test]
+    fn failed_sweep_is_not_repeated_at_once() {
+        let mut queue = EvictionQueue::default();
+        for position in 0..3 {
+            queue.push(position);
+        }
+        assert_eq!(queue.pop(|_| Candidate::Keep), None);
+        for _ in 0..3 {
+            assert_eq!(queue.pop(|_| Candidate::Evict), None);
+        }
+        assert_eq!(queue.pop(|_| Candidate::Evict), Some(0));
+    }
+}
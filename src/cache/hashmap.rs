@@ -0,0 +1,260 @@
+use crate::cache::eviction::{Candidate, EvictionQueue};
+use crate::cache::{CacheEntry, CacheStatistics, Caching};
+use std::collections::{BTreeSet, HashMap};
+use std::mem::size_of;
+use std::rc::Rc;
+
+// Cache storing the entries in a vector, the itemsets are mapped to their position in it
+pub struct HashMapCache {
+    elements: Vec<CacheEntry>,
+    positions: HashMap<Rc<[usize]>, usize>,
+    // Itemset of the entry at each position, shared with the map, None once the entry is removed
+    keys: Vec<Option<Rc<[usize]>>>,
+    // Positions of evicted entries, reused by the next insertions
+    free: Vec<usize>,
+    // Entries other than the root, in the order they were added
+    queue: EvictionQueue,
+    max_size: usize,
+    evictions: usize,
+    hits: usize,
+    misses: usize,
+}
+
+impl Caching for HashMapCache {
+    fn init(&mut self) -> Option<usize> {
+        if let Some(root) = self.positions.get(&[][..]) {
+            return Some(*root);
+        }
+        Some(self.add_entry(vec![], CacheEntry::default()))
+    }
+
+    fn get_root_infos(&self) -> Option<&CacheEntry> {
+        self.positions
+            .get(&[][..])
+            .and_then(|index| self.elements.get(*index))
+    }
+
+    fn get(&mut self, itemset: &BTreeSet<usize>, index: Option<usize>) -> Option<&mut CacheEntry> {
+        let index = match index {
+            Some(index) => index,
+            None => *self.positions.get(Self::key(itemset).as_slice())?,
+        };
+        self.elements.get_mut(index)
+    }
+
+    fn find(&self, itemset: &BTreeSet<usize>) -> Option<&CacheEntry> {
+        self.positions
+            .get(Self::key(itemset).as_slice())
+            .and_then(|index| self.elements.get(*index))
+    }
+
+    fn insert(&mut self, itemset: &BTreeSet<usize>) -> (bool, Option<usize>) {
+        let key = Self::key(itemset);
+        if let Some(index) = self.positions.get(key.as_slice()) {
+            self.hits += 1;
+            return (false, Some(*index));
+        }
+        self.misses += 1;
+        if self.max_size > 0 && self.size() >= self.max_size {
+            self.evict();
+        }
+        let item = key.last().copied().unwrap_or(<usize>::MAX);
+        (true, Some(self.add_entry(key, CacheEntry::new(item))))
+    }
+
+    fn size(&self) -> usize {
+        self.elements.len() - self.free.len()
+    }
+
+    fn reserve(&mut self, capacity: usize) {
+        self.elements
+            .reserve(capacity.saturating_sub(self.elements.len()));
+        self.keys.reserve(capacity.saturating_sub(self.keys.len()));
+        self.positions
+            .reserve(capacity.saturating_sub(self.positions.len()));
+    }
+
+    fn set_max_size(&mut self, max_size: usize) {
+        self.max_size = max_size;
+    }
+
+    fn evictions(&self) -> usize {
+        self.evictions
+    }
+
+    // The map is counted as its keys and positions, the keys owning their items and their two
+    // reference counts
+    fn statistics(&self) -> CacheStatistics {
+        let mut entries_per_depth = vec![];
+        let mut estimated_bytes = self.elements.capacity() * size_of::<CacheEntry>()
+            + self.positions.capacity() * (size_of::<Rc<[usize]>>() + size_of::<usize>())
+            + self.keys.capacity() * size_of::<Option<Rc<[usize]>>>()
+            + self.free.capacity() * size_of::<usize>()
+            + self.queue.estimated_bytes();
+        for key in self.positions.keys() {
+            if entries_per_depth.len() <= key.len() {
+                entries_per_depth.resize(key.len() + 1, 0);
+            }
+            entries_per_depth[key.len()] += 1;
+            estimated_bytes += (key.len() + 2) * size_of::<usize>();
+        }
+        CacheStatistics {
+            hits: self.hits,
+            misses: self.misses,
+            entries_per_depth,
+            estimated_bytes,
+        }
+    }
+
+    fn reset_solutions(&mut self) {
+        for entry in self.elements.iter_mut() {
+            entry.reset_solution();
+        }
+    }
+
+    fn invalidate(&mut self, invalidated: &mut dyn FnMut(&BTreeSet<usize>, &CacheEntry) -> bool) {
+        let elements = &mut self.elements;
+        let keys = &mut self.keys;
+        let free = &mut self.free;
+        self.positions.retain(|key, position| {
+            let itemset = key.iter().copied().collect::<BTreeSet<usize>>();
+            if !invalidated(&itemset, &elements[*position]) {
+                return true;
+            }
+            if key.is_empty() {
+                elements[*position].reset_solution();
+                return true;
+            }
+            keys[*position] = None;
+            free.push(*position);
+            false
+        });
+    }
+
+    fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    fn print(&self) {
+        println!("{:#?}", self.elements)
+    }
+}
+
+impl Default for HashMapCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HashMapCache {
+    pub fn new() -> Self {
+        Self {
+            elements: vec![],
+            positions: HashMap::new(),
+            keys: vec![],
+            free: vec![],
+            queue: EvictionQueue::default(),
+            max_size: 0,
+            evictions: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    // Itemsets are sorted so the same set of items always gives the same key
+    fn key(itemset: &BTreeSet<usize>) -> Vec<usize> {
+        itemset.iter().copied().collect()
+    }
+
+    fn add_entry(&mut self, key: Vec<usize>, entry: CacheEntry) -> usize {
+        let key = Rc::<[usize]>::from(key);
+        let position = match self.free.pop() {
+            Some(position) => {
+                self.elements[position] = entry;
+                self.keys[position] = Some(key.clone());
+                position
+            }
+            None => {
+                self.elements.push(entry);
+                self.keys.push(Some(key.clone()));
+                self.elements.len() - 1
+            }
+        };
+        if !key.is_empty() {
+            self.queue.push(position);
+        }
+        self.positions.insert(key, position);
+        position
+    }
+
+    // Removes the oldest leaf, the root is never evicted
+    fn evict(&mut self) {
+        let (elements, keys) = (&self.elements, &self.keys);
+        let evicted = self.queue.pop(|position| match &keys[position] {
+            None => Candidate::Stale,
+            Some(_) if elements[position].is_leaf => Candidate::Evict,
+            Some(_) => Candidate::Keep,
+        });
+        if let Some(position) = evicted {
+            if let Some(key) = self.keys[position].take() {
+                self.positions.remove(&key);
+            }
+            self.free.push(position);
+            self.evictions += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod hashmap_cache_test {
+    use crate::cache::hashmap::HashMapCache;
+    use crate::cache::Caching;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn insert_and_get_entries() {
+        let mut cache = HashMapCache::new();
+        assert!(cache.is_empty());
+        let root = cache.init();
+        assert_eq!(root, Some(0));
+
+        let itemset = BTreeSet::from([3, 0, 1]);
+        let (is_new, index) = cache.insert(&itemset);
+        assert!(is_new);
+        assert_eq!(cache.insert(&itemset), (false, index));
+
+        if let Some(entry) = cache.get(&itemset, None) {
+            entry.error = 12.0;
+        }
+        assert_eq!(
+            cache.get(&itemset, index).map(|entry| entry.error),
+            Some(12.0)
+        );
+        assert_eq!(cache.find(&itemset).map(|entry| entry.item), Some(3));
+        assert!(cache.find(&BTreeSet::from([0, 1])).is_none());
+        assert_eq!(cache.size(), 2);
+    }
+
+    #[test]
+    fn leaves_are_evicted_when_full() {
+        let mut cache = HashMapCache::new();
+        cache.set_max_size(3);
+        cache.init();
+
+        let leaf = BTreeSet::from([0]);
+        let (_, leaf_index) = cache.insert(&leaf);
+        if let Some(entry) = cache.get(&leaf, leaf_index) {
+            entry.is_leaf = true;
+        }
+        let internal = BTreeSet::from([2]);
+        cache.insert(&internal);
+
+        let (is_new, index) = cache.insert(&BTreeSet::from([4]));
+        assert!(is_new);
+        assert_eq!(index, leaf_index);
+        assert_eq!(cache.evictions(), 1);
+        assert_eq!(cache.size(), 3);
+        assert!(cache.find(&leaf).is_none());
+        assert!(cache.find(&internal).is_some());
+    }
+}
@@ -1,5 +1,9 @@
+mod eviction;
+pub mod hashmap;
 pub mod trie;
 
+use crate::searches::StopReason;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
 
 pub const MAX_ERROR: f64 = <f64>::INFINITY;
@@ -22,11 +26,53 @@ pub trait Caching {
 
     fn size(&self) -> usize;
 
+    // Allocates room for the given number of entries up front, so the cache does not grow
+    // during the search
+    fn reserve(&mut self, capacity: usize);
+
+    // Bounds the number of entries, 0 means unbounded. When the bound is reached, the oldest leaf
+    // is evicted for each new entry as leaves are cheap to recompute
+    fn set_max_size(&mut self, max_size: usize);
+
+    fn evictions(&self) -> usize;
+
+    // Lookups, entries of each depth and memory of the cache, to compare the cache types
+    fn statistics(&self) -> CacheStatistics;
+
+    // Forgets the solutions and bounds of the entries, which only hold for the constraints they
+    // were found under. The itemsets and their leaf errors are kept
+    fn reset_solutions(&mut self);
+
+    // Removes the entries for which `invalidated` holds, given their itemset, and the entries only
+    // reachable through them. The root is reset instead of being removed
+    fn invalidate(&mut self, invalidated: &mut dyn FnMut(&BTreeSet<usize>, &CacheEntry) -> bool);
+
     fn is_empty(&self) -> bool;
 
     fn print(&self);
 }
 
+// Hits and misses count the insertions finding an existing entry or creating one, since the cache
+// was created. The memory is estimated from the allocated capacity of the cache
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CacheStatistics {
+    pub hits: usize,
+    pub misses: usize,
+    // Number of entries of each itemset size, starting at the root
+    pub entries_per_depth: Vec<usize>,
+    pub estimated_bytes: usize,
+}
+
+impl CacheStatistics {
+    // Share of the insertions finding an existing entry, 0 before any insertion
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            lookups => self.hits as f64 / lookups as f64,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct CacheEntry {
     pub item: usize,
@@ -38,6 +84,7 @@ pub struct CacheEntry {
     pub target: f64,
     pub is_optimal: bool,
     pub is_leaf: bool,
+    pub leaf_reason: StopReason,
 }
 
 impl CacheEntry {
@@ -52,11 +99,23 @@ impl CacheEntry {
             target: 0.0,
             is_optimal: false,
             is_leaf: false,
+            leaf_reason: StopReason::None,
         }
     }
 
-    pub fn to_leaf(&mut self) {
+    pub fn reset_solution(&mut self) {
+        self.test = <usize>::MAX;
+        self.error = MAX_ERROR;
+        self.upper_bound = MAX_ERROR;
+        self.lower_bound = 0.0;
+        self.is_optimal = false;
+        self.is_leaf = false;
+        self.leaf_reason = StopReason::None;
+    }
+
+    pub fn to_leaf(&mut self, reason: StopReason) {
         self.is_leaf = true;
+        self.leaf_reason = reason;
         self.error = self.leaf_error;
     }
 }
@@ -73,6 +132,7 @@ impl Default for CacheEntry {
             target: 0.0,
             is_optimal: false,
             is_leaf: false,
+            leaf_reason: StopReason::None,
         }
     }
 }
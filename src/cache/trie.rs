@@ -1,11 +1,21 @@
-use crate::cache::{CacheEntry, Caching};
+use crate::cache::eviction::{Candidate, EvictionQueue};
+use crate::cache::{CacheEntry, CacheStatistics, Caching};
 use std::collections::BTreeSet;
+use std::mem::size_of;
 use std::slice::Iter;
 
 #[derive(Debug)]
 struct TrieNode {
+    // Set to usize::MAX when the node is not used
     index: usize,
+    parent: usize,
     children: Vec<usize>,
+    // Items leading to the node from its parent before its own item, only used by the path
+    // compressed trie
+    edge: Vec<usize>,
+    // Unset for the nodes created by splitting an edge, until their itemset is inserted. Such
+    // nodes are evicted like the leaves
+    stored: bool,
     infos: CacheEntry,
 }
 
@@ -13,7 +23,10 @@ impl Default for TrieNode {
     fn default() -> Self {
         Self {
             index: <usize>::MAX,
+            parent: 0,
             children: vec![],
+            edge: vec![],
+            stored: true,
             infos: CacheEntry::default(),
         }
     }
@@ -23,18 +36,44 @@ impl TrieNode {
     pub fn new(item: usize) -> Self {
         Self {
             index: <usize>::MAX,
+            parent: 0,
             children: vec![],
+            edge: vec![],
+            stored: true,
             infos: CacheEntry::new(item),
         }
     }
+
+    fn edge_len(&self) -> usize {
+        self.edge.len() + 1
+    }
+
+    fn edge_item(&self, position: usize) -> usize {
+        self.edge.get(position).copied().unwrap_or(self.infos.item)
+    }
 }
 
+// Each node holds the entry of an itemset, the items of which are sorted along the path from the
+// root. With path compression, the chains of itemsets never inserted are merged in the edge of
+// the next node, a node only existing for an inserted itemset or where the paths diverge
 pub struct Trie {
     elements: Vec<TrieNode>,
+    // Positions of evicted nodes, reused by the next insertions
+    free: Vec<usize>,
+    // Nodes other than the root, in the order they were added
+    queue: EvictionQueue,
+    max_size: usize,
+    evictions: usize,
+    hits: usize,
+    misses: usize,
+    compressed: bool,
 }
 
 impl Caching for Trie {
     fn init(&mut self) -> Option<usize> {
+        if !self.elements.is_empty() {
+            return Some(self.get_root_index());
+        }
         let root = TrieNode::default();
         Some(self.add_root(root))
     }
@@ -51,34 +90,12 @@ impl Caching for Trie {
         }
 
         // We moving using Itemset
-        let mut index = self.get_root_index();
-        for item in itemset.iter() {
-            let mut children = self.children(index);
-            if let Some(child) = children.find(|&&c| {
-                self.get_node(c)
-                    .map_or(false, |node| node.infos.item == *item)
-            }) {
-                index = *child
-            } else {
-                return None;
-            }
-        }
+        let index = self.locate(itemset)?;
         self.get_node_mut(index).map(|node| &mut node.infos)
     }
 
     fn find(&self, itemset: &BTreeSet<usize>) -> Option<&CacheEntry> {
-        let mut index = self.get_root_index();
-        for item in itemset.iter() {
-            let mut children = self.children(index);
-            if let Some(child) = children.find(|&&c| {
-                self.get_node(c)
-                    .map_or(false, |node| node.infos.item == *item)
-            }) {
-                index = *child;
-            } else {
-                return None;
-            }
-        }
+        let index = self.locate(itemset)?;
         self.get_node(index).map(|node| &node.infos)
     }
 
@@ -87,23 +104,113 @@ impl Caching for Trie {
         let mut index = self.get_root_index();
         let mut is_new = false;
 
-        for item in itemset.iter() {
-            let mut children = self.children(index);
-            if let Some(child) = children.find(|&&c| {
-                self.get_node(c)
-                    .map_or(false, |node| node.infos.item == *item)
-            }) {
-                index = *child;
-            } else {
-                is_new = true;
-                index = self.create_cache_entry(index, *item);
+        let mut items = itemset.iter().copied();
+        while let Some(item) = items.next() {
+            if let Some(child) = self.child_starting_with(index, item) {
+                let edge_len = self.elements[child].edge_len();
+                let mut matched = 1;
+                let mut next = None;
+                while matched < edge_len {
+                    match items.next() {
+                        Some(item) if item == self.elements[child].edge_item(matched) => {
+                            matched += 1
+                        }
+                        item => {
+                            next = item;
+                            break;
+                        }
+                    }
+                }
+                if matched == edge_len {
+                    index = child;
+                    continue;
+                }
+                // The itemset leaves the edge, a node is added where they diverge. As in the
+                // trie without compression, the prefixes of the stored itemsets are not new
+                index = self.split_edge(child, matched);
+                if let Some(item) = next {
+                    index = self.create_path(index, item, &mut items);
+                    is_new = true;
+                }
+                break;
             }
+            if self.max_size > 0 && self.size() >= self.max_size {
+                self.evict(index);
+            }
+            is_new = true;
+            index = self.create_path(index, item, &mut items);
+        }
+        self.elements[index].stored = true;
+        match is_new {
+            true => self.misses += 1,
+            false => self.hits += 1,
         }
         (is_new, Some(index))
     }
 
     fn size(&self) -> usize {
-        self.elements.len()
+        self.elements.len() - self.free.len()
+    }
+
+    fn reserve(&mut self, capacity: usize) {
+        self.elements
+            .reserve(capacity.saturating_sub(self.elements.len()));
+    }
+
+    fn set_max_size(&mut self, max_size: usize) {
+        self.max_size = max_size;
+    }
+
+    fn evictions(&self) -> usize {
+        self.evictions
+    }
+
+    fn statistics(&self) -> CacheStatistics {
+        let mut entries_per_depth = vec![];
+        let mut estimated_bytes = self.elements.capacity() * size_of::<TrieNode>()
+            + self.free.capacity() * size_of::<usize>()
+            + self.queue.estimated_bytes();
+        if !self.elements.is_empty() {
+            let mut stack = vec![(self.get_root_index(), 0)];
+            while let Some((index, depth)) = stack.pop() {
+                if entries_per_depth.len() <= depth {
+                    entries_per_depth.resize(depth + 1, 0);
+                }
+                entries_per_depth[depth] += 1;
+                stack.extend(
+                    self.children(index)
+                        .map(|child| (*child, depth + self.elements[*child].edge_len())),
+                );
+            }
+        }
+        for node in self.elements.iter() {
+            estimated_bytes +=
+                (node.children.capacity() + node.edge.capacity()) * size_of::<usize>();
+        }
+        CacheStatistics {
+            hits: self.hits,
+            misses: self.misses,
+            entries_per_depth,
+            estimated_bytes,
+        }
+    }
+
+    fn reset_solutions(&mut self) {
+        for node in self.elements.iter_mut() {
+            node.infos.reset_solution();
+        }
+    }
+
+    fn invalidate(&mut self, invalidated: &mut dyn FnMut(&BTreeSet<usize>, &CacheEntry) -> bool) {
+        if self.elements.is_empty() {
+            return;
+        }
+        let root = self.get_root_index();
+        let mut itemset = BTreeSet::new();
+        if invalidated(&itemset, &self.elements[root].infos) {
+            self.elements[root].infos.reset_solution();
+        }
+        self.invalidate_children(root, &mut itemset, invalidated);
     }
 
     fn is_empty(&self) -> bool {
@@ -125,20 +232,145 @@ impl Default for Trie {
 
 impl Trie {
     pub fn new() -> Self {
-        Self { elements: vec![] }
+        Self {
+            elements: vec![],
+            free: vec![],
+            queue: EvictionQueue::default(),
+            max_size: 0,
+            evictions: 0,
+            hits: 0,
+            misses: 0,
+            compressed: false,
+        }
+    }
+
+    // Trie with path compression, storing fewer nodes in deep searches at the cost of comparing
+    // the items of the edges and splitting them
+    pub fn compressed() -> Self {
+        Self {
+            compressed: true,
+            ..Self::new()
+        }
+    }
+
+    // Node of the itemset, if any
+    fn locate(&self, itemset: &BTreeSet<usize>) -> Option<usize> {
+        let mut index = self.get_root_index();
+        let mut items = itemset.iter().copied();
+        while let Some(item) = items.next() {
+            index = self.child_starting_with(index, item)?;
+            let node = &self.elements[index];
+            for position in 1..node.edge_len() {
+                if items.next() != Some(node.edge_item(position)) {
+                    return None;
+                }
+            }
+        }
+        Some(index)
+    }
+
+    fn child_starting_with(&self, index: usize, item: usize) -> Option<usize> {
+        self.children(index)
+            .find(|&&child| self.elements[child].edge_item(0) == item)
+            .copied()
+    }
+
+    // Adds the nodes of the item followed by the remaining items below the parent: a node per
+    // item, or a single node holding them in its edge with path compression
+    fn create_path(
+        &mut self,
+        parent: usize,
+        item: usize,
+        items: &mut impl Iterator<Item = usize>,
+    ) -> usize {
+        if !self.compressed {
+            let mut index = self.create_cache_entry(parent, item);
+            for item in items {
+                if self.max_size > 0 && self.size() >= self.max_size {
+                    self.evict(index);
+                }
+                index = self.create_cache_entry(index, item);
+            }
+            return index;
+        }
+        let mut edge = vec![item];
+        edge.extend(items);
+        let item = edge.pop().unwrap_or(item);
+        let mut node = TrieNode::new(item);
+        node.edge = edge;
+        self.add_node(parent, node)
+    }
+
+    // Splits the edge of the node after its first `length` items, returning the node added there.
+    // The node keeps its position, so the indices given to the searches remain valid
+    fn split_edge(&mut self, index: usize, length: usize) -> usize {
+        let parent = self.elements[index].parent;
+        let mut edge = std::mem::take(&mut self.elements[index].edge);
+        let remaining = edge.split_off(length);
+        let item = edge.pop().unwrap_or(<usize>::MAX);
+        let mut node = TrieNode::new(item);
+        node.edge = edge;
+        node.stored = false;
+        let position = self.add_node(parent, node);
+        self.elements[parent]
+            .children
+            .retain(|child| *child != index);
+        self.elements[index].edge = remaining;
+        self.elements[index].parent = position;
+        self.add_child(position, index);
+        position
     }
 
     fn add_node(&mut self, parent: usize, mut node: TrieNode) -> usize {
-        node.index = self.elements.len();
-        self.elements.push(node);
-        let position = self.elements.len() - 1;
+        node.parent = parent;
+        let position = match self.free.pop() {
+            Some(position) => {
+                node.index = position;
+                self.elements[position] = node;
+                position
+            }
+            None => {
+                node.index = self.elements.len();
+                self.elements.push(node);
+                self.elements.len() - 1
+            }
+        };
         if position == 0 {
             return position;
         }
         self.add_child(parent, position);
+        self.queue.push(position);
         position
     }
 
+    // Removes the oldest leaf without children, but the node being extended. The root is never
+    // evicted
+    fn evict(&mut self, extended: usize) {
+        let elements = &self.elements;
+        let evicted = self.queue.pop(|position| {
+            let node = &elements[position];
+            if node.index != position {
+                return Candidate::Stale;
+            }
+            match position != extended
+                && (node.infos.is_leaf || !node.stored)
+                && node.children.is_empty()
+            {
+                true => Candidate::Evict,
+                false => Candidate::Keep,
+            }
+        });
+        if let Some(position) = evicted {
+            let parent = self.elements[position].parent;
+            self.elements[parent]
+                .children
+                .retain(|child| *child != position);
+            self.elements[position] = TrieNode::default();
+            self.free.push(position);
+            self.evictions += 1;
+        }
+    }
+
     fn add_child(&mut self, parent: usize, index: usize) {
         self.elements[parent].children.push(index);
     }
@@ -163,6 +395,38 @@ impl Trie {
         self.elements.get_mut(index)
     }
 
+    fn invalidate_children(
+        &mut self,
+        index: usize,
+        itemset: &mut BTreeSet<usize>,
+        invalidated: &mut dyn FnMut(&BTreeSet<usize>, &CacheEntry) -> bool,
+    ) {
+        for child in self.elements[index].children.clone() {
+            let node = &self.elements[child];
+            let items = (0..node.edge_len())
+                .map(|position| node.edge_item(position))
+                .collect::<Vec<usize>>();
+            itemset.extend(items.iter().copied());
+            if invalidated(itemset, &self.elements[child].infos) {
+                self.elements[index].children.retain(|c| *c != child);
+                self.remove_subtree(child);
+            } else {
+                self.invalidate_children(child, itemset, invalidated);
+            }
+            for item in items.iter() {
+                itemset.remove(item);
+            }
+        }
+    }
+
+    fn remove_subtree(&mut self, index: usize) {
+        for child in std::mem::take(&mut self.elements[index].children) {
+            self.remove_subtree(child);
+        }
+        self.elements[index] = TrieNode::default();
+        self.free.push(index);
+    }
+
     fn create_cache_entry(&mut self, parent: usize, item: usize) -> usize {
         let node = TrieNode::new(item);
         self.add_node(parent, node)
@@ -212,4 +476,83 @@ mod trie_test {
 
         println!("Should have 33.0 as ub: {:#?}", infos);
     }
+
+    #[test]
+    fn compressed_trie_splits_the_edges() {
+        let mut cache = Trie::compressed();
+        cache.init();
+
+        let deep = BTreeSet::from([1, 2, 3, 4]);
+        let (is_new, index) = cache.insert(&deep);
+        assert!(is_new);
+        let index = index.unwrap();
+        cache.get(&deep, Some(index)).unwrap().upper_bound = 33.0;
+        assert_eq!(cache.size(), 2);
+        assert!(cache.find(&BTreeSet::from([1, 2])).is_none());
+
+        // Inserting a prefix splits the edge, the deep node keeps its index
+        let prefix = BTreeSet::from([1, 2]);
+        assert_eq!(cache.insert(&prefix).0, false);
+        assert_eq!(cache.size(), 3);
+        assert_eq!(cache.insert(&BTreeSet::from([1, 2, 5])).0, true);
+        assert_eq!(cache.insert(&deep), (false, Some(index)));
+        assert_eq!(cache.get(&deep, None).unwrap().upper_bound, 33.0);
+        assert_eq!(cache.size(), 4);
+
+        // Diverging in the middle of an edge adds a node not stored yet
+        assert_eq!(cache.insert(&BTreeSet::from([1, 2, 3, 6])).0, true);
+        assert!(cache.find(&BTreeSet::from([1, 2, 3])).is_some());
+        assert_eq!(cache.insert(&BTreeSet::from([1, 2, 3])).0, false);
+        assert_eq!(cache.statistics().entries_per_depth, vec![1, 0, 1, 2, 2]);
+    }
+
+    #[test]
+    fn compressed_trie_stores_the_same_itemsets() {
+        let mut trie = Trie::new();
+        let mut compressed = Trie::compressed();
+        trie.init();
+        compressed.init();
+
+        let itemsets = (0..64usize)
+            .map(|mask| {
+                (0..6)
+                    .filter(|bit| ((mask * 37 + 11) % 64) & (1 << bit) != 0)
+                    .collect::<BTreeSet<usize>>()
+            })
+            .collect::<Vec<BTreeSet<usize>>>();
+        for itemset in itemsets.iter().rev() {
+            let (is_new, index) = compressed.insert(itemset);
+            assert_eq!(trie.insert(itemset).0, is_new);
+            compressed.get(itemset, index).unwrap().upper_bound = itemset.len() as f64;
+        }
+        for itemset in itemsets.iter() {
+            assert_eq!(trie.insert(itemset).0, false);
+            assert_eq!(compressed.insert(itemset).0, false);
+            let entry = compressed.find(itemset).unwrap();
+            assert_eq!(entry.upper_bound, itemset.len() as f64);
+        }
+        assert!(compressed.size() <= trie.size());
+    }
+
+    #[test]
+    fn oldest_leaf_is_evicted_when_full() {
+        let mut trie = Trie::new();
+        trie.set_max_size(3);
+        trie.init();
+
+        let internal = BTreeSet::from([2]);
+        trie.insert(&internal);
+        let leaf = BTreeSet::from([4]);
+        let (_, leaf_index) = trie.insert(&leaf);
+        trie.get(&leaf, leaf_index).unwrap().is_leaf = true;
+
+        // Extending the internal node leaves it in the cache, the leaf is evicted
+        let (is_new, index) = trie.insert(&BTreeSet::from([2, 5]));
+        assert!(is_new);
+        assert_eq!(index, leaf_index);
+        assert_eq!(trie.evictions(), 1);
+        assert_eq!(trie.size(), 3);
+        assert!(trie.find(&leaf).is_none());
+        assert!(trie.find(&internal).is_some());
+    }
 }
@@ -0,0 +1,271 @@
+// Reader of ARFF files. The last attribute is the class and must be nominal. Nominal attributes are
+// binarized: one feature, 1 for the last declared value, with up to two values and one feature per
+// value otherwise. Numeric attributes must already be binary. Missing values (?) are read as 0.
+// Sparse data and string or date attributes are not supported.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArffType {
+    Numeric,
+    Nominal(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArffAttribute {
+    pub name: String,
+    pub kind: ArffType,
+}
+
+#[derive(Debug, Clone)]
+pub struct Arff {
+    pub relation: String,
+    pub attributes: Vec<ArffAttribute>,
+    // Class and binary features of each sample
+    pub samples: Vec<(usize, Vec<usize>)>,
+}
+
+impl Arff {
+    pub fn parse(lines: &[String]) -> Result<Arff, String> {
+        let mut relation = String::new();
+        let mut attributes = vec![];
+        let mut samples = vec![];
+        let mut in_data = false;
+
+        for (number, line) in lines.iter().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('%') {
+                continue;
+            }
+            let error = |message: String| format!("line {}: {}", number + 1, message);
+
+            if in_data {
+                if line.starts_with('{') {
+                    return Err(error("sparse data is not supported".to_string()));
+                }
+                let values = split_values(line);
+                if values.len() != attributes.len() {
+                    return Err(error(format!(
+                        "{} values for {} attributes",
+                        values.len(),
+                        attributes.len()
+                    )));
+                }
+                samples.push(binarize(&attributes, &values).map_err(error)?);
+                continue;
+            }
+
+            let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            match keyword.to_lowercase().as_str() {
+                "@relation" => relation = unquote(rest.trim()),
+                "@attribute" => attributes.push(parse_attribute(rest.trim()).map_err(error)?),
+                "@data" => {
+                    match attributes.last() {
+                        Some(ArffAttribute {
+                            kind: ArffType::Nominal(_),
+                            ..
+                        }) => {}
+                        _ => return Err(error("the class attribute must be nominal".to_string())),
+                    }
+                    in_data = true;
+                }
+                _ => return Err(error(format!("unexpected line '{}'", line))),
+            }
+        }
+
+        if !in_data {
+            return Err("the file has no @data section".to_string());
+        }
+        Ok(Arff {
+            relation,
+            attributes,
+            samples,
+        })
+    }
+
+    /// Names of the binary features, such as `color == "red"` for nominal attributes
+    pub fn feature_names(&self) -> Vec<String> {
+        let features = &self.attributes[..self.attributes.len().saturating_sub(1)];
+        features
+            .iter()
+            .flat_map(|attribute| match &attribute.kind {
+                ArffType::Numeric => vec![attribute.name.clone()],
+                ArffType::Nominal(values) => {
+                    // Up to two values give a single feature, for the last one
+                    let first = match values.len() <= 2 {
+                        true => values.len() - 1,
+                        false => 0,
+                    };
+                    values[first..]
+                        .iter()
+                        .map(|value| format!("{} == \"{}\"", attribute.name, value))
+                        .collect()
+                }
+            })
+            .collect()
+    }
+}
+
+fn parse_attribute(declaration: &str) -> Result<ArffAttribute, String> {
+    let (name, kind) = match declaration.chars().next() {
+        Some(quote @ ('\'' | '"')) => declaration[1..]
+            .split_once(quote)
+            .map(|(name, kind)| (name.to_string(), kind.trim())),
+        _ => declaration
+            .split_once(char::is_whitespace)
+            .map(|(name, kind)| (name.to_string(), kind.trim())),
+    }
+    .ok_or_else(|| format!("invalid attribute '{}'", declaration))?;
+
+    let kind = match kind.strip_prefix('{') {
+        Some(values) => {
+            let values = split_values(values.trim_end_matches('}'));
+            if values.is_empty() {
+                return Err(format!("attribute {} has no value", name));
+            }
+            ArffType::Nominal(values)
+        }
+        None => match kind.to_lowercase().as_str() {
+            "numeric" | "real" | "integer" => ArffType::Numeric,
+            _ => return Err(format!("unsupported type '{}' of attribute {}", kind, name)),
+        },
+    };
+    Ok(ArffAttribute { name, kind })
+}
+
+fn binarize(
+    attributes: &[ArffAttribute],
+    values: &[String],
+) -> Result<(usize, Vec<usize>), String> {
+    let position = |name: &str, declared: &[String], value: &str| {
+        declared
+            .iter()
+            .position(|declared| declared == value)
+            .ok_or_else(|| format!("unknown value '{}' of attribute {}", value, name))
+    };
+
+    let (class_attribute, feature_attributes) = attributes.split_last().unwrap();
+    let class = match (&class_attribute.kind, values[values.len() - 1].as_str()) {
+        (_, "?") => return Err("missing class".to_string()),
+        (ArffType::Nominal(declared), value) => position(&class_attribute.name, declared, value)?,
+        (ArffType::Numeric, _) => return Err("the class attribute must be nominal".to_string()),
+    };
+
+    let mut features = vec![];
+    for (attribute, value) in feature_attributes.iter().zip(values.iter()) {
+        let missing = value == "?";
+        match &attribute.kind {
+            ArffType::Numeric => match value.parse::<f64>() {
+                _ if missing => features.push(0),
+                Ok(number) if number == 0.0 || number == 1.0 => features.push(number as usize),
+                _ => {
+                    return Err(format!(
+                        "value '{}' of numeric attribute {} is not binary, it should be binarized first",
+                        value, attribute.name
+                    ))
+                }
+            },
+            ArffType::Nominal(declared) if declared.len() <= 2 => {
+                let rank = match missing {
+                    true => None,
+                    false => Some(position(&attribute.name, declared, value)?),
+                };
+                features.push((rank == Some(declared.len() - 1)) as usize);
+            }
+            ArffType::Nominal(declared) => {
+                let rank = match missing {
+                    true => None,
+                    false => Some(position(&attribute.name, declared, value)?),
+                };
+                features.extend((0..declared.len()).map(|k| (rank == Some(k)) as usize));
+            }
+        }
+    }
+    Ok((class, features))
+}
+
+// Comma separated values, which may be quoted
+fn split_values(line: &str) -> Vec<String> {
+    let mut values = vec![];
+    let mut value = String::new();
+    let mut quote = None;
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => value.push(c),
+            (None, '\'' | '"') => quote = Some(c),
+            (None, ',') => values.push(std::mem::take(&mut value).trim().to_string()),
+            (None, c) => value.push(c),
+        }
+    }
+    if !value.trim().is_empty() || !values.is_empty() {
+        values.push(value.trim().to_string());
+    }
+    values
+}
+
+fn unquote(value: &str) -> String {
+    value.trim_matches(|c| c == '\'' || c == '"').to_string()
+}
+
+#[cfg(test)]
+mod arff_test {
+    use crate::data::arff::{Arff, ArffType};
+    use crate::data::{BinaryData, FileReader};
+
+    fn lines(content: &str) -> Vec<String> {
+        content.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn nominal_attributes_are_binarized() {
+        let content = "% Weather\n\
+            @RELATION weather\n\
+            \n\
+            @ATTRIBUTE outlook {sunny, overcast, rainy}\n\
+            @ATTRIBUTE 'is windy' {FALSE, TRUE}\n\
+            @ATTRIBUTE hot numeric\n\
+            @ATTRIBUTE play {no, yes}\n\
+            @DATA\n\
+            sunny, FALSE, 1, no\n\
+            overcast, TRUE, 0, yes\n\
+            'rainy', ?, ?, yes\n";
+        let arff = Arff::parse(&lines(content)).unwrap();
+
+        assert_eq!(arff.relation, "weather");
+        assert_eq!(arff.attributes[1].name, "is windy");
+        assert_eq!(
+            arff.attributes[3].kind,
+            ArffType::Nominal(vec!["no".into(), "yes".into()])
+        );
+        assert_eq!(
+            arff.samples,
+            vec![
+                (0, vec![1, 0, 0, 0, 1]),
+                (1, vec![0, 1, 0, 1, 0]),
+                (1, vec![0, 0, 1, 0, 0]),
+            ]
+        );
+        assert_eq!(arff.feature_names()[3], "is windy == \"TRUE\"");
+        assert_eq!(arff.feature_names().len(), 5);
+    }
+
+    #[test]
+    fn invalid_files() {
+        let header = "@relation r\n@attribute a numeric\n@attribute c {x, y}\n@data\n";
+        assert!(Arff::parse(&lines(&format!("{}1, x\n", header))).is_ok());
+        for data in ["2, x", "1, z", "1", "{0 1, 1 x}", "1, ?"] {
+            let error = Arff::parse(&lines(&format!("{}{}\n", header, data))).unwrap_err();
+            assert!(error.starts_with("line 5"), "{}", error);
+        }
+        assert!(Arff::parse(&lines("@relation r\n@attribute c numeric\n@data\n")).is_err());
+        assert!(Arff::parse(&lines("@relation r\n@attribute s string\n")).is_err());
+        assert!(Arff::parse(&lines(header.trim_end_matches("@data\n"))).is_err());
+    }
+
+    #[test]
+    fn arff_file_matches_text_file() {
+        let arff = BinaryData::read("test_data/small.arff", false, 0.0);
+        let text = BinaryData::read("test_data/small.txt", false, 0.0);
+        assert_eq!(arff.get_train(), text.get_train());
+        assert_eq!(arff.num_labels(), 2);
+    }
+}
@@ -0,0 +1,256 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ValueEnum)]
+pub enum BinarizationStrategy {
+    Quantile,
+    EqualWidth,
+    Mdlp,
+}
+
+// Turns continuous columns into binary features. Each column gets a list of thresholds and each
+// threshold a binary feature which is 1 when the value is greater than the threshold.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Binarizer {
+    pub strategy: BinarizationStrategy,
+    pub n_bins: usize,
+    thresholds: Vec<Vec<f64>>,
+}
+
+impl Binarizer {
+    pub fn new(strategy: BinarizationStrategy, n_bins: usize) -> Self {
+        Self {
+            strategy,
+            n_bins: n_bins.max(2),
+            thresholds: vec![],
+        }
+    }
+
+    /// Computes the thresholds of each column. The target is required by the MDLP strategy
+    pub fn fit(&mut self, rows: &[Vec<f64>], target: Option<&[usize]>) -> Result<(), String> {
+        let num_columns = rows.first().map_or(0, |row| row.len());
+        if let BinarizationStrategy::Mdlp = self.strategy {
+            match target {
+                None => return Err("The MDLP strategy requires a target".to_string()),
+                Some(target) if target.len() != rows.len() => {
+                    return Err("The target and the data have different sizes".to_string())
+                }
+                _ => {}
+            }
+        }
+
+        self.thresholds = (0..num_columns)
+            .map(|column| {
+                let mut values = rows
+                    .iter()
+                    .enumerate()
+                    .map(|(i, row)| (row[column], target.map_or(0, |target| target[i])))
+                    .filter(|(value, _)| !value.is_nan())
+                    .collect::<Vec<(f64, usize)>>();
+                values.sort_by(|a, b| a.0.total_cmp(&b.0));
+                match self.strategy {
+                    BinarizationStrategy::Quantile => self.quantile_thresholds(&values),
+                    BinarizationStrategy::EqualWidth => self.equal_width_thresholds(&values),
+                    BinarizationStrategy::Mdlp => {
+                        let mut thresholds = vec![];
+                        mdlp_thresholds(&values, &mut thresholds);
+                        thresholds.sort_by(|a, b| a.total_cmp(b));
+                        thresholds
+                    }
+                }
+            })
+            .collect();
+        Ok(())
+    }
+
+    pub fn transform(&self, rows: &[Vec<f64>]) -> Vec<Vec<usize>> {
+        rows.iter()
+            .map(|row| {
+                self.thresholds
+                    .iter()
+                    .zip(row.iter())
+                    .flat_map(|(thresholds, value)| {
+                        thresholds
+                            .iter()
+                            .map(move |threshold| (*value > *threshold) as usize)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn thresholds(&self) -> &[Vec<f64>] {
+        &self.thresholds
+    }
+
+    pub fn num_features(&self) -> usize {
+        self.thresholds
+            .iter()
+            .map(|thresholds| thresholds.len())
+            .sum()
+    }
+
+    /// Column and threshold of each binary feature, in the order of the transformed data
+    pub fn feature_mapping(&self) -> Vec<(usize, f64)> {
+        self.thresholds
+            .iter()
+            .enumerate()
+            .flat_map(|(column, thresholds)| {
+                thresholds.iter().map(move |threshold| (column, *threshold))
+            })
+            .collect()
+    }
+
+    fn quantile_thresholds(&self, values: &[(f64, usize)]) -> Vec<f64> {
+        if values.is_empty() {
+            return vec![];
+        }
+        let max = values[values.len() - 1].0;
+        let mut thresholds = (1..self.n_bins)
+            .map(|bin| values[(bin * (values.len() - 1)) / self.n_bins].0)
+            .filter(|threshold| *threshold < max)
+            .collect::<Vec<f64>>();
+        thresholds.dedup();
+        thresholds
+    }
+
+    fn equal_width_thresholds(&self, values: &[(f64, usize)]) -> Vec<f64> {
+        if values.is_empty() {
+            return vec![];
+        }
+        let (min, max) = (values[0].0, values[values.len() - 1].0);
+        if min == max {
+            return vec![];
+        }
+        let width = (max - min) / self.n_bins as f64;
+        (1..self.n_bins)
+            .map(|bin| min + width * bin as f64)
+            .collect()
+    }
+}
+
+// Entropy of a set of samples with its number of classes having samples
+type Entropy = (f64, usize);
+
+// Entropy of the class counts of a set of samples
+fn entropy(counts: &[usize]) -> Entropy {
+    let total = counts.iter().sum::<usize>() as f64;
+    counts
+        .iter()
+        .filter(|count| **count > 0)
+        .fold((0.0, 0), |(entropy, classes), count| {
+            let p = *count as f64 / total;
+            (entropy - p * p.log2(), classes + 1)
+        })
+}
+
+// Fayyad and Irani entropy based discretization, the values are sorted. The cuts are evaluated in
+// one sweep, the class counts of the left part growing by one sample at each step
+fn mdlp_thresholds(values: &[(f64, usize)], thresholds: &mut Vec<f64>) {
+    let size = values.len();
+    if size < 2 {
+        return;
+    }
+    // Classes numbered from 0 in the order they appear
+    let mut numbers = HashMap::new();
+    let classes = values
+        .iter()
+        .map(|(_, label)| {
+            let next = numbers.len();
+            *numbers.entry(*label).or_insert(next)
+        })
+        .collect::<Vec<usize>>();
+    let mut right_counts = vec![0usize; numbers.len()];
+    for class in &classes {
+        right_counts[*class] += 1;
+    }
+    let (parent_entropy, parent_classes) = entropy(&right_counts);
+    if parent_entropy == 0.0 {
+        return;
+    }
+
+    let mut left_counts = vec![0usize; numbers.len()];
+    let mut best: Option<(f64, usize, [Entropy; 2])> = None;
+    for cut in 1..size {
+        left_counts[classes[cut - 1]] += 1;
+        right_counts[classes[cut - 1]] -= 1;
+        if values[cut - 1].0 == values[cut].0 {
+            continue;
+        }
+        let (left, right) = (entropy(&left_counts), entropy(&right_counts));
+        let weighted = (cut as f64 * left.0 + (size - cut) as f64 * right.0) / size as f64;
+        if best.is_none_or(|(entropy, _, _)| weighted < entropy) {
+            best = Some((weighted, cut, [left, right]));
+        }
+    }
+
+    if let Some((weighted, cut, [(left, left_classes), (right, right_classes)])) = best {
+        let gain = parent_entropy - weighted;
+        let delta = (3f64.powi(parent_classes as i32) - 2.0).log2()
+            - (parent_classes as f64 * parent_entropy
+                - left_classes as f64 * left
+                - right_classes as f64 * right);
+        if gain > ((size as f64 - 1.0).log2() + delta) / size as f64 {
+            thresholds.push((values[cut - 1].0 + values[cut].0) / 2.0);
+            mdlp_thresholds(&values[..cut], thresholds);
+            mdlp_thresholds(&values[cut..], thresholds);
+        }
+    }
+}
+
+#[cfg(test)]
+mod binarizer_test {
+    use crate::data::binarizer::{BinarizationStrategy, Binarizer};
+
+    fn column(values: &[f64]) -> Vec<Vec<f64>> {
+        values.iter().map(|value| vec![*value]).collect()
+    }
+
+    #[test]
+    fn equal_width_thresholds() {
+        let mut binarizer = Binarizer::new(BinarizationStrategy::EqualWidth, 4);
+        binarizer.fit(&column(&[0.0, 1.0, 2.0, 8.0]), None).unwrap();
+        assert_eq!(binarizer.thresholds(), &[vec![2.0, 4.0, 6.0]]);
+        assert_eq!(
+            binarizer.transform(&column(&[1.0, 5.0])),
+            vec![vec![0, 0, 0], vec![1, 1, 0]]
+        );
+    }
+
+    #[test]
+    fn quantile_thresholds_are_unique() {
+        let mut binarizer = Binarizer::new(BinarizationStrategy::Quantile, 4);
+        binarizer
+            .fit(
+                &column(&[1.0, 1.0, 1.0, 1.0, 1.0, 2.0, 3.0, 4.0, 6.0]),
+                None,
+            )
+            .unwrap();
+        assert_eq!(binarizer.thresholds(), &[vec![1.0, 3.0]]);
+        assert_eq!(binarizer.feature_mapping(), vec![(0, 1.0), (0, 3.0)]);
+    }
+
+    #[test]
+    fn mdlp_separates_the_classes() {
+        let rows = column(&[1.0, 2.0, 3.0, 4.0, 10.0, 11.0, 12.0, 13.0]);
+        let target = [0, 0, 0, 0, 1, 1, 1, 1];
+        let mut binarizer = Binarizer::new(BinarizationStrategy::Mdlp, 2);
+        assert!(binarizer.fit(&rows, None).is_err());
+        binarizer.fit(&rows, Some(&target)).unwrap();
+        assert_eq!(binarizer.thresholds(), &[vec![7.0]]);
+    }
+
+    #[test]
+    fn mdlp_cuts_between_repeated_values() {
+        let values = [
+            1.0, 1.0, 2.0, 2.0, 2.0, 5.0, 5.0, 6.0, 6.0, 6.0, 9.0, 9.0, 10.0, 10.0,
+        ];
+        let target = [7, 7, 7, 7, 7, 3, 3, 3, 3, 3, 5, 5, 5, 5];
+        let mut binarizer = Binarizer::new(BinarizationStrategy::Mdlp, 2);
+        binarizer.fit(&column(&values), Some(&target)).unwrap();
+        let mut thresholds = binarizer.thresholds()[0].clone();
+        thresholds.sort_by(|a, b| a.total_cmp(b));
+        assert_eq!(thresholds, vec![3.5, 7.5]);
+    }
+}
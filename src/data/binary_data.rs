@@ -1,9 +1,57 @@
-use crate::data::{Data, FileReader};
-use ndarray::{Array, IxDyn};
+use crate::data::arff::Arff;
+use crate::data::{Data, DataReaderError, FileReader};
+use clap::ValueEnum;
+use log::debug;
+use ndarray::{Array, ArrayBase, Axis, IxDyn};
 use rand::seq::SliceRandom;
 use rand::thread_rng;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
+// How missing values, given as NaN, are read
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ValueEnum)]
+pub enum MissingValuesPolicy {
+    Zero,
+    SeparateAttribute,
+}
+
+/// Binary input from values where NaN marks a missing value. Missing values are read as 0 and, with
+/// `SeparateAttribute`, each column having some gets an extra attribute which is 1 when the value
+/// is missing. Extra attributes come after the original ones and the columns they stand for are
+/// returned
+pub fn encode_missing_values<S: ndarray::Data<Elem = f64>>(
+    input: &ArrayBase<S, IxDyn>,
+    policy: MissingValuesPolicy,
+) -> (Array<usize, IxDyn>, Vec<usize>) {
+    let binary = input.map(|a| if a.is_nan() { 0 } else { *a as usize });
+    let missing_columns = match policy {
+        MissingValuesPolicy::Zero => vec![],
+        MissingValuesPolicy::SeparateAttribute => input
+            .axis_iter(Axis(1))
+            .enumerate()
+            .filter(|(_, column)| column.iter().any(|a| a.is_nan()))
+            .map(|(column, _)| column)
+            .collect::<Vec<usize>>(),
+    };
+    if missing_columns.is_empty() {
+        return (binary, missing_columns);
+    }
+
+    let num_rows = input.shape()[0];
+    let num_columns = input.shape()[1] + missing_columns.len();
+    let mut values = Vec::with_capacity(num_rows * num_columns);
+    for (row, binary_row) in input.axis_iter(Axis(0)).zip(binary.axis_iter(Axis(0))) {
+        values.extend(binary_row.iter().copied());
+        values.extend(
+            missing_columns
+                .iter()
+                .map(|column| row[*column].is_nan() as usize),
+        );
+    }
+    let encoded = Array::from_shape_vec(IxDyn(&[num_rows, num_columns]), values).unwrap();
+    (encoded, missing_columns)
+}
+
 #[derive(Clone)]
 pub struct BinaryData {
     filename: String,
@@ -19,40 +67,7 @@ pub struct BinaryData {
 
 impl FileReader for BinaryData {
     fn read(filename: &str, shuffle: bool, split: f64) -> Self {
-        let mut data = Self::open_file(filename).unwrap();
-        let size = data.len();
-
-        if shuffle {
-            data.shuffle(&mut thread_rng())
-        }
-
-        let test_size = (size as f64 * split) as usize;
-
-        let test = match test_size >= 1 {
-            true => Some(BinaryData::create_set(
-                data.drain(0..test_size).collect::<Vec<String>>(),
-            )),
-            false => None,
-        };
-
-        let train = BinaryData::create_set(data);
-        let train_size = train.1.len();
-        let num_attributes = train.1[0].len();
-        let num_labels = train
-            .0
-            .as_ref()
-            .map_or(0, |elem| elem.iter().collect::<HashSet<_>>().len());
-        Self {
-            filename: filename.to_string(),
-            shuffle,
-            split,
-            train,
-            test,
-            size,
-            train_size,
-            num_labels,
-            num_attributes,
-        }
+        Self::try_read(filename, shuffle, split).unwrap_or_else(|e| panic!("{}", e))
     }
 
     fn read_from_numpy(input: &Array<usize, IxDyn>, target: Option<&Array<usize, IxDyn>>) -> Self {
@@ -107,28 +122,145 @@ impl FileReader for BinaryData {
 }
 
 impl BinaryData {
-    fn create_set(data: Vec<String>) -> Data {
-        let data = data
-            .iter()
-            .map(|line| {
-                line.split_whitespace()
-                    .map(|y| y.parse().unwrap())
-                    .collect::<Vec<usize>>()
-            })
-            .collect::<Vec<Vec<usize>>>();
-        let targets = data.iter().map(|row| row[0]).collect::<Vec<usize>>();
-        let rows = data
-            .iter()
-            .map(|row| row[1..].to_vec())
-            .collect::<Vec<Vec<usize>>>();
+    /// Same as `read`, with an error giving the line, the column and the token of the first
+    /// invalid value instead of a panic. Files with the .arff extension are read as ARFF, the
+    /// others as lines holding the class and the binary features separated by whitespaces
+    pub fn try_read(filename: &str, shuffle: bool, split: f64) -> Result<Self, DataReaderError> {
+        let lines = Self::open_file(filename).map_err(|e| DataReaderError::Io {
+            file: filename.to_string(),
+            message: e.to_string(),
+        })?;
+        let mut data = match filename.to_lowercase().ends_with(".arff") {
+            true => {
+                Arff::parse(&lines)
+                    .map_err(|message| DataReaderError::Format {
+                        file: filename.to_string(),
+                        message,
+                    })?
+                    .samples
+            }
+            false => {
+                let mut data: Vec<(usize, Vec<usize>)> = vec![];
+                for (number, line) in lines.iter().enumerate() {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let width = data.first().map(|(_, features)| features.len());
+                    data.push(BinaryData::parse_line(line, width).map_err(
+                        |(column, token, message)| DataReaderError::Parse {
+                            file: filename.to_string(),
+                            line: number + 1,
+                            column,
+                            token,
+                            message,
+                        },
+                    )?);
+                }
+                data
+            }
+        };
+        if data.is_empty() {
+            return Err(DataReaderError::Format {
+                file: filename.to_string(),
+                message: "the file has no sample".to_string(),
+            });
+        }
+        let size = data.len();
+
+        if shuffle {
+            data.shuffle(&mut thread_rng())
+        }
+
+        let test_size = (size as f64 * split) as usize;
+
+        let test = match test_size >= 1 {
+            true => Some(BinaryData::create_set(
+                data.drain(0..test_size)
+                    .collect::<Vec<(usize, Vec<usize>)>>(),
+            )),
+            false => None,
+        };
+
+        let train = BinaryData::create_set(data);
+        let train_size = train.1.len();
+        let num_attributes = train.1[0].len();
+        debug!(
+            "Read {} samples with {} attributes from {}",
+            size, num_attributes, filename
+        );
+        let num_labels = train
+            .0
+            .as_ref()
+            .map_or(0, |elem| elem.iter().collect::<HashSet<_>>().len());
+        Ok(Self {
+            filename: filename.to_string(),
+            shuffle,
+            split,
+            train,
+            test,
+            size,
+            train_size,
+            num_labels,
+            num_attributes,
+        })
+    }
+
+    // Class and features of a line, which must have the given number of features. Errors give the
+    // column and the token they come from
+    fn parse_line(
+        line: &str,
+        width: Option<usize>,
+    ) -> Result<(usize, Vec<usize>), (usize, String, String)> {
+        let column = |token: &str| {
+            let offset = token.as_ptr() as usize - line.as_ptr() as usize;
+            line[..offset].chars().count() + 1
+        };
+        // Blank lines are skipped before, so there is always a class token
+        let mut tokens = line.split_whitespace();
+        let token = tokens.next().unwrap_or_default();
+        let class = token.parse::<usize>().map_err(|_| {
+            (
+                column(token),
+                token.to_string(),
+                "invalid class".to_string(),
+            )
+        })?;
+
+        let mut features = vec![];
+        for token in tokens {
+            if width == Some(features.len()) {
+                let message = format!("too many features, expected {}", features.len());
+                return Err((column(token), token.to_string(), message));
+            }
+            match token {
+                "0" => features.push(0),
+                "1" => features.push(1),
+                _ => {
+                    let message = "feature value is not binary".to_string();
+                    return Err((column(token), token.to_string(), message));
+                }
+            }
+        }
+        match width {
+            Some(width) if features.len() < width => Err((
+                line.trim_end().chars().count() + 1,
+                String::new(),
+                format!("{} features, expected {}", features.len(), width),
+            )),
+            _ => Ok((class, features)),
+        }
+    }
+
+    fn create_set(data: Vec<(usize, Vec<usize>)>) -> Data {
+        let (targets, rows) = data.into_iter().unzip();
         (Some(targets), rows)
     }
 }
 
 #[cfg(test)]
 mod binary_data_test {
-    use crate::data::binary_data::BinaryData;
-    use crate::data::FileReader;
+    use crate::data::binary_data::{encode_missing_values, BinaryData, MissingValuesPolicy};
+    use crate::data::{DataReaderError, FileReader};
     use ndarray::{arr1, arr2};
     use std::panic;
 
@@ -171,6 +303,48 @@ mod binary_data_test {
         assert_eq!(dataset.iter().eq(content.iter()), true);
     }
 
+    #[test]
+    fn parse_errors_give_their_position() {
+        let file = std::env::temp_dir().join("dtrees_parse_errors.txt");
+        let file = file.to_str().unwrap();
+        let cases = [
+            (
+                "0 1 0 1\n1 0 2 1\n",
+                2,
+                5,
+                "2",
+                "feature value is not binary",
+            ),
+            (
+                "0 1 0 1\n\n1 0 1 1 0\n",
+                3,
+                9,
+                "0",
+                "too many features, expected 3",
+            ),
+            ("0 1 0 1\n1  0 1\n", 2, 7, "", "2 features, expected 3"),
+            ("a 1 0 1\n", 1, 1, "a", "invalid class"),
+        ];
+        for (content, line, column, token, message) in cases {
+            std::fs::write(file, content).unwrap();
+            let error = BinaryData::try_read(file, false, 0.0).err().unwrap();
+            assert_eq!(
+                error,
+                DataReaderError::Parse {
+                    file: file.to_string(),
+                    line,
+                    column,
+                    token: token.to_string(),
+                    message: message.to_string(),
+                }
+            );
+        }
+        std::fs::remove_file(file).unwrap();
+
+        let error = BinaryData::try_read("test_data/missing.txt", false, 0.0).err();
+        assert!(matches!(error, Some(DataReaderError::Io { .. })));
+    }
+
     #[test]
     fn binary_dataset_no_shuffle_and_no_split() {
         let dataset = BinaryData::read("test_data/small.txt", false, 0.0);
@@ -216,4 +390,21 @@ mod binary_data_test {
         assert_eq!(dataset.shuffle, false);
         assert_eq!(dataset.test.is_none(), true);
     }
+
+    #[test]
+    fn missing_values_as_separate_attributes() {
+        let input = arr2(&[[1.0, f64::NAN, 0.0], [0.0, 1.0, 1.0]]).into_dyn();
+
+        let (zero, columns) = encode_missing_values(&input, MissingValuesPolicy::Zero);
+        assert!(columns.is_empty());
+        assert_eq!(zero, arr2(&[[1usize, 0, 0], [0, 1, 1]]).into_dyn());
+
+        let (separate, columns) =
+            encode_missing_values(&input, MissingValuesPolicy::SeparateAttribute);
+        assert_eq!(columns, vec![1]);
+        assert_eq!(
+            separate,
+            arr2(&[[1usize, 0, 0, 1], [0, 1, 1, 0]]).into_dyn()
+        );
+    }
 }
@@ -0,0 +1,162 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ValueEnum)]
+pub enum CategoricalEncoding {
+    OneHot,
+    Ordinal,
+}
+
+// Turns categorical columns into binary features. With the one-hot encoding each category of a
+// column gets a feature which is 1 when the value is this category. With the ordinal encoding the
+// sorted categories but the last give a feature which is 1 when the value comes after them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoricalEncoder {
+    pub encoding: CategoricalEncoding,
+    categories: Vec<Vec<String>>,
+}
+
+impl CategoricalEncoder {
+    pub fn new(encoding: CategoricalEncoding) -> Self {
+        Self {
+            encoding,
+            categories: vec![],
+        }
+    }
+
+    /// Records the sorted categories of each column
+    pub fn fit(&mut self, rows: &[Vec<String>]) {
+        let num_columns = rows.first().map_or(0, |row| row.len());
+        self.categories = (0..num_columns)
+            .map(|column| {
+                rows.iter()
+                    .map(|row| row[column].clone())
+                    .collect::<BTreeSet<String>>()
+                    .into_iter()
+                    .collect()
+            })
+            .collect();
+    }
+
+    /// Unknown categories have no active feature with the one-hot encoding and are an error with
+    /// the ordinal one since they cannot be ranked
+    pub fn transform(&self, rows: &[Vec<String>]) -> Result<Vec<Vec<usize>>, String> {
+        rows.iter()
+            .map(|row| {
+                if row.len() != self.categories.len() {
+                    return Err(
+                        "The number of columns differs from the one seen during fit".to_string()
+                    );
+                }
+                let mut features = Vec::with_capacity(self.num_features());
+                for (value, categories) in row.iter().zip(self.categories.iter()) {
+                    let rank = categories.binary_search(value);
+                    match self.encoding {
+                        CategoricalEncoding::OneHot => {
+                            features.extend((0..categories.len()).map(|k| (rank == Ok(k)) as usize))
+                        }
+                        CategoricalEncoding::Ordinal => {
+                            let rank = rank.map_err(|_| format!("Unknown category {}", value))?;
+                            features.extend(
+                                (0..categories.len().saturating_sub(1))
+                                    .map(|k| (rank > k) as usize),
+                            )
+                        }
+                    }
+                }
+                Ok(features)
+            })
+            .collect()
+    }
+
+    pub fn categories(&self) -> &[Vec<String>] {
+        &self.categories
+    }
+
+    pub fn num_features(&self) -> usize {
+        self.feature_mapping().len()
+    }
+
+    /// Column and category of each binary feature, in the order of the transformed data
+    pub fn feature_mapping(&self) -> Vec<(usize, String)> {
+        self.categories
+            .iter()
+            .enumerate()
+            .flat_map(|(column, categories)| {
+                let used = match self.encoding {
+                    CategoricalEncoding::OneHot => categories.len(),
+                    CategoricalEncoding::Ordinal => categories.len().saturating_sub(1),
+                };
+                categories[..used]
+                    .iter()
+                    .map(move |category| (column, category.clone()))
+            })
+            .collect()
+    }
+
+    /// Readable names of the binary features, such as `color == "red"`
+    pub fn feature_names(&self, columns: Option<&[String]>) -> Vec<String> {
+        let operator = match self.encoding {
+            CategoricalEncoding::OneHot => "==",
+            CategoricalEncoding::Ordinal => ">",
+        };
+        self.feature_mapping()
+            .into_iter()
+            .map(|(column, category)| {
+                let name = columns
+                    .and_then(|columns| columns.get(column).cloned())
+                    .unwrap_or_else(|| format!("x{}", column));
+                format!("{} {} \"{}\"", name, operator, category)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod encoder_test {
+    use crate::data::encoder::{CategoricalEncoder, CategoricalEncoding};
+
+    fn rows(values: &[[&str; 2]]) -> Vec<Vec<String>> {
+        values
+            .iter()
+            .map(|row| row.iter().map(|value| value.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn one_hot_encoding() {
+        let data = rows(&[["red", "s"], ["blue", "m"], ["red", "l"]]);
+        let mut encoder = CategoricalEncoder::new(CategoricalEncoding::OneHot);
+        encoder.fit(&data);
+
+        assert_eq!(encoder.num_features(), 5);
+        assert_eq!(
+            encoder.transform(&rows(&[["red", "m"], ["green", "s"]])),
+            Ok(vec![vec![0, 1, 0, 1, 0], vec![0, 0, 0, 0, 1]])
+        );
+        let columns = ["color".to_string(), "size".to_string()];
+        assert_eq!(
+            encoder.feature_names(Some(&columns))[1],
+            "color == \"red\"".to_string()
+        );
+    }
+
+    #[test]
+    fn ordinal_encoding() {
+        let data = rows(&[["a", "x"], ["b", "x"], ["c", "x"]]);
+        let mut encoder = CategoricalEncoder::new(CategoricalEncoding::Ordinal);
+        encoder.fit(&data);
+
+        assert_eq!(
+            encoder.feature_mapping(),
+            vec![(0, "a".into()), (0, "b".into())]
+        );
+        assert_eq!(
+            encoder.transform(&rows(&[["a", "x"], ["c", "x"]])),
+            Ok(vec![vec![0, 0], vec![1, 1]])
+        );
+        assert!(encoder.transform(&rows(&[["d", "x"]])).is_err());
+        assert_eq!(encoder.feature_names(None)[0], "x0 > \"a\"".to_string());
+    }
+}
@@ -0,0 +1,246 @@
+// Binary dataset read from the sparse libsvm/svmlight format, one `label index:value ...` line per
+// sample. Only the attributes at 1 of each sample are kept, so that very sparse datasets never go
+// through dense rows. Indices start at 1 and the labels are numbered in increasing order.
+#[derive(Debug, Clone)]
+pub struct SparseData {
+    // Class of each sample
+    pub targets: Vec<usize>,
+    // Sorted attributes at 1 of each sample
+    pub rows: Vec<Vec<usize>>,
+    pub num_attributes: usize,
+    // Original label of each class
+    pub labels: Vec<f64>,
+}
+
+// Extensions of the files read in the libsvm format
+pub const EXTENSIONS: [&str; 3] = ["svm", "libsvm", "svmlight"];
+
+pub fn is_sparse_file(filename: &str) -> bool {
+    std::path::Path::new(filename)
+        .extension()
+        .is_some_and(|extension| EXTENSIONS.iter().any(|e| extension == *e))
+}
+
+impl SparseData {
+    pub fn read(filename: &str) -> Result<SparseData, String> {
+        let content = std::fs::read_to_string(filename).map_err(|e| e.to_string())?;
+        SparseData::parse(content.lines())
+    }
+
+    /// Empty lines and comments, starting with #, are skipped. Query ids (`qid:`) are ignored and
+    /// values must be 0 or 1
+    pub fn parse<'a, I: Iterator<Item = &'a str>>(lines: I) -> Result<SparseData, String> {
+        let mut samples = vec![];
+        let mut num_attributes = 0;
+        for (number, line) in lines.enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: String| format!("line {}: {}", number + 1, message);
+
+            let mut tokens = line.split_whitespace();
+            let label = tokens
+                .next()
+                .and_then(|label| label.parse::<f64>().ok())
+                .filter(|label| label.is_finite())
+                .ok_or_else(|| error("invalid label".to_string()))?;
+            let mut attributes = vec![];
+            for token in tokens.filter(|token| !token.starts_with("qid:")) {
+                let (index, value) = token
+                    .split_once(':')
+                    .ok_or_else(|| error(format!("invalid entry '{}'", token)))?;
+                let index = index
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|index| *index > 0)
+                    .ok_or_else(|| error(format!("invalid index '{}'", index)))?;
+                match value.parse::<f64>() {
+                    Ok(0.0) => {}
+                    Ok(1.0) => attributes.push(index - 1),
+                    _ => return Err(error(format!("value '{}' is not binary", value))),
+                }
+            }
+            attributes.sort_unstable();
+            attributes.dedup();
+            num_attributes = num_attributes.max(attributes.last().map_or(0, |last| last + 1));
+            samples.push((label, attributes));
+        }
+
+        let mut labels = samples
+            .iter()
+            .map(|(label, _)| *label)
+            .collect::<Vec<f64>>();
+        labels.sort_by(|a, b| a.total_cmp(b));
+        labels.dedup();
+        let (targets, rows) = samples
+            .into_iter()
+            .map(|(label, attributes)| {
+                let class = labels.iter().position(|known| *known == label).unwrap();
+                (class, attributes)
+            })
+            .unzip();
+        Ok(SparseData {
+            targets,
+            rows,
+            num_attributes,
+            labels,
+        })
+    }
+
+    /// Reads a compressed sparse matrix, CSR when `by_rows` is set and CSC otherwise, of the given
+    /// (samples, attributes) shape. Entries absent from the sparse structure are zeros and the
+    /// stored values must be 0 or 1. The targets are already class numbers, the samples have no
+    /// class without them
+    pub fn from_compressed(
+        indptr: &[usize],
+        indices: &[usize],
+        values: &[usize],
+        shape: (usize, usize),
+        by_rows: bool,
+        target: Option<&[usize]>,
+    ) -> Result<SparseData, String> {
+        let (size, num_attributes) = shape;
+        let (outer_size, inner_size) = match by_rows {
+            true => (size, num_attributes),
+            false => (num_attributes, size),
+        };
+        if indptr.len() != outer_size + 1 {
+            return Err(format!(
+                "indptr has {} entries instead of {}",
+                indptr.len(),
+                outer_size + 1
+            ));
+        }
+        if indices.len() != values.len() {
+            return Err("indices and data have different lengths".to_string());
+        }
+        if let Some(target) = target.filter(|target| target.len() != size) {
+            return Err(format!(
+                "The target has {} values for {} samples",
+                target.len(),
+                size
+            ));
+        }
+
+        let mut rows = vec![vec![]; size];
+        for (outer, bounds) in indptr.windows(2).enumerate() {
+            if bounds[0] > bounds[1] || bounds[1] > indices.len() {
+                return Err(format!(
+                    "Invalid indptr bounds {}..{}",
+                    bounds[0], bounds[1]
+                ));
+            }
+            for position in bounds[0]..bounds[1] {
+                let inner = indices[position];
+                if inner >= inner_size {
+                    return Err(format!(
+                        "Index {} out of bounds for dimension {}",
+                        inner, inner_size
+                    ));
+                }
+                match values[position] {
+                    0 => {}
+                    1 => match by_rows {
+                        true => rows[outer].push(inner),
+                        false => rows[inner].push(outer),
+                    },
+                    value => return Err(format!("Value {} is not binary", value)),
+                }
+            }
+        }
+        // The rows of a CSC matrix are filled in increasing order of attributes
+        if by_rows {
+            for row in rows.iter_mut() {
+                row.sort_unstable();
+                row.dedup();
+            }
+        }
+
+        let targets = target.map_or(vec![], |target| target.to_vec());
+        let num_labels = targets.iter().max().map_or(0, |max| max + 1);
+        Ok(SparseData {
+            targets,
+            rows,
+            num_attributes,
+            labels: (0..num_labels).map(|class| class as f64).collect(),
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn num_labels(&self) -> usize {
+        self.labels.len()
+    }
+}
+
+#[cfg(test)]
+mod libsvm_test {
+    use crate::data::libsvm::SparseData;
+
+    #[test]
+    fn sparse_rows() {
+        let content = "# comment\n+1 3:1 1:1\n-1 qid:2 2:0 4:1\n\n1 # no attribute\n";
+        let data = SparseData::parse(content.lines()).unwrap();
+
+        assert_eq!(data.labels, vec![-1.0, 1.0]);
+        assert_eq!(data.targets, vec![1, 0, 1]);
+        assert_eq!(data.rows, vec![vec![0, 2], vec![3], vec![]]);
+        assert_eq!(data.num_attributes, 4);
+        assert_eq!(data.num_labels(), 2);
+    }
+
+    #[test]
+    fn compressed_rows_and_columns() {
+        let labels = [0usize, 0, 1, 1];
+        let csr = SparseData::from_compressed(
+            &[0, 2, 4, 4, 5],
+            &[2, 0, 1, 2, 1],
+            &[1; 5],
+            (4, 3),
+            true,
+            Some(&labels),
+        )
+        .unwrap();
+        let csc = SparseData::from_compressed(
+            &[0, 1, 3, 5],
+            &[0, 1, 3, 0, 1],
+            &[1; 5],
+            (4, 3),
+            false,
+            Some(&labels),
+        )
+        .unwrap();
+
+        for sparse in [csr, csc] {
+            assert_eq!(sparse.rows, vec![vec![0, 2], vec![1, 2], vec![], vec![1]]);
+            assert_eq!(sparse.num_attributes, 3);
+            assert_eq!(sparse.num_labels(), 2);
+            assert_eq!(sparse.targets, labels);
+        }
+    }
+
+    #[test]
+    fn invalid_compressed_matrices() {
+        let compressed = |indptr: &[usize], indices: &[usize], values: &[usize], by_rows| {
+            SparseData::from_compressed(indptr, indices, values, (2, 3), by_rows, None)
+        };
+        assert!(compressed(&[0, 1, 2], &[0, 2], &[1, 1], true).is_ok());
+        // Attribute 3 in a row, sample 2 in a column
+        assert!(compressed(&[0, 1, 2], &[0, 3], &[1, 1], true).is_err());
+        assert!(compressed(&[0, 1, 1, 2], &[0, 2], &[1, 1], false).is_err());
+        assert!(compressed(&[0, 1], &[0], &[1], true).is_err());
+        assert!(compressed(&[0, 2, 1], &[0, 1], &[1, 1], true).is_err());
+        assert!(compressed(&[0, 1, 2], &[0, 1], &[1, 2], true).is_err());
+    }
+
+    #[test]
+    fn invalid_lines() {
+        for line in ["a 1:1", "1 0:1", "1 2", "1 2:0.5", "1 x:1"] {
+            let error = SparseData::parse(["0 1:1", line].into_iter()).unwrap_err();
+            assert!(error.starts_with("line 2"), "{}", error);
+        }
+    }
+}
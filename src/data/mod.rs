@@ -1,4 +1,8 @@
+pub mod arff;
+pub mod binarizer;
 pub mod binary_data;
+pub mod encoder;
+pub mod libsvm;
 
 pub use binary_data::BinaryData;
 use ndarray::{Array, IxDyn};
@@ -7,6 +11,49 @@ use std::io::{BufRead, BufReader, Error};
 
 pub type Data = (Option<Vec<usize>>, Vec<Vec<usize>>);
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataReaderError {
+    Io {
+        file: String,
+        message: String,
+    },
+    // Line and column start at 1, the column being the one of the first character of the token
+    Parse {
+        file: String,
+        line: usize,
+        column: usize,
+        token: String,
+        message: String,
+    },
+    // Error of a whole file format, such as ARFF, with its own context
+    Format {
+        file: String,
+        message: String,
+    },
+}
+
+impl std::fmt::Display for DataReaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DataReaderError::Io { file, message } | DataReaderError::Format { file, message } => {
+                write!(f, "{}: {}", file, message)
+            }
+            DataReaderError::Parse {
+                file,
+                line,
+                column,
+                token,
+                message,
+            } => match token.is_empty() {
+                true => write!(f, "{}:{}:{}: {}", file, line, column, message),
+                false => write!(f, "{}:{}:{}: {} ('{}')", file, line, column, message, token),
+            },
+        }
+    }
+}
+
+impl std::error::Error for DataReaderError {}
+
 pub trait FileReader {
     fn read(filename: &str, shuffle: bool, split: f64) -> Self;
 
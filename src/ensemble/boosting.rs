@@ -0,0 +1,189 @@
+use crate::globals::{float_is_null, item};
+use crate::searches::optimal::d2::ErrorMinimizer;
+use crate::structures::{RevBitset, Structure};
+use crate::tree::{MissingBranch, Tree};
+
+// AdaBoost (SAMME) over optimal trees of depth 1 or 2. Each stage fits the tree of least weighted
+// error, then increases the weight of the samples it misclassifies. Predictions are the class of
+// largest total estimator weight among the stages
+pub struct BoostedODT {
+    pub n_estimators: usize,
+    pub max_depth: usize,
+    pub min_sup: usize,
+    pub learning_rate: f64,
+    pub estimators: Vec<Tree>,
+    pub estimator_weights: Vec<f64>,
+    num_labels: usize,
+}
+
+impl BoostedODT {
+    pub fn new(n_estimators: usize, max_depth: usize, min_sup: usize, learning_rate: f64) -> Self {
+        Self {
+            n_estimators,
+            max_depth,
+            min_sup,
+            learning_rate,
+            estimators: vec![],
+            estimator_weights: vec![],
+            num_labels: 0,
+        }
+    }
+
+    /// Boosting stops early once a tree makes no error, or when a tree is no better than chance.
+    /// The structure must hold labelled data and be at the root. The weights of its samples, if
+    /// any, are the initial weights of the boosting and are restored once done
+    pub fn fit(&mut self, structure: &mut RevBitset) {
+        let labels = structure.labels().expect("Boosting needs labelled data");
+        self.num_labels = structure.num_labels().max(2);
+        self.estimators.clear();
+        self.estimator_weights.clear();
+
+        let size = labels.len();
+        let initial_weights = structure.weights().map(|weights| weights.to_vec());
+        let mut weights = initial_weights.clone().unwrap_or_else(|| vec![1.0; size]);
+        let total = weights.iter().sum::<f64>();
+        weights.iter_mut().for_each(|weight| *weight /= total);
+        let mut predictions = vec![0; size];
+        let mut tids = Vec::with_capacity(size);
+        let minimizer = ErrorMinimizer::new();
+        for _ in 0..self.n_estimators {
+            structure
+                .set_weights(Some(weights.clone()))
+                .expect("One weight per sample");
+            let tree = minimizer.fit(self.min_sup, self.max_depth, structure);
+            training_predictions(
+                &tree,
+                tree.get_root_index(),
+                structure,
+                &mut tids,
+                &mut predictions,
+            );
+            let error = (0..size)
+                .filter(|tid| predictions[*tid] != labels[*tid])
+                .map(|tid| weights[tid])
+                .sum::<f64>()
+                / weights.iter().sum::<f64>();
+
+            if float_is_null(error) {
+                self.estimators.push(tree);
+                self.estimator_weights.push(1.0);
+                break;
+            }
+            if error >= 1.0 - 1.0 / self.num_labels as f64 {
+                // The first tree is kept so that the model can still predict
+                if self.estimators.is_empty() {
+                    self.estimators.push(tree);
+                    self.estimator_weights.push(1.0);
+                }
+                break;
+            }
+
+            let alpha = self.learning_rate
+                * (((1.0 - error) / error).ln() + ((self.num_labels - 1) as f64).ln());
+            for (tid, weight) in weights.iter_mut().enumerate() {
+                if predictions[tid] != labels[tid] {
+                    *weight *= alpha.exp();
+                }
+            }
+            let total = weights.iter().sum::<f64>();
+            weights.iter_mut().for_each(|weight| *weight /= total);
+            self.estimators.push(tree);
+            self.estimator_weights.push(alpha);
+        }
+        structure
+            .set_weights(initial_weights)
+            .expect("The initial weights were valid");
+    }
+
+    /// Prediction of the instance after each stage
+    pub fn staged_predict(&self, instance: &[f64], branch: MissingBranch) -> Option<Vec<f64>> {
+        let mut scores = vec![0.0; self.num_labels];
+        let mut stages = Vec::with_capacity(self.estimators.len());
+        for (tree, alpha) in self.estimators.iter().zip(self.estimator_weights.iter()) {
+            let out = tree.predict(instance, branch)? as usize;
+            if let Some(score) = scores.get_mut(out) {
+                *score += alpha;
+            }
+            // Ties go to the smallest class
+            let best =
+                scores
+                    .iter()
+                    .enumerate()
+                    .fold((0, <f64>::NEG_INFINITY), |best, (class, score)| {
+                        match *score > best.1 {
+                            true => (class, *score),
+                            false => best,
+                        }
+                    });
+            stages.push(best.0 as f64);
+        }
+        Some(stages)
+    }
+
+    pub fn predict(&self, instance: &[f64], branch: MissingBranch) -> Option<f64> {
+        self.staged_predict(instance, branch)?.last().copied()
+    }
+}
+
+// Class predicted by the tree for each training transaction, following the tests of the tree down
+// the structure
+fn training_predictions<S: Structure>(
+    tree: &Tree,
+    index: usize,
+    structure: &mut S,
+    tids: &mut Vec<usize>,
+    predictions: &mut [usize],
+) {
+    let node = match tree.get_node(index) {
+        Some(node) => node,
+        None => return,
+    };
+    match node.value.test {
+        Some(test) if node.left != node.right => {
+            for (value, child) in [(0, node.left), (1, node.right)] {
+                structure.push(item(test, value));
+                training_predictions(tree, child, structure, tids, predictions);
+                structure.backtrack();
+            }
+        }
+        _ => {
+            structure.fill_tids(tids);
+            let out = node.value.out.unwrap_or(0.0) as usize;
+            for tid in tids.iter() {
+                predictions[*tid] = out;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_boosting {
+    use crate::data::{BinaryData, FileReader};
+    use crate::ensemble::BoostedODT;
+    use crate::structures::RevBitset;
+    use crate::tree::MissingBranch;
+
+    #[test]
+    fn boosting_improves_on_a_single_tree() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+        let mut boosted = BoostedODT::new(10, 1, 1, 1.0);
+        boosted.fit(&mut structure);
+        assert!(!boosted.estimators.is_empty());
+        assert!(boosted.estimator_weights.iter().all(|alpha| *alpha > 0.0));
+
+        let (labels, rows) = data.get_train();
+        let labels = labels.as_ref().unwrap();
+        let mut correct = vec![0; boosted.estimators.len()];
+        for (row, label) in rows.iter().zip(labels.iter()) {
+            let instance = row.iter().map(|v| *v as f64).collect::<Vec<f64>>();
+            let stages = boosted
+                .staged_predict(&instance, MissingBranch::Left)
+                .unwrap();
+            for (stage, out) in stages.iter().enumerate() {
+                correct[stage] += (*out == *label as f64) as usize;
+            }
+        }
+        assert!(correct.last().unwrap() > correct.first().unwrap());
+    }
+}
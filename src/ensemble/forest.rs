@@ -0,0 +1,131 @@
+use crate::searches::greedy::LGDT;
+use crate::searches::SearchStrategy;
+use crate::structures::{RevBitset, Structure};
+use crate::tree::{MissingBranch, Tree};
+use rand::rngs::StdRng;
+use rand::seq::index::sample;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+
+// Random forest of LGDT trees. Each tree is fitted on a bootstrap sample of the transactions and
+// on a random subset of the attributes, the samples being drawn from a generator seeded once so
+// that a forest is the same for the same seed whatever the number of threads
+pub struct LGDTForest {
+    pub n_trees: usize,
+    // Attributes given to each tree, the square root of their number when None
+    pub max_features: Option<usize>,
+    pub seed: u64,
+    pub min_sup: usize,
+    pub max_depth: usize,
+    pub strategy: SearchStrategy,
+    // Trees tested on the attributes of the whole data
+    pub trees: Vec<Tree>,
+}
+
+impl LGDTForest {
+    pub fn new(n_trees: usize, min_sup: usize, max_depth: usize, strategy: SearchStrategy) -> Self {
+        Self {
+            n_trees,
+            max_features: None,
+            seed: 0,
+            min_sup,
+            max_depth,
+            strategy,
+            trees: vec![],
+        }
+    }
+
+    // Transactions and attributes of each tree
+    fn draw_samples(&self, size: usize, num_attributes: usize) -> Vec<(Vec<usize>, Vec<usize>)> {
+        let max_features = self
+            .max_features
+            .unwrap_or_else(|| (num_attributes as f64).sqrt().ceil() as usize)
+            .clamp(1, num_attributes.max(1));
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        (0..self.n_trees)
+            .map(|_| {
+                let tids = (0..size).map(|_| rng.gen_range(0..size)).collect();
+                let mut attributes = sample(&mut rng, num_attributes, max_features).into_vec();
+                attributes.sort_unstable();
+                (tids, attributes)
+            })
+            .collect()
+    }
+
+    pub fn fit(&mut self, structure: &RevBitset) {
+        let samples = self.draw_samples(structure.num_transactions(), structure.num_attributes());
+        self.trees = samples
+            .into_par_iter()
+            .map(|(tids, attributes)| {
+                let mut sampled = structure
+                    .sample(&tids, &attributes)
+                    .expect("Samples are drawn from the structure");
+                let mut learner = LGDT::new(self.min_sup, self.max_depth, self.strategy);
+                learner.fit(&mut sampled);
+                let mut tree = learner.tree;
+                for index in 0..tree.len() {
+                    if let Some(node) = tree.get_node_mut(index) {
+                        node.value.test = node.value.test.map(|test| attributes[test]);
+                    }
+                }
+                tree
+            })
+            .collect();
+    }
+
+    // Majority vote of the trees, ties going to the smallest output
+    pub fn predict(&self, instance: &[f64], branch: MissingBranch) -> Option<f64> {
+        let mut votes: Vec<(f64, usize)> = vec![];
+        for tree in &self.trees {
+            let out = tree.predict(instance, branch)?;
+            match votes.iter_mut().find(|(vote, _)| *vote == out) {
+                Some((_, count)) => *count += 1,
+                None => votes.push((out, 1)),
+            }
+        }
+        votes
+            .into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then(b.0.total_cmp(&a.0)))
+            .map(|(out, _)| out)
+    }
+}
+
+#[cfg(test)]
+mod test_ensemble {
+    use crate::data::{BinaryData, FileReader};
+    use crate::ensemble::LGDTForest;
+    use crate::searches::SearchStrategy;
+    use crate::structures::RevBitset;
+    use crate::tree::MissingBranch;
+
+    #[test]
+    fn forest_is_reproducible_and_accurate() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let structure = RevBitset::new(&data);
+        let fit = |structure: &RevBitset| {
+            let mut forest = LGDTForest::new(8, 1, 3, SearchStrategy::LessGreedyMurtree);
+            forest.seed = 42;
+            forest.max_features = Some(40);
+            forest.fit(structure);
+            forest
+        };
+        let forest = fit(&structure);
+        let again = fit(&structure);
+        assert_eq!(forest.trees.len(), 8);
+        for (tree, other) in forest.trees.iter().zip(again.trees.iter()) {
+            assert!(tree.structural_eq(other));
+        }
+
+        let (labels, rows) = data.get_train();
+        let labels = labels.as_ref().unwrap();
+        let correct = rows
+            .iter()
+            .zip(labels.iter())
+            .filter(|(row, label)| {
+                let instance = row.iter().map(|v| *v as f64).collect::<Vec<f64>>();
+                forest.predict(&instance, MissingBranch::Left) == Some(**label as f64)
+            })
+            .count();
+        assert!(correct as f64 / data.train_size() as f64 > 0.8);
+    }
+}
@@ -0,0 +1,5 @@
+mod boosting;
+mod forest;
+
+pub use boosting::BoostedODT;
+pub use forest::LGDTForest;
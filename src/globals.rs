@@ -1,5 +1,7 @@
+use crate::structures::Structure;
 use crate::tree::Tree;
 use float_cmp::{ApproxEq, F64Margin};
+use serde::{Deserialize, Deserializer};
 
 // Start: Items and Attributes switchers
 pub fn attribute(item: usize) -> usize {
@@ -27,6 +29,29 @@ pub fn float_is_null(value: f64) -> bool {
     )
 }
 
+// Scores and errors closer than this share of their magnitude are ties
+const TIE_TOLERANCE: f64 = 1e-9;
+
+// Whether two values only differ by the rounding of their computation, which can change between
+// machines
+pub fn float_ties(first: f64, second: f64) -> bool {
+    first == second
+        || (first.is_finite()
+            && second.is_finite()
+            && (first - second).abs() <= TIE_TOLERANCE * first.abs().max(second.abs()).max(1.0))
+}
+
+// Bound above the values tying with the given one, for the searches keeping the values strictly
+// below their bound
+pub fn tie_bound(value: f64) -> f64 {
+    value + 2.0 * TIE_TOLERANCE * value.abs().max(1.0)
+}
+
+// Infinite values are serialized as null, so they are read back as infinity to allow round trips
+pub fn deserialize_infinity<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    Ok(Option::<f64>::deserialize(deserializer)?.unwrap_or(<f64>::INFINITY))
+}
+
 pub fn compute_entropy(classes_support: &[usize]) -> f64 {
     let support = classes_support.iter().sum::<usize>();
     let mut entropy = 0f64;
@@ -55,3 +80,70 @@ pub fn get_tree_root_error(tree: &Tree) -> f64 {
     tree.get_node(tree.get_root_index())
         .map_or(<f64>::INFINITY, |node| node.value.error)
 }
+
+// Stores in each node of the tree the number of samples of each class reaching it
+pub fn fill_classes_support<S: Structure + ?Sized>(tree: &mut Tree, structure: &mut S) {
+    if structure.num_labels() == 0 || tree.is_empty() {
+        return;
+    }
+    let root = tree.get_root_index();
+    fill_classes_support_recursion(tree, structure, root);
+}
+
+// Stores in each node the training samples of the structure reaching it. Internal nodes keep
+// them too, so that a collapsed subtree still knows its samples
+pub fn fill_samples<S: Structure + ?Sized>(tree: &mut Tree, structure: &mut S) {
+    if tree.is_empty() {
+        return;
+    }
+    let root = tree.get_root_index();
+    fill_samples_recursion(tree, structure, root);
+}
+
+fn fill_samples_recursion<S: Structure + ?Sized>(tree: &mut Tree, structure: &mut S, index: usize) {
+    let (test, left, right) = match tree.get_node_mut(index) {
+        Some(node) => {
+            let mut samples = structure.get_tids();
+            samples.sort_unstable();
+            node.value.samples = Some(samples);
+            (node.value.test, node.left, node.right)
+        }
+        None => return,
+    };
+
+    if let Some(attribute) = test {
+        for (branch, child) in [left, right].iter().enumerate() {
+            if *child == 0 {
+                continue;
+            }
+            structure.push(item(attribute, branch));
+            fill_samples_recursion(tree, structure, *child);
+            structure.backtrack();
+        }
+    }
+}
+
+fn fill_classes_support_recursion<S: Structure + ?Sized>(
+    tree: &mut Tree,
+    structure: &mut S,
+    index: usize,
+) {
+    let (test, left, right) = match tree.get_node_mut(index) {
+        Some(node) => {
+            node.value.classes_support = Some(structure.labels_support().to_vec());
+            (node.value.test, node.left, node.right)
+        }
+        None => return,
+    };
+
+    if let Some(attribute) = test {
+        for (branch, child) in [left, right].iter().enumerate() {
+            if *child == 0 {
+                continue;
+            }
+            structure.push(item(attribute, branch));
+            fill_classes_support_recursion(tree, structure, *child);
+            structure.backtrack();
+        }
+    }
+}
@@ -1,9 +1,45 @@
-use crate::globals::{compute_entropy, item};
+use crate::globals::{compute_entropy, float_ties, item};
 use crate::structures::Structure;
 use float_cmp::{ApproxEq, F64Margin};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::cell::RefCell;
 
 pub trait Heuristic {
     fn compute(&self, structure: &mut dyn Structure, candidates: &mut Vec<usize>);
+
+    // Score of each candidate, the higher the better, for the heuristics ranking the candidates
+    // by a score
+    fn scores(&self, _structure: &mut dyn Structure, _candidates: &[usize]) -> Option<Vec<f64>> {
+        None
+    }
+}
+
+// Sorts the candidates by decreasing score. Scores equal up to rounding are ties, the candidates
+// then keep the order they were given in, so that the order is the same on every machine
+pub(crate) fn sort_by_scores(candidates: &mut [usize], scores: &[f64]) {
+    let mut ranked = candidates
+        .iter()
+        .copied()
+        .zip(scores.iter().copied())
+        .enumerate()
+        .collect::<Vec<(usize, (usize, f64))>>();
+    ranked.sort_by(|a, b| b.1 .1.partial_cmp(&a.1 .1).unwrap());
+    let mut start = 0;
+    while start < ranked.len() {
+        let leader = ranked[start].1 .1;
+        let end = start
+            + ranked[start..]
+                .iter()
+                .take_while(|(_, (_, score))| float_ties(*score, leader))
+                .count();
+        ranked[start..end].sort_by_key(|(position, _)| *position);
+        start = end;
+    }
+    for (candidate, (_, (attribute, _))) in candidates.iter_mut().zip(ranked) {
+        *candidate = attribute;
+    }
 }
 
 #[derive(Default)]
@@ -13,22 +49,95 @@ impl Heuristic for NoHeuristic {
     fn compute(&self, _structure: &mut dyn Structure, _candidates: &mut Vec<usize>) {}
 }
 
+// Shuffles the candidates. The generator is seeded once, so a search explores the same order for
+// the same seed
+pub struct RandomHeuristic {
+    rng: RefCell<StdRng>,
+}
+
+impl RandomHeuristic {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: RefCell::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+}
+
+impl Default for RandomHeuristic {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl Heuristic for RandomHeuristic {
+    fn compute(&self, _structure: &mut dyn Structure, candidates: &mut Vec<usize>) {
+        candidates.shuffle(&mut *self.rng.borrow_mut());
+    }
+}
+
+// Orders the candidates by the first heuristic, ties being broken by the next ones and finally by
+// the smallest attribute index. Heuristics sort stably, so they are applied from the last one
+pub struct CompositeHeuristic {
+    heuristics: Vec<Box<dyn Heuristic>>,
+}
+
+impl CompositeHeuristic {
+    pub fn new(heuristics: Vec<Box<dyn Heuristic>>) -> Self {
+        Self { heuristics }
+    }
+}
+
+impl Heuristic for CompositeHeuristic {
+    fn compute(&self, structure: &mut dyn Structure, candidates: &mut Vec<usize>) {
+        candidates.sort_unstable();
+        for heuristic in self.heuristics.iter().rev() {
+            heuristic.compute(structure, candidates);
+        }
+    }
+
+    // Scores of the first heuristic, the one the order mostly depends on
+    fn scores(&self, structure: &mut dyn Structure, candidates: &[usize]) -> Option<Vec<f64>> {
+        self.heuristics
+            .first()
+            .and_then(|heuristic| heuristic.scores(structure, candidates))
+    }
+}
+
 #[derive(Default)]
 pub struct GiniIndex;
 
 impl Heuristic for GiniIndex {
     fn compute(&self, structure: &mut dyn Structure, candidates: &mut Vec<usize>) {
         let root_classes_support = structure.labels_support().to_vec();
-        let mut candidates_sorted = vec![];
-        for attribute in candidates.iter() {
-            let gini = Self::gini_index(*attribute, structure, &root_classes_support);
-            candidates_sorted.push((*attribute, gini));
-        }
-        candidates_sorted.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
-        *candidates = candidates_sorted
+        // The lowest impurities come first
+        let scores = candidates
             .iter()
-            .map(|(a, _)| *a)
-            .collect::<Vec<usize>>();
+            .map(|attribute| -Self::gini_index(*attribute, structure, &root_classes_support))
+            .collect::<Vec<f64>>();
+        sort_by_scores(candidates, &scores);
+    }
+
+    // Decrease of the Gini impurity brought by the split
+    fn scores(&self, structure: &mut dyn Structure, candidates: &[usize]) -> Option<Vec<f64>> {
+        let root_classes_support = structure.labels_support().to_vec();
+        let size = root_classes_support.iter().sum::<usize>();
+        let root_gini = match size {
+            0 => 0f64,
+            _ => {
+                1. - root_classes_support
+                    .iter()
+                    .map(|support| (*support as f64 / size as f64).powf(2.))
+                    .sum::<f64>()
+            }
+        };
+        Some(
+            candidates
+                .iter()
+                .map(|attribute| {
+                    root_gini - Self::gini_index(*attribute, structure, &root_classes_support)
+                })
+                .collect(),
+        )
     }
 }
 
@@ -76,6 +185,80 @@ impl GiniIndex {
     }
 }
 
+#[derive(Default)]
+pub struct WeightOfEvidence;
+
+impl Heuristic for WeightOfEvidence {
+    fn compute(&self, structure: &mut dyn Structure, candidates: &mut Vec<usize>) {
+        let values = self.scores(structure, candidates).unwrap_or_default();
+        sort_by_scores(candidates, &values);
+    }
+
+    fn scores(&self, structure: &mut dyn Structure, candidates: &[usize]) -> Option<Vec<f64>> {
+        let root_classes_support = structure.labels_support().to_vec();
+        Some(
+            candidates
+                .iter()
+                .map(|attribute| {
+                    Self::information_value(*attribute, structure, &root_classes_support)
+                })
+                .collect(),
+        )
+    }
+}
+
+impl WeightOfEvidence {
+    // Added to each cell count so that empty branches do not lead to infinite weights
+    const SMOOTHING: f64 = 0.5;
+
+    // Information value of the split, averaged over the classes taken as event one versus the rest
+    fn information_value(
+        attribute: usize,
+        structure: &mut dyn Structure,
+        root_classes_support: &[usize],
+    ) -> f64 {
+        let _ = structure.push(item(attribute, 0));
+        let left_classes_supports = structure.labels_support().to_vec();
+        structure.backtrack();
+
+        let right_classes_support = root_classes_support
+            .iter()
+            .enumerate()
+            .map(|(idx, val)| *val - left_classes_supports[idx])
+            .collect::<Vec<usize>>();
+
+        let actual_size = root_classes_support.iter().sum::<usize>();
+        let mut information_value = 0f64;
+        let mut num_events = 0;
+
+        for (class, events) in root_classes_support.iter().enumerate() {
+            if *events == 0 {
+                continue;
+            }
+            num_events += 1;
+            let non_events = actual_size - *events;
+
+            for branch in [&left_classes_supports, &right_classes_support] {
+                let branch_events = branch[class];
+                let branch_non_events = branch.iter().sum::<usize>() - branch_events;
+
+                let events_rate = (branch_events as f64 + Self::SMOOTHING)
+                    / (*events as f64 + 2. * Self::SMOOTHING);
+                let non_events_rate = (branch_non_events as f64 + Self::SMOOTHING)
+                    / (non_events as f64 + 2. * Self::SMOOTHING);
+
+                information_value +=
+                    (events_rate - non_events_rate) * (events_rate / non_events_rate).ln();
+            }
+        }
+
+        match num_events {
+            0 => 0f64,
+            _ => information_value / num_events as f64,
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct InformationGain;
 
@@ -85,6 +268,10 @@ impl Heuristic for InformationGain {
     fn compute(&self, structure: &mut dyn Structure, candidates: &mut Vec<usize>) {
         self.internally_compute(structure, candidates, false);
     }
+
+    fn scores(&self, structure: &mut dyn Structure, candidates: &[usize]) -> Option<Vec<f64>> {
+        Some(self.information_gains(structure, candidates, false))
+    }
 }
 
 #[derive(Default)]
@@ -96,6 +283,10 @@ impl Heuristic for InformationGainRatio {
     fn compute(&self, structure: &mut dyn Structure, candidates: &mut Vec<usize>) {
         self.internally_compute(structure, candidates, true);
     }
+
+    fn scores(&self, structure: &mut dyn Structure, candidates: &[usize]) -> Option<Vec<f64>> {
+        Some(self.information_gains(structure, candidates, true))
+    }
 }
 
 // Information Gain and Information Gain Ratio handler
@@ -107,24 +298,30 @@ trait Handler {
         attributes: &mut Vec<usize>,
         ratio: bool,
     ) {
+        let info_gains = self.information_gains(structure, attributes, ratio);
+        sort_by_scores(attributes, &info_gains);
+    }
+
+    fn information_gains(
+        &self,
+        structure: &mut dyn Structure,
+        attributes: &[usize],
+        ratio: bool,
+    ) -> Vec<f64> {
         let root_classes_support = structure.labels_support().to_vec();
         let parent_entropy = compute_entropy(&root_classes_support);
-        let mut candidates_sorted = vec![];
-        for attribute in attributes.iter() {
-            let info_gain = Self::information_gain(
-                *attribute,
-                structure,
-                &root_classes_support,
-                parent_entropy,
-                ratio,
-            );
-            candidates_sorted.push((*attribute, info_gain));
-        }
-        candidates_sorted.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-        *attributes = candidates_sorted
+        attributes
             .iter()
-            .map(|(a, _)| *a)
-            .collect::<Vec<usize>>();
+            .map(|attribute| {
+                Self::information_gain(
+                    *attribute,
+                    structure,
+                    &root_classes_support,
+                    parent_entropy,
+                    ratio,
+                )
+            })
+            .collect()
     }
 
     fn information_gain(
@@ -188,3 +385,115 @@ trait Handler {
         info_gain
     }
 }
+
+#[cfg(test)]
+mod heuristics_test {
+    use crate::data::{BinaryData, FileReader};
+    use crate::heuristics::{
+        sort_by_scores, CompositeHeuristic, GiniIndex, Handler, Heuristic, InformationGain,
+        InformationGainRatio, NoHeuristic, RandomHeuristic, WeightOfEvidence,
+    };
+    use crate::structures::{Bitset, Structure};
+
+    #[test]
+    fn near_ties_keep_the_given_order() {
+        let mut candidates = vec![3, 1, 2, 0];
+        sort_by_scores(&mut candidates, &[1.0, 1.0 + 1e-15, 0.5, 2.0]);
+        assert_eq!(candidates, vec![0, 3, 1, 2]);
+    }
+
+    #[test]
+    fn scores_follow_the_order() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = Bitset::new(&data);
+        let heuristics: Vec<Box<dyn Heuristic>> = vec![
+            Box::new(InformationGain),
+            Box::new(InformationGainRatio),
+            Box::new(GiniIndex),
+            Box::new(WeightOfEvidence),
+        ];
+        for heuristic in heuristics {
+            let mut candidates = (0..structure.num_attributes()).collect::<Vec<usize>>();
+            heuristic.compute(&mut structure, &mut candidates);
+            let scores = heuristic.scores(&mut structure, &candidates).unwrap();
+            assert!(scores.windows(2).all(|pair| pair[0] >= pair[1] - 1e-12));
+        }
+        assert!(NoHeuristic.scores(&mut structure, &[0, 1]).is_none());
+    }
+
+    #[test]
+    fn weight_of_evidence_handles_empty_cells() {
+        let data = BinaryData::read("test_data/small.txt", false, 0.0);
+        let mut structure = Bitset::new(&data);
+        let mut candidates = vec![0, 1, 2];
+
+        WeightOfEvidence.compute(&mut structure, &mut candidates);
+        assert_eq!(candidates, vec![2, 0, 1]);
+    }
+
+    #[test]
+    fn random_heuristic_is_reproducible() {
+        let data = BinaryData::read("test_data/small.txt", false, 0.0);
+        let mut structure = Bitset::new(&data);
+
+        let mut orders = |seed: u64| {
+            let heuristic = RandomHeuristic::new(seed);
+            (0..5)
+                .map(|_| {
+                    let mut candidates = (0..20).collect::<Vec<usize>>();
+                    heuristic.compute(&mut structure, &mut candidates);
+                    candidates
+                })
+                .collect::<Vec<Vec<usize>>>()
+        };
+        let first = orders(42);
+        assert_eq!(first, orders(42));
+        assert_ne!(first, orders(43));
+        assert_ne!(first[0], first[1]);
+    }
+
+    // Scores every candidate the same except the given one, put first
+    struct Favorite(usize);
+
+    impl Heuristic for Favorite {
+        fn compute(&self, _: &mut dyn Structure, candidates: &mut Vec<usize>) {
+            candidates.sort_by_key(|attribute| *attribute != self.0);
+        }
+    }
+
+    #[test]
+    fn composite_heuristic_breaks_ties() {
+        let data = BinaryData::read("test_data/small.txt", false, 0.0);
+        let mut structure = Bitset::new(&data);
+
+        let mut candidates = vec![2, 1, 0];
+        CompositeHeuristic::new(vec![Box::<NoHeuristic>::default()])
+            .compute(&mut structure, &mut candidates);
+        assert_eq!(candidates, vec![0, 1, 2]);
+
+        // The second heuristic only orders the candidates tied by the first one
+        let heuristic = CompositeHeuristic::new(vec![
+            Box::new(Favorite(1)),
+            Box::<InformationGain>::default(),
+        ]);
+        let mut candidates = vec![0, 1, 2];
+        heuristic.compute(&mut structure, &mut candidates);
+        assert_eq!(candidates, vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn information_gain_ratio_divides_by_the_split_information() {
+        let data = BinaryData::read("test_data/small.txt", false, 0.0);
+        let mut structure = Bitset::new(&data);
+
+        // Attribute 2 separates the classes, attribute 0 isolates a single sample
+        let gain = InformationGain::information_gain(0, &mut structure, &[2, 2], 1.0, false);
+        let ratio = InformationGainRatio::information_gain(0, &mut structure, &[2, 2], 1.0, true);
+        let split_info = -(0.25f64 * 0.25f64.log2() + 0.75 * 0.75f64.log2());
+        assert!((ratio - gain / split_info).abs() < 1e-9);
+
+        let mut candidates = vec![0, 1, 2];
+        InformationGainRatio.compute(&mut structure, &mut candidates);
+        assert_eq!(candidates, vec![2, 0, 1]);
+    }
+}
@@ -1,5 +1,6 @@
 pub mod cache;
 pub mod data;
+pub mod ensemble;
 pub mod globals;
 pub mod heuristics;
 pub mod searches;
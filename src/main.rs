@@ -1,21 +1,30 @@
+use crate::cache::hashmap::HashMapCache;
 use crate::cache::trie::Trie;
 use crate::cache::Caching;
-use crate::data::{BinaryData, FileReader};
-use crate::heuristics::{GiniIndex, Heuristic, InformationGain, InformationGainRatio, NoHeuristic};
-use crate::parser::{App, ArgCommand};
+use crate::data::FileReader;
+use crate::globals::fill_classes_support;
+use crate::heuristics::{
+    CompositeHeuristic, GiniIndex, Heuristic, InformationGain, InformationGainRatio, NoHeuristic,
+    RandomHeuristic, WeightOfEvidence,
+};
+use crate::parser::{App, ArgCommand, OutputFormat};
 use crate::searches::errors::NativeError;
 use crate::searches::greedy::LGDT;
 use crate::searches::optimal::d2::GenericDepth2;
 use crate::searches::optimal::DL85;
+use crate::searches::trace::JsonlTracer;
 use crate::searches::{
-    CacheType, D2Objective, NodeExposedData, SearchHeuristic, SearchStrategy, Statistics,
+    CacheType, D2Objective, NodeExposedData, Objective, SearchHeuristic, SearchStrategy, Statistics,
 };
-use crate::structures::RevBitset;
+use crate::structures::{RevBitset, Structure};
 use crate::tree::Tree;
-use clap::Parser;
+use serde_json::json;
+use std::path::PathBuf;
 
+mod bench;
 mod cache;
 mod data;
+mod ensemble;
 mod globals;
 mod heuristics;
 mod parser;
@@ -23,19 +32,65 @@ mod searches;
 mod structures;
 mod tree;
 
+// Writes the records to stderr, prefixed by their level
+struct StderrLogger;
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("[{}] {}", record.level(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: StderrLogger = StderrLogger;
+
 fn main() {
-    let app = App::parse();
+    let app = App::parse_with_config();
+    log::set_logger(&LOGGER).expect("Unable to set the logger");
+    log::set_max_level(app.verbose.level_filter());
+
+    if let ArgCommand::bench {
+        dir,
+        extension,
+        depths,
+        algorithms,
+        caches,
+        support,
+        timeout,
+    } = &app.command
+    {
+        let records = bench::run(
+            dir, extension, &depths.0, algorithms, caches, *support, *timeout,
+        )
+        .expect("Unable to read the datasets directory");
+        let output = match app.format {
+            OutputFormat::Text => bench::to_csv(&records),
+            OutputFormat::Json => serde_json::to_string_pretty(&records).unwrap() + "\n",
+        };
+        write_output(app.output.as_ref(), &output);
+        return;
+    }
 
-    if !app.input.exists() {
+    if !app.input().exists() {
         panic!("File does not exist");
     }
 
-    let file = app.input.to_str().unwrap();
-    let data = BinaryData::read(file, false, 0.0);
-    let mut structure = RevBitset::new(&data);
+    let file = app.input().to_str().unwrap().to_string();
+    let mut structure = bench::Dataset::read(&file).structure();
+    structure
+        .set_sparse_threshold(app.sparse_threshold)
+        .unwrap_or_else(|e| panic!("{}", e));
 
     let mut statistics = Statistics::default();
     let mut tree = Tree::default();
+    let command = serde_json::to_value(&app.command).unwrap();
 
     match app.command {
         ArgCommand::d2_odt {
@@ -53,12 +108,15 @@ fn main() {
 
             let mut learner = GenericDepth2::new(strategy);
             tree = learner.fit(support, depth, &mut structure);
+            fill_classes_support(&mut tree, &mut structure);
         }
 
         ArgCommand::lgdt {
             support,
             depth,
             objective,
+            heuristic,
+            min_score,
         } => {
             let strategy = match objective {
                 D2Objective::Error => SearchStrategy::LessGreedyMurtree,
@@ -66,6 +124,8 @@ fn main() {
             };
 
             let mut learner = LGDT::new(support, depth, strategy);
+            learner.heuristic = native_heuristic(heuristic, 0);
+            learner.min_score = min_score;
             learner.fit(&mut structure);
             statistics = learner.statistics;
             tree = learner.tree.clone();
@@ -74,15 +134,36 @@ fn main() {
         ArgCommand::dl85 {
             support,
             depth,
-            sorting_once,
+            sort_policy,
             specialization,
             lower_bound_heuristic,
+            lb_policy,
             branching,
             cache_type,
             cache_init_size,
+            max_cache_size,
+            max_cache_bytes,
+            cache_full,
+            cover_keys,
+            max_leaves,
+            minimize_leaves,
+            beam_width,
+            depth_beam_widths,
+            min_score,
+            min_relative_score,
+            purity_thresholds,
+            leaf_rule,
+            discrepancy_budget,
             init_strategy,
             heuristic,
+            tie_breakers,
+            seed,
+            tie_break,
             max_error,
+            objective,
+            greedy_warm_start,
+            auto_depth,
+            trace,
             timeout,
         } => {
             let timeout = match timeout {
@@ -90,17 +171,25 @@ fn main() {
                 Some(t) => t,
             };
 
-            let heuristic_fn: Box<dyn Heuristic> = match heuristic {
-                SearchHeuristic::None_ => Box::<NoHeuristic>::default(),
-                SearchHeuristic::InformationGain => Box::<InformationGain>::default(),
-                SearchHeuristic::InformationGainRatio => Box::<InformationGainRatio>::default(),
-                SearchHeuristic::GiniIndex => Box::<GiniIndex>::default(),
+            let native_heuristic = |heuristic| native_heuristic(heuristic, seed);
+            let heuristic_fn: Box<dyn Heuristic> = match tie_breakers.is_empty() {
+                true => native_heuristic(heuristic),
+                false => Box::new(CompositeHeuristic::new(
+                    std::iter::once(heuristic)
+                        .chain(tie_breakers)
+                        .map(native_heuristic)
+                        .collect(),
+                )),
             };
             let cache: Box<dyn Caching> = match cache_type {
                 CacheType::Trie => Box::<Trie>::default(),
-                CacheType::Hashmap => {
-                    panic!("Not yet implemented")
-                }
+                CacheType::CompressedTrie => Box::new(Trie::compressed()),
+                CacheType::Hashmap => Box::<HashMapCache>::default(),
+            };
+
+            let error_function = match objective {
+                Objective::Error => NativeError::default(),
+                Objective::BalancedAccuracy => NativeError::balanced(structure.labels_support()),
             };
 
             let mut learner = DL85::new(
@@ -108,7 +197,7 @@ fn main() {
                 depth,
                 max_error,
                 timeout,
-                sorting_once,
+                sort_policy,
                 cache_init_size,
                 init_strategy,
                 specialization,
@@ -116,22 +205,93 @@ fn main() {
                 branching,
                 NodeExposedData::ClassesSupport,
                 cache,
-                Box::<NativeError>::default(),
+                Box::new(error_function),
                 heuristic_fn,
             );
+            learner.constraints.max_cache_size = max_cache_size;
+            learner.constraints.max_cache_bytes = max_cache_bytes;
+            learner.constraints.cache_full_policy = cache_full;
+            learner.constraints.cover_keys = cover_keys;
+            learner.constraints.lower_bound_policy = lb_policy;
+            learner.constraints.tie_break = tie_break;
+            learner.constraints.seed = seed;
+            learner.constraints.max_leaves = max_leaves;
+            learner.constraints.minimize_leaves = minimize_leaves;
+            learner.constraints.beam_width = beam_width;
+            learner.depth_beam_widths = depth_beam_widths;
+            learner.constraints.min_heuristic_score = min_score;
+            learner.constraints.min_relative_score = min_relative_score;
+            learner.stop_conditions.purity_thresholds = purity_thresholds;
+            learner.stop_conditions.leaf_rule = leaf_rule;
+            if let Some(budget) = discrepancy_budget {
+                learner.constraints.search_strategy = SearchStrategy::DiscrepancySearch;
+                learner.constraints.discrepancy_budget = budget;
+            }
+            if let Some(path) = trace {
+                let tracer = JsonlTracer::create(path).expect("Unable to create the trace file");
+                learner.tracer = Some(Box::new(tracer));
+            }
 
-            learner.fit(&mut structure);
+            match (auto_depth, greedy_warm_start) {
+                (true, _) => learner.fit_increasing_depths(&mut structure).map(|_| ()),
+                (false, true) => learner.fit_with_greedy_warm_start(&mut structure),
+                (false, false) => learner.fit(&mut structure),
+            }
+            .unwrap_or_else(|e| panic!("{}", e));
+            if let Some(tracer) = learner.tracer.as_mut() {
+                tracer.finish().expect("Unable to write the trace file");
+            }
 
             statistics = learner.statistics;
             tree = learner.tree.clone();
         }
+
+        ArgCommand::bench { .. } => unreachable!("Benchmarks are run before reading the input"),
+    }
+
+    let mut output = String::new();
+    match app.format {
+        OutputFormat::Text => {
+            if app.print_stats {
+                output.push_str(&format!("{:#?}\n", statistics));
+            }
+            if app.print_tree {
+                output.push_str(&tree.to_text(None, None));
+            }
+        }
+        OutputFormat::Json => {
+            let document = json!({
+                "input": file,
+                "command": command,
+                "statistics": statistics,
+                "tree": tree,
+            });
+            output = serde_json::to_string_pretty(&document).unwrap();
+            output.push('\n');
+        }
+    }
+
+    if let Some(path) = app.export_dot {
+        std::fs::write(&path, tree.to_dot(None, None)).expect("Unable to write the DOT file");
     }
 
-    if app.print_stats {
-        println!("{:#?}", statistics);
+    write_output(app.output.as_ref(), &output);
+}
+
+fn write_output(path: Option<&PathBuf>, output: &str) {
+    match path {
+        Some(path) => std::fs::write(path, output).expect("Unable to write the output file"),
+        None => print!("{}", output),
     }
+}
 
-    if app.print_tree {
-        tree.print();
+fn native_heuristic(heuristic: SearchHeuristic, seed: u64) -> Box<dyn Heuristic> {
+    match heuristic {
+        SearchHeuristic::None_ => Box::<NoHeuristic>::default(),
+        SearchHeuristic::InformationGain => Box::<InformationGain>::default(),
+        SearchHeuristic::InformationGainRatio => Box::<InformationGainRatio>::default(),
+        SearchHeuristic::GiniIndex => Box::<GiniIndex>::default(),
+        SearchHeuristic::WeightOfEvidence => Box::<WeightOfEvidence>::default(),
+        SearchHeuristic::Random => Box::new(RandomHeuristic::new(seed)),
     }
 }
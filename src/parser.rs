@@ -1,16 +1,23 @@
+use crate::searches::rules::LeafRule;
 use crate::searches::{
-    BranchingStrategy, CacheInitStrategy, CacheType, D2Objective, LowerBoundStrategy,
-    SearchHeuristic, SearchStrategy, Specialization,
+    BranchingStrategy, CacheFullPolicy, CacheInitStrategy, CacheType, D2Objective,
+    LowerBoundPolicy, LowerBoundStrategy, Objective, SearchHeuristic, SearchStrategy, SortPolicy,
+    Specialization, TieBreak,
 };
-use clap::{arg, Parser, Subcommand};
+use clap::error::ErrorKind;
+use clap::{
+    arg, ArgAction, Command, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
 use std::path::PathBuf;
 
 #[derive(Debug, Parser)]
 #[clap(name = "dt-trees", version, author, about)]
 pub struct App {
-    /// Dataset input file path
+    /// Dataset input file path, required by all the commands but bench. Files with the .arff extension are read as ARFF, the .svm, .libsvm and .svmlight ones in the sparse libsvm format
     #[clap(short, long, value_parser)]
-    pub(crate) input: PathBuf,
+    pub(crate) input: Option<PathBuf>,
 
     #[clap(subcommand)]
     pub(crate) command: ArgCommand,
@@ -22,9 +29,225 @@ pub struct App {
     /// Printing Tree
     #[arg(long, default_value_t = false)]
     pub(crate) print_tree: bool,
+
+    /// Output format. The json format writes the input, the command, the statistics and the tree as a single document, whatever the printing flags
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub(crate) format: OutputFormat,
+
+    /// File the output is written to instead of stdout
+    #[arg(long)]
+    pub(crate) output: Option<PathBuf>,
+
+    /// Also write the learned tree in the Graphviz DOT format to this file
+    #[arg(long)]
+    pub(crate) export_dot: Option<PathBuf>,
+
+    /// Density of the covers, as the share of the transactions set in their non empty words, under which the data structure lists the transactions of the covers instead of going through their words. 0 disables it
+    #[arg(long, default_value_t = 0.0)]
+    pub(crate) sparse_threshold: f64,
+
+    /// Configuration file giving arguments, with a table for the command. Read as YAML for .yaml and .yml files, as TOML otherwise. The command line overrides its values
+    #[arg(long)]
+    pub(crate) config: Option<PathBuf>,
+
+    /// Level of the messages logged to stderr. -v alone logs the info messages
+    #[arg(short, long, value_enum, num_args = 0..=1, require_equals = true, default_value_t = Verbosity::Warn, default_missing_value = "info")]
+    pub(crate) verbose: Verbosity,
+}
+
+impl App {
+    pub(crate) fn input(&self) -> &PathBuf {
+        self.input.as_ref().unwrap_or_else(|| {
+            App::command()
+                .error(
+                    ErrorKind::MissingRequiredArgument,
+                    "the --input argument is required by this command",
+                )
+                .exit()
+        })
+    }
+
+    // Parses the command line. The arguments of the configuration file are put before the ones of
+    // the command line at each level, the last occurrence of an argument being the one kept
+    pub(crate) fn parse_with_config() -> App {
+        let mut command = App::command().args_override_self(true);
+        for name in ["dl85", "d2-odt", "lgdt", "bench"] {
+            command =
+                command.mut_subcommand(name, |subcommand| subcommand.args_override_self(true));
+        }
+        let args = std::env::args().collect::<Vec<String>>();
+        let (position, config) = scan_args(&command, &args);
+        let args = match config {
+            None => args,
+            Some(path) => {
+                let config = read_config(&path).unwrap_or_else(|e| {
+                    command
+                        .error(ErrorKind::Io, format!("{}: {}", path, e))
+                        .exit()
+                });
+                merge_args(&command, &args, position, &config)
+                    .unwrap_or_else(|e| command.error(ErrorKind::InvalidValue, e).exit())
+            }
+        };
+        let matches = command.get_matches_from(args);
+        App::from_arg_matches(&matches).unwrap_or_else(|e| e.exit())
+    }
 }
 
-#[derive(Debug, Subcommand)]
+// Position of the command and path of the configuration file in the command line arguments
+fn scan_args(command: &Command, args: &[String]) -> (Option<usize>, Option<String>) {
+    let mut config = None;
+    let mut index = 1;
+    while index < args.len() {
+        let arg = &args[index];
+        let (name, inline) = match arg.strip_prefix("--") {
+            Some(long) => match long.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (long, None),
+            },
+            None => match arg.strip_prefix('-').filter(|short| !short.is_empty()) {
+                Some(short) => match short.char_indices().nth(1) {
+                    Some((split, _)) => (&short[..split], Some(&short[split..])),
+                    None => (short, None),
+                },
+                None => return (Some(index), config),
+            },
+        };
+        let takes_value = command
+            .get_arguments()
+            .find(|argument| {
+                argument.get_long() == Some(name)
+                    || argument.get_short().map(String::from).as_deref() == Some(name)
+            })
+            .map_or(false, |argument| argument.get_action().takes_values());
+        let value = match (takes_value, inline) {
+            (true, None) => {
+                index += 1;
+                args.get(index).map(String::as_str)
+            }
+            (_, inline) => inline,
+        };
+        if name == "config" {
+            config = value.map(String::from);
+        }
+        index += 1;
+    }
+    (None, config)
+}
+
+fn read_config(path: &str) -> Result<Map<String, Value>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    match path.ends_with(".yaml") || path.ends_with(".yml") {
+        true => serde_yaml::from_str(&content).map_err(|e| e.to_string()),
+        false => toml::from_str(&content).map_err(|e| e.to_string()),
+    }
+}
+
+// Command line with the arguments of the configuration file. Its tables give the arguments of the
+// commands, the one of the command line or the only one of the file being used
+fn merge_args(
+    command: &Command,
+    args: &[String],
+    position: Option<usize>,
+    config: &Map<String, Value>,
+) -> Result<Vec<String>, String> {
+    let tables = config
+        .iter()
+        .filter_map(|(key, value)| {
+            value
+                .as_object()
+                .map(|table| (key.replace('_', "-"), table))
+        })
+        .collect::<Vec<(String, &Map<String, Value>)>>();
+    for (name, _) in &tables {
+        if command.find_subcommand(name).is_none() {
+            return Err(format!(
+                "unknown command '{}' in the configuration file",
+                name
+            ));
+        }
+    }
+
+    let subcommand = match position {
+        Some(position) => Some(args[position].clone()),
+        None if tables.len() == 1 => Some(tables[0].0.clone()),
+        None => None,
+    };
+    let subcommand_args = tables
+        .iter()
+        .find(|(name, _)| Some(name) == subcommand.as_ref())
+        .map_or(vec![], |(_, table)| config_args(table));
+
+    let global = config
+        .iter()
+        .filter(|(_, value)| !value.is_object())
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect::<Map<String, Value>>();
+    let split = position.unwrap_or(args.len());
+    let mut merged = vec![args[0].clone()];
+    merged.extend(config_args(&global));
+    merged.extend_from_slice(&args[1..split]);
+    merged.extend(subcommand);
+    merged.extend(subcommand_args);
+    merged.extend(args.iter().skip(split + 1).cloned());
+    Ok(merged)
+}
+
+// Arguments given by the values of a table. Flags are set by true values, lists are joined by
+// commas and nested tables, like the leaf rule, are passed as JSON
+fn config_args(table: &Map<String, Value>) -> Vec<String> {
+    table
+        .iter()
+        .filter_map(|(key, value)| {
+            let value = match value {
+                Value::Null | Value::Bool(false) => return None,
+                Value::Bool(true) => return Some(format!("--{}", key.replace('_', "-"))),
+                Value::String(value) => value.clone(),
+                Value::Array(values) => values
+                    .iter()
+                    .map(|value| match value {
+                        Value::String(value) => value.clone(),
+                        value => value.to_string(),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(","),
+                value => value.to_string(),
+            };
+            Some(format!("--{}={}", key.replace('_', "-"), value))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum Verbosity {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Verbosity {
+    pub(crate) fn level_filter(self) -> log::LevelFilter {
+        match self {
+            Verbosity::Off => log::LevelFilter::Off,
+            Verbosity::Error => log::LevelFilter::Error,
+            Verbosity::Warn => log::LevelFilter::Warn,
+            Verbosity::Info => log::LevelFilter::Info,
+            Verbosity::Debug => log::LevelFilter::Debug,
+            Verbosity::Trace => log::LevelFilter::Trace,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub(crate) enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Subcommand, Serialize)]
 pub(crate) enum ArgCommand {
     /// DL8.5 Optimal search Algorithm with no depth limit and classification error as criterion.
     /// TODO : More arguments will be added to support LDS.
@@ -37,9 +260,9 @@ pub(crate) enum ArgCommand {
         #[arg(short, long)]
         depth: usize,
 
-        /// Sorting Features based on heuristic only at the root (true) or at each node
-        #[arg(long, default_value_t = true)]
-        sorting_once: bool,
+        /// Nodes whose candidates are sorted by the heuristic: root-only, always, or a depth k for the nodes up to depth k, the root being at depth 0. The other nodes keep the order of the root candidates
+        #[arg(long, default_value = "root-only")]
+        sort_policy: SortPolicy,
 
         /// Use Murtree Specialization Algorithm
         #[arg(long, value_enum, default_value_t = Specialization::None_)]
@@ -49,19 +272,78 @@ pub(crate) enum ArgCommand {
         #[arg(long="lb", value_enum, default_value_t = LowerBoundStrategy::None_)]
         lower_bound_heuristic: LowerBoundStrategy,
 
+        /// Covers of the solved siblings the similarity lower bound is computed from: the last one, or two as in MurTree, replacing the one closer to the new cover
+        #[arg(long, value_enum, default_value_t = LowerBoundPolicy::TwoCovers)]
+        lb_policy: LowerBoundPolicy,
+
         /// Branching type
         #[arg(short, long, value_enum, default_value_t = BranchingStrategy::None_)]
         branching: BranchingStrategy,
 
+        /// Cache type
         #[arg(long="cache", value_enum, default_value_t = CacheType::Trie)]
         cache_type: CacheType,
 
         /// Cache init size
-        /// Represents the reserved starting size of the cache
+        /// Represents the reserved starting size of the cache, used by the user allocation
         #[arg(long, default_value_t = 0)]
         cache_init_size: usize,
 
-        /// Cache Initialization strategy
+        /// Maximum number of cache entries, leaves are evicted when it is reached. 0 means unbounded
+        #[arg(long, default_value_t = 0)]
+        max_cache_size: usize,
+
+        /// Maximum estimated memory of the cache entries in bytes. 0 means unbounded
+        #[arg(long, default_value_t = 0)]
+        max_cache_bytes: usize,
+
+        /// What happens once the cache is full: evicting leaves keeps the search exact, stopping turns the nodes not explored yet into leaves
+        #[arg(long, value_enum, default_value_t = CacheFullPolicy::Evict)]
+        cache_full: CacheFullPolicy,
+
+        /// Share the cache entry of the itemsets of the same size covering the same samples, so that equivalent nodes are solved once
+        #[arg(long)]
+        cover_keys: bool,
+
+        /// Maximum number of leaves of the tree. 0 means unbounded
+        #[arg(long, default_value_t = 0)]
+        max_leaves: usize,
+
+        /// Among the trees of optimal error, return one with the fewest leaves
+        #[arg(long)]
+        minimize_leaves: bool,
+
+        /// Number of candidates explored at each node, the first ones according to the heuristic. 0 means all of them
+        #[arg(long, visible_alias = "topk", default_value_t = 0)]
+        beam_width: usize,
+
+        /// Beam width of each depth, replacing the beam width. Nodes deeper than the list use its last width
+        #[arg(long, value_delimiter = ',', action = ArgAction::Set)]
+        depth_beam_widths: Vec<usize>,
+
+        /// Minimum heuristic score of the candidates, the information gain when the heuristic has no scores. Candidates scoring less are dropped. 0 keeps them all
+        #[arg(long, default_value_t = 0.0)]
+        min_score: f64,
+
+        /// Minimum heuristic score of the candidates as a share of the best score of their node. 0 keeps them all
+        #[arg(long, default_value_t = 0.0)]
+        min_relative_score: f64,
+
+        /// Minimum proportion of the majority class turning a node into a leaf, for each depth starting at the children of the root. Nodes deeper than the list use its last threshold
+        #[arg(long, visible_alias = "purity", value_delimiter = ',', action = ArgAction::Set)]
+        purity_thresholds: Vec<f64>,
+
+        /// Rule turning the nodes for which it holds into leaves, in the JSON format of the Python bindings, e.g. '{"Or": [{"Purity": 0.95}, {"MaxSupport": 10}]}'
+        #[arg(long, value_parser = parse_leaf_rule)]
+        leaf_rule: Option<LeafRule>,
+
+        /// Use the limited discrepancy search: each path strays at most this many times from the heuristic order, choosing the candidate of rank i costing i discrepancies
+        #[arg(long, visible_alias = "discrepancy")]
+        discrepancy_budget: Option<usize>,
+
+        /// Cache Initialization strategy: the user allocation reserves the cache init size, the
+        /// dynamic allocation reserves the itemsets the search can reach and inserts the depth 1
+        /// itemsets
         #[arg(long, value_enum, default_value_t = CacheInitStrategy::None_)]
         init_strategy: CacheInitStrategy,
 
@@ -69,10 +351,38 @@ pub(crate) enum ArgCommand {
         #[arg(short, long, value_enum, default_value_t = SearchHeuristic::None_)]
         heuristic: SearchHeuristic,
 
+        /// Heuristics breaking the ties of the sorting heuristic, in order. Remaining ties are broken by the smallest attribute index
+        #[arg(long, value_enum, value_delimiter = ',', action = ArgAction::Set)]
+        tie_breakers: Vec<SearchHeuristic>,
+
+        /// Seed of the random heuristic and of the random tie breaking
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+
+        /// Order of the candidates whose heuristic scores or tree errors are equal up to rounding: the smallest index first, or a permutation of the attributes drawn from the seed
+        #[arg(long, value_enum, default_value_t = TieBreak::Index)]
+        tie_break: TieBreak,
+
         /// Tree error initial upper bound
         #[arg(long, default_value_t = <f64>::INFINITY)]
         max_error: f64,
 
+        /// Criterion to minimize. The balanced accuracy weights each error by the inverse of the support of its class
+        #[arg(long, value_enum, default_value_t = Objective::Error)]
+        objective: Objective,
+
+        /// Use the error of a greedy (LGDT) tree as initial upper bound
+        #[arg(long)]
+        greedy_warm_start: bool,
+
+        /// Solve the depths 1 to the maximum depth within the timeout and keep the tree of the first depth not proven optimal, the deepest optimal depth being in the statistics
+        #[arg(long, conflicts_with = "greedy_warm_start")]
+        auto_depth: bool,
+
+        /// Write each evaluated node (itemset, bounds, decision and reason) as a JSON line to this file. The leaves budget and discrepancy searches are not traced
+        #[arg(long)]
+        trace: Option<PathBuf>,
+
         /// Maximum time allowed to the search
         #[clap(long, short)]
         timeout: Option<usize>,
@@ -107,5 +417,82 @@ pub(crate) enum ArgCommand {
         /// Objective function inside
         #[arg(short, long, value_enum, default_value_t = D2Objective::Error)]
         objective: D2Objective,
+
+        /// Heuristic scoring the splits for --min-score, the information gain when it has no scores
+        #[arg(long, value_enum, default_value_t = SearchHeuristic::None_)]
+        heuristic: SearchHeuristic,
+
+        /// Minimum heuristic score of the split of each node on its samples. Nodes whose split scores less become leaves. 0 keeps them all
+        #[arg(long, default_value_t = 0.0)]
+        min_score: f64,
     },
+
+    /// Runs algorithms on the datasets of a directory for several depths and summarizes the runs, as CSV with the text format
+    bench {
+        /// Directory of the datasets
+        #[arg(long)]
+        dir: PathBuf,
+
+        /// Extension of the dataset files, the other files of the directory are ignored
+        #[arg(long, default_value = "txt")]
+        extension: String,
+
+        /// Maximum depths, as an inclusive range like 2..5 or a list like 2,4
+        #[arg(long, value_parser = parse_depths, default_value = "2")]
+        depths: Depths,
+
+        /// Algorithms run on each dataset and depth. Depth 2 optimal trees are only learned up to depth 2
+        #[arg(long, value_enum, value_delimiter = ',', action = ArgAction::Set, default_values_t = [BenchAlgorithm::Dl85, BenchAlgorithm::Lgdt])]
+        algorithms: Vec<BenchAlgorithm>,
+
+        /// Caches compared on the DL8.5 runs, each one giving its own run
+        #[arg(long, value_enum, value_delimiter = ',', action = ArgAction::Set, default_values_t = [CacheType::Trie])]
+        caches: Vec<CacheType>,
+
+        /// Minimum support
+        #[arg(short, long, default_value_t = 1)]
+        support: usize,
+
+        /// Maximum time allowed to each DL8.5 search
+        #[arg(short, long)]
+        timeout: Option<usize>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum BenchAlgorithm {
+    Dl85,
+    D2Odt,
+    Lgdt,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Depths(pub(crate) Vec<usize>);
+
+fn parse_depths(depths: &str) -> Result<Depths, String> {
+    let depth = |depth: &str| {
+        depth
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| format!("invalid depth '{}'", depth))
+    };
+    match depths.split_once("..") {
+        Some((start, end)) => {
+            let (start, end) = (depth(start)?, depth(end.trim_start_matches('='))?);
+            match start <= end {
+                true => Ok(Depths((start..=end).collect())),
+                false => Err(format!("empty range of depths '{}'", depths)),
+            }
+        }
+        None => depths
+            .split(',')
+            .map(depth)
+            .collect::<Result<_, _>>()
+            .map(Depths),
+    }
+}
+
+fn parse_leaf_rule(rule: &str) -> Result<LeafRule, String> {
+    serde_json::from_str(rule).map_err(|e| e.to_string())
 }
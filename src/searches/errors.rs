@@ -1,14 +1,52 @@
 pub trait ErrorWrapper {
     fn compute(&self, data: &[usize]) -> (f64, f64);
+
+    // Weights of the classes of a weighted classification error, also applied by the depth 2
+    // specialization which does not call the error function
+    fn class_weights(&self) -> Option<&[f64]> {
+        None
+    }
+
+    // Continuous targets of the samples, indexed by tid. The error functions returning them are
+    // given the target values of each node with compute_targets
+    fn targets(&self) -> Option<&[f64]> {
+        None
+    }
+
+    fn compute_targets(&self, _values: &[f64]) -> (f64, f64) {
+        unimplemented!("Only the error functions with targets compute their error on target values")
+    }
 }
 
+#[derive(Clone)]
 pub struct NativeError {
     function: fn(&[usize]) -> (f64, f64),
+    // Cost of a misclassified sample of each class, the classification error is then weighted
+    weights: Option<Vec<f64>>,
 }
 
 impl NativeError {
     pub fn new(function: fn(&[usize]) -> (f64, f64)) -> Self {
-        NativeError { function }
+        NativeError {
+            function,
+            weights: None,
+        }
+    }
+
+    // Classification error where each misclassified sample costs the weight of its class. The
+    // output of a leaf is the class of least weighted error, the first one on ties
+    pub fn weighted(weights: Vec<f64>) -> Self {
+        NativeError {
+            function: classification_error,
+            weights: Some(weights),
+        }
+    }
+
+    // Weights inversely proportional to the support of each class, scaled so that the weighted
+    // supports sum to the number of samples. One minus the error divided by the number of samples
+    // is then the balanced accuracy, which is additive over the leaves unlike the F-scores
+    pub fn balanced(classes_support: &[usize]) -> Self {
+        Self::weighted(balanced_weights(classes_support))
     }
 }
 
@@ -20,7 +58,14 @@ impl Default for NativeError {
 
 impl ErrorWrapper for NativeError {
     fn compute(&self, data: &[usize]) -> (f64, f64) {
-        (self.function)(data)
+        match &self.weights {
+            Some(weights) => weighted_classification_error(data, weights),
+            None => (self.function)(data),
+        }
+    }
+
+    fn class_weights(&self) -> Option<&[f64]> {
+        self.weights.as_deref()
     }
 }
 
@@ -39,3 +84,73 @@ pub fn classification_error(classes_support: &[usize]) -> (f64, f64) {
     let error = total - max_value;
     (error as f64, max_idx as f64)
 }
+
+// Classes missing from the data get a null weight
+pub fn balanced_weights(classes_support: &[usize]) -> Vec<f64> {
+    let total = classes_support.iter().sum::<usize>() as f64;
+    let present = classes_support
+        .iter()
+        .filter(|support| **support > 0)
+        .count() as f64;
+    classes_support
+        .iter()
+        .map(|support| match support {
+            0 => 0.0,
+            _ => total / (present * *support as f64),
+        })
+        .collect()
+}
+
+pub fn weighted_classification_error(classes_support: &[usize], weights: &[f64]) -> (f64, f64) {
+    let weighted = classes_support
+        .iter()
+        .zip(weights.iter())
+        .map(|(support, weight)| *support as f64 * weight)
+        .collect::<Vec<f64>>();
+    let total = weighted.iter().sum::<f64>();
+    let mut best = (total, 0.0);
+    for (class, correct) in weighted.iter().enumerate() {
+        if total - correct < best.0 {
+            best = (total - correct, class as f64);
+        }
+    }
+    best
+}
+
+// Regression error. The leaf output is the mean of the targets of the node and its error the sum
+// of squared deviations to this mean. The node data can be its tids or its target values
+pub struct SquaredError {
+    targets: Vec<f64>,
+}
+
+impl SquaredError {
+    pub fn new(targets: Vec<f64>) -> Self {
+        SquaredError { targets }
+    }
+}
+
+impl ErrorWrapper for SquaredError {
+    fn compute(&self, tids: &[usize]) -> (f64, f64) {
+        let values = tids
+            .iter()
+            .map(|tid| self.targets[*tid])
+            .collect::<Vec<f64>>();
+        self.compute_targets(&values)
+    }
+
+    fn targets(&self) -> Option<&[f64]> {
+        Some(&self.targets)
+    }
+
+    fn compute_targets(&self, values: &[f64]) -> (f64, f64) {
+        if values.is_empty() {
+            return (0.0, 0.0);
+        }
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let error = values
+            .iter()
+            .map(|value| (value - mean).powi(2))
+            .sum::<f64>();
+        (error, mean)
+    }
+}
@@ -0,0 +1,136 @@
+use crate::globals::{attribute, item};
+use crate::tree::Tree;
+use std::collections::HashMap;
+
+pub trait CandidatesFilter {
+    // Called at each node with its path (sorted items), depth and support. Candidates can be
+    // dropped or reordered, they are explored in the order they are left in
+    fn filter(&self, path: &[usize], depth: usize, support: usize, candidates: &mut Vec<usize>);
+}
+
+// Filters applied one after the other
+impl CandidatesFilter for Vec<Box<dyn CandidatesFilter>> {
+    fn filter(&self, path: &[usize], depth: usize, support: usize, candidates: &mut Vec<usize>) {
+        for filter in self {
+            filter.filter(path, depth, support, candidates);
+        }
+    }
+}
+
+// Keeps the candidates whose cost, added to the cost of the features tested on the path, stays
+// within the budget. Every root to leaf path of the tree then costs at most max_cost
+pub struct FeatureCostFilter {
+    costs: Vec<f64>,
+    max_cost: f64,
+}
+
+impl FeatureCostFilter {
+    pub fn new(costs: Vec<f64>, max_cost: f64) -> Self {
+        Self { costs, max_cost }
+    }
+
+    pub fn path_cost(&self, path: &[usize]) -> f64 {
+        path.iter().map(|item| self.cost(attribute(*item))).sum()
+    }
+
+    fn cost(&self, feature: usize) -> f64 {
+        self.costs.get(feature).copied().unwrap_or(0.0)
+    }
+}
+
+impl CandidatesFilter for FeatureCostFilter {
+    fn filter(&self, path: &[usize], _depth: usize, _support: usize, candidates: &mut Vec<usize>) {
+        let spent = self.path_cost(path);
+        candidates.retain(|candidate| spent + self.cost(*candidate) <= self.max_cost);
+    }
+}
+
+// Restricts the features which can be tested. When a root feature is given, it is the only
+// candidate of the root whatever the other constraints
+pub struct FeatureSetFilter {
+    include: Option<Vec<usize>>,
+    exclude: Vec<usize>,
+    root: Option<usize>,
+}
+
+impl FeatureSetFilter {
+    pub fn new(include: Option<Vec<usize>>, exclude: Vec<usize>, root: Option<usize>) -> Self {
+        Self {
+            include,
+            exclude,
+            root,
+        }
+    }
+}
+
+impl CandidatesFilter for FeatureSetFilter {
+    fn filter(&self, _path: &[usize], depth: usize, _support: usize, candidates: &mut Vec<usize>) {
+        match self.root {
+            Some(root) if depth == 0 => candidates.retain(|candidate| *candidate == root),
+            _ => candidates.retain(|candidate| {
+                self.include
+                    .as_ref()
+                    .is_none_or(|include| include.contains(candidate))
+                    && !self.exclude.contains(candidate)
+            }),
+        }
+    }
+}
+
+// Fixes the top splits of the tree to the ones of a partial tree, the template. A node reached by
+// the path of a template split can only test its feature, the nodes below the template leaves are
+// free, so the search optimally completes the template
+pub struct TemplateFilter {
+    // Feature of each split of the template, by the sorted items of its path
+    splits: HashMap<Vec<usize>, usize>,
+}
+
+impl TemplateFilter {
+    pub fn new(template: &Tree) -> Self {
+        let mut splits = HashMap::new();
+        if !template.is_empty() {
+            Self::collect(template, template.get_root_index(), vec![], &mut splits);
+        }
+        Self { splits }
+    }
+
+    // Features tested by the splits of the template
+    pub fn features(&self) -> impl Iterator<Item = usize> + '_ {
+        self.splits.values().copied()
+    }
+
+    fn collect(
+        template: &Tree,
+        index: usize,
+        path: Vec<usize>,
+        splits: &mut HashMap<Vec<usize>, usize>,
+    ) {
+        let node = match template.get_node(index) {
+            Some(node) => node,
+            None => return,
+        };
+        // Nodes without a test are left open, the splits may have no children
+        let feature = match node.value.test {
+            Some(feature) => feature,
+            None => return,
+        };
+        // The left child holds the samples where the feature is 0
+        for (child, item_type) in [(node.left, 0), (node.right, 1)] {
+            if child != 0 {
+                let mut child_path = path.clone();
+                child_path.push(item(feature, item_type));
+                child_path.sort_unstable();
+                Self::collect(template, child, child_path, splits);
+            }
+        }
+        splits.insert(path, feature);
+    }
+}
+
+impl CandidatesFilter for TemplateFilter {
+    fn filter(&self, path: &[usize], _depth: usize, _support: usize, candidates: &mut Vec<usize>) {
+        if let Some(feature) = self.splits.get(path) {
+            candidates.retain(|candidate| candidate == feature);
+        }
+    }
+}
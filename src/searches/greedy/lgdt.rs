@@ -1,10 +1,13 @@
-use crate::globals::{float_is_null, get_tree_root_error, item};
+use crate::globals::{fill_classes_support, float_is_null, get_tree_root_error, item};
+use crate::heuristics::{Heuristic, InformationGain, NoHeuristic};
 use crate::searches::errors::{ErrorWrapper, NativeError};
+use crate::searches::monotonic::{repair_monotonicity, MonotonicFeatures};
 use crate::searches::optimal::d2::GenericDepth2;
 use crate::searches::utils::{Constraints, SearchStrategy};
 use crate::searches::Statistics;
 use crate::structures::Structure;
 use crate::tree::{NodeInfos, Tree, TreeNode};
+use log::info;
 
 pub struct LGDT {
     pub error: f64,
@@ -13,6 +16,13 @@ pub struct LGDT {
     search_method: GenericDepth2,
     error_function: NativeError,
     pub tree: Tree,
+    // The leaves breaking these constraints are relabeled once the tree is built
+    pub monotonic_features: MonotonicFeatures,
+    // Heuristic scoring the splits of the learned tree for min_score, the information gain when it
+    // has no scores
+    pub heuristic: Box<dyn Heuristic>,
+    // The splits scoring less on the samples of their node are turned into leaves. 0 keeps them all
+    pub min_score: f64,
 }
 
 impl LGDT {
@@ -39,6 +49,9 @@ impl LGDT {
             search_method: GenericDepth2::new(strategy),
             error_function: NativeError::default(),
             tree: Tree::default(),
+            monotonic_features: MonotonicFeatures::new(),
+            heuristic: Box::<NoHeuristic>::default(),
+            min_score: 0.0,
         }
     }
 
@@ -63,7 +76,7 @@ impl LGDT {
 
             if let Some(root) = root_tree.get_node(root_tree.get_root_index()) {
                 solution_tree.add_root(TreeNode {
-                    value: root.value,
+                    value: root.value.clone(),
                     index: 0,
                     left: 0,
                     right: 0,
@@ -84,8 +97,22 @@ impl LGDT {
             self.tree = solution_tree;
         }
 
+        if self.min_score > 0.0 && !self.tree.is_empty() {
+            self.drop_weak_splits(structure, self.tree.get_root_index());
+            self.tree
+                .compact()
+                .expect("The learned tree has valid children");
+        }
+
+        fill_classes_support(&mut self.tree, structure);
+        repair_monotonicity(&mut self.tree, &self.monotonic_features);
         self.error = get_tree_root_error(&self.tree);
-        self.update_statistics(structure)
+        self.update_statistics(structure);
+        info!(
+            "LGDT tree of depth {} learned with error {}",
+            self.tree.depth(),
+            self.error
+        );
     }
 
     fn recursion<S>(
@@ -142,7 +169,7 @@ impl LGDT {
                     } else if let Some(child) = tree.get_node_mut(child_index) {
                         let mut child_next = None;
                         if let Some(root) = child_tree.get_node(child_tree.get_root_index()) {
-                            child.value = root.value;
+                            child.value = root.value.clone();
                             child_next = child.value.test;
                         }
                         child_error =
@@ -159,6 +186,53 @@ impl LGDT {
         };
     }
 
+    // Turns into leaves the splits scoring less than min_score on the samples of their node, from
+    // the root down, and returns the error of the subtree
+    fn drop_weak_splits<S>(&mut self, structure: &mut S, index: usize) -> f64
+    where
+        S: Structure,
+    {
+        let (attribute, (left, right)) = match self.tree.get_node(index) {
+            Some(node) => match (node.value.test, node.children()) {
+                (Some(attribute), Some(children)) => (attribute, children),
+                _ => return node.value.error,
+            },
+            None => return 0.0,
+        };
+        let score = self
+            .heuristic
+            .scores(structure, &[attribute])
+            .or_else(|| InformationGain.scores(structure, &[attribute]))
+            .and_then(|scores| scores.first().copied())
+            .unwrap_or(0.0);
+
+        let error = match score < self.min_score {
+            true => {
+                let (error, out) = self.error_function.compute(structure.labels_support());
+                if let Some(node) = self.tree.get_node_mut(index) {
+                    node.value.test = None;
+                    node.value.out = Some(out);
+                    node.left = 0;
+                    node.right = 0;
+                }
+                error
+            }
+            false => {
+                let mut error = 0.0;
+                for (branch, child) in [left, right].into_iter().enumerate() {
+                    let _ = structure.push(item(attribute, branch));
+                    error += self.drop_weak_splits(structure, child);
+                    structure.backtrack();
+                }
+                error
+            }
+        };
+        if let Some(node) = self.tree.get_node_mut(index) {
+            node.value.error = error;
+        }
+        error
+    }
+
     fn create_child(&self, tree: &mut Tree, parent: usize, is_left: bool) -> usize {
         let value = NodeInfos::default();
         let node = TreeNode::new(value);
@@ -194,7 +268,7 @@ impl LGDT {
     ) {
         if let Some(source_node) = source_tree.get_node(source_index) {
             if let Some(root) = dest_tree.get_node_mut(dest_index) {
-                root.value = source_node.value;
+                root.value = source_node.value.clone();
             }
             let source_left_index = source_node.left;
 
@@ -227,6 +301,19 @@ impl LGDT {
         self.statistics.tree_error = self.error;
         self.statistics.num_samples = structure.support();
         self.statistics.num_attributes = structure.num_attributes();
+        // Up to depth 2 the tree is learned by the optimal depth 2 search, minimizing the error
+        // with the Murtree strategy
+        let optimal = self.constraints.max_depth <= 2
+            && self.min_score <= 0.0
+            && matches!(
+                self.constraints.search_strategy,
+                SearchStrategy::LessGreedyMurtree
+            );
+        self.statistics.best_lower_bound = match optimal {
+            true => self.error,
+            false => 0.0,
+        };
+        self.statistics.gap = self.error - self.statistics.best_lower_bound;
     }
 }
 
@@ -250,4 +337,27 @@ mod test_lgdt {
         lgdt.fit(&mut structure);
         lgdt.tree.print()
     }
+
+    #[test]
+    fn weak_splits_become_leaves() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = Bitset::new(&data);
+        let fit = |structure: &mut Bitset, min_score: f64| {
+            let mut lgdt = LGDT::new(1, 4, SearchStrategy::LessGreedyMurtree);
+            lgdt.min_score = min_score;
+            lgdt.fit(structure);
+            lgdt
+        };
+
+        let full = fit(&mut structure, 0.0);
+        let pruned = fit(&mut structure, 0.05);
+        assert!(pruned.tree.len() < full.tree.len());
+        assert!(pruned.error >= full.error);
+        assert_eq!(pruned.error, pruned.tree.clone().update_errors());
+
+        // More than any split gains, the root becomes a leaf
+        let leaf = fit(&mut structure, 10.0);
+        assert_eq!(leaf.tree.len(), 1);
+        assert_eq!(leaf.tree.get_node(0).unwrap().value.test, None);
+    }
 }
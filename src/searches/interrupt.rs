@@ -0,0 +1,5 @@
+pub trait SearchInterrupter {
+    // Polled at each node of the search. Once it returns true, the remaining nodes are turned into
+    // leaves and the best tree found so far is returned
+    fn should_stop(&mut self) -> bool;
+}
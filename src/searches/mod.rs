@@ -1,6 +1,15 @@
 pub mod errors;
+pub mod filters;
 pub mod greedy;
+pub mod interrupt;
+pub mod monotonic;
 pub mod optimal;
+pub mod progress;
+pub mod rates;
+pub mod rules;
+pub mod schedule;
+pub mod trace;
+pub mod tuning;
 mod utils;
 
 pub use utils::*;
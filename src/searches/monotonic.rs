@@ -0,0 +1,180 @@
+use crate::tree::Tree;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Direction of the prediction of the positive class (the second one) when a feature goes from 0 to 1
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ValueEnum)]
+pub enum Monotonicity {
+    Increasing,
+    Decreasing,
+}
+
+pub type MonotonicFeatures = BTreeMap<usize, Monotonicity>;
+
+impl Monotonicity {
+    /// Class predicted by each branch of a split when all its leaves get the same class
+    pub fn forced_classes(&self) -> [usize; 2] {
+        match self {
+            Monotonicity::Increasing => [0, 1],
+            Monotonicity::Decreasing => [1, 0],
+        }
+    }
+}
+
+// With binary classes, a tree is monotonic when at each split on a constrained feature, the classes
+// of the leaves of one branch never go past the ones of the other branch
+pub fn is_monotonic(tree: &Tree, features: &MonotonicFeatures) -> bool {
+    features.is_empty() || leaves_range(tree, tree.get_root_index(), features).is_some()
+}
+
+// Range of the classes predicted by the leaves of a subtree, None when it is not monotonic
+fn leaves_range(tree: &Tree, index: usize, features: &MonotonicFeatures) -> Option<(f64, f64)> {
+    let node = tree.get_node(index)?;
+    if node.left == 0 && node.right == 0 {
+        let out = node.value.out.unwrap_or(0.0);
+        return Some((out, out));
+    }
+    let left = leaves_range(tree, node.left, features)?;
+    let right = leaves_range(tree, node.right, features)?;
+    let valid = match node.value.test.and_then(|test| features.get(&test)) {
+        Some(Monotonicity::Increasing) => left.1 <= right.0,
+        Some(Monotonicity::Decreasing) => left.0 >= right.1,
+        None => true,
+    };
+    match valid {
+        true => Some((left.0.min(right.0), left.1.max(right.1))),
+        false => None,
+    }
+}
+
+// Makes a tree monotonic by relabeling leaves. At each violated split, starting from the bottom,
+// all the leaves of one branch get the class allowed by the constraint, choosing the branch with
+// the lowest error. The leaves must know their classes support
+pub fn repair_monotonicity(tree: &mut Tree, features: &MonotonicFeatures) {
+    if !features.is_empty() && !tree.is_empty() {
+        let root = tree.get_root_index();
+        repair_recursion(tree, root, features);
+    }
+}
+
+fn repair_recursion(tree: &mut Tree, index: usize, features: &MonotonicFeatures) -> (f64, f64) {
+    let (test, left, right) = match tree.get_node(index) {
+        Some(node) => (node.value.test, node.left, node.right),
+        None => return (0.0, 0.0),
+    };
+    if left == 0 && right == 0 {
+        let out = tree
+            .get_node(index)
+            .and_then(|node| node.value.out)
+            .unwrap_or(0.0);
+        return (out, out);
+    }
+
+    let mut ranges = [
+        repair_recursion(tree, left, features),
+        repair_recursion(tree, right, features),
+    ];
+    if let Some(direction) = test.and_then(|test| features.get(&test)) {
+        let violated = match direction {
+            Monotonicity::Increasing => ranges[0].1 > ranges[1].0,
+            Monotonicity::Decreasing => ranges[0].0 < ranges[1].1,
+        };
+        if violated {
+            let classes = direction.forced_classes();
+            let costs = [
+                relabeling_error(tree, left, classes[0]) + subtree_error(tree, right),
+                subtree_error(tree, left) + relabeling_error(tree, right, classes[1]),
+            ];
+            let branch = (costs[1] < costs[0]) as usize;
+            relabel(tree, [left, right][branch], classes[branch]);
+            let class = classes[branch] as f64;
+            ranges[branch] = (class, class);
+        }
+    }
+
+    let error = subtree_error(tree, left) + subtree_error(tree, right);
+    if let Some(node) = tree.get_node_mut(index) {
+        node.value.error = error;
+    }
+    (ranges[0].0.min(ranges[1].0), ranges[0].1.max(ranges[1].1))
+}
+
+fn subtree_error(tree: &Tree, index: usize) -> f64 {
+    tree.get_node(index).map_or(0.0, |node| node.value.error)
+}
+
+fn relabeling_error(tree: &Tree, index: usize, class: usize) -> f64 {
+    match tree.get_node(index) {
+        Some(node) if node.left == 0 && node.right == 0 => node
+            .value
+            .classes_support
+            .as_ref()
+            .map_or(node.value.error, |support| {
+                (support.iter().sum::<usize>() - support.get(class).copied().unwrap_or(0)) as f64
+            }),
+        Some(node) => {
+            relabeling_error(tree, node.left, class) + relabeling_error(tree, node.right, class)
+        }
+        None => 0.0,
+    }
+}
+
+fn relabel(tree: &mut Tree, index: usize, class: usize) -> f64 {
+    let (left, right) = match tree.get_node(index) {
+        Some(node) => (node.left, node.right),
+        None => return 0.0,
+    };
+    let error = match left == 0 && right == 0 {
+        true => relabeling_error(tree, index, class),
+        false => relabel(tree, left, class) + relabel(tree, right, class),
+    };
+    if let Some(node) = tree.get_node_mut(index) {
+        if left == 0 && right == 0 {
+            node.value.out = Some(class as f64);
+        }
+        node.value.error = error;
+    }
+    error
+}
+
+#[cfg(test)]
+mod monotonic_test {
+    use crate::searches::monotonic::{
+        is_monotonic, repair_monotonicity, MonotonicFeatures, Monotonicity,
+    };
+    use crate::tree::{NodeInfos, Tree, TreeNode};
+
+    fn node(test: Option<usize>, out: Option<f64>, support: Vec<usize>) -> TreeNode {
+        let mut value = NodeInfos::new();
+        value.test = test;
+        value.out = out;
+        value.error = match out {
+            Some(out) => (support.iter().sum::<usize>() - support[out as usize]) as f64,
+            None => 0.0,
+        };
+        value.classes_support = Some(support);
+        TreeNode::new(value)
+    }
+
+    #[test]
+    fn repaired_tree_is_monotonic() {
+        // The positive class is predicted when feature 0 is 0, which breaks the constraint
+        let mut tree = Tree::new();
+        let root = tree.add_root(node(Some(0), None, vec![11, 9]));
+        let left = tree.add_left_node(root, node(None, Some(1.0), vec![2, 6]));
+        tree.add_right_node(root, node(None, Some(0.0), vec![9, 3]));
+
+        let features = MonotonicFeatures::from([(0, Monotonicity::Increasing)]);
+        assert!(!is_monotonic(&tree, &features));
+
+        repair_monotonicity(&mut tree, &features);
+        assert!(is_monotonic(&tree, &features));
+        // Relabeling the left leaf gives 6 + 3 errors while relabeling the right one gives 2 + 9
+        assert_eq!(tree.get_node(root).unwrap().value.error, 9.0);
+        assert_eq!(tree.get_node(left).unwrap().value.out, Some(0.0));
+
+        let features = MonotonicFeatures::from([(0, Monotonicity::Decreasing)]);
+        assert!(is_monotonic(&tree, &features));
+    }
+}
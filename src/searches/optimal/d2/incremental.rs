@@ -0,0 +1,78 @@
+use crate::searches::optimal::d2::{candidates_covers, matrix_from_covers};
+use crate::structures::{ones, Structure};
+
+// Depth 2 matrix of the last call with the covers it was built from. Consecutive calls of a search
+// are often made on nodes sharing most of their samples. The matrix is then updated from the samples
+// entering or leaving the node instead of intersecting all the pairs of covers again
+#[derive(Default)]
+pub struct IncrementalMatrix {
+    labels: Vec<usize>,
+    candidates: Vec<usize>,
+    node: Vec<u64>,
+    covers: Vec<Vec<Vec<u64>>>,
+    matrix: Vec<Vec<Vec<usize>>>,
+    // Number of matrices obtained by an update
+    pub(crate) updates: usize,
+}
+
+impl IncrementalMatrix {
+    pub fn update<S: Structure>(
+        &mut self,
+        structure: &mut S,
+        candidates: &[usize],
+        labels: &[usize],
+    ) -> Vec<Vec<Vec<usize>>> {
+        let mut node = vec![0u64; labels.len().div_ceil(64)];
+        let tids = structure.get_tids();
+        for tid in tids.iter() {
+            node[tid / 64] |= 1u64 << (tid % 64);
+        }
+        let covers = candidates_covers(structure, candidates, labels);
+
+        let reusable =
+            !self.matrix.is_empty() && self.candidates == candidates && self.labels == labels;
+        let changed = match reusable {
+            true => changed_samples(&self.node, &node),
+            false => vec![],
+        };
+
+        if reusable && changed.len() * 2 < tids.len() {
+            for tid in changed {
+                let added = node[tid / 64] >> (tid % 64) & 1 == 1;
+                let label = labels[tid];
+                let source = if added { &covers } else { &self.covers };
+                let members = (0..candidates.len())
+                    .filter(|i| source[*i][label][tid / 64] >> (tid % 64) & 1 == 1)
+                    .collect::<Vec<usize>>();
+                for (position, first) in members.iter().enumerate() {
+                    for second in members[position..].iter() {
+                        for (i, j) in [(*first, *second), (*second, *first)] {
+                            let count = &mut self.matrix[i][j][label];
+                            *count = if added { *count + 1 } else { *count - 1 };
+                            if i == j {
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+            self.updates += 1;
+        } else {
+            self.matrix = matrix_from_covers(&covers);
+            self.candidates = candidates.to_vec();
+            self.labels = labels.to_vec();
+        }
+        self.node = node;
+        self.covers = covers;
+        self.matrix.clone()
+    }
+}
+
+fn changed_samples(previous: &[u64], current: &[u64]) -> Vec<usize> {
+    let words = previous
+        .iter()
+        .zip(current.iter())
+        .map(|(previous, current)| previous ^ current)
+        .enumerate();
+    ones(words).map(|(chunk, bit)| chunk * 64 + bit).collect()
+}
@@ -1,13 +1,19 @@
+mod incremental;
 mod murtree;
 mod odt_info_gain;
+mod weighted;
 
 use crate::globals::item;
-use crate::structures::Structure;
+use crate::structures::{count_intersection, Structure};
 use crate::tree::Tree;
+use rayon::prelude::*;
+use std::cell::RefCell;
 
 use crate::searches::SearchStrategy;
+pub use incremental::IncrementalMatrix;
 pub use murtree::Murtree;
 pub use odt_info_gain::InfoGainDT;
+pub use weighted::ErrorMinimizer;
 
 pub enum GenericDepth2 {
     Murtree(Murtree),
@@ -37,6 +43,11 @@ const MAX_ERROR: f64 = <f64>::INFINITY;
 pub trait Depth2Algorithm {
     fn fit<S: Structure>(&self, min_sup: usize, depth: usize, structure: &mut S) -> Tree;
 
+    // Matrix of the previous call, reused when the samples of the node barely changed
+    fn last_matrix(&self) -> Option<&RefCell<IncrementalMatrix>> {
+        None
+    }
+
     fn generate_candidates_list<S: Structure>(
         &self,
         structure: &mut S,
@@ -59,6 +70,13 @@ pub trait Depth2Algorithm {
         structure: &mut S,
         candidates: &Vec<usize>,
     ) -> Vec<Vec<Vec<usize>>> {
+        if let Some(labels) = structure.labels() {
+            return match self.last_matrix() {
+                Some(last) => last.borrow_mut().update(structure, candidates, &labels),
+                None => build_depth_two_matrix_parallel(structure, candidates, &labels),
+            };
+        }
+
         let size = candidates.len();
         let mut matrix = vec![vec![vec![]; size]; size];
         for i in 0..size {
@@ -79,4 +97,62 @@ pub trait Depth2Algorithm {
     }
 }
 
-// TODO : Move this in a utils files in search
+// The covers of the candidates are split by label into bitsets once. The cells of the matrix are then
+// independent intersections, computed in parallel
+fn build_depth_two_matrix_parallel<S: Structure>(
+    structure: &mut S,
+    candidates: &[usize],
+    labels: &[usize],
+) -> Vec<Vec<Vec<usize>>> {
+    let covers = candidates_covers(structure, candidates, labels);
+    matrix_from_covers(&covers)
+}
+
+// Samples of the node having each candidate set to 1, as one bitset per label
+fn candidates_covers<S: Structure>(
+    structure: &mut S,
+    candidates: &[usize],
+    labels: &[usize],
+) -> Vec<Vec<Vec<u64>>> {
+    let num_labels = structure.num_labels();
+    let words = labels.len().div_ceil(64);
+    candidates
+        .iter()
+        .map(|candidate| {
+            structure.push(item(*candidate, 1));
+            let mut cover = vec![vec![0u64; words]; num_labels];
+            for tid in structure.get_tids() {
+                cover[labels[tid]][tid / 64] |= 1u64 << (tid % 64);
+            }
+            structure.backtrack();
+            cover
+        })
+        .collect()
+}
+
+fn matrix_from_covers(covers: &[Vec<Vec<u64>>]) -> Vec<Vec<Vec<usize>>> {
+    let size = covers.len();
+    let rows = (0..size)
+        .into_par_iter()
+        .map(|i| {
+            (i..size)
+                .map(|second| {
+                    covers[i]
+                        .iter()
+                        .zip(covers[second].iter())
+                        .map(|(first, second)| count_intersection(first, second))
+                        .collect::<Vec<usize>>()
+                })
+                .collect::<Vec<Vec<usize>>>()
+        })
+        .collect::<Vec<Vec<Vec<usize>>>>();
+
+    let mut matrix = vec![vec![vec![]; size]; size];
+    for (i, row) in rows.into_iter().enumerate() {
+        for (offset, cell) in row.into_iter().enumerate() {
+            matrix[i + offset][i] = cell.clone();
+            matrix[i][i + offset] = cell;
+        }
+    }
+    matrix
+}
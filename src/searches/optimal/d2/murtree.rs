@@ -1,13 +1,18 @@
-use crate::globals::{float_is_null, item};
+use crate::globals::{float_is_null, float_ties, item};
 use crate::searches::deduce_sibling_error;
 use crate::searches::errors::{ErrorWrapper, NativeError};
-use crate::searches::optimal::d2::{Depth2Algorithm, MAX_ERROR};
+use crate::searches::optimal::d2::{Depth2Algorithm, IncrementalMatrix, MAX_ERROR};
 use crate::structures::Structure;
 use crate::tree::Tree;
+use std::cell::RefCell;
 
 #[derive(Default)]
 pub struct Murtree {
     error_function: NativeError,
+    last_matrix: RefCell<IncrementalMatrix>,
+    // Rank of each attribute among the trees of equal errors, the first ranked is kept. Empty
+    // keeps the smallest attribute index
+    tie_ranks: Vec<usize>,
 }
 
 impl Depth2Algorithm for Murtree {
@@ -20,11 +25,37 @@ impl Depth2Algorithm for Murtree {
             }
         }
     }
+
+    fn last_matrix(&self) -> Option<&RefCell<IncrementalMatrix>> {
+        Some(&self.last_matrix)
+    }
 }
 
 impl Murtree {
+    // Uses a weighted classification error instead of the plain one
+    pub fn new(error_function: NativeError) -> Self {
+        Self {
+            error_function,
+            last_matrix: RefCell::default(),
+            tie_ranks: vec![],
+        }
+    }
+
+    pub fn set_tie_ranks(&mut self, tie_ranks: Vec<usize>) {
+        self.tie_ranks = tie_ranks;
+    }
+
+    // Candidates in the order their trees are kept in on equal errors
+    fn ranked_candidates<S: Structure>(&self, structure: &mut S, min_sup: usize) -> Vec<usize> {
+        let mut candidates = self.generate_candidates_list(structure, min_sup);
+        if !self.tie_ranks.is_empty() {
+            candidates.sort_by_key(|attribute| self.tie_ranks[*attribute]);
+        }
+        candidates
+    }
+
     fn depth_one<S: Structure>(&self, min_sup: usize, structure: &mut S) -> Tree {
-        let candidates = self.generate_candidates_list(structure, min_sup);
+        let candidates = self.ranked_candidates(structure, min_sup);
         if candidates.is_empty() {
             return Tree::empty_tree(1);
         }
@@ -52,7 +83,7 @@ impl Murtree {
             if let Some(root) = tree.get_node_mut(tree.get_root_index()) {
                 past_error = root.value.error;
             }
-            if error < past_error {
+            if error < past_error && !float_ties(error, past_error) {
                 if let Some(root) = tree.get_node_mut(tree.get_root_index()) {
                     root.value.error = error;
                     root.value.test = Some(*candidate);
@@ -72,7 +103,7 @@ impl Murtree {
 
     fn depth_two<S: Structure>(&self, min_sup: usize, structure: &mut S) -> Tree {
         // TODO : depth attribute
-        let candidates = self.generate_candidates_list(structure, min_sup);
+        let candidates = self.ranked_candidates(structure, min_sup);
 
         if candidates.is_empty() {
             return Tree::empty_tree(2);
@@ -178,7 +209,8 @@ impl Murtree {
                         self.error_function.compute(&i_left_j_left_classes_support);
 
                     // TODO Upper bound control here
-                    if (left_leaf_error.0 + right_leaf_error.0) >= feat_error {
+                    let split_error = left_leaf_error.0 + right_leaf_error.0;
+                    if split_error >= feat_error || float_ties(split_error, feat_error) {
                         continue;
                     }
 
@@ -278,7 +310,8 @@ impl Murtree {
                         self.error_function.compute(i_right_j_right_classes_support);
 
                     // TODO Upper bound control here
-                    if (left_leaf_error.0 + right_leaf_error.0) >= feat_error {
+                    let split_error = left_leaf_error.0 + right_leaf_error.0;
+                    if split_error >= feat_error || float_ties(split_error, feat_error) {
                         continue;
                     }
 
@@ -324,7 +357,7 @@ impl Murtree {
                 if let Some(node) = tree.get_node(tree.get_root_index()) {
                     best_error = node.value.error;
                 }
-                if best_error > feat_error {
+                if best_error > feat_error && !float_ties(best_error, feat_error) {
                     tree = root_tree;
                 }
 
@@ -340,9 +373,10 @@ impl Murtree {
 #[cfg(test)]
 mod murtree_test {
     use crate::data::{BinaryData, FileReader};
+    use crate::globals::item;
     use crate::searches::errors::NativeError;
     use crate::searches::optimal::d2::{Depth2Algorithm, Murtree};
-    use crate::structures::Bitset;
+    use crate::structures::{Bitset, Horizontal, Structure};
 
     #[test]
     fn run_small_data() {
@@ -363,4 +397,41 @@ mod murtree_test {
         let error = tree.get_node(0).unwrap().value.error;
         assert_eq!(error, 137.0)
     }
+
+    #[test]
+    fn updated_matrix_matches_new_one() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = Bitset::new(&data);
+        let method = Murtree::default();
+        let candidates = (0..10).collect::<Vec<usize>>();
+
+        // Almost all the samples have the attribute 22 set to 1
+        structure.push(item(20, 0));
+        method.build_depth_two_matrix(&mut structure, &candidates);
+        structure.push(item(22, 1));
+        let updated = method.build_depth_two_matrix(&mut structure, &candidates);
+
+        assert_eq!(method.last_matrix.borrow().updates, 1);
+        assert_eq!(
+            updated,
+            Murtree::default().build_depth_two_matrix(&mut structure, &candidates)
+        );
+    }
+
+    #[test]
+    fn parallel_matrix_matches_sequential_one() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut bitset = Bitset::new(&data);
+        let mut horizontal = Horizontal::new(&data);
+        assert!(horizontal.labels().is_none());
+
+        bitset.push(9);
+        horizontal.push(9);
+        let method = Murtree::default();
+        let candidates = (0..10).collect::<Vec<usize>>();
+        assert_eq!(
+            method.build_depth_two_matrix(&mut bitset, &candidates),
+            method.build_depth_two_matrix(&mut horizontal, &candidates)
+        );
+    }
 }
@@ -3,13 +3,15 @@ use crate::globals::{
 };
 use crate::searches::deduce_sibling_error;
 use crate::searches::errors::{ErrorWrapper, NativeError};
-use crate::searches::optimal::d2::{Depth2Algorithm, MAX_ERROR};
+use crate::searches::optimal::d2::{Depth2Algorithm, IncrementalMatrix, MAX_ERROR};
 use crate::structures::Structure;
 use crate::tree::Tree;
+use std::cell::RefCell;
 
 #[derive(Default)]
 pub struct InfoGainDT {
     error_function: NativeError,
+    last_matrix: RefCell<IncrementalMatrix>,
 }
 
 impl Depth2Algorithm for InfoGainDT {
@@ -22,6 +24,10 @@ impl Depth2Algorithm for InfoGainDT {
             }
         }
     }
+
+    fn last_matrix(&self) -> Option<&RefCell<IncrementalMatrix>> {
+        Some(&self.last_matrix)
+    }
 }
 
 impl InfoGainDT {
@@ -0,0 +1,126 @@
+use crate::globals::{float_is_null, item};
+use crate::structures::Structure;
+use crate::tree::{NodeInfos, Tree, TreeNode};
+
+// Optimal tree of depth 1 or 2 minimizing a classification error where each transaction has the
+// weight given by the structure, as needed by boosting. The counting matrices of Murtree only hold
+// numbers of samples, so the weighted counts of each cover are asked to the structure instead
+#[derive(Default)]
+pub struct ErrorMinimizer;
+
+// Best subtree found at a node, its children being kept when it is split
+struct Subtree {
+    test: Option<usize>,
+    error: f64,
+    out: f64,
+    classes_support: Vec<usize>,
+    children: Option<Box<(Subtree, Subtree)>>,
+}
+
+impl ErrorMinimizer {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Tree of least weighted error below the current position of the structure, leaves predict
+    /// the class of largest weight. `min_sup` still counts samples
+    pub fn fit<S: Structure>(&self, min_sup: usize, depth: usize, structure: &mut S) -> Tree {
+        if depth == 0 || depth > 2 {
+            panic!("Depth must be 1 or 2")
+        }
+        let subtree = self.search(min_sup, depth, structure);
+        let mut tree = Tree::new();
+        let root = tree.add_root(TreeNode::new(NodeInfos::new()));
+        Self::fill_tree(&mut tree, root, subtree);
+        tree
+    }
+
+    fn search<S: Structure>(&self, min_sup: usize, depth: usize, structure: &mut S) -> Subtree {
+        let classes_support = structure.labels_support().to_vec();
+        let weighted = structure.weighted_labels_count();
+        let total = weighted.iter().sum::<f64>();
+        let (out, largest) =
+            weighted
+                .iter()
+                .enumerate()
+                .fold((0, <f64>::NEG_INFINITY), |best, (class, weight)| {
+                    match *weight > best.1 {
+                        true => (class, *weight),
+                        false => best,
+                    }
+                });
+        let mut best = Subtree {
+            test: None,
+            error: (total - largest).max(0.0),
+            out: out as f64,
+            classes_support,
+            children: None,
+        };
+        if depth == 0 || float_is_null(best.error) {
+            return best;
+        }
+
+        for attribute in 0..structure.num_attributes() {
+            if structure.temp_push(item(attribute, 0)) < min_sup
+                || structure.temp_push(item(attribute, 1)) < min_sup
+            {
+                continue;
+            }
+            structure.push(item(attribute, 0));
+            let left = self.search(min_sup, depth - 1, structure);
+            structure.backtrack();
+            structure.push(item(attribute, 1));
+            let right = self.search(min_sup, depth - 1, structure);
+            structure.backtrack();
+
+            let error = left.error + right.error;
+            if error < best.error && !float_is_null(best.error - error) {
+                best.test = Some(attribute);
+                best.error = error;
+                best.children = Some(Box::new((left, right)));
+            }
+        }
+        best
+    }
+
+    fn fill_tree(tree: &mut Tree, index: usize, subtree: Subtree) {
+        if let Some(node) = tree.get_node_mut(index) {
+            node.value.test = subtree.test;
+            node.value.error = subtree.error;
+            node.value.classes_support = Some(subtree.classes_support);
+            if subtree.children.is_none() {
+                node.value.out = Some(subtree.out);
+            }
+        }
+        if let Some(children) = subtree.children {
+            let (left, right) = *children;
+            let left_index = tree.add_left_node(index, TreeNode::new(NodeInfos::new()));
+            Self::fill_tree(tree, left_index, left);
+            let right_index = tree.add_right_node(index, TreeNode::new(NodeInfos::new()));
+            Self::fill_tree(tree, right_index, right);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_weighted {
+    use crate::data::{BinaryData, FileReader};
+    use crate::globals::get_tree_root_error;
+    use crate::searches::optimal::d2::{ErrorMinimizer, Murtree};
+    use crate::searches::optimal::Depth2Algorithm;
+    use crate::structures::RevBitset;
+
+    #[test]
+    fn uniform_weights_scale_the_murtree_error() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+        structure.set_weights(Some(vec![2.0; 812])).unwrap();
+
+        let tree = ErrorMinimizer::new().fit(1, 2, &mut structure);
+        let expected = Murtree::default().fit(1, 2, &mut structure);
+        assert_eq!(
+            get_tree_root_error(&tree),
+            2.0 * get_tree_root_error(&expected)
+        );
+    }
+}
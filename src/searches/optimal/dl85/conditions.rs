@@ -1,10 +1,17 @@
 use crate::cache::CacheEntry;
 use crate::globals::float_is_null;
+use crate::searches::rules::LeafRule;
 use crate::searches::utils::StopReason;
 use std::time::Duration;
 
 #[derive(Default)]
-pub struct StopConditions;
+pub struct StopConditions {
+    // Minimum proportion of the majority class turning a node into a leaf, for each depth starting
+    // at the children of the root. Nodes deeper than the list use its last threshold
+    pub purity_thresholds: Vec<f64>,
+    // Nodes for which the rule holds become leaves
+    pub leaf_rule: Option<LeafRule>,
+}
 
 impl StopConditions {
     pub(crate) fn check(
@@ -37,6 +44,14 @@ impl StopConditions {
         if self.pure_node(node) {
             return (true, StopReason::PureNode);
         }
+
+        if self.purity_threshold_reached(support, current_depth, node) {
+            return (true, StopReason::PurityThresholdReached);
+        }
+
+        if self.leaf_rule_holds(support, current_depth, node) {
+            return (true, StopReason::LeafRuleHolds);
+        }
         (false, StopReason::None)
     }
 
@@ -49,12 +64,17 @@ impl StopConditions {
             return (true, StopReason::LowerBoundConstrained);
         }
         if node.leaf_error <= node.lower_bound {
-            node.to_leaf();
+            node.to_leaf(StopReason::PureNode);
             return (true, StopReason::PureNode);
         }
         (false, StopReason::None)
     }
 
+    pub(crate) fn interrupted(&self, node: &mut CacheEntry) -> (bool, StopReason) {
+        node.to_leaf(StopReason::Interrupted);
+        (true, StopReason::Interrupted)
+    }
+
     fn time_limit_reached(
         &self,
         current_time: Duration,
@@ -62,7 +82,7 @@ impl StopConditions {
         node: &mut CacheEntry,
     ) -> bool {
         current_time.as_secs() as usize >= max_time && {
-            node.to_leaf();
+            node.to_leaf(StopReason::TimeLimitReached);
             true
         }
     }
@@ -73,21 +93,50 @@ impl StopConditions {
 
     fn max_depth_reached(&self, depth: usize, max_depth: usize, node: &mut CacheEntry) -> bool {
         depth == max_depth && {
-            node.to_leaf();
+            node.to_leaf(StopReason::MaxDepthReached);
             true
         }
     }
 
     fn not_enough_support(&self, support: usize, min_sup: usize, node: &mut CacheEntry) -> bool {
         support < min_sup * 2 && {
-            node.to_leaf();
+            node.to_leaf(StopReason::NotEnoughSupport);
             true
         }
     }
 
+    fn purity_threshold_reached(
+        &self,
+        support: usize,
+        depth: usize,
+        node: &mut CacheEntry,
+    ) -> bool {
+        let threshold = match (depth, self.purity_thresholds.last()) {
+            (0, _) | (_, None) => return false,
+            (_, Some(last)) => *self.purity_thresholds.get(depth - 1).unwrap_or(last),
+        };
+        support > 0 && 1.0 - node.leaf_error / support as f64 >= threshold && {
+            node.to_leaf(StopReason::PurityThresholdReached);
+            true
+        }
+    }
+
+    // As with the purity thresholds, the root is always split
+    fn leaf_rule_holds(&self, support: usize, depth: usize, node: &mut CacheEntry) -> bool {
+        depth > 0
+            && self
+                .leaf_rule
+                .as_ref()
+                .is_some_and(|rule| rule.holds(support, depth, node.leaf_error))
+            && {
+                node.to_leaf(StopReason::LeafRuleHolds);
+                true
+            }
+    }
+
     fn pure_node(&self, node: &mut CacheEntry) -> bool {
         float_is_null(node.leaf_error - node.lower_bound) && {
-            node.to_leaf();
+            node.to_leaf(StopReason::PureNode);
             true
         }
     }
@@ -0,0 +1,445 @@
+use crate::cache::Caching;
+use crate::globals::{float_is_null, item, tie_bound};
+use crate::heuristics::Heuristic;
+use crate::searches::errors::ErrorWrapper;
+use crate::searches::optimal::dl85::DL85;
+use crate::searches::rates::{
+    remaining_class_budget, split_class_budget, useful_class_budget, ClassErrorsBudget,
+};
+use crate::searches::SearchStrategy;
+use crate::structures::Structure;
+use crate::tree::{NodeInfos, Tree, TreeNode};
+use log::debug;
+use std::collections::{BTreeSet, HashMap};
+
+// Best split of a node for a given number of leaves, with the budgets of its left child and the
+// branch forced to a single class by a monotonicity constraint. When exact is false, error is only
+// a lower bound: no tree with less error than it exists. rank is the position of the split
+// attribute among the sorted candidates, the discrepancies spent by choosing it
+#[derive(Clone, Copy)]
+struct BudgetEntry {
+    error: f64,
+    exact: bool,
+    split: Option<(usize, usize, Option<usize>, ClassErrorsBudget)>,
+    rank: usize,
+}
+
+type BudgetKey = (Vec<usize>, usize, Option<usize>, ClassErrorsBudget);
+
+// Depth of a node with its budgets of leaves and of misclassified samples of each class
+#[derive(Clone, Copy)]
+struct NodeBudget {
+    depth: usize,
+    leaves: usize,
+    classes: ClassErrorsBudget,
+}
+
+struct BudgetContext<'a> {
+    itemset: BTreeSet<usize>,
+    candidates: &'a [usize],
+    // Discrepancies left on the current path with the discrepancy search
+    discrepancies: Option<usize>,
+    memo: HashMap<BudgetKey, BudgetEntry>,
+}
+
+impl<C, E, H> DL85<C, E, H>
+where
+    C: Caching + ?Sized,
+    E: ErrorWrapper + ?Sized,
+    H: Heuristic + ?Sized,
+{
+    // Search used when the number of leaves is bounded, when some features are monotonic, with
+    // rate constraints or with the discrepancy search. The budget of a node is shared between its
+    // children, so nodes are memoized by itemset and budget instead of using the cache. With binary
+    // classes, a split on a monotonic feature is only explored when one of its branches is a leaf
+    // predicting the class allowed by the constraint. This restricts the monotonic trees instead of
+    // checking the predictions: the trees found are monotonic, but those whose monotonic splits
+    // have two subtrees are never explored, so the tree is only optimal among the restricted ones.
+    // Rate constraints give each class a budget of misclassified samples, shared the same way, and
+    // nodes without a leaf within it have an infinite error. The discrepancy search only explores
+    // the paths straying at most `discrepancy_budget` times from the heuristic order, choosing the
+    // candidate of rank i costing i discrepancies
+    pub(super) fn leaves_search<S: Structure>(
+        &mut self,
+        structure: &mut S,
+        upper_bound: f64,
+        candidates: &[usize],
+    ) {
+        let mut context = BudgetContext {
+            itemset: BTreeSet::new(),
+            candidates,
+            discrepancies: match self.constraints.search_strategy {
+                SearchStrategy::DiscrepancySearch => Some(self.constraints.discrepancy_budget),
+                _ => None,
+            },
+            memo: HashMap::new(),
+        };
+        let budget = match self.constraints.max_leaves {
+            0 => <usize>::MAX,
+            max_leaves => max_leaves,
+        };
+        let root_budget = NodeBudget {
+            depth: 0,
+            leaves: budget,
+            classes: match self.rate_constraints.is_empty() {
+                true => [None; 2],
+                false => self.rate_constraints.budget(structure.labels_support()),
+            },
+        };
+        self.leaves_recursion(structure, root_budget, upper_bound, &mut context);
+
+        let mut tree = Tree::new();
+        let root = tree.add_root(TreeNode::new(NodeInfos::default()));
+        let error = self.build_budget_tree(structure, root_budget, &mut context, &mut tree, root);
+
+        self.statistics.cache_size = context.memo.len();
+        self.statistics.duration = self.runtime.elapsed();
+        self.statistics.tree_error = error;
+        self.tree = tree;
+    }
+
+    fn leaves_recursion<S: Structure>(
+        &mut self,
+        structure: &mut S,
+        node: NodeBudget,
+        upper_bound: f64,
+        context: &mut BudgetContext,
+    ) -> f64 {
+        let depth = node.depth;
+        self.explored_nodes += 1;
+        if self.explored_nodes.checked_rem(self.progress_interval) == Some(0) {
+            self.notify_progress();
+        }
+
+        let class_budget = self.useful_class_budget(structure, node.classes);
+        let (leaf_error, _) = self.bounded_leaf_error(structure, class_budget);
+        let budget = self.useful_budget(depth, node.leaves, structure.support());
+        if budget <= 1 || float_is_null(leaf_error) || self.is_interrupted() {
+            return leaf_error;
+        }
+        if self.runtime.elapsed().as_secs() as usize >= self.constraints.max_time {
+            self.statistics.time_limit_reached = true;
+            return leaf_error;
+        }
+
+        let key = (
+            context.itemset.iter().copied().collect::<Vec<usize>>(),
+            budget,
+            context.discrepancies,
+            class_budget,
+        );
+        if let Some(entry) = context.memo.get(&key) {
+            if entry.exact || entry.error >= upper_bound {
+                return entry.error;
+            }
+        }
+
+        let mut node_candidates =
+            self.get_node_candidates(structure, <usize>::MAX, context.candidates);
+        if self.constraints.sort_policy.sorts(depth) {
+            self.heuristic.compute(structure, &mut node_candidates);
+        }
+        if let Some(filter) = &self.candidates_filter {
+            filter.filter(&key.0, depth, structure.support(), &mut node_candidates);
+        }
+
+        let mut best = leaf_error;
+        if depth == 0 {
+            self.record_incumbent(best);
+        }
+        let mut split = None;
+        let mut split_rank = 0;
+        let child_budget = self.useful_budget(depth + 1, budget - 1, structure.support());
+        let explored = self.beam_size(node_candidates.len(), depth);
+        let explored = self.discrepancy_size(explored, context.discrepancies);
+        let discrepancies = context.discrepancies;
+        for (rank, attribute) in node_candidates.into_iter().take(explored).enumerate() {
+            context.discrepancies = discrepancies.map(|left| left - rank);
+            let forced_classes = self
+                .monotonic_features
+                .get(&attribute)
+                .map(|direction| direction.forced_classes());
+            let splits = match forced_classes {
+                None => (budget.saturating_sub(child_budget).max(1)..=child_budget.min(budget - 1))
+                    .map(|left_budget| (left_budget, None))
+                    .collect::<Vec<(usize, Option<usize>)>>(),
+                Some(_) => vec![(1, Some(0)), (budget - 1, Some(1))],
+            };
+            let class_splits = split_class_budget(class_budget);
+            for (left_budget, forced) in splits {
+                for (left_class_budget, right_class_budget) in class_splits.iter().copied() {
+                    let bound = best.min(upper_bound);
+                    if float_is_null(bound) {
+                        break;
+                    }
+                    let left_error = match (forced, forced_classes) {
+                        (Some(0), Some(classes)) => {
+                            self.forced_leaf_error(structure, item(attribute, 0), classes[0])
+                        }
+                        _ => self.leaves_child(
+                            structure,
+                            item(attribute, 0),
+                            NodeBudget {
+                                depth: depth + 1,
+                                leaves: left_budget,
+                                classes: left_class_budget,
+                            },
+                            bound,
+                            context,
+                        ),
+                    };
+                    if left_error >= bound {
+                        continue;
+                    }
+                    let right_error = match (forced, forced_classes) {
+                        (Some(1), Some(classes)) => {
+                            self.forced_leaf_error(structure, item(attribute, 1), classes[1])
+                        }
+                        _ => self.leaves_child(
+                            structure,
+                            item(attribute, 1),
+                            NodeBudget {
+                                depth: depth + 1,
+                                leaves: budget - left_budget,
+                                classes: right_class_budget,
+                            },
+                            bound - left_error,
+                            context,
+                        ),
+                    };
+                    if left_error + right_error < bound {
+                        best = left_error + right_error;
+                        if depth == 0 {
+                            debug!("New best tree with error {}", best);
+                            self.record_incumbent(best);
+                        }
+                        split = Some((attribute, left_budget, forced, left_class_budget));
+                        split_rank = rank;
+                    }
+                }
+            }
+        }
+        context.discrepancies = discrepancies;
+
+        let exact = best < upper_bound;
+        context.memo.insert(
+            key,
+            BudgetEntry {
+                error: if exact { best } else { upper_bound },
+                exact,
+                split: if exact { split } else { None },
+                rank: split_rank,
+            },
+        );
+        best
+    }
+
+    // Replaces the tree, proven optimal, by one with the same error and the fewest leaves. The
+    // leaves budget search runs with budgets of 1, 2, ... leaves until one reaches the error, its
+    // memo keying the nodes by itemset and budget being shared between the budgets
+    pub(super) fn smallest_tree<S: Structure>(&mut self, structure: &mut S, candidates: &[usize]) {
+        let error = self.statistics.tree_error;
+        let mut context = BudgetContext {
+            itemset: BTreeSet::new(),
+            candidates,
+            discrepancies: None,
+            memo: HashMap::new(),
+        };
+        for budget in 1..self.tree.num_leaves() {
+            let root_budget = NodeBudget {
+                depth: 0,
+                leaves: budget,
+                classes: [None; 2],
+            };
+            let budget_error =
+                self.leaves_recursion(structure, root_budget, tie_bound(error), &mut context);
+            if budget_error < tie_bound(error) {
+                let mut tree = Tree::new();
+                let root = tree.add_root(TreeNode::new(NodeInfos::default()));
+                self.statistics.tree_error =
+                    self.build_budget_tree(structure, root_budget, &mut context, &mut tree, root);
+                debug!("Tree of error {} reduced to {} leaves", error, budget);
+                self.tree = tree;
+                return;
+            }
+        }
+    }
+
+    fn leaves_child<S: Structure>(
+        &mut self,
+        structure: &mut S,
+        child: usize,
+        budget: NodeBudget,
+        upper_bound: f64,
+        context: &mut BudgetContext,
+    ) -> f64 {
+        context.itemset.insert(child);
+        structure.push(child);
+        let error = self.leaves_recursion(structure, budget, upper_bound, context);
+        structure.backtrack();
+        context.itemset.remove(&child);
+        error
+    }
+
+    // Number of candidates within the discrepancies left, the others are never explored
+    fn discrepancy_size(&mut self, explored: usize, discrepancies: Option<usize>) -> usize {
+        match discrepancies {
+            Some(left) if left < explored.saturating_sub(1) => {
+                self.statistics.discrepancy_limited = true;
+                left + 1
+            }
+            _ => explored,
+        }
+    }
+
+    // A node cannot have more leaves than its remaining depth and its support allow
+    fn useful_budget(&self, depth: usize, budget: usize, support: usize) -> usize {
+        let remaining = self.constraints.max_depth.saturating_sub(depth);
+        let budget = budget.min(support / self.constraints.min_sup.max(1));
+        match remaining >= usize::BITS as usize - 1 {
+            true => budget,
+            false => budget.min(1 << remaining),
+        }
+    }
+
+    // Budget of misclassified samples of each class that still constrains the node
+    fn useful_class_budget<S: Structure>(
+        &self,
+        structure: &mut S,
+        class_budget: ClassErrorsBudget,
+    ) -> ClassErrorsBudget {
+        match class_budget == [None; 2] {
+            true => class_budget,
+            false => useful_class_budget(class_budget, structure.labels_support()),
+        }
+    }
+
+    // Best leaf misclassifying no more samples of each class than its budget, with an infinite
+    // error when no class fits. A misclassified sample costs the weight of its class, if any
+    fn bounded_leaf_error<S: Structure>(
+        &self,
+        structure: &mut S,
+        class_budget: ClassErrorsBudget,
+    ) -> (f64, f64) {
+        if class_budget == [None; 2] {
+            return self.error_as_leaf(structure);
+        }
+        let weights = self.error_function.class_weights();
+        let support = structure.labels_support();
+        let mut best = (<f64>::INFINITY, 0.0);
+        for class in 0..support.len() {
+            let others = (0..support.len()).filter(|other| *other != class);
+            let within_budget = others.clone().all(|other| {
+                class_budget
+                    .get(other)
+                    .copied()
+                    .flatten()
+                    .is_none_or(|allowed| support[other] <= allowed)
+            });
+            if !within_budget {
+                continue;
+            }
+            let error = others
+                .map(|other| support[other] as f64 * weights.map_or(1.0, |w| w[other]))
+                .sum::<f64>();
+            if error < best.0 {
+                best = (error, class as f64);
+            }
+        }
+        best
+    }
+
+    // Error of a leaf predicting the given class
+    fn forced_leaf_error<S: Structure>(
+        &self,
+        structure: &mut S,
+        child: usize,
+        class: usize,
+    ) -> f64 {
+        structure.push(child);
+        let support = structure.labels_support();
+        let error = support.iter().sum::<usize>() - support.get(class).copied().unwrap_or(0);
+        structure.backtrack();
+        error as f64
+    }
+
+    fn build_budget_tree<S: Structure>(
+        &self,
+        structure: &mut S,
+        node: NodeBudget,
+        context: &mut BudgetContext,
+        tree: &mut Tree,
+        index: usize,
+    ) -> f64 {
+        let class_budget = self.useful_class_budget(structure, node.classes);
+        let key = (
+            context.itemset.iter().copied().collect::<Vec<usize>>(),
+            self.useful_budget(node.depth, node.leaves, structure.support()),
+            context.discrepancies,
+            class_budget,
+        );
+        let split = context
+            .memo
+            .get(&key)
+            .and_then(|entry| entry.split.map(|split| (split, entry.error, entry.rank)));
+
+        let error = match split {
+            None => {
+                let (error, out) = self.bounded_leaf_error(structure, class_budget);
+                if let Some(node) = tree.get_node_mut(index) {
+                    node.value.out = Some(out);
+                }
+                error
+            }
+            Some(((attribute, left_budget, forced, left_class_budget), error, rank)) => {
+                if let Some(node) = tree.get_node_mut(index) {
+                    node.value.test = Some(attribute);
+                }
+                let discrepancies = context.discrepancies;
+                context.discrepancies = discrepancies.map(|left| left - rank);
+                let class_budgets = [
+                    left_class_budget,
+                    remaining_class_budget(class_budget, left_class_budget),
+                ];
+                for (branch, child_budget) in [left_budget, key.1 - left_budget].iter().enumerate()
+                {
+                    let child = item(attribute, branch);
+                    let child_index = match branch {
+                        0 => tree.add_left_node(index, TreeNode::new(NodeInfos::default())),
+                        _ => tree.add_right_node(index, TreeNode::new(NodeInfos::default())),
+                    };
+                    let forced_class = self
+                        .monotonic_features
+                        .get(&attribute)
+                        .filter(|_| forced == Some(branch))
+                        .map(|direction| direction.forced_classes()[branch]);
+                    if let Some(class) = forced_class {
+                        let error = self.forced_leaf_error(structure, child, class);
+                        if let Some(node) = tree.get_node_mut(child_index) {
+                            node.value.out = Some(class as f64);
+                            node.value.error = error;
+                        }
+                        continue;
+                    }
+                    context.itemset.insert(child);
+                    structure.push(child);
+                    let child_budget = NodeBudget {
+                        depth: node.depth + 1,
+                        leaves: *child_budget,
+                        classes: class_budgets[branch],
+                    };
+                    self.build_budget_tree(structure, child_budget, context, tree, child_index);
+                    structure.backtrack();
+                    context.itemset.remove(&child);
+                }
+                context.discrepancies = discrepancies;
+                error
+            }
+        };
+
+        if let Some(node) = tree.get_node_mut(index) {
+            node.value.error = error;
+        }
+        error
+    }
+}
@@ -1,24 +1,45 @@
 mod conditions;
+mod leaves;
 mod similarity;
 
 use crate::cache::{CacheEntry, Caching};
-use crate::globals::{attribute, float_is_null, get_tree_root_error, item};
-use crate::heuristics::Heuristic;
-use crate::searches::errors::ErrorWrapper;
+use crate::globals::{
+    attribute, fill_classes_support, float_is_null, get_tree_root_error, item, item_type,
+};
+use crate::heuristics::{Heuristic, InformationGain};
+use crate::searches::errors::{ErrorWrapper, NativeError};
+use crate::searches::filters::CandidatesFilter;
+use crate::searches::greedy::LGDT;
+use crate::searches::interrupt::SearchInterrupter;
+use crate::searches::monotonic::{is_monotonic, MonotonicFeatures};
 use crate::searches::optimal::d2::Murtree;
 use crate::searches::optimal::dl85::conditions::StopConditions;
 use crate::searches::optimal::dl85::similarity::SimilarityCover;
 use crate::searches::optimal::Depth2Algorithm;
+use crate::searches::progress::{ProgressObserver, SearchProgress};
+use crate::searches::rates::RateConstraints;
+use crate::searches::trace::{SearchTracer, TraceDecision, TraceEvent};
 use crate::searches::utils::{
-    BranchingStrategy, CacheInitStrategy, Constraints, LowerBoundStrategy, NodeExposedData,
-    SearchStrategy, Specialization, Statistics, StopReason,
+    BranchingStrategy, CacheFullPolicy, CacheInitStrategy, Constraints, LowerBoundPolicy,
+    LowerBoundStrategy, NodeExposedData, SearchStrategy, SortPolicy, Specialization, Statistics,
+    StopReason, TieBreak,
 };
 use crate::structures::Structure;
 use crate::tree::NodeInfos;
 use crate::tree::{Tree, TreeNode};
-use std::collections::BTreeSet;
+use log::{debug, info, trace, warn};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::cell::RefCell;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap};
+use std::mem::size_of;
 use std::time::Instant;
 
+// Entries reserved at most by the dynamic allocation of the cache
+const DYNAMIC_ALLOCATION_LIMIT: usize = 1 << 16;
+
 // The search will return the node error, the reason the search was stop and if we did a projection in the database
 pub type SearchReturn = (f64, StopReason, bool);
 pub type BranchChoice = (usize, f64, f64);
@@ -28,15 +49,46 @@ where
     E: ErrorWrapper + ?Sized,
     H: Heuristic + ?Sized,
 {
-    constraints: Constraints,
+    pub constraints: Constraints,
     pub statistics: Statistics,
-    stop_conditions: StopConditions,
+    pub stop_conditions: StopConditions,
     cache: Box<C>,
     error_function: Box<E>,
     heuristic: Box<H>,
     pub tree: Tree,
+    // Applied on the candidates of each node. It is not used by the Murtree specialization
+    pub candidates_filter: Option<Box<dyn CandidatesFilter>>,
+    // Allows stopping the search from outside, for instance on user interruption
+    pub interrupter: Option<Box<dyn SearchInterrupter>>,
+    // Notified every progress_interval explored nodes
+    pub progress_observer: Option<Box<dyn ProgressObserver>>,
+    pub progress_interval: usize,
+    // Told about each node of the search, except with the leaves budget searches
+    pub tracer: Option<Box<dyn SearchTracer>>,
+    // Features whose predictions must be monotonic, only with binary classes. One branch of their
+    // splits is then always a leaf, see leaves_search
+    pub monotonic_features: MonotonicFeatures,
+    // Bounds on the false negative and false positive rates, only with binary classes
+    pub rate_constraints: RateConstraints,
+    // Beam width of each depth, replacing `beam_width` when not empty. Nodes deeper than the list
+    // use its last width
+    pub depth_beam_widths: Vec<usize>,
+    explored_nodes: usize,
     runtime: Instant,
     murtree: Murtree,
+    // Reused to give the tids or the target values of each node to the error function
+    tids: RefCell<Vec<usize>>,
+    targets: RefCell<Vec<f64>>,
+    // Covers of the solved children of each explored node, kept so that the similarity lower
+    // bounds of a node explored again, or of a restart, start from what the previous explorations
+    // learned. They are bounded like the cache and forgotten by a fit which is not a restart
+    similarities: HashMap<BTreeSet<usize>, SimilarityCover>,
+    // With the cover keys, first itemset met for each cover fingerprint, support and size, with
+    // its sorted tids. The itemsets with the same key and the same tids use its cache entry
+    equivalent_itemsets: HashMap<(u64, usize, usize), (Vec<usize>, BTreeSet<usize>)>,
+    // Set by relax and prepare_restart, the next fit runs on the same data and keeps the cover
+    // merges and the similarity covers
+    continued: bool,
 }
 
 impl<C, E, H> DL85<C, E, H>
@@ -50,7 +102,7 @@ where
         max_depth: usize,
         max_error: f64,
         max_time: usize,
-        one_time_sort: bool,
+        sort_policy: SortPolicy,
         cache_init_size: usize,
         cache_init_strategy: CacheInitStrategy,
         specialization: Specialization,
@@ -66,16 +118,35 @@ where
             min_sup,
             max_error,
             max_time,
-            one_time_sort,
+            sort_policy,
             specialization,
             node_exposed_data: data_format,
             lower_bound_strategy,
+            lower_bound_policy: LowerBoundPolicy::TwoCovers,
             branching_strategy: branching,
             cache_init_size,
             cache_init_strategy,
+            max_cache_size: 0,
+            max_cache_bytes: 0,
+            cache_full_policy: CacheFullPolicy::Evict,
+            max_leaves: 0,
+            beam_width: 0,
+            min_heuristic_score: 0.0,
+            min_relative_score: 0.0,
             discrepancy_budget: 0,
             search_strategy: SearchStrategy::None_,
+            explain_leaves: false,
+            record_anytime: false,
+            cover_keys: false,
+            tie_break: TieBreak::Index,
+            seed: 0,
+            minimize_leaves: false,
         };
+        // The depth 2 specialization applies the same class weights
+        let murtree = Murtree::new(match error_function.class_weights() {
+            Some(weights) => NativeError::weighted(weights.to_vec()),
+            None => NativeError::default(),
+        });
 
         Self {
             constraints,
@@ -88,19 +159,211 @@ where
             error_function,
             heuristic,
             tree: Tree::default(),
+            candidates_filter: None,
+            interrupter: None,
+            progress_observer: None,
+            progress_interval: 1000,
+            tracer: None,
+            monotonic_features: MonotonicFeatures::new(),
+            rate_constraints: RateConstraints::default(),
+            depth_beam_widths: vec![],
+            explored_nodes: 0,
             runtime: Instant::now(),
-            murtree: Murtree::default(),
+            murtree,
+            tids: RefCell::default(),
+            targets: RefCell::default(),
+            similarities: HashMap::new(),
+            equivalent_itemsets: HashMap::new(),
+            continued: false,
+        }
+    }
+
+    // An error is returned when the monotonicity or rate constraints do not fit the data
+    pub fn fit<S: Structure>(&mut self, structure: &mut S) -> Result<(), String> {
+        self.search(structure, None)
+    }
+
+    // The warm start tree is re-evaluated on the data and, if valid, its error is used as the initial
+    // upper bound. It is returned as the solution when the search cannot find a better tree. The
+    // tree may come from the user, an error is returned when it tests unknown attributes.
+    pub fn fit_with_warm_start<S: Structure>(
+        &mut self,
+        structure: &mut S,
+        warm_start: &Tree,
+    ) -> Result<(), String> {
+        warm_start.check_attributes(structure.num_attributes())?;
+        self.search(structure, Some(warm_start))
+    }
+
+    // Same as the warm start with the tree learned by LGDT under the same constraints. Its error
+    // usually gives a much tighter initial upper bound than `max_error`. Data without labels cannot
+    // be used by LGDT and falls back to a plain fit.
+    pub fn fit_with_greedy_warm_start<S: Structure>(
+        &mut self,
+        structure: &mut S,
+    ) -> Result<(), String> {
+        self.check_constraints(structure)?;
+        if structure.num_labels() == 0 {
+            return self.fit(structure);
+        }
+        let mut greedy = LGDT::new(
+            self.constraints.min_sup,
+            self.constraints.max_depth,
+            SearchStrategy::LessGreedyMurtree,
+        );
+        greedy.monotonic_features = self.monotonic_features.clone();
+        greedy.fit(structure);
+        structure.reset();
+        self.search(structure, Some(&greedy.tree))
+    }
+
+    // Raises the maximum depth and lowers the minimum support of a fitted learner, so that the next
+    // fit continues from its cache. Itemsets and their leaf errors stay valid, only the solutions
+    // and bounds found under the tighter constraints are forgotten. Passing the previous tree as
+    // warm start also keeps its error as initial upper bound.
+    pub fn relax(&mut self, max_depth: usize, min_sup: usize) -> Result<(), String> {
+        if max_depth < self.constraints.max_depth || min_sup > self.constraints.min_sup {
+            return Err(format!(
+                "Constraints can only be relaxed, from max_depth {} and min_sup {}",
+                self.constraints.max_depth, self.constraints.min_sup
+            ));
+        }
+        self.constraints.max_depth = max_depth;
+        self.constraints.min_sup = min_sup;
+        // Internal nodes of the depth 2 specialization are cached without their leaf error
+        self.cache
+            .invalidate(&mut |_, entry| entry.leaf_error.is_infinite());
+        self.cache.reset_solutions();
+        // The errors of the saved covers were found under the tighter constraints
+        self.similarities.clear();
+        self.continued = true;
+        Ok(())
+    }
+
+    // Marks the next fit as a restart of the search on the same data and under the same
+    // constraints, after a time limit or a budget stopped it. The cover merges and the similarity
+    // covers found by the previous fits are then kept
+    pub fn prepare_restart(&mut self) {
+        self.continued = true;
+    }
+
+    // Solves the depths 1, 2, ... up to the maximum depth within the time limit, shared by all
+    // the depths. Each depth relaxes the previous one, so that its cache is reused, and is warm
+    // started by its tree. It stops at the first depth that cannot be proven optimal, whose best
+    // tree is kept as the learned tree, or once a tree makes no error. Returns the tree of the
+    // deepest depth proven optimal, also recorded in the statistics, None when there is none.
+    // The learner must not have been fitted before, the first depth starting from an empty cache
+    pub fn fit_increasing_depths<S: Structure>(
+        &mut self,
+        structure: &mut S,
+    ) -> Result<Option<Tree>, String> {
+        let max_depth = self.constraints.max_depth;
+        let time_limit = self.constraints.max_time;
+        let start = Instant::now();
+        let mut optimal: Option<(usize, Tree)> = None;
+        for depth in 1..=max_depth {
+            let remaining = time_limit.saturating_sub(start.elapsed().as_secs() as usize);
+            if remaining == 0 {
+                break;
+            }
+            self.constraints.max_time = remaining;
+            match &optimal {
+                Some((_, tree)) => {
+                    self.relax(depth, self.constraints.min_sup)?;
+                    let tree = tree.clone();
+                    self.fit_with_warm_start(structure, &tree)?;
+                }
+                None => {
+                    self.constraints.max_depth = depth;
+                    self.fit(structure)?;
+                }
+            }
+            if !self.statistics.is_optimal() {
+                break;
+            }
+            optimal = Some((depth, self.tree.clone()));
+            if self.statistics.tree_error == 0.0 {
+                break;
+            }
+        }
+        self.constraints.max_time = time_limit;
+        self.statistics.constraints.max_time = time_limit;
+        self.statistics.duration = start.elapsed();
+        self.statistics.optimal_depth = optimal.as_ref().map(|(depth, _)| *depth);
+        Ok(optimal.map(|(_, tree)| tree))
+    }
+
+    // Forgets the cache entries whose cover holds one of the samples, given as class and binary
+    // row, added to or removed from the structure since the last fit. The other entries keep their
+    // solutions, so that the next fit only searches again the parts of the tree the samples reach
+    pub fn invalidate_samples(&mut self, samples: &[(usize, Vec<usize>)]) {
+        let covers = |itemset: &BTreeSet<usize>, row: &[usize]| {
+            itemset
+                .iter()
+                .all(|it| row.get(attribute(*it)) == Some(&item_type(*it)))
+        };
+        self.cache
+            .invalidate(&mut |itemset, _| samples.iter().any(|(_, row)| covers(itemset, row)));
+        self.similarities.clear();
+        self.equivalent_itemsets.clear();
+    }
+
+    // The monotonicity and rate constraints are only defined for binary classes
+    fn check_constraints<S: Structure>(&self, structure: &S) -> Result<(), String> {
+        if !self.monotonic_features.is_empty() && structure.num_labels() != 2 {
+            return Err("Monotonicity constraints require binary classes".to_string());
+        }
+        if !self.rate_constraints.is_empty() {
+            if structure.num_labels() != 2 {
+                return Err("Rate constraints require binary classes".to_string());
+            }
+            if !self.monotonic_features.is_empty() {
+                return Err(
+                    "Rate constraints cannot be combined with monotonicity constraints".to_string(),
+                );
+            }
         }
+        Ok(())
     }
 
-    pub fn fit<S: Structure>(&mut self, structure: &mut S) {
+    fn search<S: Structure>(
+        &mut self,
+        structure: &mut S,
+        warm_start: Option<&Tree>,
+    ) -> Result<(), String> {
+        self.check_constraints(structure)?;
+        self.statistics.constraints = self.constraints;
+        self.statistics.interrupted = false;
+        self.statistics.beam_truncated = false;
+        self.statistics.filtered_candidates = 0;
+        self.statistics.discrepancy_limited = false;
+        self.statistics.cache_limit_reached = false;
+        self.statistics.time_limit_reached = false;
+        self.statistics.anytime_curve.clear();
+        self.statistics.optimal_depth = None;
+        self.explored_nodes = 0;
+        // The covers and the merges only hold for the data of the previous fit, which may have
+        // changed since then unless the learner was relaxed or restarted
+        if !std::mem::take(&mut self.continued) {
+            self.similarities.clear();
+            self.equivalent_itemsets.clear();
+        }
+        self.statistics.merged_itemsets = 0;
         self.statistics.num_attributes = structure.num_attributes();
         self.statistics.num_samples = structure.support();
 
         // Init cache
-        // TODO: This should take in strategy and init_capacity and also the structure to get the leaf error
+        self.cache
+            .set_max_size(match self.constraints.cache_full_policy {
+                CacheFullPolicy::Evict => self.cache_capacity(),
+                CacheFullPolicy::Stop => 0,
+            });
         let root_index = self.cache.init();
 
+        // The candidates of every node follow the order of the root ones on ties
+        let tie_ranks = self.tie_ranks(structure.num_attributes());
+        self.murtree.set_tie_ranks(tie_ranks.clone());
+
         // Collect the potential candidates based on the support constraint and sort them based on the heuristic
         let mut candidates = Vec::new();
         if self.constraints.min_sup == 1 {
@@ -115,30 +378,105 @@ where
             }
         }
 
+        if !tie_ranks.is_empty() {
+            candidates.sort_by_key(|attribute| tie_ranks[*attribute]);
+        }
         self.heuristic.compute(structure, &mut candidates);
+        self.init_cache(structure, &candidates);
+        debug!(
+            "DL8.5 search of depth {} on {} samples, {} candidates out of {} attributes",
+            self.constraints.max_depth,
+            self.statistics.num_samples,
+            candidates.len(),
+            self.statistics.num_attributes
+        );
 
         let mut itemset = BTreeSet::new();
 
         let mut similarity = SimilarityCover::default();
 
+        let mut upper_bound = self.constraints.max_error;
+        let mut warm_start_tree = None;
+        if let Some(tree) = warm_start {
+            let mut tree = tree.clone();
+            let root = tree.get_root_index();
+            let error = self.evaluate_tree(structure, &mut tree, root, 0, &mut BTreeSet::new());
+            if !self.rate_constraints.is_empty() {
+                fill_classes_support(&mut tree, structure);
+            }
+            if error < upper_bound
+                && (self.constraints.max_leaves == 0
+                    || tree.num_leaves() <= self.constraints.max_leaves)
+                && is_monotonic(&tree, &self.monotonic_features)
+                && self
+                    .rate_constraints
+                    .satisfied_by(&tree, structure.labels_support())
+            {
+                upper_bound = error;
+                warm_start_tree = Some(tree);
+                info!(
+                    "Warm start tree used as initial solution, with error {}",
+                    error
+                );
+            } else {
+                info!("Warm start tree discarded, with error {}", error);
+            }
+        }
+
         // Starting the search
         self.runtime = Instant::now();
-        self.recursion(
-            structure,
-            0,
-            self.constraints.max_error,
-            <usize>::MAX,
-            &mut itemset,
-            &candidates,
-            root_index,
-            true,
-            &mut similarity,
-        );
+        if warm_start_tree.is_some() {
+            self.record_incumbent(upper_bound);
+        }
+        let budget_search = self.constraints.max_leaves > 0
+            || !self.monotonic_features.is_empty()
+            || !self.rate_constraints.is_empty()
+            || matches!(
+                self.constraints.search_strategy,
+                SearchStrategy::DiscrepancySearch
+            );
+        if budget_search {
+            self.leaves_search(structure, upper_bound, &candidates);
+        } else {
+            self.recursion(
+                structure,
+                0,
+                upper_bound,
+                <usize>::MAX,
+                &mut itemset,
+                &candidates,
+                root_index,
+                true,
+                &mut similarity,
+            );
+
+            self.update_statistics();
+            self.get_solution_tree(structure);
+        }
 
-        self.update_statistics();
-        self.get_solution_tree();
+        if let Some(tree) = warm_start_tree {
+            if get_tree_root_error(&self.tree) >= upper_bound {
+                self.tree = tree;
+                self.statistics.tree_error = upper_bound;
+            }
+        }
+        if self.constraints.minimize_leaves && !budget_search && self.statistics.is_optimal() {
+            self.smallest_tree(structure, &candidates);
+        }
+        // The depth 2 specialization finds the root solution at once
+        self.record_incumbent(self.statistics.tree_error);
+        fill_classes_support(&mut self.tree, structure);
+        // The leaves budget searches do not use the cache, their only bound is the proven optimum
+        let root_lower_bound = self
+            .cache
+            .get_root_infos()
+            .map_or(0.0, |root| root.lower_bound);
+        self.statistics.set_lower_bound(root_lower_bound);
+        self.log_outcome();
+        Ok(())
     }
 
+    // Explores a node and reports it to the tracer
     fn recursion<S: Structure>(
         &mut self,
         structure: &mut S,
@@ -150,12 +488,60 @@ where
         parent_index: Option<usize>,
         parent_is_new: bool,
         similarity: &mut SimilarityCover,
+    ) -> SearchReturn {
+        let result = self.explore(
+            structure,
+            depth,
+            upper_bound,
+            parent_item,
+            itemset,
+            candidates,
+            parent_index,
+            parent_is_new,
+            similarity,
+        );
+        if self.tracer.is_some() {
+            self.trace_node(itemset, depth, upper_bound, parent_index, &result);
+        }
+        result
+    }
+
+    fn explore<S: Structure>(
+        &mut self,
+        structure: &mut S,
+        depth: usize,
+        upper_bound: f64,
+        parent_item: usize,
+        itemset: &mut BTreeSet<usize>,
+        candidates: &[usize],
+        parent_index: Option<usize>,
+        parent_is_new: bool,
+        similarity: &mut SimilarityCover,
     ) -> SearchReturn {
         let mut child_upper_bound = upper_bound;
         let current_support = structure.support();
 
+        self.explored_nodes += 1;
+        if self.explored_nodes.checked_rem(self.progress_interval) == Some(0) {
+            self.notify_progress();
+        }
+
         // BEGIN STEP: Check if we should stop
 
+        if self.is_interrupted() {
+            if let Some(node) = self.cache.get(itemset, parent_index) {
+                let return_condition = self.stop_conditions.interrupted(node);
+                return (node.error, return_condition.1, false);
+            }
+        }
+
+        if self.is_cache_full() {
+            if let Some(node) = self.cache.get(itemset, parent_index) {
+                node.to_leaf(StopReason::CacheLimitReached);
+                return (node.error, StopReason::CacheLimitReached, false);
+            }
+        }
+
         if let Some(node) = self.cache.get(itemset, parent_index) {
             let return_condition = self.stop_conditions.check(
                 node,
@@ -169,6 +555,9 @@ where
             );
 
             if return_condition.0 {
+                if let StopReason::TimeLimitReached = return_condition.1 {
+                    self.statistics.time_limit_reached = true;
+                }
                 return (node.error, return_condition.1, false);
             }
         }
@@ -208,40 +597,55 @@ where
         let mut node_candidates =
             self.get_node_candidates(structure, attribute(parent_item), candidates);
 
+        if self.constraints.sort_policy.sorts(depth) {
+            self.heuristic.compute(structure, &mut node_candidates);
+        }
+        self.drop_low_scores(structure, &mut node_candidates);
+
+        // The filter only restricts the tests of this node, the children choose among all the
+        // candidates
+        let mut unfiltered_candidates = None;
+        if let Some(filter) = &self.candidates_filter {
+            unfiltered_candidates = Some(node_candidates.clone());
+            let path = itemset.iter().copied().collect::<Vec<usize>>();
+            filter.filter(&path, depth, structure.support(), &mut node_candidates);
+        }
+
         if node_candidates.is_empty() {
             if let Some(node) = self.cache.get(itemset, parent_index) {
-                node.to_leaf();
-                return (node.error, StopReason::None, true);
+                node.to_leaf(StopReason::NoCandidates);
+                return (node.error, StopReason::NoCandidates, true);
             }
         }
 
-        if !self.constraints.one_time_sort {
-            self.heuristic.compute(structure, &mut node_candidates);
-        }
-
-        let mut child_similarity_data = SimilarityCover::default();
+        let mut child_similarity_data = self.similarities.remove(itemset).unwrap_or_default();
         let mut min_lower_bound = <f64>::INFINITY;
 
-        for child in node_candidates.iter() {
+        // The children may use all the candidates, only the exploration of this node is restricted
+        let children_candidates = unfiltered_candidates.as_ref().unwrap_or(&node_candidates);
+        let explored = self.beam_size(node_candidates.len(), depth);
+        for child in node_candidates[..explored].iter() {
             let branching_choice =
                 self.branching_strategy(*child, itemset, structure, &mut child_similarity_data);
 
             let it = item(*child, branching_choice.0);
             itemset.insert(it);
+            let mut equivalent = self.equivalent_itemset(structure, itemset, it);
+            let child_itemset = equivalent.as_mut().unwrap_or(&mut *itemset);
 
-            let (is_new, child_index) = self.cache.insert(itemset);
+            let (is_new, child_index) = self.cache.insert(child_itemset);
 
             // TODO : Move this in a function
             if is_new {
                 structure.push(it);
                 let error = self.error_as_leaf(structure);
-                if let Some(node) = self.cache.get(itemset, child_index) {
+                if let Some(node) = self.cache.get(child_itemset, child_index) {
                     node.leaf_error = error.0;
                     node.target = error.1;
                 }
             }
 
-            if let Some(node) = self.cache.get(itemset, child_index) {
+            if let Some(node) = self.cache.get(child_itemset, child_index) {
                 node.lower_bound = branching_choice.1;
             }
 
@@ -250,8 +654,8 @@ where
                 depth + 1,
                 child_upper_bound,
                 it,
-                itemset,
-                &node_candidates,
+                child_itemset,
+                children_candidates,
                 child_index,
                 is_new,
                 &mut child_similarity_data,
@@ -262,7 +666,7 @@ where
             // Now that the search is done. We have to see if the we new to go back to previous
             self.backtrack(
                 structure,
-                itemset,
+                child_itemset,
                 is_new,
                 &it,
                 &first_child_return,
@@ -271,7 +675,7 @@ where
             );
 
             if left_error >= child_upper_bound - branching_choice.2 {
-                if let Some(node) = self.cache.get(itemset, child_index) {
+                if let Some(node) = self.cache.get(child_itemset, child_index) {
                     min_lower_bound = <f64>::min(
                         min_lower_bound,
                         match left_error.is_finite() {
@@ -291,18 +695,20 @@ where
             let right_upper_bound = child_upper_bound - left_error;
             let it = item(*child, (branching_choice.0 + 1) % 2);
             itemset.insert(it);
+            let mut equivalent = self.equivalent_itemset(structure, itemset, it);
+            let child_itemset = equivalent.as_mut().unwrap_or(&mut *itemset);
 
-            let (is_new, child_index) = self.cache.insert(itemset);
+            let (is_new, child_index) = self.cache.insert(child_itemset);
 
             if is_new {
                 structure.push(it);
                 let error = self.error_as_leaf(structure);
-                if let Some(node) = self.cache.get(itemset, child_index) {
+                if let Some(node) = self.cache.get(child_itemset, child_index) {
                     node.leaf_error = error.0;
                     node.target = error.1;
                 }
             }
-            if let Some(node) = self.cache.get(itemset, child_index) {
+            if let Some(node) = self.cache.get(child_itemset, child_index) {
                 node.lower_bound = branching_choice.2;
             }
 
@@ -311,8 +717,8 @@ where
                 depth + 1,
                 right_upper_bound,
                 it,
-                itemset,
-                &node_candidates,
+                child_itemset,
+                children_candidates,
                 child_index,
                 is_new,
                 &mut child_similarity_data,
@@ -323,7 +729,7 @@ where
             // Now that the search is done. We have to see if the we new to go back to previous
             self.backtrack(
                 structure,
-                itemset,
+                child_itemset,
                 is_new,
                 &it,
                 &second_child_return,
@@ -340,6 +746,10 @@ where
 
             if feature_error < child_upper_bound {
                 child_upper_bound = feature_error;
+                if depth == 0 {
+                    debug!("New best tree with error {}", feature_error);
+                    self.record_incumbent(feature_error);
+                }
 
                 if let Some(parent_node) = self.cache.get(itemset, parent_index) {
                     parent_node.error = child_upper_bound;
@@ -354,6 +764,9 @@ where
                 min_lower_bound = <f64>::min(feature_error, min_lower_bound);
             }
         }
+        if let LowerBoundStrategy::Similarity = self.constraints.lower_bound_strategy {
+            self.save_similarities(itemset, child_similarity_data);
+        }
 
         let mut node_error = 0.0;
         if let Some(node) = self.cache.get(itemset, parent_index) {
@@ -368,6 +781,53 @@ where
         (node_error, StopReason::Done, true)
     }
 
+    // Keeps the covers of the children of a node for its next exploration. With a bounded cache,
+    // they are at most as many as the cache entries: once the bound is reached, the covers of the
+    // itemsets evicted from the cache are dropped, and all of them when none was evicted. Only the
+    // lower bounds are weakened, the next explorations then starting from empty covers
+    fn save_similarities(&mut self, itemset: &BTreeSet<usize>, covers: SimilarityCover) {
+        let capacity = self.cache_capacity();
+        if capacity > 0 && self.similarities.len() >= capacity {
+            let cache = &self.cache;
+            self.similarities
+                .retain(|itemset, _| cache.find(itemset).is_some());
+            if self.similarities.len() >= capacity {
+                self.similarities.clear();
+            }
+        }
+        self.similarities.insert(itemset.clone(), covers);
+    }
+
+    // With the cover keys, the itemset whose cache entry stands for the itemset ending with the
+    // given item: the first one met with the same cover, support and size. None when the itemset
+    // stands for itself
+    fn equivalent_itemset<S: Structure>(
+        &mut self,
+        structure: &mut S,
+        itemset: &BTreeSet<usize>,
+        it: usize,
+    ) -> Option<BTreeSet<usize>> {
+        if !self.constraints.cover_keys {
+            return None;
+        }
+        let support = structure.push(it);
+        let key = (structure.cover_fingerprint(), support, itemset.len());
+        let tids = sorted_tids(structure);
+        structure.backtrack();
+        match self.equivalent_itemsets.entry(key) {
+            // The fingerprints of different covers may collide, the tids tell them apart
+            Entry::Occupied(entry) if entry.get().1 != *itemset && entry.get().0 == tids => {
+                self.statistics.merged_itemsets += 1;
+                Some(entry.get().1.clone())
+            }
+            Entry::Occupied(_) => None,
+            Entry::Vacant(entry) => {
+                entry.insert((tids, itemset.clone()));
+                None
+            }
+        }
+    }
+
     fn get_node_candidates<S: Structure>(
         &self,
         structure: &mut S,
@@ -412,12 +872,85 @@ where
     }
 
     fn error_as_leaf<S: Structure>(&self, structure: &mut S) -> (f64, f64) {
-        let error = match self.constraints.node_exposed_data {
-            NodeExposedData::ClassesSupport => {
+        let error = match (
+            self.constraints.node_exposed_data,
+            self.error_function.targets(),
+        ) {
+            (NodeExposedData::ClassesSupport, _) => {
                 self.error_function.compute(structure.labels_support())
             }
-            NodeExposedData::Tids => self.error_function.compute(&structure.get_tids()),
+            (NodeExposedData::Targets, Some(targets)) => {
+                let mut values = self.targets.borrow_mut();
+                structure.fill_targets(targets, &mut values);
+                self.error_function.compute_targets(&values)
+            }
+            (NodeExposedData::Tids | NodeExposedData::Targets, _) => {
+                let mut tids = self.tids.borrow_mut();
+                structure.fill_tids(&mut tids);
+                self.error_function.compute(&tids)
+            }
+        };
+        error
+    }
+
+    // Recomputes the errors and leaf outputs of a tree on the current data. Returns infinity when
+    // the tree does not satisfy the depth and support constraints or uses filtered candidates
+    fn evaluate_tree<S: Structure>(
+        &self,
+        structure: &mut S,
+        tree: &mut Tree,
+        index: usize,
+        depth: usize,
+        path: &mut BTreeSet<usize>,
+    ) -> f64 {
+        let (test, left, right) = match tree.get_node(index) {
+            Some(node) => (node.value.test, node.left, node.right),
+            None => return <f64>::INFINITY,
+        };
+
+        let error = if left == 0 && right == 0 {
+            if structure.support() < self.constraints.min_sup {
+                return <f64>::INFINITY;
+            }
+            let (error, out) = self.error_as_leaf(structure);
+            if let Some(node) = tree.get_node_mut(index) {
+                node.value.test = None;
+                node.value.out = Some(out);
+            }
+            error
+        } else {
+            let attribute = match test {
+                Some(attribute) if left > 0 && right > 0 && depth < self.constraints.max_depth => {
+                    attribute
+                }
+                _ => return <f64>::INFINITY,
+            };
+            if let Some(filter) = &self.candidates_filter {
+                let items = path.iter().copied().collect::<Vec<usize>>();
+                let mut kept = vec![attribute];
+                filter.filter(&items, depth, structure.support(), &mut kept);
+                if kept.is_empty() {
+                    return <f64>::INFINITY;
+                }
+            }
+
+            let mut error = 0.0;
+            for (branch, child) in [left, right].iter().enumerate() {
+                path.insert(item(attribute, branch));
+                structure.push(item(attribute, branch));
+                error += self.evaluate_tree(structure, tree, *child, depth + 1, path);
+                structure.backtrack();
+                path.remove(&item(attribute, branch));
+            }
+            if let Some(node) = tree.get_node_mut(index) {
+                node.value.out = None;
+            }
+            error
         };
+
+        if let Some(node) = tree.get_node_mut(index) {
+            node.value.error = error;
+        }
         error
     }
 
@@ -480,16 +1013,280 @@ where
             structure.push(*item);
         }
         if let LowerBoundStrategy::Similarity = self.constraints.lower_bound_strategy {
-            if !matches!(return_infos.1, StopReason::LowerBoundConstrained) {
+            // The errors of the stopped searches are not optimal, they would not bound the next
+            // fits of the learner
+            if !matches!(
+                return_infos.1,
+                StopReason::LowerBoundConstrained
+                    | StopReason::TimeLimitReached
+                    | StopReason::Interrupted
+                    | StopReason::CacheLimitReached
+            ) {
                 if let Some(node) = self.cache.get(itemset, child_index) {
-                    child_similarity_data.update(node.error, structure);
+                    child_similarity_data.update(
+                        node.error,
+                        structure,
+                        self.constraints.lower_bound_policy,
+                    );
                 }
             }
         }
         structure.backtrack();
     }
+    fn notify_progress(&mut self) {
+        if self.statistics.interrupted {
+            return;
+        }
+        trace!(
+            "{} explored nodes in {:?}, {} cache entries",
+            self.explored_nodes,
+            self.runtime.elapsed(),
+            self.cache.size()
+        );
+        if let Some(observer) = self.progress_observer.as_mut() {
+            let progress = SearchProgress {
+                explored_nodes: self.explored_nodes,
+                upper_bound: self
+                    .cache
+                    .get_root_infos()
+                    .map_or(<f64>::INFINITY, |root| root.error),
+                elapsed: self.runtime.elapsed(),
+                cache_size: self.cache.size(),
+            };
+            if observer.on_progress(&progress) {
+                self.statistics.interrupted = true;
+            }
+        }
+    }
+
+    // Number of candidates explored at a node, the first ones according to the heuristic
+    fn beam_size(&mut self, num_candidates: usize, depth: usize) -> usize {
+        let width = match self.depth_beam_widths.is_empty() {
+            true => self.constraints.beam_width,
+            false => self.depth_beam_widths[depth.min(self.depth_beam_widths.len() - 1)],
+        };
+        match width {
+            0 => num_candidates,
+            width if width < num_candidates => {
+                self.statistics.beam_truncated = true;
+                width
+            }
+            _ => num_candidates,
+        }
+    }
+
+    // Rank of each attribute on ties, empty when they keep their index order
+    fn tie_ranks(&self, num_attributes: usize) -> Vec<usize> {
+        match self.constraints.tie_break {
+            TieBreak::Index => vec![],
+            TieBreak::Random => {
+                let mut order = (0..num_attributes).collect::<Vec<usize>>();
+                order.shuffle(&mut StdRng::seed_from_u64(self.constraints.seed));
+                let mut ranks = vec![0; num_attributes];
+                for (rank, attribute) in order.iter().enumerate() {
+                    ranks[*attribute] = rank;
+                }
+                ranks
+            }
+        }
+    }
+
+    // Drops the candidates whose heuristic score is below the absolute threshold or below the
+    // given share of the best score of the node. The candidates are scored by the information gain
+    // when the heuristic has no scores
+    fn drop_low_scores<S: Structure>(&mut self, structure: &mut S, candidates: &mut Vec<usize>) {
+        let (absolute, relative) = (
+            self.constraints.min_heuristic_score,
+            self.constraints.min_relative_score,
+        );
+        if (absolute <= 0.0 && relative <= 0.0) || candidates.is_empty() {
+            return;
+        }
+        let scores = match self.heuristic.scores(structure, candidates) {
+            Some(scores) => scores,
+            None => InformationGain
+                .scores(structure, candidates)
+                .unwrap_or_default(),
+        };
+        let best = scores.iter().copied().fold(<f64>::NEG_INFINITY, f64::max);
+        let threshold = absolute.max(relative * best);
+        if threshold <= 0.0 {
+            return;
+        }
+        let num_candidates = candidates.len();
+        let mut scores = scores.iter();
+        candidates.retain(|_| scores.next().is_some_and(|score| *score >= threshold));
+        self.statistics.filtered_candidates += num_candidates - candidates.len();
+    }
+
+    // Allocates the cache according to its initialization strategy. The user allocation reserves
+    // `cache_init_size` entries. The dynamic allocation reserves the itemsets the search can
+    // reach, within the cache bounds, and inserts the depth 1 itemsets with their leaf errors
+    fn init_cache<S: Structure>(&mut self, structure: &mut S, candidates: &[usize]) {
+        match self.constraints.cache_init_strategy {
+            CacheInitStrategy::None_ => {}
+            CacheInitStrategy::UserAllocation => {
+                self.cache.reserve(self.constraints.cache_init_size);
+            }
+            CacheInitStrategy::DynamicAllocation => {
+                let limit = match self.cache_capacity() {
+                    0 => DYNAMIC_ALLOCATION_LIMIT,
+                    capacity => capacity.min(DYNAMIC_ALLOCATION_LIMIT),
+                };
+                self.cache.reserve(reachable_itemsets(
+                    candidates.len(),
+                    self.constraints.max_depth,
+                    limit,
+                ));
+
+                // The depth 2 specialization solves the root without the cache
+                let at_root_specialization = self.constraints.max_depth <= 2
+                    && matches!(self.constraints.specialization, Specialization::Murtree);
+                if self.constraints.max_depth == 0 || at_root_specialization {
+                    return;
+                }
+                let mut itemset = BTreeSet::new();
+                for attribute in candidates {
+                    for value in 0..2 {
+                        let it = item(*attribute, value);
+                        itemset.insert(it);
+                        let (is_new, index) = self.cache.insert(&itemset);
+                        if is_new {
+                            structure.push(it);
+                            let error = self.error_as_leaf(structure);
+                            structure.backtrack();
+                            if let Some(node) = self.cache.get(&itemset, index) {
+                                node.leaf_error = error.0;
+                                node.target = error.1;
+                            }
+                        }
+                        itemset.remove(&it);
+                    }
+                }
+            }
+        }
+    }
+
+    // Maximum number of cache entries given the entries and bytes bounds, 0 means unbounded. The
+    // memory of an entry is estimated from the size of its infos
+    fn cache_capacity(&self) -> usize {
+        let by_bytes = match self.constraints.max_cache_bytes {
+            0 => 0,
+            bytes => (bytes / size_of::<CacheEntry>()).max(1),
+        };
+        match (self.constraints.max_cache_size, by_bytes) {
+            (0, bound) | (bound, 0) => bound,
+            (entries, by_bytes) => entries.min(by_bytes),
+        }
+    }
+
+    // With the stop policy, nodes are not explored anymore once the cache is full
+    fn is_cache_full(&mut self) -> bool {
+        if !self.statistics.cache_limit_reached
+            && matches!(self.constraints.cache_full_policy, CacheFullPolicy::Stop)
+        {
+            let capacity = self.cache_capacity();
+            self.statistics.cache_limit_reached = capacity > 0 && self.cache.size() >= capacity;
+        }
+        self.statistics.cache_limit_reached
+    }
+
+    // Once the interrupter asked to stop, it is not polled anymore
+    fn is_interrupted(&mut self) -> bool {
+        if !self.statistics.interrupted {
+            if let Some(interrupter) = self.interrupter.as_mut() {
+                self.statistics.interrupted = interrupter.should_stop();
+            }
+        }
+        self.statistics.interrupted
+    }
+
+    fn trace_node(
+        &mut self,
+        itemset: &BTreeSet<usize>,
+        depth: usize,
+        upper_bound: f64,
+        index: Option<usize>,
+        result: &SearchReturn,
+    ) {
+        let (lower_bound, test, is_leaf) = self
+            .cache
+            .get(itemset, index)
+            .map_or((0.0, <usize>::MAX, true), |node| {
+                (node.lower_bound, node.test, node.is_leaf)
+            });
+        let error = result.0;
+        let decision = match (error.is_finite() && error < upper_bound, is_leaf) {
+            (false, _) => TraceDecision::Pruned,
+            (true, true) => TraceDecision::Leaf,
+            (true, false) if test == <usize>::MAX => TraceDecision::Leaf,
+            (true, false) => TraceDecision::Split,
+        };
+        let event = TraceEvent {
+            itemset: itemset.iter().copied().collect(),
+            depth,
+            lower_bound,
+            upper_bound,
+            error,
+            test: (decision == TraceDecision::Split).then_some(test),
+            decision,
+            reason: result.1,
+        };
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.on_node(&event);
+        }
+    }
+
+    // Reports the result and what kept it from being proven optimal
+    fn log_outcome(&self) {
+        info!(
+            "DL8.5 search done in {:?} with error {}, {} nodes explored and {} cache entries",
+            self.statistics.duration,
+            self.statistics.tree_error,
+            self.explored_nodes,
+            self.statistics.cache_size
+        );
+        if self.statistics.interrupted {
+            warn!("The search was interrupted, the tree may not be optimal");
+        }
+        if self.statistics.time_limit_reached {
+            warn!("The time limit was reached, the tree may not be optimal");
+        }
+        if self.statistics.cache_limit_reached {
+            warn!("The cache was full, the tree may not be optimal");
+        }
+        if self.statistics.beam_truncated || self.statistics.discrepancy_limited {
+            info!("Candidates were left out by the beam or the discrepancy budget");
+        }
+        if self.statistics.filtered_candidates > 0 {
+            info!(
+                "{} candidates were dropped by the heuristic score thresholds",
+                self.statistics.filtered_candidates
+            );
+        }
+        debug!(
+            "Cache hit rate {:.3}, {:?} entries per depth, about {} bytes",
+            self.statistics.cache.hit_rate(),
+            self.statistics.cache.entries_per_depth,
+            self.statistics.cache.estimated_bytes
+        );
+    }
+
+    // Adds a point to the anytime curve when the error improves on the last recorded one
+    fn record_incumbent(&mut self, error: f64) {
+        if !self.constraints.record_anytime || !error.is_finite() {
+            return;
+        }
+        let curve = &mut self.statistics.anytime_curve;
+        if curve.last().is_none_or(|(_, last)| error < *last) {
+            curve.push((self.runtime.elapsed().as_secs_f64(), error));
+        }
+    }
+
     fn update_statistics(&mut self) {
         self.statistics.cache_size = self.cache.size();
+        self.statistics.cache_evictions = self.cache.evictions();
+        self.statistics.cache = self.cache.statistics();
         self.statistics.duration = self.runtime.elapsed();
         if let Some(infos) = self.cache.get_root_infos() {
             self.statistics.tree_error = infos.error;
@@ -524,10 +1321,11 @@ where
         if let Some(tree_node) = tree.get_node(tree_index) {
             if let Some(cache_node) = self.cache.get(itemset, index) {
                 cache_node.error = tree_node.value.error;
-                cache_node.leaf_error = tree_node.value.error;
 
                 if tree_node.value.test.is_none() {
+                    cache_node.leaf_error = tree_node.value.error;
                     cache_node.is_leaf = true;
+                    cache_node.leaf_reason = StopReason::FromSpecializedAlgorithm;
                     cache_node.target = tree_node.value.out.unwrap_or(0.0);
                     return;
                 } else {
@@ -554,24 +1352,29 @@ where
         match cache_entry.is_leaf {
             true => {
                 infos.out = Some(cache_entry.target);
+                if self.constraints.explain_leaves {
+                    infos.reason = Some(cache_entry.leaf_reason);
+                }
             }
             false => infos.test = Some(cache_entry.test),
         };
         infos
     }
 
-    fn get_solution_tree(&mut self) {
+    fn get_solution_tree<S: Structure>(&mut self, structure: &mut S) {
         let mut tree = Tree::new();
         let mut path = BTreeSet::new();
         if let Some(cache_root) = self.cache.get_root_infos() {
             let infos = self.create_solution_tree_entry(cache_root);
+            let test = cache_root.test;
             let root = tree.add_root(TreeNode::new(infos));
-            self.get_solution_tree_recursion(cache_root.test, &mut path, &mut tree, root);
+            self.get_solution_tree_recursion(structure, test, &mut path, &mut tree, root);
         }
         self.tree = tree;
     }
-    fn get_solution_tree_recursion(
+    fn get_solution_tree_recursion<S: Structure>(
         &self,
+        structure: &mut S,
         attribute: usize,
         path: &mut BTreeSet<usize>,
         tree: &mut Tree,
@@ -583,29 +1386,121 @@ where
 
         for branch in 0..2 {
             path.insert(item(attribute, branch));
-            if let Some(cache_node) = self.cache.find(path) {
-                let node_infos = self.create_solution_tree_entry(cache_node);
-                let child_index = tree.add_node(index, branch == 0, TreeNode::new(node_infos));
-                if !cache_node.is_leaf {
-                    self.get_solution_tree_recursion(cache_node.test, path, tree, child_index)
+            match self.solution_entry(structure, path) {
+                Some(cache_node) => {
+                    let node_infos = self.create_solution_tree_entry(cache_node);
+                    let child_index = tree.add_node(index, branch == 0, TreeNode::new(node_infos));
+                    if !cache_node.is_leaf {
+                        let test = cache_node.test;
+                        self.get_solution_tree_recursion(structure, test, path, tree, child_index)
+                    }
+                }
+                // Only leaves are evicted from the cache, they are computed again
+                None => {
+                    let node_infos = self.evicted_leaf_entry(structure, path);
+                    tree.add_node(index, branch == 0, TreeNode::new(node_infos));
                 }
             }
             path.remove(&item(attribute, branch));
         }
     }
+
+    // Entry of the node of the path. With the cover keys, the node may have been solved through
+    // the entry of another itemset with the same cover, its own entry being absent or unsolved
+    fn solution_entry<S: Structure>(
+        &self,
+        structure: &mut S,
+        path: &BTreeSet<usize>,
+    ) -> Option<&CacheEntry> {
+        let entry = self.cache.find(path);
+        let solved = entry.is_some_and(|entry| entry.is_leaf || entry.error.is_finite());
+        if !self.constraints.cover_keys || solved {
+            return entry;
+        }
+        let support = structure.change_position(&path.iter().copied().collect::<Vec<usize>>());
+        let key = (structure.cover_fingerprint(), support, path.len());
+        let tids = sorted_tids(structure);
+        structure.reset();
+        self.equivalent_itemsets
+            .get(&key)
+            .filter(|(cover, _)| *cover == tids)
+            .and_then(|(_, itemset)| self.cache.find(itemset))
+            .or(entry)
+    }
+
+    fn evicted_leaf_entry<S: Structure>(
+        &self,
+        structure: &mut S,
+        path: &BTreeSet<usize>,
+    ) -> NodeInfos {
+        for it in path.iter() {
+            structure.push(*it);
+        }
+        let (error, out) = self.error_as_leaf(structure);
+        for _ in path.iter() {
+            structure.backtrack();
+        }
+        NodeInfos {
+            error,
+            out: Some(out),
+            ..Default::default()
+        }
+    }
+}
+
+// Tids of the current cover in increasing order, whatever the order the structure lists them in
+fn sorted_tids<S: Structure>(structure: &S) -> Vec<usize> {
+    let mut tids = structure.get_tids();
+    tids.sort_unstable();
+    tids
+}
+
+// Number of itemsets of at most `depth` items among the candidates, two items per candidate,
+// stopping at the limit
+fn reachable_itemsets(num_candidates: usize, depth: usize, limit: usize) -> usize {
+    let mut total = 1usize;
+    let mut layer = 1usize;
+    for size in 1..=depth.min(num_candidates) {
+        layer = layer * 2 * (num_candidates - size + 1) / size;
+        total += layer;
+        if total >= limit {
+            return limit;
+        }
+    }
+    total.min(limit)
 }
 
 #[cfg(test)]
 mod dl85_test {
+    use crate::cache::hashmap::HashMapCache;
     use crate::cache::trie::Trie;
+    use crate::cache::{CacheEntry, Caching};
     use crate::data::{BinaryData, FileReader};
-    use crate::heuristics::NoHeuristic;
-    use crate::searches::errors::NativeError;
+    use crate::globals::{fill_samples, get_tree_root_error, item};
+    use crate::heuristics::{InformationGain, NoHeuristic};
+    use crate::searches::errors::{ErrorWrapper, NativeError, SquaredError};
+    use crate::searches::filters::{
+        CandidatesFilter, FeatureCostFilter, FeatureSetFilter, TemplateFilter,
+    };
+    use crate::searches::greedy::LGDT;
+    use crate::searches::interrupt::SearchInterrupter;
+    use crate::searches::monotonic::{is_monotonic, MonotonicFeatures, Monotonicity};
+    use crate::searches::optimal::dl85::similarity::SimilarityCover;
     use crate::searches::optimal::dl85::DL85;
+    use crate::searches::progress::{ProgressObserver, SearchProgress};
+    use crate::searches::rates::RateConstraints;
+    use crate::searches::rules::LeafRule;
+    use crate::searches::trace::{JsonlTracer, SearchTracer};
     use crate::searches::utils::{
-        BranchingStrategy, CacheInitStrategy, LowerBoundStrategy, NodeExposedData, Specialization,
+        BranchingStrategy, CacheFullPolicy, CacheInitStrategy, LowerBoundPolicy,
+        LowerBoundStrategy, NodeExposedData, SearchStrategy, SortPolicy, Specialization,
+        StopReason, TieBreak,
     };
-    use crate::structures::{Bitset, RevBitset};
+    use crate::structures::{Bitset, RevBitset, Structure};
+    use crate::tree::{MissingBranch, NodeInfos, Tree, TreeNode};
+    use std::collections::BTreeSet;
+    use std::mem::size_of;
+    use std::time::Duration;
 
     #[test]
     fn run_basic_dl85() {
@@ -620,7 +1515,7 @@ mod dl85_test {
             4,
             <f64>::INFINITY,
             600,
-            false,
+            SortPolicy::Always,
             0,
             CacheInitStrategy::None_,
             Specialization::Murtree,
@@ -631,7 +1526,1362 @@ mod dl85_test {
             error_function,
             heuristics,
         );
-        learner.fit(&mut structure);
+        learner.fit(&mut structure).unwrap();
         println!("{:#?}", learner.statistics)
     }
+
+    fn depth_two_learner() -> DL85<Trie, NativeError, NoHeuristic> {
+        DL85::new(
+            1,
+            2,
+            <f64>::INFINITY,
+            600,
+            SortPolicy::Always,
+            0,
+            CacheInitStrategy::None_,
+            Specialization::None_,
+            LowerBoundStrategy::None_,
+            BranchingStrategy::None_,
+            NodeExposedData::ClassesSupport,
+            Box::<Trie>::default(),
+            Box::<NativeError>::default(),
+            Box::<NoHeuristic>::default(),
+        )
+    }
+
+    #[test]
+    fn cache_init_strategies_find_the_same_tree() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut errors = vec![];
+        for (strategy, size) in [
+            (CacheInitStrategy::None_, 0),
+            (CacheInitStrategy::UserAllocation, 5000),
+            (CacheInitStrategy::DynamicAllocation, 0),
+        ] {
+            let mut learner = depth_two_learner();
+            learner.constraints.max_depth = 3;
+            learner.constraints.min_sup = 20;
+            learner.constraints.cache_init_strategy = strategy;
+            learner.constraints.cache_init_size = size;
+            learner.fit(&mut structure).unwrap();
+            errors.push(learner.statistics.tree_error);
+        }
+        assert!(errors.iter().all(|error| *error == errors[0]));
+
+        // The depth 1 itemsets are inserted with their leaf errors before the search
+        let mut learner = depth_two_learner();
+        learner.constraints.max_depth = 3;
+        learner.constraints.cache_init_strategy = CacheInitStrategy::DynamicAllocation;
+        learner.cache.init();
+        learner.init_cache(&mut structure, &[0, 3]);
+        assert_eq!(learner.cache.size(), 5);
+        structure.push(item(3, 1));
+        let expected = learner.error_as_leaf(&mut structure);
+        structure.backtrack();
+        let entry = learner.cache.find(&BTreeSet::from([item(3, 1)])).unwrap();
+        assert_eq!((entry.leaf_error, entry.target), expected);
+    }
+
+    #[test]
+    fn warm_start_with_optimal_tree_is_kept() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.fit(&mut structure).unwrap();
+        assert_eq!(learner.statistics.tree_error, 137.0);
+
+        let mut warm_learner = depth_two_learner();
+        warm_learner
+            .fit_with_warm_start(&mut structure, &learner.tree)
+            .unwrap();
+        assert_eq!(warm_learner.statistics.tree_error, 137.0);
+        assert_eq!(get_tree_root_error(&warm_learner.tree), 137.0);
+    }
+
+    #[test]
+    fn warm_start_with_unknown_attribute_is_rejected() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut stump = Tree::new();
+        let root = stump.add_root(TreeNode::new(NodeInfos {
+            test: Some(structure.num_attributes()),
+            ..NodeInfos::new()
+        }));
+        stump.add_left_node(root, TreeNode::new(NodeInfos::new()));
+        stump.add_right_node(root, TreeNode::new(NodeInfos::new()));
+
+        let mut learner = depth_two_learner();
+        assert!(learner.fit_with_warm_start(&mut structure, &stump).is_err());
+        assert!(learner.tree.is_empty());
+    }
+
+    #[test]
+    fn explained_leaves_have_a_reason() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.constraints.explain_leaves = true;
+        learner.fit(&mut structure).unwrap();
+
+        for index in 0..learner.tree.len() {
+            let node = learner.tree.get_node(index).unwrap();
+            let is_leaf = node.left == 0 && node.right == 0;
+            assert_eq!(node.value.reason.is_some(), is_leaf);
+        }
+        let reasons = learner.tree.leaf_reasons();
+        assert_eq!(reasons.values().sum::<usize>(), learner.tree.num_leaves());
+    }
+
+    #[test]
+    fn leaves_store_classes_support() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.fit(&mut structure).unwrap();
+
+        let root = learner.tree.get_node(0).unwrap();
+        let root_support = root.value.classes_support.clone().unwrap();
+        assert_eq!(root_support.iter().sum::<usize>(), structure.support());
+
+        let mut leaves_support = vec![0; root_support.len()];
+        for index in 0..learner.tree.len() {
+            let node = learner.tree.get_node(index).unwrap();
+            if node.left == 0 && node.right == 0 {
+                let support = node.value.classes_support.as_ref().unwrap();
+                for (class, count) in support.iter().enumerate() {
+                    leaves_support[class] += count;
+                }
+            }
+        }
+        assert_eq!(leaves_support, root_support);
+    }
+
+    struct StopAfter(usize);
+
+    impl SearchInterrupter for StopAfter {
+        fn should_stop(&mut self) -> bool {
+            self.0 = self.0.saturating_sub(1);
+            self.0 == 0
+        }
+    }
+
+    #[test]
+    fn interrupted_search_returns_a_tree() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.fit(&mut structure).unwrap();
+        let optimal_error = get_tree_root_error(&learner.tree);
+
+        let mut learner = depth_two_learner();
+        learner.interrupter = Some(Box::new(StopAfter(50)));
+        learner.fit(&mut structure).unwrap();
+
+        assert!(learner.statistics.interrupted);
+        assert!(!learner.tree.is_empty());
+        assert!(get_tree_root_error(&learner.tree) >= optimal_error);
+    }
+
+    #[test]
+    fn masked_fit_matches_a_fit_on_the_copied_subset() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+        let kept = (0..812).filter(|tid| tid % 4 != 1).collect::<Vec<usize>>();
+        let attributes = (0..structure.num_attributes()).collect::<Vec<usize>>();
+        let mut copied = structure.sample(&kept, &attributes).unwrap();
+        structure.mask(&kept).unwrap();
+
+        let mut learner = depth_two_learner();
+        learner.fit(&mut structure).unwrap();
+        let mut expected = depth_two_learner();
+        expected.fit(&mut copied).unwrap();
+        assert_eq!(learner.statistics.num_samples, kept.len());
+        assert_eq!(
+            learner.statistics.tree_error,
+            expected.statistics.tree_error
+        );
+    }
+
+    #[test]
+    fn similarity_covers_are_kept_between_restarts() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.constraints.max_depth = 3;
+        learner.constraints.min_sup = 50;
+        learner.constraints.lower_bound_strategy = LowerBoundStrategy::Similarity;
+        learner.fit(&mut structure).unwrap();
+        let error = learner.statistics.tree_error;
+        assert!(!learner.similarities.is_empty());
+
+        // An itemset no search explores, to tell whether the covers were kept
+        let marker = BTreeSet::from([usize::MAX]);
+        learner
+            .similarities
+            .insert(marker.clone(), SimilarityCover::default());
+        learner.prepare_restart();
+        learner.fit(&mut structure).unwrap();
+        assert_eq!(learner.statistics.tree_error, error);
+        assert!(learner.statistics.is_optimal());
+        assert!(learner.similarities.contains_key(&marker));
+
+        // A new fit may be given other data
+        learner.fit(&mut structure).unwrap();
+        assert!(!learner.similarities.contains_key(&marker));
+
+        learner.relax(3, 50).unwrap();
+        assert!(learner.similarities.is_empty());
+    }
+
+    #[test]
+    fn similarity_covers_are_bounded_by_the_cache() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.constraints.max_depth = 3;
+        learner.constraints.min_sup = 50;
+        learner.constraints.lower_bound_strategy = LowerBoundStrategy::Similarity;
+        learner.fit(&mut structure).unwrap();
+        let error = learner.statistics.tree_error;
+        let unbounded = learner.similarities.len();
+
+        let mut learner = depth_two_learner();
+        learner.constraints.max_depth = 3;
+        learner.constraints.min_sup = 50;
+        learner.constraints.lower_bound_strategy = LowerBoundStrategy::Similarity;
+        learner.constraints.max_cache_size = unbounded / 2;
+        learner.fit(&mut structure).unwrap();
+        assert_eq!(learner.statistics.tree_error, error);
+        assert!(learner.similarities.len() <= unbounded / 2);
+    }
+
+    #[test]
+    fn relaxed_search_reuses_the_cache() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.constraints.max_depth = 1;
+        learner.constraints.min_sup = 5;
+        learner.fit(&mut structure).unwrap();
+        assert!(learner.relax(0, 5).is_err());
+        assert!(learner.relax(1, 6).is_err());
+        let stump_cache_size = learner.statistics.cache_size;
+
+        learner.relax(2, 1).unwrap();
+        let stump = learner.tree.clone();
+        learner.fit_with_warm_start(&mut structure, &stump).unwrap();
+        assert_eq!(learner.statistics.tree_error, 137.0);
+        assert!(learner.statistics.is_optimal());
+        assert!(learner.statistics.cache_size >= stump_cache_size);
+        assert_eq!(learner.tree.depth(), 2);
+    }
+
+    #[test]
+    fn refit_after_adding_samples_matches_a_fresh_search() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+        let tids = (0..812).step_by(40).collect::<Vec<usize>>();
+        let removed = structure.remove_samples(&tids).unwrap();
+
+        let mut learner = depth_two_learner();
+        learner.fit(&mut structure).unwrap();
+        let cache_size = learner.statistics.cache_size;
+
+        structure.add_samples(&removed).unwrap();
+        learner.invalidate_samples(&removed);
+        // Only the itemsets covering one of the samples are forgotten
+        assert!(learner.cache.size() > 1 && learner.cache.size() < cache_size);
+        learner.fit(&mut structure).unwrap();
+        assert_eq!(learner.statistics.tree_error, 137.0);
+    }
+
+    #[test]
+    fn relaxed_search_with_the_depth_two_specialization() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.constraints.specialization = Specialization::Murtree;
+        learner.fit(&mut structure).unwrap();
+        learner.relax(3, 1).unwrap();
+        learner.fit(&mut structure).unwrap();
+
+        let mut fresh = depth_two_learner();
+        fresh.constraints.specialization = Specialization::Murtree;
+        fresh.constraints.max_depth = 3;
+        fresh.fit(&mut structure).unwrap();
+        assert_eq!(learner.statistics.tree_error, fresh.statistics.tree_error);
+    }
+
+    #[test]
+    fn balanced_accuracy_objective_weights_the_classes() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+        let (targets, rows) = data.get_train();
+        let targets = targets.as_ref().unwrap();
+        let supports = structure.labels_support().to_vec();
+        let weights = supports
+            .iter()
+            .map(|support| 812.0 / (2.0 * *support as f64))
+            .collect::<Vec<f64>>();
+        // Weighted and plain errors of the predictions of a tree
+        let errors = |tree: &Tree| {
+            let mut errors = (0.0, 0);
+            for (row, target) in rows.iter().zip(targets.iter()) {
+                let instance = row.iter().map(|value| *value as f64).collect::<Vec<f64>>();
+                if tree.predict(&instance, MissingBranch::Left) != Some(*target as f64) {
+                    errors = (errors.0 + weights[*target], errors.1 + 1);
+                }
+            }
+            errors
+        };
+
+        let mut plain = depth_two_learner();
+        plain.fit(&mut structure).unwrap();
+        let mut balanced = DL85::new(
+            1,
+            2,
+            <f64>::INFINITY,
+            600,
+            SortPolicy::Always,
+            0,
+            CacheInitStrategy::None_,
+            Specialization::None_,
+            LowerBoundStrategy::None_,
+            BranchingStrategy::None_,
+            NodeExposedData::ClassesSupport,
+            Box::<Trie>::default(),
+            Box::new(NativeError::balanced(&supports)),
+            Box::<NoHeuristic>::default(),
+        );
+        balanced.fit(&mut structure).unwrap();
+
+        let (weighted_error, error) = errors(&balanced.tree);
+        assert!((weighted_error - balanced.statistics.tree_error).abs() < 1e-6);
+        assert!(weighted_error <= errors(&plain.tree).0 + 1e-6);
+        assert!(error >= 137);
+    }
+
+    #[test]
+    fn weighted_error_predicts_the_cheapest_class() {
+        let error = NativeError::balanced(&[30, 10]);
+        assert_eq!(error.compute(&[3, 5]), (2.0, 1.0));
+        assert_eq!(error.compute(&[9, 2]), (4.0, 0.0));
+        assert_eq!(error.class_weights().map(|weights| weights.len()), Some(2));
+        let error = NativeError::weighted(vec![1.0, 1.0]);
+        assert_eq!(error.compute(&[2, 2]), (2.0, 0.0));
+        assert_eq!(error.compute(&[0, 0]), (0.0, 0.0));
+    }
+
+    #[test]
+    fn depth_two_specialization_applies_the_class_weights() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+        let weights = vec![3.0, 1.0];
+
+        let mut errors = vec![];
+        for specialization in [Specialization::Murtree, Specialization::None_] {
+            let mut learner = DL85::new(
+                1,
+                3,
+                <f64>::INFINITY,
+                600,
+                SortPolicy::RootOnly,
+                0,
+                CacheInitStrategy::None_,
+                specialization,
+                LowerBoundStrategy::Similarity,
+                BranchingStrategy::Dynamic,
+                NodeExposedData::ClassesSupport,
+                Box::<Trie>::default(),
+                Box::new(NativeError::weighted(weights.clone())),
+                Box::<NoHeuristic>::default(),
+            );
+            learner.fit(&mut structure).unwrap();
+            assert!(learner.statistics.is_optimal());
+            errors.push(learner.statistics.tree_error);
+        }
+        assert!((errors[0] - errors[1]).abs() < 1e-6);
+    }
+
+    #[test]
+    fn gap_is_null_once_optimality_is_proven() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.fit(&mut structure).unwrap();
+        assert!(learner.statistics.is_optimal());
+        assert_eq!(learner.statistics.best_lower_bound, 137.0);
+        assert_eq!(learner.statistics.gap, 0.0);
+        assert!(matches!(learner.statistics.stop_reason(), StopReason::Done));
+
+        let mut learner = depth_two_learner();
+        learner.interrupter = Some(Box::new(StopAfter(50)));
+        learner.fit(&mut structure).unwrap();
+        let statistics = &learner.statistics;
+        assert!(!statistics.is_optimal());
+        assert!(statistics.best_lower_bound <= 137.0);
+        assert!(statistics.gap > 0.0);
+        assert!(matches!(statistics.stop_reason(), StopReason::Interrupted));
+        assert_eq!(
+            statistics.gap,
+            statistics.tree_error - statistics.best_lower_bound
+        );
+    }
+
+    #[test]
+    fn stop_reason_comes_from_the_search_and_not_the_duration() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.constraints.max_time = 0;
+        learner.fit(&mut structure).unwrap();
+        assert!(learner.statistics.time_limit_reached);
+        assert!(matches!(
+            learner.statistics.stop_reason(),
+            StopReason::TimeLimitReached
+        ));
+        assert!(!learner.statistics.is_optimal());
+
+        // A completed search taking longer than the time limit, in its post processing for
+        // instance, is still optimal
+        let mut learner = depth_two_learner();
+        learner.fit(&mut structure).unwrap();
+        learner.statistics.duration = Duration::from_secs(learner.constraints.max_time as u64 + 1);
+        assert!(matches!(learner.statistics.stop_reason(), StopReason::Done));
+        assert!(learner.statistics.is_optimal());
+    }
+
+    struct StopOnProgress;
+
+    impl ProgressObserver for StopOnProgress {
+        fn on_progress(&mut self, progress: &SearchProgress) -> bool {
+            assert_eq!(progress.explored_nodes, 10);
+            true
+        }
+    }
+
+    #[test]
+    fn progress_observer_can_stop_the_search() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.progress_observer = Some(Box::new(StopOnProgress));
+        learner.progress_interval = 10;
+        learner.fit(&mut structure).unwrap();
+
+        assert!(learner.statistics.interrupted);
+        assert!(!learner.tree.is_empty());
+    }
+
+    #[test]
+    fn regression_with_squared_error() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+        // The target is a function of a feature, a single split explains it
+        let targets = data
+            .get_train()
+            .1
+            .iter()
+            .map(|row| 1.0 + 2.5 * row[5] as f64)
+            .collect::<Vec<f64>>();
+
+        let mut learner = DL85::new(
+            1,
+            1,
+            <f64>::INFINITY,
+            600,
+            SortPolicy::Always,
+            0,
+            CacheInitStrategy::None_,
+            Specialization::None_,
+            LowerBoundStrategy::None_,
+            BranchingStrategy::None_,
+            NodeExposedData::Tids,
+            Box::<Trie>::default(),
+            Box::new(SquaredError::new(targets)),
+            Box::<NoHeuristic>::default(),
+        );
+        learner.fit(&mut structure).unwrap();
+
+        assert!(get_tree_root_error(&learner.tree).abs() < 1e-9);
+        let root = learner.tree.get_node(0).unwrap();
+        let left = learner.tree.get_left_child(root).unwrap();
+        let right = learner.tree.get_right_child(root).unwrap();
+        let mut outs = [left.value.out.unwrap(), right.value.out.unwrap()];
+        outs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(outs, [1.0, 3.5]);
+    }
+
+    #[test]
+    fn regression_on_target_values_matches_the_tids() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        // Targets which no tree of depth 2 explains exactly
+        let targets = (0..data.train_size())
+            .map(|tid| ((tid * 7919) % 101) as f64 / 10.0)
+            .collect::<Vec<f64>>();
+
+        let mut trees = vec![];
+        for data_format in [NodeExposedData::Tids, NodeExposedData::Targets] {
+            let mut structure = RevBitset::new(&data);
+            let mut learner = DL85::new(
+                1,
+                2,
+                <f64>::INFINITY,
+                600,
+                SortPolicy::Always,
+                0,
+                CacheInitStrategy::None_,
+                Specialization::None_,
+                LowerBoundStrategy::None_,
+                BranchingStrategy::None_,
+                data_format,
+                Box::<Trie>::default(),
+                Box::new(SquaredError::new(targets.clone())),
+                Box::<NoHeuristic>::default(),
+            );
+            learner.fit(&mut structure).unwrap();
+            trees.push(learner.tree);
+        }
+
+        let errors = trees.iter().map(get_tree_root_error).collect::<Vec<f64>>();
+        assert!(errors[0] > 0.0);
+        assert!((errors[0] - errors[1]).abs() < 1e-9);
+    }
+
+    #[test]
+    fn hashmap_cache_finds_the_same_tree() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = DL85::new(
+            1,
+            2,
+            <f64>::INFINITY,
+            600,
+            SortPolicy::Always,
+            0,
+            CacheInitStrategy::None_,
+            Specialization::None_,
+            LowerBoundStrategy::Similarity,
+            BranchingStrategy::Dynamic,
+            NodeExposedData::ClassesSupport,
+            Box::<HashMapCache>::default(),
+            Box::<NativeError>::default(),
+            Box::<NoHeuristic>::default(),
+        );
+        learner.fit(&mut structure).unwrap();
+        assert_eq!(get_tree_root_error(&learner.tree), 137.0);
+
+        // Both caches see the same insertions, the trie also stores the prefixes of the itemsets
+        let mut trie_learner = depth_two_learner();
+        trie_learner.constraints.lower_bound_strategy = LowerBoundStrategy::Similarity;
+        trie_learner.constraints.branching_strategy = BranchingStrategy::Dynamic;
+        trie_learner.fit(&mut structure).unwrap();
+        let (trie, hashmap) = (&trie_learner.statistics, &learner.statistics);
+        assert_eq!(trie.cache.hits, hashmap.cache.hits);
+        assert_eq!(trie.cache.misses, hashmap.cache.misses);
+        assert_eq!(trie.cache.entries_per_depth.len(), 3);
+        assert_eq!(trie.cache.entries_per_depth[0], 1);
+        assert!(trie.cache.entries_per_depth[1] >= hashmap.cache.entries_per_depth[1]);
+        assert_eq!(
+            trie.cache.entries_per_depth[2],
+            hashmap.cache.entries_per_depth[2]
+        );
+        assert_eq!(
+            trie.cache.entries_per_depth.iter().sum::<usize>(),
+            trie.cache_size
+        );
+        assert_eq!(hashmap.cache.misses + 1, hashmap.cache_size);
+        assert!(trie.cache.hit_rate() > 0.0 && trie.cache.hit_rate() < 1.0);
+        assert!(trie.cache.estimated_bytes >= trie.cache_size * size_of::<CacheEntry>());
+        assert!(hashmap.cache.estimated_bytes >= hashmap.cache_size * size_of::<CacheEntry>());
+    }
+
+    #[test]
+    fn compressed_trie_finds_the_same_tree() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut errors = vec![];
+        let mut statistics = vec![];
+        for cache in [Trie::new(), Trie::compressed()] {
+            let mut learner = DL85::new(
+                1,
+                3,
+                <f64>::INFINITY,
+                600,
+                SortPolicy::Always,
+                0,
+                CacheInitStrategy::None_,
+                Specialization::None_,
+                LowerBoundStrategy::Similarity,
+                BranchingStrategy::Dynamic,
+                NodeExposedData::ClassesSupport,
+                Box::new(cache),
+                Box::<NativeError>::default(),
+                Box::<NoHeuristic>::default(),
+            );
+            learner.fit(&mut structure).unwrap();
+            errors.push(get_tree_root_error(&learner.tree));
+            statistics.push(learner.statistics);
+        }
+        assert_eq!(errors[0], errors[1]);
+        let (trie, compressed) = (&statistics[0], &statistics[1]);
+        assert_eq!(trie.cache.hits, compressed.cache.hits);
+        assert_eq!(trie.cache.misses, compressed.cache.misses);
+        assert!(compressed.cache_size <= trie.cache_size);
+    }
+
+    #[test]
+    fn colliding_cover_fingerprints_are_not_merged() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.constraints.cover_keys = true;
+        let it = item(0, 0);
+        let support = structure.push(it);
+        let key = (structure.cover_fingerprint(), support, 1);
+        structure.backtrack();
+        // Another itemset with the same key but another cover, as with a fingerprint collision
+        learner
+            .equivalent_itemsets
+            .insert(key, (vec![usize::MAX], BTreeSet::from([item(1, 0)])));
+
+        let itemset = BTreeSet::from([it]);
+        assert!(learner
+            .equivalent_itemset(&mut structure, &itemset, it)
+            .is_none());
+        assert_eq!(learner.statistics.merged_itemsets, 0);
+    }
+
+    #[test]
+    fn cover_merges_are_forgotten_by_a_new_fit() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.constraints.cover_keys = true;
+        // A key no cover has, to tell whether the merges were kept
+        let marker = (0, 0, usize::MAX);
+        learner
+            .equivalent_itemsets
+            .insert(marker, (vec![], BTreeSet::new()));
+        learner.fit(&mut structure).unwrap();
+        assert!(!learner.equivalent_itemsets.contains_key(&marker));
+
+        learner
+            .equivalent_itemsets
+            .insert(marker, (vec![], BTreeSet::new()));
+        learner.relax(2, 1).unwrap();
+        learner.fit(&mut structure).unwrap();
+        assert!(learner.equivalent_itemsets.contains_key(&marker));
+    }
+
+    #[test]
+    fn cover_keys_find_the_same_tree_with_fewer_entries() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.constraints.max_depth = 3;
+        learner.constraints.lower_bound_strategy = LowerBoundStrategy::Similarity;
+        learner.constraints.branching_strategy = BranchingStrategy::Dynamic;
+        learner.fit(&mut structure).unwrap();
+
+        let mut keyed = depth_two_learner();
+        keyed.constraints = learner.constraints;
+        keyed.constraints.cover_keys = true;
+        keyed.fit(&mut structure).unwrap();
+        assert_eq!(get_tree_root_error(&keyed.tree), 112.0);
+        assert!(keyed.statistics.merged_itemsets > 0);
+        assert!(keyed.statistics.cache_size < learner.statistics.cache_size);
+        assert_eq!(learner.statistics.merged_itemsets, 0);
+
+        // The leaves of the tree hold their own error, even when their entry is shared
+        let mut tree = keyed.tree.clone();
+        let root = tree.get_root_index();
+        let error = keyed.evaluate_tree(&mut structure, &mut tree, root, 0, &mut BTreeSet::new());
+        assert_eq!(error, 112.0);
+
+        // The depth 2 specialization caches its subtrees under the itemsets it is applied on
+        let mut specialized = depth_two_learner();
+        specialized.constraints = keyed.constraints;
+        specialized.constraints.specialization = Specialization::Murtree;
+        specialized.fit(&mut structure).unwrap();
+        let mut tree = specialized.tree.clone();
+        let error =
+            specialized.evaluate_tree(&mut structure, &mut tree, root, 0, &mut BTreeSet::new());
+        assert_eq!(error, 112.0);
+    }
+
+    #[test]
+    fn lower_bound_policies_find_the_same_tree() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut sizes = vec![];
+        for policy in [LowerBoundPolicy::LastCover, LowerBoundPolicy::TwoCovers] {
+            let mut learner = depth_two_learner();
+            learner.constraints.max_depth = 3;
+            learner.constraints.specialization = Specialization::Murtree;
+            learner.constraints.lower_bound_strategy = LowerBoundStrategy::Similarity;
+            learner.constraints.lower_bound_policy = policy;
+            learner.constraints.branching_strategy = BranchingStrategy::Dynamic;
+            learner.fit(&mut structure).unwrap();
+            assert_eq!(get_tree_root_error(&learner.tree), 112.0);
+            sizes.push(learner.statistics.cache_size);
+        }
+        // The second cover only tightens the bounds
+        assert!(sizes[1] <= sizes[0]);
+    }
+
+    #[test]
+    fn sort_policies_find_the_same_tree() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        for policy in [
+            SortPolicy::RootOnly,
+            SortPolicy::UpToDepth(1),
+            SortPolicy::Always,
+        ] {
+            let mut learner = DL85::new(
+                1,
+                3,
+                <f64>::INFINITY,
+                600,
+                policy,
+                0,
+                CacheInitStrategy::None_,
+                Specialization::Murtree,
+                LowerBoundStrategy::Similarity,
+                BranchingStrategy::Dynamic,
+                NodeExposedData::ClassesSupport,
+                Box::<Trie>::default(),
+                Box::<NativeError>::default(),
+                Box::<InformationGain>::default(),
+            );
+            learner.fit(&mut structure).unwrap();
+            assert_eq!(get_tree_root_error(&learner.tree), 112.0);
+        }
+
+        assert!(SortPolicy::RootOnly.sorts(0));
+        assert!(!SortPolicy::RootOnly.sorts(1));
+        assert!(SortPolicy::UpToDepth(1).sorts(1));
+        assert!(!SortPolicy::UpToDepth(1).sorts(2));
+        assert!(SortPolicy::Always.sorts(5));
+    }
+
+    #[test]
+    fn sparse_covers_find_the_same_tree() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+        structure.set_sparse_threshold(0.5).unwrap();
+
+        let mut learner = depth_two_learner();
+        learner.constraints.max_depth = 3;
+        learner.constraints.specialization = Specialization::Murtree;
+        learner.constraints.lower_bound_strategy = LowerBoundStrategy::Similarity;
+        learner.constraints.branching_strategy = BranchingStrategy::Dynamic;
+        learner.fit(&mut structure).unwrap();
+        assert_eq!(get_tree_root_error(&learner.tree), 112.0);
+    }
+
+    #[test]
+    fn increasing_depths_reach_the_maximum_depth() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.constraints.max_depth = 3;
+        learner.constraints.specialization = Specialization::Murtree;
+        let optimal = learner.fit_increasing_depths(&mut structure).unwrap();
+        assert_eq!(learner.statistics.optimal_depth, Some(3));
+        assert_eq!(get_tree_root_error(&optimal.unwrap()), 112.0);
+        assert_eq!(get_tree_root_error(&learner.tree), 112.0);
+        assert_eq!(learner.statistics.constraints.max_time, 600);
+
+        // Without time, no depth is solved
+        let mut learner = depth_two_learner();
+        learner.constraints.max_time = 0;
+        assert!(learner
+            .fit_increasing_depths(&mut structure)
+            .unwrap()
+            .is_none());
+        assert_eq!(learner.statistics.optimal_depth, None);
+    }
+
+    #[test]
+    fn fewest_leaves_among_optimal_trees() {
+        let data = BinaryData::read("test_data/small.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+        let learner = |minimize_leaves, max_leaves| {
+            let mut learner = depth_two_learner();
+            learner.constraints.max_depth = 3;
+            learner.constraints.minimize_leaves = minimize_leaves;
+            learner.constraints.max_leaves = max_leaves;
+            learner
+        };
+
+        let mut optimal = learner(false, 0);
+        optimal.fit(&mut structure).unwrap();
+        let mut smallest = learner(true, 0);
+        smallest.fit(&mut structure).unwrap();
+        assert_eq!(get_tree_root_error(&optimal.tree), 0.0);
+        assert_eq!(get_tree_root_error(&smallest.tree), 0.0);
+        let leaves = smallest.tree.num_leaves();
+        assert!(leaves < optimal.tree.num_leaves());
+
+        // One leaf less cannot reach the optimal error
+        let mut budget = learner(false, leaves - 1);
+        budget.fit(&mut structure).unwrap();
+        assert!(budget.statistics.tree_error > 0.0);
+    }
+
+    #[test]
+    fn greedy_warm_start_finds_the_same_tree() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.fit_with_greedy_warm_start(&mut structure).unwrap();
+        assert_eq!(get_tree_root_error(&learner.tree), 137.0);
+    }
+
+    #[test]
+    fn max_leaves_bounds_the_tree_size() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut stump = depth_two_learner();
+        stump.constraints.max_depth = 1;
+        stump.fit(&mut structure).unwrap();
+        let stump_error = get_tree_root_error(&stump.tree);
+
+        let mut learner = depth_two_learner();
+        learner.constraints.max_leaves = 4;
+        learner.fit(&mut structure).unwrap();
+        assert_eq!(get_tree_root_error(&learner.tree), 137.0);
+
+        learner.constraints.max_leaves = 2;
+        learner.fit(&mut structure).unwrap();
+        assert_eq!(get_tree_root_error(&learner.tree), stump_error);
+
+        learner.constraints.max_leaves = 3;
+        learner.fit(&mut structure).unwrap();
+        let error = get_tree_root_error(&learner.tree);
+        assert!(error >= 137.0 && error <= stump_error);
+        assert_eq!(learner.tree.num_leaves(), 3);
+        assert!(learner.tree.depth() <= 2);
+        let leaves_error = (0..learner.tree.len())
+            .map(|index| learner.tree.get_node(index).unwrap())
+            .filter(|node| node.left == 0 && node.right == 0)
+            .map(|node| node.value.error)
+            .sum::<f64>();
+        assert_eq!(leaves_error, error);
+    }
+
+    #[test]
+    fn beam_restricts_the_explored_candidates() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.constraints.beam_width = structure.num_attributes();
+        learner.fit(&mut structure).unwrap();
+        assert!(!learner.statistics.beam_truncated);
+        assert_eq!(get_tree_root_error(&learner.tree), 137.0);
+
+        let mut learner = depth_two_learner();
+        learner.constraints.beam_width = 1;
+        learner.fit(&mut structure).unwrap();
+        assert!(learner.statistics.beam_truncated);
+        assert!(get_tree_root_error(&learner.tree) >= 137.0);
+    }
+
+    #[test]
+    fn random_tie_break_is_reproducible() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut fit = |tie_break: TieBreak, seed: u64| {
+            let mut learner = depth_two_learner();
+            learner.constraints.max_depth = 3;
+            learner.constraints.specialization = Specialization::Murtree;
+            learner.constraints.tie_break = tie_break;
+            learner.constraints.seed = seed;
+            learner.fit(&mut structure).unwrap();
+            assert_eq!(get_tree_root_error(&learner.tree), 112.0);
+            (0..learner.tree.len())
+                .map(|index| learner.tree.get_node(index).unwrap().value.test)
+                .collect::<Vec<Option<usize>>>()
+        };
+        let by_index = fit(TieBreak::Index, 0);
+        assert_eq!(fit(TieBreak::Index, 2), by_index);
+        assert_eq!(fit(TieBreak::Random, 2), fit(TieBreak::Random, 2));
+        assert_ne!(fit(TieBreak::Random, 2), by_index);
+    }
+
+    #[test]
+    fn low_scores_are_dropped() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        // Without heuristic scores, the candidates are scored by the information gain
+        let mut learner = depth_two_learner();
+        learner.constraints.min_relative_score = 0.5;
+        learner.fit(&mut structure).unwrap();
+        assert!(learner.statistics.filtered_candidates > 0);
+        assert!(!learner.statistics.is_optimal());
+        assert!(get_tree_root_error(&learner.tree) >= 137.0);
+
+        // A threshold above every score drops all the candidates, the root becomes a leaf
+        let mut learner = depth_two_learner();
+        learner.constraints.min_heuristic_score = 2.0;
+        learner.fit(&mut structure).unwrap();
+        assert!(learner.statistics.filtered_candidates > 0);
+        assert_eq!(learner.tree.num_leaves(), 1);
+    }
+
+    #[test]
+    fn beam_width_can_depend_on_the_depth() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        // Only the root is restricted, the second level explores all the candidates
+        let mut learner = depth_two_learner();
+        learner.depth_beam_widths = vec![1, 0];
+        learner.fit(&mut structure).unwrap();
+        assert!(learner.statistics.beam_truncated);
+
+        let mut beam = depth_two_learner();
+        beam.constraints.beam_width = 1;
+        beam.fit(&mut structure).unwrap();
+        let root_test = |tree: &Tree| tree.get_node(tree.get_root_index()).unwrap().value.test;
+        assert_eq!(root_test(&learner.tree), root_test(&beam.tree));
+        assert!(get_tree_root_error(&learner.tree) <= get_tree_root_error(&beam.tree));
+    }
+
+    #[test]
+    fn purity_thresholds_turn_nodes_into_leaves() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut stump = depth_two_learner();
+        stump.constraints.max_depth = 1;
+        stump.fit(&mut structure).unwrap();
+
+        // Every child of the root has a majority class of at least half of its samples
+        let mut learner = depth_two_learner();
+        learner.stop_conditions.purity_thresholds = vec![0.5];
+        learner.fit(&mut structure).unwrap();
+        assert_eq!(
+            get_tree_root_error(&learner.tree),
+            get_tree_root_error(&stump.tree)
+        );
+
+        let mut learner = depth_two_learner();
+        learner.stop_conditions.purity_thresholds = vec![1.0];
+        learner.fit(&mut structure).unwrap();
+        assert_eq!(get_tree_root_error(&learner.tree), 137.0);
+    }
+
+    #[test]
+    fn leaf_rule_turns_nodes_into_leaves() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut stump = depth_two_learner();
+        stump.constraints.max_depth = 1;
+        stump.fit(&mut structure).unwrap();
+
+        let mut learner = depth_two_learner();
+        learner.stop_conditions.leaf_rule = Some(LeafRule::Or(vec![
+            LeafRule::Purity(0.5),
+            LeafRule::MaxSupport(0),
+        ]));
+        learner.fit(&mut structure).unwrap();
+        assert_eq!(
+            get_tree_root_error(&learner.tree),
+            get_tree_root_error(&stump.tree)
+        );
+
+        let mut learner = depth_two_learner();
+        learner.stop_conditions.leaf_rule = Some(LeafRule::Not(Box::new(LeafRule::MinDepth(0))));
+        learner.fit(&mut structure).unwrap();
+        assert_eq!(get_tree_root_error(&learner.tree), 137.0);
+    }
+
+    #[test]
+    fn discrepancy_budget_limits_the_explored_paths() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.constraints.search_strategy = SearchStrategy::DiscrepancySearch;
+        learner.constraints.discrepancy_budget = 2 * structure.num_attributes();
+        learner.fit(&mut structure).unwrap();
+        assert!(!learner.statistics.discrepancy_limited);
+        assert_eq!(get_tree_root_error(&learner.tree), 137.0);
+
+        // Without discrepancies, only the first candidate of each node is explored, as with a beam
+        // of width 1
+        let mut beam = depth_two_learner();
+        beam.constraints.beam_width = 1;
+        beam.fit(&mut structure).unwrap();
+        let mut learner = depth_two_learner();
+        learner.constraints.search_strategy = SearchStrategy::DiscrepancySearch;
+        learner.fit(&mut structure).unwrap();
+        assert!(learner.statistics.discrepancy_limited);
+        assert_eq!(
+            get_tree_root_error(&learner.tree),
+            get_tree_root_error(&beam.tree)
+        );
+    }
+
+    #[test]
+    fn anytime_curve_records_the_improvements() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.fit(&mut structure).unwrap();
+        assert!(learner.statistics.anytime_curve.is_empty());
+
+        learner.constraints.record_anytime = true;
+        learner.fit(&mut structure).unwrap();
+        let curve = &learner.statistics.anytime_curve;
+        assert!(curve.len() > 1);
+        assert!(curve
+            .windows(2)
+            .all(|points| points[0].0 <= points[1].0 && points[0].1 > points[1].1));
+        assert_eq!(curve.last().unwrap().1, 137.0);
+
+        learner.constraints.max_leaves = 3;
+        learner.fit(&mut structure).unwrap();
+        let curve = &learner.statistics.anytime_curve;
+        assert_eq!(curve.last().unwrap().1, learner.statistics.tree_error);
+    }
+
+    #[test]
+    fn trace_reports_each_node() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+        let file = std::env::temp_dir().join("dtrees_dl85_trace.jsonl");
+
+        let mut learner = depth_two_learner();
+        learner.tracer = Some(Box::new(JsonlTracer::create(&file).unwrap()));
+        learner.fit(&mut structure).unwrap();
+        learner.tracer.as_mut().unwrap().finish().unwrap();
+
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        let events = content
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert!(events.len() > structure.num_attributes());
+        // The root is the last node to be done
+        let root = events.last().unwrap();
+        assert_eq!(root["itemset"], serde_json::json!([]));
+        assert_eq!(root["depth"], 0);
+        assert_eq!(root["decision"], "split");
+        assert_eq!(root["error"], 137.0);
+        let root_test = learner
+            .tree
+            .get_node(learner.tree.get_root_index())
+            .unwrap();
+        assert_eq!(root["test"], root_test.value.test.unwrap());
+        assert!(events.iter().any(|event| event["decision"] == "pruned"));
+        assert!(events
+            .iter()
+            .filter(|event| event["decision"] == "leaf")
+            .all(|event| event["test"].is_null()));
+    }
+
+    #[test]
+    fn bounded_cache_finds_the_same_tree() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.constraints.max_cache_size = 20;
+        learner.fit(&mut structure).unwrap();
+
+        assert!(learner.statistics.cache_evictions > 0);
+        assert_eq!(get_tree_root_error(&learner.tree), 137.0);
+        let leaves_error = (0..learner.tree.len())
+            .map(|index| learner.tree.get_node(index).unwrap())
+            .filter(|node| node.left == 0 && node.right == 0)
+            .map(|node| node.value.error)
+            .sum::<f64>();
+        assert_eq!(leaves_error, 137.0);
+    }
+
+    #[test]
+    fn full_cache_stops_the_search() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.constraints.max_cache_bytes = 20 * size_of::<CacheEntry>();
+        learner.constraints.cache_full_policy = CacheFullPolicy::Stop;
+        learner.fit(&mut structure).unwrap();
+
+        assert!(learner.statistics.cache_limit_reached);
+        assert_eq!(learner.statistics.cache_evictions, 0);
+        let error = get_tree_root_error(&learner.tree);
+        assert!(error >= 137.0);
+        let leaves_error = (0..learner.tree.len())
+            .map(|index| learner.tree.get_node(index).unwrap())
+            .filter(|node| node.left == 0 && node.right == 0)
+            .map(|node| node.value.error)
+            .sum::<f64>();
+        assert_eq!(leaves_error, error);
+    }
+
+    struct ForbidAttribute(usize);
+
+    impl CandidatesFilter for ForbidAttribute {
+        fn filter(&self, _: &[usize], _: usize, _: usize, candidates: &mut Vec<usize>) {
+            candidates.retain(|attribute| *attribute != self.0);
+        }
+    }
+
+    #[test]
+    fn filtered_candidates_are_not_used() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.fit(&mut structure).unwrap();
+        let forbidden = learner.tree.get_node(0).unwrap().value.test.unwrap();
+
+        let mut learner = depth_two_learner();
+        learner.candidates_filter = Some(Box::new(ForbidAttribute(forbidden)));
+        learner.fit(&mut structure).unwrap();
+
+        for index in 0..learner.tree.len() {
+            let node = learner.tree.get_node(index).unwrap();
+            assert_ne!(node.value.test, Some(forbidden));
+        }
+        assert!(learner.statistics.tree_error >= 137.0);
+    }
+
+    #[test]
+    fn forced_root_feature_is_kept_with_warm_start() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.candidates_filter = Some(Box::new(FeatureSetFilter::new(None, vec![], Some(5))));
+        learner.fit_with_greedy_warm_start(&mut structure).unwrap();
+
+        let root = learner
+            .tree
+            .get_node(learner.tree.get_root_index())
+            .unwrap();
+        assert_eq!(root.value.test, Some(5));
+        // Feature 5 is the root of the optimal tree, whose children remain free
+        assert_eq!(get_tree_root_error(&learner.tree), 137.0);
+    }
+
+    #[test]
+    fn leaves_partition_the_training_samples() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut learner = depth_two_learner();
+        learner.fit(&mut structure).unwrap();
+        fill_samples(&mut learner.tree, &mut structure);
+
+        let mut samples = vec![];
+        for index in 0..learner.tree.len() {
+            let node = learner.tree.get_node(index).unwrap();
+            if node.left != node.right {
+                continue;
+            }
+            let leaf_samples = node.value.samples.clone().unwrap();
+            let support = node.value.classes_support.as_ref().unwrap();
+            assert_eq!(leaf_samples.len(), support.iter().sum::<usize>());
+            samples.extend(leaf_samples);
+        }
+        samples.sort_unstable();
+        assert_eq!(
+            samples,
+            (0..structure.num_transactions()).collect::<Vec<usize>>()
+        );
+    }
+
+    #[test]
+    fn template_splits_are_completed_optimally() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        // Root on feature 5, its left child on feature 46 and its right child left open
+        let mut template = Tree::new();
+        let split = |feature| {
+            TreeNode::new(NodeInfos {
+                test: Some(feature),
+                ..NodeInfos::new()
+            })
+        };
+        let root = template.add_root(split(5));
+        template.add_left_node(root, split(46));
+        template.add_right_node(root, TreeNode::new(NodeInfos::new()));
+
+        let mut learner = depth_two_learner();
+        learner.candidates_filter = Some(Box::new(TemplateFilter::new(&template)));
+        learner.fit(&mut structure).unwrap();
+        let tree = &learner.tree;
+        let root = tree.get_node(tree.get_root_index()).unwrap();
+        assert_eq!(root.value.test, Some(5));
+        assert_eq!(tree.get_left_child(root).unwrap().value.test, Some(46));
+
+        // The open branch is the one of the optimal tree with the same root
+        let mut forced = depth_two_learner();
+        forced.candidates_filter = Some(Box::new(FeatureSetFilter::new(None, vec![], Some(5))));
+        forced.fit(&mut structure).unwrap();
+        let forced_root = forced.tree.get_node(forced.tree.get_root_index()).unwrap();
+        assert_eq!(
+            tree.get_right_child(root).unwrap().value.error,
+            forced
+                .tree
+                .get_right_child(forced_root)
+                .unwrap()
+                .value
+                .error
+        );
+        assert!(get_tree_root_error(tree) > get_tree_root_error(&forced.tree));
+    }
+
+    #[test]
+    fn monotonic_tree_beats_repaired_greedy_tree() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+        let features = (0..structure.num_attributes())
+            .map(|attribute| (attribute, Monotonicity::Increasing))
+            .collect::<MonotonicFeatures>();
+
+        let mut greedy = LGDT::new(1, 2, SearchStrategy::LessGreedyMurtree);
+        greedy.monotonic_features = features.clone();
+        greedy.fit(&mut structure);
+        assert!(is_monotonic(&greedy.tree, &features));
+
+        let mut learner = depth_two_learner();
+        learner.monotonic_features = features.clone();
+        learner.fit(&mut structure).unwrap();
+        let error = get_tree_root_error(&learner.tree);
+
+        assert!(is_monotonic(&learner.tree, &features));
+        assert!(error >= 137.0 && error <= greedy.error);
+        let leaves_error = (0..learner.tree.len())
+            .map(|index| learner.tree.get_node(index).unwrap())
+            .filter(|node| node.left == 0 && node.right == 0)
+            .map(|node| node.value.error)
+            .sum::<f64>();
+        assert_eq!(leaves_error, error);
+    }
+
+    #[test]
+    fn constraints_without_binary_classes_are_rejected() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+        let mut learner = depth_two_learner();
+        learner.monotonic_features = MonotonicFeatures::from([(0, Monotonicity::Increasing)]);
+        learner.rate_constraints = RateConstraints {
+            max_false_negative_rate: Some(0.1),
+            max_false_positive_rate: None,
+        };
+        assert!(learner.fit(&mut structure).is_err());
+        assert!(learner.fit_with_greedy_warm_start(&mut structure).is_err());
+        assert!(learner.fit_increasing_depths(&mut structure).is_err());
+        assert!(learner.tree.is_empty());
+    }
+
+    #[test]
+    fn false_negative_rate_is_bounded() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+        let positives = structure.labels_support()[1];
+
+        let mut errors = vec![];
+        for rate in [1.0, 0.01, 0.0] {
+            let mut learner = depth_two_learner();
+            learner.rate_constraints = RateConstraints {
+                max_false_negative_rate: Some(rate),
+                max_false_positive_rate: None,
+            };
+            learner.fit(&mut structure).unwrap();
+            let false_negatives = (0..learner.tree.len())
+                .map(|index| learner.tree.get_node(index).unwrap())
+                .filter(|node| node.left == 0 && node.right == 0 && node.value.out == Some(0.0))
+                .map(|node| node.value.classes_support.as_ref().unwrap()[1])
+                .sum::<usize>();
+            assert!(false_negatives as f64 <= rate * positives as f64);
+            errors.push(get_tree_root_error(&learner.tree));
+        }
+        assert_eq!(errors[0], 137.0);
+        assert!(errors[0] <= errors[1] && errors[1] <= errors[2]);
+        // Without any false negative, a single leaf predicting the positive class always fits
+        assert!(errors[2] <= (structure.support() - positives) as f64);
+    }
+
+    #[test]
+    fn feature_costs_bound_the_paths() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut stump = depth_two_learner();
+        stump.constraints.max_depth = 1;
+        stump.fit(&mut structure).unwrap();
+
+        let costs = vec![1.0; structure.num_attributes()];
+        let mut learner = depth_two_learner();
+        learner.candidates_filter = Some(Box::new(FeatureCostFilter::new(costs.clone(), 1.5)));
+        learner.fit(&mut structure).unwrap();
+        assert_eq!(learner.tree.actual_len(), 3);
+        assert_eq!(
+            get_tree_root_error(&learner.tree),
+            get_tree_root_error(&stump.tree)
+        );
+
+        let mut learner = depth_two_learner();
+        learner.candidates_filter = Some(Box::new(FeatureCostFilter::new(costs, 2.0)));
+        learner.fit(&mut structure).unwrap();
+        assert_eq!(get_tree_root_error(&learner.tree), 137.0);
+    }
+
+    #[test]
+    fn warm_start_with_worse_tree_is_improved() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+
+        let mut stump = Tree::new();
+        let root = stump.add_root(TreeNode::new(NodeInfos {
+            test: Some(0),
+            ..NodeInfos::default()
+        }));
+        stump.add_left_node(root, TreeNode::new(NodeInfos::default()));
+        stump.add_right_node(root, TreeNode::new(NodeInfos::default()));
+
+        let mut learner = depth_two_learner();
+        learner.fit_with_warm_start(&mut structure, &stump).unwrap();
+        assert_eq!(learner.statistics.tree_error, 137.0);
+    }
 }
@@ -1,3 +1,4 @@
+use crate::searches::LowerBoundPolicy;
 use crate::structures::{DataCover, Structure};
 
 // Contains the cover of the current data in form of Vec<usize>. To compute the similarity
@@ -8,10 +9,19 @@ pub struct SimilarityCover {
 }
 
 impl SimilarityCover {
-    pub fn update<S: Structure>(&mut self, error: f64, structure: &mut S) {
+    pub fn update<S: Structure>(
+        &mut self,
+        error: f64,
+        structure: &mut S,
+        policy: LowerBoundPolicy,
+    ) {
         // Check if a Data cover is set otherwise compute it
         let mut data_cover = structure.get_data_cover();
         data_cover.error = error;
+        if let LowerBoundPolicy::LastCover = policy {
+            self.first = Some(data_cover);
+            return;
+        }
         if self.first.is_none() {
             self.first = Some(data_cover);
             return;
@@ -0,0 +1,16 @@
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SearchProgress {
+    pub explored_nodes: usize,
+    // Error of the best complete tree found so far, infinite when there is none yet
+    pub upper_bound: f64,
+    pub elapsed: Duration,
+    pub cache_size: usize,
+}
+
+pub trait ProgressObserver {
+    // Called periodically during the search. Returning true stops the search, which then keeps the
+    // best tree found so far
+    fn on_progress(&mut self, progress: &SearchProgress) -> bool;
+}
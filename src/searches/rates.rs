@@ -0,0 +1,119 @@
+use crate::tree::Tree;
+use serde::{Deserialize, Serialize};
+
+// Bounds on the error rates of a binary classifier, the second class being the positive one. The
+// false negative rate is the share of positive samples predicted negative, the false positive rate
+// the share of negative samples predicted positive
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RateConstraints {
+    pub max_false_negative_rate: Option<f64>,
+    pub max_false_positive_rate: Option<f64>,
+}
+
+// Maximum number of misclassified samples of each class, None when unbounded
+pub type ClassErrorsBudget = [Option<usize>; 2];
+
+impl RateConstraints {
+    pub fn is_empty(&self) -> bool {
+        self.max_false_negative_rate.is_none() && self.max_false_positive_rate.is_none()
+    }
+
+    /// Misclassified samples allowed for each class given the classes support of the data
+    pub fn budget(&self, classes_support: &[usize]) -> ClassErrorsBudget {
+        let allowed = |rate: Option<f64>, support: usize| {
+            // The epsilon keeps rates such as 0.1 of 30 samples at 3
+            rate.map(|rate| (rate * support as f64 + 1e-9).floor().max(0.0) as usize)
+        };
+        [
+            allowed(self.max_false_positive_rate, classes_support[0]),
+            allowed(self.max_false_negative_rate, classes_support[1]),
+        ]
+    }
+
+    /// Whether the leaves of a tree misclassify no more samples of each class than allowed. The
+    /// leaves must know their classes support
+    pub fn satisfied_by(&self, tree: &Tree, classes_support: &[usize]) -> bool {
+        if self.is_empty() || tree.is_empty() {
+            return true;
+        }
+        let budget = self.budget(classes_support);
+        let misclassified = misclassified_by_class(tree, tree.get_root_index());
+        budget
+            .iter()
+            .zip(misclassified.iter())
+            .all(|(allowed, count)| allowed.is_none_or(|allowed| *count <= allowed))
+    }
+}
+
+// Misclassified samples of each class by the leaves of a subtree
+fn misclassified_by_class(tree: &Tree, index: usize) -> [usize; 2] {
+    let node = match tree.get_node(index) {
+        Some(node) => node,
+        None => return [0; 2],
+    };
+    if node.left != 0 || node.right != 0 {
+        let mut misclassified = [0; 2];
+        for child in [node.left, node.right].iter().filter(|child| **child != 0) {
+            let child_misclassified = misclassified_by_class(tree, *child);
+            misclassified[0] += child_misclassified[0];
+            misclassified[1] += child_misclassified[1];
+        }
+        return misclassified;
+    }
+    let mut misclassified = [0; 2];
+    if let (Some(out), Some(support)) = (node.value.out, &node.value.classes_support) {
+        for (class, count) in misclassified.iter_mut().enumerate() {
+            if out as usize != class {
+                *count = support.get(class).copied().unwrap_or(0);
+            }
+        }
+    }
+    misclassified
+}
+
+// Bounds the misclassified samples of a class to the budget at a node. A budget at least as large
+// as the support of its class no longer constrains anything
+pub fn useful_class_budget(
+    budget: ClassErrorsBudget,
+    classes_support: &[usize],
+) -> ClassErrorsBudget {
+    let mut useful = budget;
+    for (class, allowed) in useful.iter_mut().enumerate() {
+        let support = classes_support.get(class).copied().unwrap_or(0);
+        if allowed.is_some_and(|allowed| allowed >= support) {
+            *allowed = None;
+        }
+    }
+    useful
+}
+
+// Ways to share a budget between the two children of a node, the left budget first
+pub fn split_class_budget(
+    budget: ClassErrorsBudget,
+) -> Vec<(ClassErrorsBudget, ClassErrorsBudget)> {
+    let shares = |allowed: Option<usize>| match allowed {
+        Some(allowed) => (0..=allowed)
+            .map(|left| (Some(left), Some(allowed - left)))
+            .collect::<Vec<(Option<usize>, Option<usize>)>>(),
+        None => vec![(None, None)],
+    };
+    let mut splits = vec![];
+    for (left_0, right_0) in shares(budget[0]) {
+        for (left_1, right_1) in shares(budget[1]) {
+            splits.push(([left_0, left_1], [right_0, right_1]));
+        }
+    }
+    splits
+}
+
+// Budget left to the right child of a node once its left child got its share
+pub fn remaining_class_budget(
+    budget: ClassErrorsBudget,
+    left: ClassErrorsBudget,
+) -> ClassErrorsBudget {
+    let mut remaining = budget;
+    for (allowed, used) in remaining.iter_mut().zip(left.iter()) {
+        *allowed = allowed.map(|allowed| allowed - used.unwrap_or(0));
+    }
+    remaining
+}
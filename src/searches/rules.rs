@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+// Condition turning a node into a leaf without trying to split it. Rules only look at the node:
+// its support, its depth and its error as a leaf
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LeafRule {
+    // The majority class holds at least this proportion of the samples
+    Purity(f64),
+    // The node has at most this many samples
+    MaxSupport(usize),
+    // The node is at least this deep
+    MinDepth(usize),
+    // The node makes at most this error as a leaf
+    MaxLeafError(f64),
+    And(Vec<LeafRule>),
+    Or(Vec<LeafRule>),
+    Not(Box<LeafRule>),
+}
+
+impl LeafRule {
+    pub fn holds(&self, support: usize, depth: usize, leaf_error: f64) -> bool {
+        match self {
+            LeafRule::Purity(threshold) => {
+                support > 0 && 1.0 - leaf_error / support as f64 >= *threshold
+            }
+            LeafRule::MaxSupport(max_support) => support <= *max_support,
+            LeafRule::MinDepth(min_depth) => depth >= *min_depth,
+            LeafRule::MaxLeafError(max_error) => leaf_error <= *max_error,
+            LeafRule::And(rules) => rules
+                .iter()
+                .all(|rule| rule.holds(support, depth, leaf_error)),
+            LeafRule::Or(rules) => rules
+                .iter()
+                .any(|rule| rule.holds(support, depth, leaf_error)),
+            LeafRule::Not(rule) => !rule.holds(support, depth, leaf_error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod rules_test {
+    use crate::searches::rules::LeafRule;
+
+    #[test]
+    fn composed_rules() {
+        let rule = LeafRule::Or(vec![
+            LeafRule::Purity(0.95),
+            LeafRule::And(vec![
+                LeafRule::MinDepth(2),
+                LeafRule::Not(Box::new(LeafRule::MaxSupport(10))),
+            ]),
+        ]);
+        // 96% of the samples in the majority class
+        assert!(rule.holds(100, 0, 4.0));
+        assert!(!rule.holds(100, 1, 10.0));
+        assert!(rule.holds(100, 2, 10.0));
+        assert!(!rule.holds(10, 2, 5.0));
+    }
+}
@@ -0,0 +1,69 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+// How the budget of successive restarts grows
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ValueEnum)]
+pub enum RestartSchedule {
+    // Powers of the growth factor following the Luby sequence: 1, 1, f, 1, 1, f, f², ...
+    Luby,
+    // The budget doubles at each restart
+    Exponential,
+    // The budget grows by one at each restart
+    Linear,
+    // The budget is multiplied by the growth factor at each restart
+    Geometric,
+}
+
+impl RestartSchedule {
+    /// Budget of a restart, the first one being the iteration 0
+    pub fn budget(&self, iteration: usize, initial: f64, growth_factor: f64) -> f64 {
+        match self {
+            RestartSchedule::Luby => initial * growth_factor.powi(luby_exponent(iteration + 1)),
+            RestartSchedule::Exponential => initial * 2f64.powi(iteration as i32),
+            RestartSchedule::Geometric => initial * growth_factor.powi(iteration as i32),
+            RestartSchedule::Linear => initial + iteration as f64,
+        }
+    }
+}
+
+// Exponent of the i-th term of the Luby sequence, starting at 1. The term is 2^(k - 1) when
+// i = 2^k - 1, otherwise the sequence repeats itself from the last such index
+fn luby_exponent(mut index: usize) -> i32 {
+    loop {
+        let mut size = 1;
+        while (1usize << size) - 1 < index {
+            size += 1;
+        }
+        if (1usize << size) - 1 == index {
+            return size - 1;
+        }
+        index -= (1usize << (size - 1)) - 1;
+    }
+}
+
+#[cfg(test)]
+mod schedule_test {
+    use crate::searches::schedule::RestartSchedule;
+
+    #[test]
+    fn restart_budgets() {
+        let budgets = |schedule: RestartSchedule, factor: f64| {
+            (0..7)
+                .map(|iteration| schedule.budget(iteration, 1.0, factor))
+                .collect::<Vec<f64>>()
+        };
+        assert_eq!(
+            budgets(RestartSchedule::Luby, 2.0),
+            vec![1.0, 1.0, 2.0, 1.0, 1.0, 2.0, 4.0]
+        );
+        assert_eq!(
+            budgets(RestartSchedule::Exponential, 3.0),
+            vec![1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0]
+        );
+        assert_eq!(
+            budgets(RestartSchedule::Geometric, 1.5)[..3],
+            [1.0, 1.5, 2.25]
+        );
+        assert_eq!(budgets(RestartSchedule::Linear, 3.0)[..3], [1.0, 2.0, 3.0]);
+    }
+}
@@ -0,0 +1,88 @@
+use crate::searches::StopReason;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+// What the search did with a node: split it on its best feature, keep it as a leaf or prune it
+// because no subtree improves on its upper bound
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceDecision {
+    Split,
+    Leaf,
+    Pruned,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceEvent {
+    // Items of the path to the node, in increasing order
+    pub itemset: Vec<usize>,
+    pub depth: usize,
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+    // Error of the best subtree found, infinite when there is none under the upper bound
+    pub error: f64,
+    // Feature of the split, for split nodes
+    pub test: Option<usize>,
+    pub decision: TraceDecision,
+    pub reason: StopReason,
+}
+
+pub trait SearchTracer {
+    // Called once the evaluation of a node is over, including nodes found in the cache
+    fn on_node(&mut self, event: &TraceEvent);
+
+    // Called by the owner of the tracer once the search is over, flushes the events and reports
+    // the errors met while tracing
+    fn finish(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// Writes each event as a JSON object on its own line. Once a write fails, the next events are
+// dropped and the error is returned by `finish`
+pub struct JsonlTracer<W: Write> {
+    writer: W,
+    error: Option<std::io::Error>,
+}
+
+impl<W: Write> JsonlTracer<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            error: None,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl JsonlTracer<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> SearchTracer for JsonlTracer<W> {
+    fn on_node(&mut self, event: &TraceEvent) {
+        if self.error.is_some() {
+            return;
+        }
+        let written = serde_json::to_writer(&mut self.writer, event)
+            .map_err(std::io::Error::from)
+            .and_then(|_| self.writer.write_all(b"\n"));
+        if let Err(error) = written {
+            self.error = Some(error);
+        }
+    }
+
+    fn finish(&mut self) -> std::io::Result<()> {
+        match self.error.take() {
+            Some(error) => Err(error),
+            None => self.writer.flush(),
+        }
+    }
+}
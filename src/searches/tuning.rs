@@ -0,0 +1,233 @@
+use crate::cache::Caching;
+use crate::heuristics::Heuristic;
+use crate::searches::errors::ErrorWrapper;
+use crate::searches::optimal::DL85;
+use crate::structures::Structure;
+use crate::tree::Tree;
+
+// Tree found for one configuration of the grid
+#[derive(Clone)]
+pub struct TuningResult {
+    pub max_depth: usize,
+    pub min_sup: usize,
+    pub error: f64,
+    // Number of leaves of the tree, its complexity
+    pub leaves: usize,
+    pub is_optimal: bool,
+    pub tree: Tree,
+}
+
+/// Fits every pair of maximum depth and minimum support and returns the Pareto set of
+/// (leaves, error). Each minimum support has one learner going through the depths from the
+/// shallowest one, relaxed between depths so that its cache is reused. The trees of a shallower
+/// depth or of a larger minimum support are valid for the next configuration, the best of them
+/// warm starts its search. `learner` builds a fresh learner, its constraints being overwritten.
+/// The errors of the relaxations and of the fits are returned
+pub fn tune_depth_and_support<C, E, H, S, F>(
+    structure: &mut S,
+    depths: &[usize],
+    min_sups: &[usize],
+    mut learner: F,
+) -> Result<Vec<TuningResult>, String>
+where
+    C: Caching + ?Sized,
+    E: ErrorWrapper + ?Sized,
+    H: Heuristic + ?Sized,
+    S: Structure,
+    F: FnMut() -> DL85<C, E, H>,
+{
+    let mut depths = depths.to_vec();
+    depths.sort_unstable();
+    depths.dedup();
+    let mut min_sups = min_sups.to_vec();
+    min_sups.sort_unstable_by(|a, b| b.cmp(a));
+    min_sups.dedup();
+
+    let mut results: Vec<TuningResult> = vec![];
+    // Best tree of each depth under the previous, larger, minimum support
+    let mut previous_support: Vec<Option<(f64, Tree)>> = vec![None; depths.len()];
+    for min_sup in min_sups {
+        let mut dl85 = learner();
+        let mut previous_depth: Option<(f64, Tree)> = None;
+        for (position, max_depth) in depths.iter().enumerate() {
+            // After the first fit, the cache holds the solutions of the shallower depth
+            match position {
+                0 => {
+                    dl85.constraints.max_depth = *max_depth;
+                    dl85.constraints.min_sup = min_sup;
+                }
+                _ => dl85.relax(*max_depth, min_sup)?,
+            }
+            let warm_start = [&previous_depth, &previous_support[position]]
+                .into_iter()
+                .flatten()
+                .min_by(|a, b| a.0.total_cmp(&b.0))
+                .map(|(_, tree)| tree.clone());
+            match warm_start {
+                Some(tree) => dl85.fit_with_warm_start(structure, &tree)?,
+                None => dl85.fit(structure)?,
+            }
+            let error = dl85.statistics.tree_error;
+            results.push(TuningResult {
+                max_depth: *max_depth,
+                min_sup,
+                error,
+                leaves: dl85.tree.num_leaves(),
+                is_optimal: dl85.statistics.is_optimal(),
+                tree: dl85.tree.clone(),
+            });
+            if !dl85.tree.is_empty() {
+                previous_depth = Some((error, dl85.tree.clone()));
+                previous_support[position] = previous_depth.clone();
+            }
+        }
+    }
+    Ok(pareto_front(results))
+}
+
+/// Fits every depth from 1 to `max_depth` under a single minimum support and returns the depths
+/// whose tree has a lower error than the trees of all shallower depths, from the shallowest one.
+/// One learner goes through the depths, relaxed between them so that the whole front shares its
+/// cache, and the tree of each depth warm starts the search of the next one. The errors of the
+/// relaxations and of the fits are returned
+pub fn depth_front<C, E, H, S>(
+    structure: &mut S,
+    max_depth: usize,
+    min_sup: usize,
+    learner: &mut DL85<C, E, H>,
+) -> Result<Vec<TuningResult>, String>
+where
+    C: Caching + ?Sized,
+    E: ErrorWrapper + ?Sized,
+    H: Heuristic + ?Sized,
+    S: Structure,
+{
+    let mut front: Vec<TuningResult> = vec![];
+    let mut previous: Option<Tree> = None;
+    for depth in 1..=max_depth {
+        // After the first fit, the cache holds the solutions of the shallower depth
+        match depth {
+            1 => {
+                learner.constraints.max_depth = depth;
+                learner.constraints.min_sup = min_sup;
+            }
+            _ => learner.relax(depth, min_sup)?,
+        }
+        match &previous {
+            Some(tree) => learner.fit_with_warm_start(structure, tree)?,
+            None => learner.fit(structure)?,
+        }
+        let error = learner.statistics.tree_error;
+        if !learner.tree.is_empty() {
+            previous = Some(learner.tree.clone());
+        }
+        if learner.tree.is_empty() || front.last().is_some_and(|last| error >= last.error) {
+            continue;
+        }
+        front.push(TuningResult {
+            max_depth: depth,
+            min_sup,
+            error,
+            leaves: learner.tree.num_leaves(),
+            is_optimal: learner.statistics.is_optimal(),
+            tree: learner.tree.clone(),
+        });
+    }
+    Ok(front)
+}
+
+/// Results that no other one beats on both the number of leaves and the error, sorted by number
+/// of leaves. Among equal ones, the first is kept
+pub fn pareto_front(mut results: Vec<TuningResult>) -> Vec<TuningResult> {
+    // The sort is stable, so equal results keep their order
+    results.sort_by(|a, b| a.leaves.cmp(&b.leaves).then(a.error.total_cmp(&b.error)));
+    let mut front: Vec<TuningResult> = vec![];
+    for result in results {
+        if front.last().is_none_or(|last| result.error < last.error) {
+            front.push(result);
+        }
+    }
+    front
+}
+
+#[cfg(test)]
+mod test_tuning {
+    use crate::cache::trie::Trie;
+    use crate::data::{BinaryData, FileReader};
+    use crate::heuristics::NoHeuristic;
+    use crate::searches::errors::NativeError;
+    use crate::searches::optimal::DL85;
+    use crate::searches::tuning::{depth_front, tune_depth_and_support};
+    use crate::searches::{
+        BranchingStrategy, CacheInitStrategy, LowerBoundStrategy, NodeExposedData, SortPolicy,
+        Specialization,
+    };
+    use crate::structures::RevBitset;
+
+    #[test]
+    fn pareto_set_of_depths_and_supports() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+        let front = tune_depth_and_support(&mut structure, &[1, 2, 3], &[1, 50], || {
+            DL85::new(
+                1,
+                1,
+                <f64>::INFINITY,
+                600,
+                SortPolicy::Always,
+                0,
+                CacheInitStrategy::None_,
+                Specialization::Murtree,
+                LowerBoundStrategy::None_,
+                BranchingStrategy::None_,
+                NodeExposedData::ClassesSupport,
+                Box::<Trie>::default(),
+                Box::<NativeError>::default(),
+                Box::<NoHeuristic>::default(),
+            )
+        })
+        .unwrap();
+        assert!(!front.is_empty());
+        for pair in front.windows(2) {
+            assert!(pair[0].leaves < pair[1].leaves);
+            assert!(pair[0].error > pair[1].error);
+        }
+        // The depth 2 optimum of the dataset belongs to the front
+        assert!(front
+            .iter()
+            .any(|result| result.error == 137.0 && result.max_depth == 2));
+        assert!(front.iter().all(|result| result.is_optimal));
+    }
+
+    #[test]
+    fn depth_front_improves_with_depth() {
+        let data = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&data);
+        let mut learner = DL85::new(
+            1,
+            1,
+            <f64>::INFINITY,
+            600,
+            SortPolicy::Always,
+            0,
+            CacheInitStrategy::None_,
+            Specialization::Murtree,
+            LowerBoundStrategy::None_,
+            BranchingStrategy::None_,
+            NodeExposedData::ClassesSupport,
+            Box::<Trie>::default(),
+            Box::<NativeError>::default(),
+            Box::<NoHeuristic>::default(),
+        );
+        let front = depth_front(&mut structure, 3, 1, &mut learner).unwrap();
+        assert_eq!(front[0].max_depth, 1);
+        for pair in front.windows(2) {
+            assert!(pair[0].max_depth < pair[1].max_depth);
+            assert!(pair[0].error > pair[1].error);
+        }
+        assert!(front
+            .iter()
+            .any(|result| result.error == 137.0 && result.max_depth == 2));
+        assert!(front.iter().all(|result| result.is_optimal));
+    }
+}
@@ -1,22 +1,70 @@
+use crate::cache::CacheStatistics;
+use crate::globals::deserialize_infinity;
 use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 use std::time::Duration;
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Constraints {
     pub max_depth: usize,
     pub min_sup: usize,
+    #[serde(deserialize_with = "deserialize_infinity")]
     pub max_error: f64,
     pub max_time: usize,
-    pub one_time_sort: bool,
+    // Depths at which the candidates are sorted by the heuristic
+    #[serde(default)]
+    pub sort_policy: SortPolicy,
     pub node_exposed_data: NodeExposedData,
     pub specialization: Specialization,
     pub lower_bound_strategy: LowerBoundStrategy,
+    // Covers of the solved siblings kept for the similarity lower bound
+    #[serde(default)]
+    pub lower_bound_policy: LowerBoundPolicy,
     pub branching_strategy: BranchingStrategy,
     pub cache_init_strategy: CacheInitStrategy,
     pub search_strategy: SearchStrategy,
     pub cache_init_size: usize,
+    // Maximum number of cache entries, 0 means unbounded
+    #[serde(default)]
+    pub max_cache_size: usize,
+    // Estimated memory of the cache entries in bytes, 0 means unbounded
+    #[serde(default)]
+    pub max_cache_bytes: usize,
+    // What happens once the cache is full
+    #[serde(default)]
+    pub cache_full_policy: CacheFullPolicy,
+    // Maximum number of leaves of the tree, 0 means unbounded
+    #[serde(default)]
+    pub max_leaves: usize,
+    // Number of candidates explored at each node, 0 means all of them
+    #[serde(default)]
+    pub beam_width: usize,
+    // Candidates whose heuristic score is below this value are dropped, 0 keeps them all
+    #[serde(default)]
+    pub min_heuristic_score: f64,
+    // Candidates whose heuristic score is below this share of the best score of their node are
+    // dropped, 0 keeps them all
+    #[serde(default)]
+    pub min_relative_score: f64,
+    // Discrepancies allowed on each path with the discrepancy search
     pub discrepancy_budget: usize,
+    pub explain_leaves: bool,
+    // Records the anytime curve in the statistics
+    #[serde(default)]
+    pub record_anytime: bool,
+    // Itemsets of the same size covering the same transactions share their cache entry
+    #[serde(default)]
+    pub cover_keys: bool,
+    // Order of the candidates with equal scores or errors
+    #[serde(default)]
+    pub tie_break: TieBreak,
+    // Seed of the random tie breaking
+    #[serde(default)]
+    pub seed: u64,
+    // Among the trees of optimal error, return one with the fewest leaves
+    #[serde(default)]
+    pub minimize_leaves: bool,
 }
 
 impl Default for Constraints {
@@ -26,28 +74,81 @@ impl Default for Constraints {
             min_sup: 1,
             max_error: <f64>::INFINITY,
             max_time: 600,
-            one_time_sort: false,
+            sort_policy: SortPolicy::Always,
             node_exposed_data: NodeExposedData::ClassesSupport,
             specialization: Specialization::None_,
             lower_bound_strategy: LowerBoundStrategy::None_,
+            lower_bound_policy: LowerBoundPolicy::TwoCovers,
             branching_strategy: BranchingStrategy::None_,
             cache_init_strategy: CacheInitStrategy::None_,
             search_strategy: SearchStrategy::None_,
             cache_init_size: 0,
+            max_cache_size: 0,
+            max_cache_bytes: 0,
+            cache_full_policy: CacheFullPolicy::Evict,
+            max_leaves: 0,
+            beam_width: 0,
+            min_heuristic_score: 0.0,
+            min_relative_score: 0.0,
             discrepancy_budget: 0,
+            explain_leaves: false,
+            record_anytime: false,
+            cover_keys: false,
+            tie_break: TieBreak::Index,
+            seed: 0,
+            minimize_leaves: false,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Statistics {
     pub cache_size: usize,
+    #[serde(default)]
+    pub cache_evictions: usize,
+    // Hits, entries per depth and memory of the cache at the end of the search
+    #[serde(default)]
+    pub cache: CacheStatistics,
+    // Itemsets solved through the entry of another itemset with the same cover
+    #[serde(default)]
+    pub merged_itemsets: usize,
     pub cache_callbacks: usize,
     pub search_space_size: usize,
+    #[serde(deserialize_with = "deserialize_infinity")]
     pub tree_error: f64,
+    // Best proven lower bound of the error, equal to the tree error once it is proven optimal
+    #[serde(default)]
+    pub best_lower_bound: f64,
+    // Difference between the tree error and its best lower bound, 0 when optimality is proven
+    #[serde(default, deserialize_with = "deserialize_infinity")]
+    pub gap: f64,
     pub duration: Duration,
     pub num_attributes: usize,
     pub num_samples: usize,
+    // Set when the search was stopped by an interrupter before completion
+    #[serde(default)]
+    pub interrupted: bool,
+    // Set when the beam dropped candidates, the tree may then not be optimal
+    #[serde(default)]
+    pub beam_truncated: bool,
+    // Candidates dropped by the heuristic score thresholds, the tree may then not be optimal
+    #[serde(default)]
+    pub filtered_candidates: usize,
+    // Set when the discrepancy budget kept candidates from being explored
+    #[serde(default)]
+    pub discrepancy_limited: bool,
+    // Set when the full cache stopped the search
+    #[serde(default)]
+    pub cache_limit_reached: bool,
+    // Set when the search turned nodes into leaves because of the time limit
+    #[serde(default)]
+    pub time_limit_reached: bool,
+    // Elapsed seconds and error of the best tree each time it improved, when recorded
+    #[serde(default)]
+    pub anytime_curve: Vec<(f64, f64)>,
+    // Deepest depth whose tree was proven optimal when solving increasing depths
+    #[serde(default)]
+    pub optimal_depth: Option<usize>,
     pub constraints: Constraints,
 }
 
@@ -55,21 +156,71 @@ impl Default for Statistics {
     fn default() -> Self {
         Self {
             cache_size: 0,
+            cache_evictions: 0,
+            cache: CacheStatistics::default(),
+            merged_itemsets: 0,
             cache_callbacks: 0,
             search_space_size: 0,
             tree_error: 0.0,
+            best_lower_bound: 0.0,
+            gap: 0.0,
             duration: Duration::default(),
             num_attributes: 0,
             num_samples: 0,
+            interrupted: false,
+            beam_truncated: false,
+            filtered_candidates: 0,
+            discrepancy_limited: false,
+            cache_limit_reached: false,
+            time_limit_reached: false,
+            anytime_curve: vec![],
+            optimal_depth: None,
             constraints: Constraints::default(),
         }
     }
 }
 
+impl Statistics {
+    // The search ran to completion, it was neither interrupted, stopped by the time limit nor
+    // restricted by the beam, the score thresholds, the discrepancy budget or the full cache
+    pub fn is_optimal(&self) -> bool {
+        matches!(self.stop_reason(), StopReason::Done)
+            && !self.beam_truncated
+            && self.filtered_candidates == 0
+            && !self.discrepancy_limited
+    }
+
+    // Why the search stopped: the interrupter, the time limit and the full cache come before its
+    // completion, which holds even when the beam or the discrepancy budget left candidates out
+    pub fn stop_reason(&self) -> StopReason {
+        if self.interrupted {
+            StopReason::Interrupted
+        } else if self.time_limit_reached {
+            StopReason::TimeLimitReached
+        } else if self.cache_limit_reached {
+            StopReason::CacheLimitReached
+        } else {
+            StopReason::Done
+        }
+    }
+
+    // Sets the lower bound, raised to the tree error when it is proven optimal, and the gap
+    pub fn set_lower_bound(&mut self, lower_bound: f64) {
+        self.best_lower_bound = match self.is_optimal() {
+            true => self.tree_error,
+            false => lower_bound.min(self.tree_error),
+        };
+        self.gap = self.tree_error - self.best_lower_bound;
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum NodeExposedData {
     ClassesSupport,
     Tids,
+    // Target values of the transactions of the node, for the error functions on continuous
+    // targets. The other error functions are given the tids
+    Targets,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, ValueEnum)]
@@ -84,6 +235,59 @@ pub enum LowerBoundStrategy {
     None_,
 }
 
+// Candidates whose heuristic scores or tree errors are equal up to rounding keep the smallest
+// attribute index first, or the order of a permutation of the attributes drawn from the seed
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, ValueEnum)]
+pub enum TieBreak {
+    #[default]
+    Index,
+    Random,
+}
+
+// Keeping two covers, as MurTree does, replaces the one closer to the new cover so that the kept
+// covers stay different from each other
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, ValueEnum)]
+pub enum LowerBoundPolicy {
+    LastCover,
+    #[default]
+    TwoCovers,
+}
+
+// Depths at which the candidates are sorted by the heuristic. The deeper nodes keep the order of
+// the candidates of the root, sorting them again is mostly useful close to the root
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SortPolicy {
+    RootOnly,
+    // Nodes up to this depth, the root being at depth 0
+    UpToDepth(usize),
+    #[default]
+    Always,
+}
+
+impl SortPolicy {
+    pub fn sorts(&self, depth: usize) -> bool {
+        match self {
+            SortPolicy::RootOnly => depth == 0,
+            SortPolicy::UpToDepth(max_depth) => depth <= *max_depth,
+            SortPolicy::Always => true,
+        }
+    }
+}
+
+impl FromStr for SortPolicy {
+    type Err = String;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy {
+            "root-only" => Ok(SortPolicy::RootOnly),
+            "always" => Ok(SortPolicy::Always),
+            depth => depth.parse().map(SortPolicy::UpToDepth).map_err(|_| {
+                format!("The sort policy must be root-only, always or a depth, not {depth}")
+            }),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, ValueEnum)]
 pub enum BranchingStrategy {
     Dynamic,
@@ -93,9 +297,19 @@ pub enum BranchingStrategy {
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, ValueEnum)]
 pub enum CacheType {
     Trie,
+    // Trie merging the chains of itemsets never inserted
+    CompressedTrie,
     Hashmap,
 }
 
+// Evicting leaves keeps the search exact, stopping turns the nodes not explored yet into leaves
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, ValueEnum)]
+pub enum CacheFullPolicy {
+    #[default]
+    Evict,
+    Stop,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, ValueEnum)]
 pub enum CacheInitStrategy {
     DynamicAllocation,
@@ -119,6 +333,11 @@ pub enum StopReason {
     MaxDepthReached,
     NotEnoughSupport,
     PureNode,
+    PurityThresholdReached,
+    CacheLimitReached,
+    LeafRuleHolds,
+    NoCandidates,
+    Interrupted,
     FromSpecializedAlgorithm,
     None,
 }
@@ -128,9 +347,18 @@ pub enum SearchHeuristic {
     InformationGain,
     InformationGainRatio,
     GiniIndex,
+    WeightOfEvidence,
+    Random,
     None_,
 }
 
+// Criterion minimized by DL8.5 on classification tasks
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ValueEnum)]
+pub enum Objective {
+    Error,
+    BalancedAccuracy,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, ValueEnum)]
 pub enum D2Objective {
     Error,
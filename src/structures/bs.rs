@@ -1,9 +1,10 @@
 // * Done
 use crate::data::FileReader;
 use crate::globals::{attribute, item_type};
+use crate::structures::simd::{count_intersect_with_many, count_intersection};
 use crate::structures::types::BitsetStructData;
 
-use crate::structures::{format_data_into_bitset, DataCover, Difference, Structure};
+use crate::structures::{format_data_into_bitset, ones, DataCover, Difference, Structure};
 
 pub struct Bitset {
     inputs: BitsetStructData,
@@ -28,12 +29,7 @@ impl Structure for Bitset {
         let support = <usize>::MAX;
         if label < self.num_labels {
             if let Some(state) = self.get_last_state() {
-                let mut count = 0;
-                let label_bitset = &self.inputs.targets[label];
-                for (i, label_chunk) in label_bitset.iter().enumerate() {
-                    count += (*label_chunk & state[i]).count_ones();
-                }
-                return count as usize;
+                return count_intersection(state, &self.inputs.targets[label]);
             }
         }
         support
@@ -47,27 +43,22 @@ impl Structure for Bitset {
 
         if self.num_labels == 2 {
             if let Some(state) = self.get_last_state() {
-                let mut count = 0;
-                let label_bitset = &self.inputs.targets[0];
-                for (i, label_chunk) in label_bitset.iter().enumerate() {
-                    count += (*label_chunk & state[i]).count_ones();
-                }
-                self.labels_support.push(count as usize);
+                let mut count = [0];
+                count_intersect_with_many(state, &self.inputs.targets[..1], &mut count);
+                self.labels_support.push(count[0]);
                 let support = self.support();
-                self.labels_support.push(support - count as usize);
+                self.labels_support.push(support - count[0]);
             }
             return &self.labels_support;
         }
 
         if let Some(state) = self.state.last() {
-            for label in 0..self.num_labels {
-                let mut count = 0;
-                let label_bitset = &self.inputs.targets[label];
-                for (i, label_chunk) in label_bitset.iter().enumerate() {
-                    count += (*label_chunk & state[i]).count_ones();
-                }
-                self.labels_support.push(count as usize);
-            }
+            self.labels_support.resize(self.num_labels, 0);
+            count_intersect_with_many(
+                state,
+                &self.inputs.targets[..self.num_labels],
+                &mut self.labels_support,
+            );
             return &self.labels_support;
         }
         &self.labels_support
@@ -155,21 +146,19 @@ impl Structure for Bitset {
             return (0..self.inputs.size).collect();
         }
 
-        let mut tids = vec![];
-        let nb_chunks = self.inputs.chunks;
-        let nb_trans = self.inputs.size;
-        if let Some(state) = self.get_last_state() {
-            for (idx, chunk) in state.iter().enumerate().rev() {
-                let mut word = *chunk;
-                while word != 0 {
-                    let set_bit = word.trailing_zeros() as usize;
-                    let tid = nb_trans - ((nb_chunks - 1 - idx) * 64 + set_bit) - 1;
-                    tids.push(tid);
-                    word &= !(1u64 << set_bit);
-                }
-            }
+        match self.get_last_state() {
+            Some(state) => ones(state.iter().copied().enumerate().rev())
+                .map(|(chunk, bit)| self.inputs.tid(chunk, bit))
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    fn labels(&self) -> Option<Vec<usize>> {
+        match self.num_labels {
+            0 => None,
+            _ => Some(self.inputs.labels()),
         }
-        tids
     }
 }
 
@@ -1,3 +1,4 @@
+use crate::data::libsvm::SparseData;
 use crate::data::FileReader;
 use crate::structures::types::BitsetStructData;
 
@@ -7,6 +8,7 @@ pub use dp::DoublePointer;
 pub use hs::Horizontal;
 pub use rsbs::RevBitset;
 pub use rws::RawBinary;
+pub(crate) use simd::{count_intersect_at_with_many, count_intersection};
 
 // In out difference between data
 pub type Difference = (usize, usize);
@@ -15,6 +17,7 @@ mod dp;
 mod hs;
 mod rsbs;
 mod rws;
+mod simd;
 mod types;
 
 pub trait Structure {
@@ -42,6 +45,83 @@ pub trait Structure {
     fn get_difference(&self, data_cover: &DataCover) -> Difference;
 
     fn get_tids(&self) -> Vec<usize>;
+
+    // Same as get_tids, reusing the allocation of the given vector
+    fn fill_tids(&self, tids: &mut Vec<usize>) {
+        *tids = self.get_tids();
+    }
+
+    // Target values of the transactions of the current cover, read from the continuous targets
+    // of the dataset indexed by tid
+    fn fill_targets(&self, targets: &[f64], values: &mut Vec<f64>) {
+        values.clear();
+        values.extend(self.get_tids().iter().map(|tid| targets[*tid]));
+    }
+
+    // Sum of the weights of the samples of the current cover. Structures without sample weights
+    // count each sample once
+    fn weighted_count(&mut self) -> f64 {
+        self.support() as f64
+    }
+
+    // Same sum for each label
+    fn weighted_labels_count(&mut self) -> Vec<f64> {
+        self.labels_support()
+            .iter()
+            .map(|support| *support as f64)
+            .collect()
+    }
+
+    // Label of each transaction of the dataset, when the structure can provide them
+    fn labels(&self) -> Option<Vec<usize>> {
+        None
+    }
+
+    // Fingerprint of the current cover, the same for all the itemsets covering the same
+    // transactions. Different covers rarely share a fingerprint
+    fn cover_fingerprint(&self) -> u64 {
+        self.get_tids().iter().fold(0, |fingerprint, tid| {
+            fingerprint.wrapping_add(mix_cover_word(*tid, 1))
+        })
+    }
+}
+
+// Scrambles a word of a cover with its position. The fingerprint of a cover sums those of its
+// words, so it does not depend on the order they are visited in
+pub(crate) fn mix_cover_word(position: usize, word: u64) -> u64 {
+    let mut mixed = word ^ (position as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    mixed = (mixed ^ (mixed >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    mixed = (mixed ^ (mixed >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    mixed ^ (mixed >> 31)
+}
+
+// Set bits of a sequence of words, given with their chunk, as chunk and bit pairs. Empty words
+// are skipped and each word yields its bits through trailing_zeros
+pub(crate) struct Ones<I> {
+    words: I,
+    chunk: usize,
+    word: u64,
+}
+
+pub(crate) fn ones<I: IntoIterator<Item = (usize, u64)>>(words: I) -> Ones<I::IntoIter> {
+    Ones {
+        words: words.into_iter(),
+        chunk: 0,
+        word: 0,
+    }
+}
+
+impl<I: Iterator<Item = (usize, u64)>> Iterator for Ones<I> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.word == 0 {
+            (self.chunk, self.word) = self.words.next()?;
+        }
+        let bit = self.word.trailing_zeros() as usize;
+        self.word &= self.word - 1;
+        Some((self.chunk, bit))
+    }
 }
 
 pub fn format_data_into_bitset<T>(data: &T) -> BitsetStructData
@@ -95,6 +175,96 @@ where
     }
 }
 
+// Same bitsets filled from the attributes at 1 of each sample
+pub fn format_sparse_data_into_bitset(data: &SparseData) -> BitsetStructData {
+    let size = data.size();
+    let chunks = match size > 64 {
+        true => size.div_ceil(64),
+        false => 1,
+    };
+
+    let mut inputs = vec![vec![0u64; chunks]; data.num_attributes];
+    let mut targets = vec![vec![0u64; chunks]; data.num_labels()];
+    for (position, row) in data.rows.iter().enumerate() {
+        let tid = size - 1 - position;
+        let row_chunk = chunks - 1 - tid / 64;
+        for attribute in row {
+            inputs[*attribute][row_chunk] |= 1u64 << (tid % 64);
+        }
+        // Samples without target have no class
+        if let Some(class) = data.targets.get(position) {
+            targets[*class][row_chunk] |= 1u64 << (tid % 64);
+        }
+    }
+
+    BitsetStructData {
+        inputs,
+        targets,
+        chunks,
+        size,
+    }
+}
+
+// Same bitsets from columns already packed, sample t being bit t % 64 of word t / 64 of each
+// column. The structure stores the samples in reverse order: the bits of each word are reversed
+// and shifted by the unused bits of the last word
+pub fn format_packed_into_bitset(
+    columns: Vec<Vec<u64>>,
+    labels: Option<&[usize]>,
+    size: usize,
+) -> Result<BitsetStructData, String> {
+    if size == 0 {
+        return Err("At least one sample is required".to_string());
+    }
+    let chunks = size.div_ceil(64);
+    if let Some(column) = columns.iter().position(|words| words.len() != chunks) {
+        return Err(format!(
+            "Column {} has {} words instead of {}",
+            column,
+            columns[column].len(),
+            chunks
+        ));
+    }
+    if labels.is_some_and(|labels| labels.len() != size) {
+        return Err("There must be one label per sample".to_string());
+    }
+
+    let unused = chunks * 64 - size;
+    let last_mask = u64::MAX >> unused;
+    let inputs = columns
+        .into_iter()
+        .map(|mut words| {
+            words[chunks - 1] &= last_mask;
+            (0..chunks)
+                .map(|chunk| {
+                    let word = words[chunk].reverse_bits();
+                    match (unused, chunk) {
+                        (0, _) => word,
+                        (_, 0) => word >> unused,
+                        _ => (word >> unused) | (words[chunk - 1].reverse_bits() << (64 - unused)),
+                    }
+                })
+                .collect::<Vec<u64>>()
+        })
+        .collect::<Vec<Vec<u64>>>();
+
+    let mut data = BitsetStructData {
+        inputs,
+        targets: vec![],
+        chunks,
+        size,
+    };
+    if let Some(labels) = labels {
+        let num_labels = labels.iter().max().map_or(0, |label| label + 1);
+        data.targets = vec![vec![0u64; chunks]; num_labels];
+        for (tid, label) in labels.iter().enumerate() {
+            let (chunk, bit) = data.bit(tid);
+            data.targets[*label][chunk] |= 1u64 << bit;
+        }
+    }
+    Ok(data)
+}
+
 #[derive(Clone)]
 pub struct DataCover {
     cover: Vec<u64>, // u64 because of the bitset
@@ -133,3 +303,16 @@ impl Default for DataCover {
         }
     }
 }
+
+#[cfg(test)]
+mod test_ones {
+    use crate::structures::ones;
+
+    #[test]
+    fn ones_skip_the_empty_words() {
+        let words = [0u64, 0b1010, 0, 1 << 63 | 1];
+        let bits = ones(words.iter().copied().enumerate()).collect::<Vec<_>>();
+        assert_eq!(bits, vec![(1, 1), (1, 3), (3, 0), (3, 63)]);
+        assert_eq!(ones([(0, 0u64)]).count(), 0);
+    }
+}
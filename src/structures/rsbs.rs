@@ -1,9 +1,17 @@
 // * Done
+use crate::data::libsvm::SparseData;
 use crate::data::FileReader;
-use crate::globals::{attribute, item_type};
+use crate::globals::{attribute, item, item_type};
 use crate::structures::types::BitsetStructData;
-use crate::structures::{format_data_into_bitset, DataCover, Difference, Structure};
+use crate::structures::{
+    count_intersect_at_with_many, format_data_into_bitset, format_packed_into_bitset,
+    format_sparse_data_into_bitset, mix_cover_word, ones, DataCover, Difference, Structure,
+};
 use search_trail::{ReversibleU64, SaveAndRestore, StateManager, U64Manager};
+use std::collections::HashSet;
+
+// Density of the cover under which the pushes switch to sparse covers by default
+const DEFAULT_SPARSE_THRESHOLD: f64 = 0.0;
 
 pub struct RevBitset {
     inputs: BitsetStructData,
@@ -17,6 +25,21 @@ pub struct RevBitset {
     index: Vec<usize>,
     limit: Vec<isize>,
     distance: ReversibleU64, // Steps to restore to attain the initial state
+    // Weight of each transaction, used by the weighted counts only
+    weights: Option<Vec<f64>>,
+    // Whether the last input is a mask of the kept transactions, pushed first as a virtual item
+    masked: bool,
+    // Covers of the sparse pushes, as the positions chunk * 64 + bit of their transactions. The
+    // first sparse_depth ones are in use, the next ones are kept to reuse their allocation
+    sparse_covers: Vec<Vec<usize>>,
+    sparse_depth: usize,
+    // Share of set bits in the non empty words under which the next pushes list the positions of
+    // the cover instead of updating its words, 0 disabling the sparse pushes
+    sparse_threshold: f64,
+    // Words of the current cover and their positions in the bitsets, gathered from the sparse set
+    // of the state for the batched label counts
+    live_words: Vec<u64>,
+    live_positions: Vec<usize>,
 }
 
 impl Structure for RevBitset {
@@ -33,18 +56,26 @@ impl Structure for RevBitset {
         let state = &self.state;
         let support = usize::MAX;
 
+        if let (Some(cover), true) = (self.sparse_cover(), label < self.num_labels) {
+            return self.count_positions(cover, &self.inputs.targets[label]);
+        }
         if label < self.num_labels {
             if let Some(limit) = self.limit.last() {
-                let mut count = 0;
+                let mut count = [0];
                 if *limit >= 0 {
-                    let label_bitset = &self.inputs.targets[label];
-                    for i in 0..(*limit + 1) as usize {
-                        let cursor = self.index[i];
-                        let val = self.state_manager.get_u64(state[cursor]);
-                        count += (label_bitset[cursor] & val).count_ones()
-                    }
+                    let positions = &self.index[..=*limit as usize];
+                    let words = positions
+                        .iter()
+                        .map(|cursor| self.state_manager.get_u64(state[*cursor]))
+                        .collect::<Vec<u64>>();
+                    count_intersect_at_with_many(
+                        &words,
+                        positions,
+                        &self.inputs.targets[label..=label],
+                        &mut count,
+                    );
                 }
-                return count as usize;
+                return count[0];
             }
         }
         support
@@ -60,33 +91,40 @@ impl Structure for RevBitset {
             self.labels_support.push(0);
         }
 
-        if let Some(limit) = self.limit.last() {
-            if self.num_labels == 2 {
-                if *limit >= 0 {
-                    let label_bitset = &self.inputs.targets[0];
-                    let mut count = 0;
-                    for i in 0..(*limit + 1) as usize {
-                        let cursor = self.index[i];
-                        let val = self.state_manager.get_u64(self.state[cursor]);
-                        count += (label_bitset[cursor] & val).count_ones()
-                    }
-                    self.labels_support[0] = count as usize;
-                    self.labels_support[1] = self.support() - count as usize;
-                }
-                return &self.labels_support;
-            }
+        if let Some(cover) = self.sparse_cover() {
+            let supports = self
+                .inputs
+                .targets
+                .iter()
+                .map(|label_bitset| self.count_positions(cover, label_bitset))
+                .collect::<Vec<usize>>();
+            self.labels_support = supports;
+            return &self.labels_support;
+        }
 
-            for label in 0..self.num_labels {
-                let mut count = 0;
-                if *limit >= 0 {
-                    let label_bitset = &self.inputs.targets[label];
-                    for i in 0..(*limit + 1) as usize {
-                        let cursor = self.index[i];
-                        let val = self.state_manager.get_u64(self.state[cursor]);
-                        count += (label_bitset[cursor] & val).count_ones()
-                    }
+        if let Some(limit) = self.limit.last() {
+            if *limit >= 0 {
+                let positions = &self.index[..=*limit as usize];
+                self.live_words.clear();
+                self.live_words.extend(
+                    positions
+                        .iter()
+                        .map(|cursor| self.state_manager.get_u64(self.state[*cursor])),
+                );
+                // With two labels the second count is the rest of the support
+                let counted = match self.num_labels {
+                    2 => 1,
+                    _ => self.num_labels,
+                };
+                count_intersect_at_with_many(
+                    &self.live_words,
+                    positions,
+                    &self.inputs.targets[..counted],
+                    &mut self.labels_support[..counted],
+                );
+                if self.num_labels == 2 {
+                    self.labels_support[1] = self.support() - self.labels_support[0];
                 }
-                self.labels_support[label] = count as usize;
             }
             return &self.labels_support;
         }
@@ -97,6 +135,10 @@ impl Structure for RevBitset {
         if !self.support == usize::MAX {
             return self.support;
         }
+        if let Some(cover) = self.sparse_cover() {
+            self.support = cover.len();
+            return self.support;
+        }
         self.support = 0;
         if let Some(limit) = self.limit.last() {
             if *limit >= 0 {
@@ -115,6 +157,11 @@ impl Structure for RevBitset {
     }
 
     fn push(&mut self, item: usize) -> usize {
+        if self.sparse_depth > 0 || self.is_sparse() {
+            self.position.push(item);
+            self.sparse_pushing(item);
+            return self.support();
+        }
         self.position.push(item);
         let current_distance = self.state_manager.get_u64(self.distance);
         self.state_manager
@@ -127,6 +174,13 @@ impl Structure for RevBitset {
 
     fn backtrack(&mut self) {
         // TODO: Remove the support computation
+        if self.sparse_depth > 0 {
+            self.position.pop();
+            self.sparse_depth -= 1;
+            self.support = usize::MAX;
+            self.labels_support.clear();
+            return;
+        }
         if !self.position.is_empty() {
             self.position.pop();
             let limit_size = self.limit.len();
@@ -144,6 +198,13 @@ impl Structure for RevBitset {
 
     fn temp_push(&mut self, item: usize) -> usize {
         // TODO: Change this to avoid recomputing the support & labels support
+        if let Some(cover) = self.sparse_cover() {
+            let set = self.count_positions(cover, &self.inputs.inputs[attribute(item)]);
+            return match item_type(item) {
+                0 => cover.len() - set,
+                _ => set,
+            };
+        }
         let mut support = 0;
         if let Some(limit) = self.limit.last() {
             let limit = *limit;
@@ -167,25 +228,42 @@ impl Structure for RevBitset {
         support
     }
 
+    // Backtracks to the root, which is below the mask when there is one
     fn reset(&mut self) {
-        self.position = Vec::with_capacity(self.num_attributes);
-        self.limit = Vec::with_capacity(self.num_attributes);
-        self.limit.push((self.inputs.chunks - 1) as isize);
-        let distance = self.state_manager.get_u64(self.distance);
-        for _ in 0..distance + 1 {
-            self.state_manager.restore_state();
-        }
-        self.support = self.inputs.size;
+        while self.position.len() > self.masked as usize {
+            self.backtrack();
+        }
+        self.support = usize::MAX;
         self.labels_support.clear();
+        self.support();
     }
 
     fn get_position(&self) -> &[usize] {
-        &self.position
+        &self.position[self.masked as usize..]
     }
 
     fn get_data_cover(&mut self) -> DataCover {
         let mut current_state = vec![0; self.inputs.chunks];
         let mut data_cover = DataCover::default();
+        if let Some(cover) = self.sparse_cover() {
+            for position in cover {
+                current_state[position >> 6] |= 1u64 << (position & 63);
+            }
+            // The non empty words come first, as in the index of the dense covers
+            let mut index = (0..self.inputs.chunks).collect::<Vec<usize>>();
+            index.sort_by_key(|cursor| current_state[*cursor] == 0);
+            let words = current_state.iter().filter(|word| **word != 0).count();
+            if words > 0 {
+                data_cover = DataCover {
+                    cover: current_state,
+                    limit: words as isize - 1,
+                    index,
+                    support: cover.len(),
+                    ..DataCover::default()
+                };
+            }
+            return data_cover;
+        }
         if let Some(limit) = self.limit.last() {
             if *limit >= 0 {
                 for i in 0..(*limit + 1) {
@@ -207,6 +285,19 @@ impl Structure for RevBitset {
     }
 
     fn get_difference(&self, data_cover: &DataCover) -> Difference {
+        if let Some(cover) = self.sparse_cover() {
+            let in_count = cover
+                .iter()
+                .filter(|position| {
+                    data_cover
+                        .cover
+                        .get(*position >> 6)
+                        .is_none_or(|word| (word >> (*position & 63)) & 1 == 0)
+                })
+                .count();
+            let out_count = data_cover.support - (cover.len() - in_count);
+            return (in_count, out_count);
+        }
         let current_limit = self.get_current_limit();
         let data_cover_limit = data_cover.limit;
         let current_index = &self.index;
@@ -236,28 +327,79 @@ impl Structure for RevBitset {
     }
 
     fn get_tids(&self) -> Vec<usize> {
-        if self.position.is_empty() {
-            return (0..self.inputs.size).collect::<Vec<usize>>();
-        }
         let mut tids = Vec::with_capacity(self.inputs.size);
-        let nb_chunks = self.inputs.chunks;
-        let nb_trans = self.inputs.size;
-        if let Some(limit) = self.limit.last() {
-            if *limit >= 0 {
-                let limit = *limit as usize;
-                for i in 0..limit + 1 {
-                    let cursor = self.index[i];
-                    let mut word = self.state_manager.get_u64(self.state[cursor]);
-                    while word != 0 {
-                        let set_bits = word.trailing_zeros() as usize;
-                        let tid = nb_trans - ((nb_chunks - 1 - cursor) * 64 + set_bits) - 1;
-                        tids.push(tid);
-                        word &= !(1 << set_bits);
+        self.fill_tids(&mut tids);
+        tids
+    }
+
+    fn fill_tids(&self, tids: &mut Vec<usize>) {
+        tids.clear();
+        self.for_each_tid(|tid| tids.push(tid));
+    }
+
+    fn fill_targets(&self, targets: &[f64], values: &mut Vec<f64>) {
+        values.clear();
+        self.for_each_tid(|tid| values.push(targets[tid]));
+    }
+
+    // Only the non empty words, the ones before the limit, are part of the fingerprint
+    fn cover_fingerprint(&self) -> u64 {
+        if let Some(cover) = self.sparse_cover() {
+            // The positions of a word follow each other in the sparse covers
+            let mut fingerprint = 0u64;
+            let mut word = (usize::MAX, 0u64);
+            for position in cover {
+                if position >> 6 != word.0 {
+                    if word.1 != 0 {
+                        fingerprint = fingerprint.wrapping_add(mix_cover_word(word.0, word.1));
                     }
+                    word = (position >> 6, 0);
                 }
+                word.1 |= 1u64 << (position & 63);
             }
+            if word.1 != 0 {
+                fingerprint = fingerprint.wrapping_add(mix_cover_word(word.0, word.1));
+            }
+            return fingerprint;
+        }
+        let limit = self.get_current_limit();
+        self.index
+            .iter()
+            .take((limit + 1).max(0) as usize)
+            .fold(0, |fingerprint, cursor| {
+                let word = self.state_manager.get_u64(self.state[*cursor]);
+                fingerprint.wrapping_add(mix_cover_word(*cursor, word))
+            })
+    }
+
+    fn weighted_count(&mut self) -> f64 {
+        match &self.weights {
+            Some(weights) => self.weighted_sum(weights, None),
+            None => self.support() as f64,
+        }
+    }
+
+    fn weighted_labels_count(&mut self) -> Vec<f64> {
+        match &self.weights {
+            Some(weights) => self
+                .inputs
+                .targets
+                .iter()
+                .map(|label_bitset| self.weighted_sum(weights, Some(label_bitset)))
+                .collect(),
+            None => self
+                .labels_support()
+                .iter()
+                .map(|support| *support as f64)
+                .collect(),
+        }
+    }
+
+    fn labels(&self) -> Option<Vec<usize>> {
+        match self.num_labels {
+            0 => None,
+            _ => Some(self.inputs.labels()),
         }
-        tids
     }
 }
 
@@ -266,7 +408,204 @@ impl RevBitset {
     where
         T: FileReader,
     {
-        let inputs = format_data_into_bitset(inputs);
+        Self::from_bitsets(format_data_into_bitset(inputs))
+    }
+
+    // Built without dense rows, from the attributes at 1 of each sample
+    pub fn from_sparse(inputs: &SparseData) -> RevBitset {
+        Self::from_bitsets(format_sparse_data_into_bitset(inputs))
+    }
+
+    // Built from bit-packed columns, see format_packed_into_bitset
+    pub fn from_packed(
+        columns: Vec<Vec<u64>>,
+        labels: Option<&[usize]>,
+        size: usize,
+    ) -> Result<RevBitset, String> {
+        Ok(Self::from_bitsets(format_packed_into_bitset(
+            columns, labels, size,
+        )?))
+    }
+
+    // Restricts the structure to the given transactions without copying the data. The mask is an
+    // extra attribute, set for the kept transactions, pushed as a virtual item: the searches and
+    // the resets then start from the kept transactions. A new mask replaces the previous one
+    pub fn mask(&mut self, tids: &[usize]) -> Result<(), String> {
+        if let Some(tid) = tids.iter().find(|tid| **tid >= self.inputs.size) {
+            return Err(format!("Unknown transaction {}", tid));
+        }
+        if tids.is_empty() {
+            return Err("At least one sample must be kept".to_string());
+        }
+        self.unmask();
+        let mut bitset = vec![0u64; self.inputs.chunks];
+        for tid in tids {
+            let (chunk, bit) = self.inputs.bit(*tid);
+            bitset[chunk] |= 1u64 << bit;
+        }
+        self.inputs.inputs.push(bitset);
+        self.masked = true;
+        self.push(item(self.num_attributes, 1));
+        Ok(())
+    }
+
+    // Removes the mask, the structure is reset to the whole dataset
+    pub fn unmask(&mut self) {
+        while !self.position.is_empty() {
+            self.backtrack();
+        }
+        if self.masked {
+            self.inputs.inputs.pop();
+            self.masked = false;
+        }
+        self.support = usize::MAX;
+        self.labels_support.clear();
+        self.support();
+    }
+
+    pub fn is_masked(&self) -> bool {
+        self.masked
+    }
+
+    // Gives a weight to each transaction, for instance the number of occurrences of a deduplicated
+    // sample or a boosting weight. The supports and min_sup still count samples
+    pub fn set_weights(&mut self, weights: Option<Vec<f64>>) -> Result<(), String> {
+        if let Some(weights) = &weights {
+            if weights.len() != self.inputs.size {
+                return Err(format!(
+                    "Expected {} weights, one per sample",
+                    self.inputs.size
+                ));
+            }
+            if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+                return Err("Weights must be finite and positive".to_string());
+            }
+        }
+        self.weights = weights;
+        Ok(())
+    }
+
+    pub fn weights(&self) -> Option<&[f64]> {
+        self.weights.as_deref()
+    }
+
+    // Sum of the weights of the transactions of the current cover, restricted to the ones set in
+    // the mask
+    fn weighted_sum(&self, weights: &[f64], mask: Option<&[u64]>) -> f64 {
+        let mut sum = 0.0;
+        if let Some(cover) = self.sparse_cover() {
+            for position in cover {
+                let (chunk, bit) = (position >> 6, position & 63);
+                if mask.is_none_or(|mask| (mask[chunk] >> bit) & 1 == 1) {
+                    sum += weights[self.inputs.tid(chunk, bit)];
+                }
+            }
+            return sum;
+        }
+        let words = self.active_words().map(|(cursor, word)| match mask {
+            Some(mask) => (cursor, word & mask[cursor]),
+            None => (cursor, word),
+        });
+        ones(words)
+            .map(|(chunk, bit)| weights[self.inputs.tid(chunk, bit)])
+            .sum()
+    }
+
+    // Number of transactions of the whole dataset, whatever the current position
+    pub fn num_transactions(&self) -> usize {
+        self.inputs.size
+    }
+
+    // Structure made of the given transactions, repeats allowed as in a bootstrap sample, and of
+    // the given attributes renumbered in order
+    pub fn sample(&self, tids: &[usize], attributes: &[usize]) -> Result<RevBitset, String> {
+        if let Some(tid) = tids.iter().find(|tid| **tid >= self.inputs.size) {
+            return Err(format!("Unknown transaction {}", tid));
+        }
+        if let Some(attribute) = attributes.iter().find(|a| **a >= self.num_attributes) {
+            return Err(format!("Unknown attribute {}", attribute));
+        }
+        if tids.is_empty() {
+            return Err("At least one sample must be drawn".to_string());
+        }
+        let mut sample = Self::from_bitsets(self.inputs.project(tids, attributes));
+        sample.sparse_threshold = self.sparse_threshold;
+        sample.weights = self
+            .weights
+            .as_ref()
+            .map(|weights| tids.iter().map(|tid| weights[*tid]).collect());
+        Ok(sample)
+    }
+
+    // Appends samples, given as class and binary row, after the current ones, weighing 1 when the
+    // transactions are weighted. The structure is reset to the whole dataset and unmasked
+    pub fn add_samples(&mut self, samples: &[(usize, Vec<usize>)]) -> Result<(), String> {
+        for (class, row) in samples {
+            if row.len() != self.num_attributes || row.iter().any(|value| *value > 1) {
+                return Err(format!(
+                    "Samples must be binary rows of {} attributes",
+                    self.num_attributes
+                ));
+            }
+            if self.num_labels > 0 && *class >= self.num_labels {
+                return Err(format!("Unknown class {}", class));
+            }
+        }
+        self.unmask();
+        let mut inputs = std::mem::take(&mut self.inputs);
+        inputs.append(samples);
+        self.rebuild(inputs);
+        if let Some(weights) = self.weights.as_mut() {
+            weights.resize(weights.len() + samples.len(), 1.0);
+        }
+        Ok(())
+    }
+
+    // Removes the samples of the given transactions, the next ones are renumbered in order. The
+    // removed samples are returned as class and binary row and the structure is reset to the
+    // whole dataset and unmasked
+    pub fn remove_samples(&mut self, tids: &[usize]) -> Result<Vec<(usize, Vec<usize>)>, String> {
+        if let Some(tid) = tids.iter().find(|tid| **tid >= self.inputs.size) {
+            return Err(format!("Unknown transaction {}", tid));
+        }
+        let mut tids = tids.to_vec();
+        tids.sort_unstable();
+        tids.dedup();
+        if tids.len() == self.inputs.size {
+            return Err("At least one sample must remain".to_string());
+        }
+        self.unmask();
+        let labels = self.inputs.labels();
+        let removed = tids
+            .iter()
+            .map(|tid| (labels[*tid], self.inputs.row(*tid)))
+            .collect();
+        let mut inputs = std::mem::take(&mut self.inputs);
+        inputs.remove(&tids);
+        self.rebuild(inputs);
+        if let Some(weights) = self.weights.as_mut() {
+            let removed = tids.into_iter().collect::<HashSet<usize>>();
+            *weights = std::mem::take(weights)
+                .into_iter()
+                .enumerate()
+                .filter(|(tid, _)| !removed.contains(tid))
+                .map(|(_, weight)| weight)
+                .collect();
+        }
+        Ok(removed)
+    }
+
+    // Builds the state of the structure again for its updated bitsets, keeping its weights and
+    // sparse threshold
+    fn rebuild(&mut self, inputs: BitsetStructData) {
+        let weights = self.weights.take();
+        let threshold = self.sparse_threshold;
+        *self = Self::from_bitsets(inputs);
+        self.weights = weights;
+        self.sparse_threshold = threshold;
+    }
+
+    fn from_bitsets(inputs: BitsetStructData) -> RevBitset {
         let index = (0..inputs.chunks).collect::<Vec<usize>>();
         let num_attributes = inputs.inputs.len();
         let mut state = Vec::with_capacity(inputs.chunks);
@@ -309,6 +648,13 @@ impl RevBitset {
             index,
             limit,
             distance,
+            weights: None,
+            masked: false,
+            sparse_covers: vec![],
+            sparse_depth: 0,
+            sparse_threshold: DEFAULT_SPARSE_THRESHOLD,
+            live_words: vec![],
+            live_positions: vec![],
         };
         structure.support();
         structure
@@ -329,6 +675,8 @@ impl RevBitset {
                 let value = item_type(item);
                 let feature_vec = &self.inputs.inputs[feature];
                 let mut lim = limit as usize;
+                self.live_words.clear();
+                self.live_positions.clear();
                 for i in (0..lim + 1).rev() {
                     let cursor = self.index[i];
                     let val = self.state_manager.get_u64(self.state[cursor]);
@@ -345,23 +693,27 @@ impl RevBitset {
                             break;
                         }
                     } else {
-                        let word_count = word.count_ones() as usize;
-                        self.support += word_count;
-                        if self.num_labels == 2 {
-                            let label_val = &self.inputs.targets[0][cursor];
-                            let zero_count = (label_val & word).count_ones() as usize;
-                            self.labels_support[0] += zero_count;
-                            self.labels_support[1] += word_count - zero_count;
-                        } else {
-                            for j in 0..self.num_labels {
-                                let label_val = &self.inputs.targets[j][cursor];
-                                self.labels_support[j] += (label_val & word).count_ones() as usize;
-                            }
-                        }
-
+                        self.support += word.count_ones() as usize;
+                        self.live_words.push(word);
+                        self.live_positions.push(cursor);
                         self.state_manager.set_u64(self.state[cursor], word);
                     }
                 }
+
+                // With two labels the second count is the rest of the support
+                let counted = match self.num_labels {
+                    2 => 1,
+                    _ => self.num_labels,
+                };
+                count_intersect_at_with_many(
+                    &self.live_words,
+                    &self.live_positions,
+                    &self.inputs.targets[..counted],
+                    &mut self.labels_support[..counted],
+                );
+                if self.num_labels == 2 {
+                    self.labels_support[1] = self.support - self.labels_support[0];
+                }
             }
 
             self.limit.push(limit);
@@ -369,6 +721,9 @@ impl RevBitset {
     }
 
     fn is_empty(&self) -> bool {
+        if let Some(cover) = self.sparse_cover() {
+            return cover.is_empty();
+        }
         if let Some(limit) = self.limit.last() {
             return *limit < 0;
         }
@@ -378,11 +733,99 @@ impl RevBitset {
     pub fn get_current_limit(&self) -> isize {
         self.limit.last().copied().unwrap_or(-1)
     }
+
+    // Non empty words of the current dense cover, with their chunk
+    fn active_words(&self) -> impl Iterator<Item = (usize, u64)> + '_ {
+        self.index[..(self.get_current_limit() + 1) as usize]
+            .iter()
+            .map(|cursor| (*cursor, self.state_manager.get_u64(self.state[*cursor])))
+    }
+
+    // Sets the density of the cover under which the pushes switch to sparse covers, see
+    // sparse_threshold. Deep and narrow branches then skip the words holding few transactions
+    pub fn set_sparse_threshold(&mut self, threshold: f64) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err("The sparse threshold must be between 0 and 1".to_string());
+        }
+        self.sparse_threshold = threshold;
+        Ok(())
+    }
+
+    // Calls the function on the tid of each transaction of the current cover
+    fn for_each_tid<F: FnMut(usize)>(&self, mut function: F) {
+        if let Some(cover) = self.sparse_cover() {
+            for position in cover {
+                function(self.inputs.tid(position >> 6, position & 63));
+            }
+            return;
+        }
+        if self.position.is_empty() {
+            (0..self.inputs.size).for_each(function);
+            return;
+        }
+        for (chunk, bit) in ones(self.active_words()) {
+            function(self.inputs.tid(chunk, bit));
+        }
+    }
+
+    fn sparse_cover(&self) -> Option<&[usize]> {
+        match self.sparse_depth {
+            0 => None,
+            depth => Some(&self.sparse_covers[depth - 1]),
+        }
+    }
+
+    // Whether the current dense cover holds few enough transactions for its words
+    fn is_sparse(&mut self) -> bool {
+        let words = self.get_current_limit() + 1;
+        self.sparse_threshold > 0.0
+            && words > 0
+            && (self.support() as f64) < self.sparse_threshold * (words * 64) as f64
+    }
+
+    // Lists the positions of the cover of the item among the current cover, from its words when
+    // it is still dense
+    fn sparse_pushing(&mut self, item: usize) {
+        if self.sparse_covers.len() == self.sparse_depth {
+            self.sparse_covers.push(vec![]);
+        }
+        let mut cover = std::mem::take(&mut self.sparse_covers[self.sparse_depth]);
+        cover.clear();
+        let feature = &self.inputs.inputs[attribute(item)];
+        let value = item_type(item) as u64;
+        match self.sparse_depth {
+            0 => {
+                let words = self.active_words().map(|(cursor, word)| match value {
+                    0 => (cursor, word & !feature[cursor]),
+                    _ => (cursor, word & feature[cursor]),
+                });
+                cover.extend(ones(words).map(|(chunk, bit)| chunk * 64 + bit));
+            }
+            depth => cover.extend(
+                self.sparse_covers[depth - 1]
+                    .iter()
+                    .filter(|position| (feature[*position >> 6] >> (*position & 63)) & 1 == value),
+            ),
+        }
+        self.sparse_covers[self.sparse_depth] = cover;
+        self.sparse_depth += 1;
+        self.support = usize::MAX;
+        self.labels_support.clear();
+    }
+
+    // Number of positions of the cover set in the bitset
+    fn count_positions(&self, cover: &[usize], bitset: &[u64]) -> usize {
+        cover
+            .iter()
+            .filter(|position| (bitset[*position >> 6] >> (*position & 63)) & 1 == 1)
+            .count()
+    }
 }
 
 #[cfg(test)]
 mod test_trail {
     use crate::data::binary_data::BinaryData;
+    use crate::data::libsvm::SparseData;
     use crate::data::FileReader;
     use crate::globals::item;
     use crate::structures::{RevBitset, Structure};
@@ -556,4 +999,291 @@ mod test_trail {
         println!("nSupport {:?}", support);
         println!("Label support {:?}", structure.labels_support());
     }
+
+    #[test]
+    fn sparse_structure_matches_dense_one() {
+        let dataset = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let (targets, rows) = dataset.get_train();
+        let lines = rows
+            .iter()
+            .zip(targets.as_ref().unwrap())
+            .map(|(row, target)| {
+                let attributes = row.iter().enumerate().filter(|(_, value)| **value == 1);
+                let entries = attributes.map(|(i, _)| format!(" {}:1", i + 1));
+                format!("{}{}", target, entries.collect::<String>())
+            })
+            .collect::<Vec<String>>();
+        let sparse = SparseData::parse(lines.iter().map(|line| line.as_str())).unwrap();
+
+        let mut dense = RevBitset::new(&dataset);
+        let mut structure = RevBitset::from_sparse(&sparse);
+        assert_eq!(structure.num_attributes(), dense.num_attributes());
+        assert_eq!(structure.labels_support(), dense.labels_support());
+        for attribute in 0..dense.num_attributes() {
+            for value in 0..2 {
+                assert_eq!(
+                    structure.push(item(attribute, value)),
+                    dense.push(item(attribute, value))
+                );
+                assert_eq!(structure.labels_support(), dense.labels_support());
+                structure.push(item((attribute + 1) % dense.num_attributes(), 1));
+                dense.push(item((attribute + 1) % dense.num_attributes(), 1));
+                assert_eq!(structure.labels_support(), dense.labels_support());
+                structure.backtrack();
+                structure.backtrack();
+                dense.backtrack();
+                dense.backtrack();
+            }
+        }
+    }
+
+    #[test]
+    fn unlabelled_sparse_structure_keeps_its_samples() {
+        let sparse = SparseData::from_compressed(
+            &[0, 1, 3, 5],
+            &[0, 1, 3, 0, 1],
+            &[1; 5],
+            (4, 3),
+            false,
+            None,
+        )
+        .unwrap();
+        let mut structure = RevBitset::from_sparse(&sparse);
+        assert_eq!(structure.num_labels(), 0);
+        assert_eq!(structure.support(), 4);
+        assert_eq!(structure.push(item(1, 1)), 2);
+        assert_eq!(structure.push(item(2, 0)), 1);
+    }
+
+    #[test]
+    fn filled_tids_reuse_the_buffer() {
+        let dataset = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&dataset);
+        let mut tids = vec![];
+        structure.fill_tids(&mut tids);
+        assert_eq!(tids, (0..structure.support()).collect::<Vec<usize>>());
+
+        let capacity = tids.capacity();
+        structure.push(item(4, 1));
+        structure.push(item(7, 0));
+        structure.fill_tids(&mut tids);
+        assert_eq!(tids, structure.get_tids());
+        assert_eq!(tids.len(), structure.support());
+        assert_eq!(tids.capacity(), capacity);
+    }
+
+    #[test]
+    fn weighted_counts_sum_the_sample_weights() {
+        let dataset = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&dataset);
+        let labels = structure.labels().unwrap();
+        assert_eq!(structure.weighted_count(), structure.support() as f64);
+
+        let weights = (0..structure.support())
+            .map(|tid| (tid % 3) as f64 * 0.5)
+            .collect::<Vec<f64>>();
+        assert!(structure.set_weights(Some(vec![1.0; 3])).is_err());
+        structure.set_weights(Some(weights.clone())).unwrap();
+        for pushed in [None, Some(item(4, 1)), Some(item(7, 0))] {
+            if let Some(pushed) = pushed {
+                structure.push(pushed);
+            }
+            let tids = structure.get_tids();
+            let mut expected = vec![0.0; structure.num_labels()];
+            for tid in tids.iter() {
+                expected[labels[*tid]] += weights[*tid];
+            }
+            assert_eq!(structure.weighted_labels_count(), expected);
+            assert_eq!(structure.weighted_count(), expected.iter().sum::<f64>());
+        }
+
+        structure.backtrack();
+        structure.backtrack();
+        structure.remove_samples(&[0, 1]).unwrap();
+        assert_eq!(structure.weights().unwrap(), &weights[2..]);
+    }
+
+    #[test]
+    fn packed_columns_give_the_same_structure() {
+        let dataset = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let (labels, rows) = dataset.get_train();
+        let labels = labels.as_ref().unwrap();
+        for size in [812usize, 128, 50] {
+            let mut columns = vec![vec![0u64; size.div_ceil(64)]; dataset.num_attributes()];
+            for (tid, row) in rows.iter().take(size).enumerate() {
+                for (attribute, value) in row.iter().enumerate() {
+                    if *value == 1 {
+                        columns[attribute][tid / 64] |= 1u64 << (tid % 64);
+                    }
+                }
+            }
+            let mut packed = RevBitset::from_packed(columns, Some(&labels[..size]), size).unwrap();
+            let kept = (0..size).collect::<Vec<usize>>();
+            let mut expected = RevBitset::new(&dataset);
+            expected.mask(&kept).unwrap();
+
+            assert_eq!(packed.labels_support(), expected.labels_support());
+            for attribute in 0..dataset.num_attributes() {
+                for value in 0..2 {
+                    packed.push(item(attribute, value));
+                    expected.push(item(attribute, value));
+                    assert_eq!(packed.labels_support(), expected.labels_support());
+                    assert_eq!(packed.get_tids().len(), packed.support());
+                    packed.backtrack();
+                    expected.backtrack();
+                }
+            }
+        }
+        assert!(RevBitset::from_packed(vec![vec![0; 2]], None, 50).is_err());
+        assert!(RevBitset::from_packed(vec![vec![0; 1]], None, 0).is_err());
+    }
+
+    #[test]
+    fn masked_structure_starts_from_the_kept_transactions() {
+        let dataset = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&dataset);
+        let kept = (0..812).filter(|tid| tid % 3 != 0).collect::<Vec<usize>>();
+        assert!(structure.mask(&[812]).is_err());
+        structure.mask(&kept).unwrap();
+        assert!(structure.get_position().is_empty());
+        assert_eq!(structure.support(), kept.len());
+        let mut tids = structure.get_tids();
+        tids.sort_unstable();
+        assert_eq!(tids, kept);
+
+        structure.push(item(4, 1));
+        let pushed = structure.get_tids();
+        assert!(pushed.iter().all(|tid| tid % 3 != 0));
+        structure.reset();
+        assert!(structure.is_masked());
+        assert_eq!(structure.support(), kept.len());
+        assert_eq!(structure.num_attributes(), dataset.num_attributes());
+
+        structure.unmask();
+        assert_eq!(structure.support(), 812);
+        assert!(!structure.is_masked());
+    }
+
+    #[test]
+    fn sparse_covers_match_the_dense_ones() {
+        let dataset = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut dense = RevBitset::new(&dataset);
+        let mut sparse = RevBitset::new(&dataset);
+        assert!(sparse.set_sparse_threshold(1.5).is_err());
+        sparse.set_sparse_threshold(1.0).unwrap();
+        let weights = (0..812).map(|tid| (tid % 7) as f64).collect::<Vec<f64>>();
+        dense.set_weights(Some(weights.clone())).unwrap();
+        sparse.set_weights(Some(weights)).unwrap();
+
+        let itemset = [item(4, 1), item(7, 0), item(12, 1), item(2, 0)];
+        let mut covers = vec![];
+        for it in itemset {
+            assert_eq!(sparse.push(it), dense.push(it));
+            assert_eq!(sparse.labels_support(), dense.labels_support());
+            assert_eq!(sparse.label_support(1), dense.label_support(1));
+            assert_eq!(
+                sparse.weighted_labels_count(),
+                dense.weighted_labels_count()
+            );
+            assert_eq!(sparse.cover_fingerprint(), dense.cover_fingerprint());
+            let (mut sparse_tids, mut dense_tids) = (sparse.get_tids(), dense.get_tids());
+            sparse_tids.sort_unstable();
+            dense_tids.sort_unstable();
+            assert_eq!(sparse_tids, dense_tids);
+            for attribute in 0..dataset.num_attributes() {
+                let it = item(attribute, attribute % 2);
+                assert_eq!(sparse.temp_push(it), dense.temp_push(it));
+            }
+            let own_cover = sparse.get_data_cover();
+            assert_eq!(sparse.get_difference(&own_cover), (0, 0));
+            covers.push(dense.get_data_cover());
+            covers.push(own_cover);
+            for cover in covers.iter() {
+                assert_eq!(sparse.get_difference(cover), dense.get_difference(cover));
+            }
+        }
+        sparse.backtrack();
+        dense.backtrack();
+        assert_eq!(sparse.labels_support(), dense.labels_support());
+        sparse.reset();
+        dense.reset();
+        assert_eq!(sparse.support(), 812);
+        assert_eq!(sparse.push(item(4, 0)), dense.push(item(4, 0)));
+    }
+
+    #[test]
+    fn equal_covers_share_their_fingerprint() {
+        let dataset = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let mut structure = RevBitset::new(&dataset);
+        let mut fingerprints = vec![];
+        for itemset in [[item(4, 1), item(7, 0)], [item(7, 0), item(4, 1)]] {
+            for it in itemset {
+                structure.push(it);
+            }
+            fingerprints.push(structure.cover_fingerprint());
+            structure.reset();
+        }
+        assert_eq!(fingerprints[0], fingerprints[1]);
+
+        structure.push(item(4, 0));
+        assert_ne!(structure.cover_fingerprint(), fingerprints[0]);
+        let mut tids = vec![];
+        structure.fill_tids(&mut tids);
+        let mut copied = RevBitset::new(&dataset);
+        copied.mask(&tids).unwrap();
+        assert_eq!(copied.cover_fingerprint(), structure.cover_fingerprint());
+    }
+
+    #[test]
+    fn samples_can_be_removed_and_added_back() {
+        let dataset = BinaryData::read("test_data/anneal.txt", false, 0.0);
+        let (targets, rows) = dataset.get_train();
+        let mut original = RevBitset::new(&dataset);
+        let mut structure = RevBitset::new(&dataset);
+
+        let removed = structure.remove_samples(&[70, 3, 200]).unwrap();
+        assert_eq!(removed.len(), 3);
+        assert_eq!(removed[0], (targets.as_ref().unwrap()[3], rows[3].clone()));
+        assert_eq!(structure.support(), original.support() - 3);
+        assert_eq!(structure.get_tids().len(), original.support() - 3);
+        let covered = removed.iter().filter(|(_, row)| row[4] == 1).count();
+        assert_eq!(
+            structure.push(item(4, 1)),
+            original.push(item(4, 1)) - covered
+        );
+        original.backtrack();
+        // The next transactions are renumbered in order
+        let kept = (0..original.support())
+            .filter(|tid| ![3, 70, 200].contains(tid))
+            .collect::<Vec<usize>>();
+        let labels = structure.inputs.labels();
+        for (tid, kept) in kept.iter().enumerate() {
+            assert_eq!(structure.inputs.row(tid), rows[*kept]);
+            assert_eq!(labels[tid], targets.as_ref().unwrap()[*kept]);
+        }
+
+        structure.add_samples(&removed).unwrap();
+        let labels = structure.inputs.labels();
+        for (tid, (class, row)) in kept
+            .iter()
+            .map(|tid| (targets.as_ref().unwrap()[*tid], &rows[*tid]))
+            .chain(removed.iter().map(|(class, row)| (*class, row)))
+            .enumerate()
+        {
+            assert_eq!(&structure.inputs.row(tid), row);
+            assert_eq!(labels[tid], class);
+        }
+        assert_eq!(structure.support(), original.support());
+        assert_eq!(structure.labels_support(), original.labels_support());
+        for attribute in 0..original.num_attributes() {
+            assert_eq!(
+                structure.temp_push(item(attribute, 1)),
+                original.temp_push(item(attribute, 1))
+            );
+        }
+
+        assert!(structure.remove_samples(&[original.support()]).is_err());
+        assert!(structure.add_samples(&[(0, vec![1])]).is_err());
+        assert!(structure.add_samples(&[(5, rows[0].clone())]).is_err());
+    }
 }
@@ -0,0 +1,281 @@
+// Batched AND + popcount of the words of a state against many bitsets, the hot loop of the label
+// counts. The kernel is selected at runtime: AVX2 on x86_64, where the bytes are counted by a
+// lookup of their nibbles, NEON on aarch64 with its byte popcount, the scalar loop otherwise.
+
+// Count of the bits of the state set in each bitset, the state and the bitsets having the same
+// words
+pub(crate) fn count_intersect_with_many<B: AsRef<[u64]>>(
+    state: &[u64],
+    bitsets: &[B],
+    counts: &mut [usize],
+) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // Safety: the features required by the function were detected
+            unsafe { avx2::count_intersect_with_many(state, bitsets, counts) };
+            return;
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            // Safety: the features required by the function were detected
+            unsafe { neon::count_intersect_with_many(state, bitsets, counts) };
+            return;
+        }
+    }
+    for (count, bitset) in counts.iter_mut().zip(bitsets.iter()) {
+        *count = state
+            .iter()
+            .zip(bitset.as_ref().iter())
+            .map(|(a, b)| (a & b).count_ones() as usize)
+            .sum();
+    }
+}
+
+pub(crate) fn count_intersection(first: &[u64], second: &[u64]) -> usize {
+    let mut count = [0];
+    count_intersect_with_many(first, &[second], &mut count);
+    count[0]
+}
+
+// Same count for the words of a sparse set, word i of the state being word positions[i] of the
+// bitsets
+pub(crate) fn count_intersect_at_with_many<B: AsRef<[u64]>>(
+    words: &[u64],
+    positions: &[usize],
+    bitsets: &[B],
+    counts: &mut [usize],
+) {
+    let len = words.len().min(positions.len());
+    let (words, positions) = (&words[..len], &positions[..len]);
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // Safety: the features required by the function were detected
+            unsafe { avx2::count_intersect_at_with_many(words, positions, bitsets, counts) };
+            return;
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            // Safety: the features required by the function were detected
+            unsafe { neon::count_intersect_at_with_many(words, positions, bitsets, counts) };
+            return;
+        }
+    }
+    for (count, bitset) in counts.iter_mut().zip(bitsets.iter()) {
+        let bitset = bitset.as_ref();
+        *count = words
+            .iter()
+            .zip(positions.iter())
+            .map(|(word, position)| (word & bitset[*position]).count_ones() as usize)
+            .sum();
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use std::arch::x86_64::*;
+
+    // Bits set in each of the four words, the bytes being counted by a lookup of their two nibbles
+    // then summed by word
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn popcount_words(words: __m256i) -> __m256i {
+        let lookup = _mm256_setr_epi8(
+            0, 1, 1, 2, 1, 2, 2, 3, 1, 2, 2, 3, 2, 3, 3, 4, 0, 1, 1, 2, 1, 2, 2, 3, 1, 2, 2, 3, 2,
+            3, 3, 4,
+        );
+        let nibbles = _mm256_set1_epi8(0x0f);
+        let low = _mm256_and_si256(words, nibbles);
+        let high = _mm256_and_si256(_mm256_srli_epi16::<4>(words), nibbles);
+        let bytes = _mm256_add_epi8(
+            _mm256_shuffle_epi8(lookup, low),
+            _mm256_shuffle_epi8(lookup, high),
+        );
+        _mm256_sad_epu8(bytes, _mm256_setzero_si256())
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn sum_words(words: __m256i) -> usize {
+        let mut sums = [0u64; 4];
+        _mm256_storeu_si256(sums.as_mut_ptr() as *mut __m256i, words);
+        sums.iter().sum::<u64>() as usize
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn count_intersect_with_many<B: AsRef<[u64]>>(
+        state: &[u64],
+        bitsets: &[B],
+        counts: &mut [usize],
+    ) {
+        for (count, bitset) in counts.iter_mut().zip(bitsets.iter()) {
+            let bitset = bitset.as_ref();
+            let len = state.len().min(bitset.len());
+            let mut total = _mm256_setzero_si256();
+            let mut i = 0;
+            while i + 4 <= len {
+                let first = _mm256_loadu_si256(state.as_ptr().add(i) as *const __m256i);
+                let second = _mm256_loadu_si256(bitset.as_ptr().add(i) as *const __m256i);
+                let word_counts = popcount_words(_mm256_and_si256(first, second));
+                total = _mm256_add_epi64(total, word_counts);
+                i += 4;
+            }
+            *count = sum_words(total)
+                + (i..len)
+                    .map(|j| (state[j] & bitset[j]).count_ones() as usize)
+                    .sum::<usize>();
+        }
+    }
+
+    // The words of the bitsets are loaded one by one, the gather instructions being slower on most
+    // processors
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn count_intersect_at_with_many<B: AsRef<[u64]>>(
+        words: &[u64],
+        positions: &[usize],
+        bitsets: &[B],
+        counts: &mut [usize],
+    ) {
+        let len = words.len();
+        for (count, bitset) in counts.iter_mut().zip(bitsets.iter()) {
+            let bitset = bitset.as_ref();
+            let mut total = _mm256_setzero_si256();
+            let mut i = 0;
+            while i + 4 <= len {
+                let first = _mm256_loadu_si256(words.as_ptr().add(i) as *const __m256i);
+                let second = _mm256_set_epi64x(
+                    bitset[positions[i + 3]] as i64,
+                    bitset[positions[i + 2]] as i64,
+                    bitset[positions[i + 1]] as i64,
+                    bitset[positions[i]] as i64,
+                );
+                let word_counts = popcount_words(_mm256_and_si256(first, second));
+                total = _mm256_add_epi64(total, word_counts);
+                i += 4;
+            }
+            *count = sum_words(total)
+                + (i..len)
+                    .map(|j| (words[j] & bitset[positions[j]]).count_ones() as usize)
+                    .sum::<usize>();
+        }
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod neon {
+    use std::arch::aarch64::*;
+
+    #[inline]
+    #[target_feature(enable = "neon")]
+    unsafe fn popcount_and(first: uint64x2_t, second: uint64x2_t) -> usize {
+        vaddlvq_u8(vcntq_u8(vreinterpretq_u8_u64(vandq_u64(first, second)))) as usize
+    }
+
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn count_intersect_with_many<B: AsRef<[u64]>>(
+        state: &[u64],
+        bitsets: &[B],
+        counts: &mut [usize],
+    ) {
+        for (count, bitset) in counts.iter_mut().zip(bitsets.iter()) {
+            let bitset = bitset.as_ref();
+            let len = state.len().min(bitset.len());
+            let mut total = 0;
+            let mut i = 0;
+            while i + 2 <= len {
+                let first = vld1q_u64(state.as_ptr().add(i));
+                let second = vld1q_u64(bitset.as_ptr().add(i));
+                total += popcount_and(first, second);
+                i += 2;
+            }
+            *count = total
+                + (i..len)
+                    .map(|j| (state[j] & bitset[j]).count_ones() as usize)
+                    .sum::<usize>();
+        }
+    }
+
+    // NEON has no gather, the two words of the bitsets are loaded one by one
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn count_intersect_at_with_many<B: AsRef<[u64]>>(
+        words: &[u64],
+        positions: &[usize],
+        bitsets: &[B],
+        counts: &mut [usize],
+    ) {
+        let len = words.len();
+        for (count, bitset) in counts.iter_mut().zip(bitsets.iter()) {
+            let bitset = bitset.as_ref();
+            let mut total = 0;
+            let mut i = 0;
+            while i + 2 <= len {
+                let first = vld1q_u64(words.as_ptr().add(i));
+                let second = vcombine_u64(
+                    vcreate_u64(bitset[positions[i]]),
+                    vcreate_u64(bitset[positions[i + 1]]),
+                );
+                total += popcount_and(first, second);
+                i += 2;
+            }
+            *count = total
+                + (i..len)
+                    .map(|j| (words[j] & bitset[positions[j]]).count_ones() as usize)
+                    .sum::<usize>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod simd_test {
+    use crate::structures::simd::{
+        count_intersect_at_with_many, count_intersect_with_many, count_intersection,
+    };
+
+    fn words(len: u64, seed: u64) -> Vec<u64> {
+        (0..len)
+            .map(|i| (i + seed).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+            .collect()
+    }
+
+    #[test]
+    fn counts_match_the_scalar_version() {
+        let state = words(37, 0);
+        let bitsets = (1..5u64).map(|k| words(37, k)).collect::<Vec<Vec<u64>>>();
+
+        let mut counts = vec![0; bitsets.len()];
+        count_intersect_with_many(&state, &bitsets, &mut counts);
+        for (count, bitset) in counts.iter().zip(bitsets.iter()) {
+            let expected = state
+                .iter()
+                .zip(bitset.iter())
+                .map(|(a, b)| (a & b).count_ones() as usize)
+                .sum::<usize>();
+            assert_eq!(*count, expected);
+            assert_eq!(count_intersection(&state, bitset), expected);
+        }
+    }
+
+    #[test]
+    fn indexed_counts_match_the_scalar_version() {
+        let bitsets = (1..4u64).map(|k| words(40, k)).collect::<Vec<Vec<u64>>>();
+        // Every third word of the bitsets, backwards, as in the sparse set of a reversible bitset
+        let positions = (0..40).rev().step_by(3).collect::<Vec<usize>>();
+        let state = words(positions.len() as u64, 7);
+
+        let mut counts = vec![0; bitsets.len()];
+        count_intersect_at_with_many(&state, &positions, &bitsets, &mut counts);
+        for (count, bitset) in counts.iter().zip(bitsets.iter()) {
+            let expected = state
+                .iter()
+                .zip(positions.iter())
+                .map(|(word, position)| (word & bitset[*position]).count_ones() as usize)
+                .sum::<usize>();
+            assert_eq!(*count, expected);
+        }
+    }
+}
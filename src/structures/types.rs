@@ -1,3 +1,4 @@
+#[derive(Default)]
 pub struct BitsetStructData {
     pub(crate) inputs: Vec<Vec<u64>>,
     pub(crate) targets: Vec<Vec<u64>>,
@@ -5,6 +6,183 @@ pub struct BitsetStructData {
     pub(crate) size: usize,
 }
 
+impl BitsetStructData {
+    // Label of each transaction, transactions are stored in reverse order in the bitsets
+    pub(crate) fn labels(&self) -> Vec<usize> {
+        let mut labels = vec![0; self.size];
+        for (label, bitset) in self.targets.iter().enumerate() {
+            for (tid, tid_label) in labels.iter_mut().enumerate() {
+                let position = self.size - 1 - tid;
+                if (bitset[self.chunks - 1 - position / 64] >> (position % 64)) & 1 == 1 {
+                    *tid_label = label;
+                }
+            }
+        }
+        labels
+    }
+
+    // Chunk and bit of a transaction
+    pub(crate) fn bit(&self, tid: usize) -> (usize, usize) {
+        let position = self.size - 1 - tid;
+        (self.chunks - 1 - position / 64, position % 64)
+    }
+
+    // Transaction of a bit, the inverse of bit
+    pub(crate) fn tid(&self, chunk: usize, bit: usize) -> usize {
+        self.size - ((self.chunks - 1 - chunk) * 64 + bit) - 1
+    }
+
+    // Binary row of a transaction
+    pub(crate) fn row(&self, tid: usize) -> Vec<usize> {
+        let (chunk, bit) = self.bit(tid);
+        self.inputs
+            .iter()
+            .map(|bitset| ((bitset[chunk] >> bit) & 1) as usize)
+            .collect()
+    }
+
+    // Dataset made of the given transactions, repeats allowed, restricted to the given attributes
+    // renumbered in order
+    pub(crate) fn project(&self, tids: &[usize], attributes: &[usize]) -> BitsetStructData {
+        let labels = self.labels();
+        let samples = tids
+            .iter()
+            .map(|tid| {
+                let row = self.row(*tid);
+                let row = attributes.iter().map(|attribute| row[*attribute]).collect();
+                (labels[*tid], row)
+            })
+            .collect::<Vec<(usize, Vec<usize>)>>();
+        let empty = BitsetStructData {
+            inputs: vec![vec![]; attributes.len()],
+            targets: vec![vec![]; self.targets.len()],
+            chunks: 0,
+            size: 0,
+        };
+        empty.with_samples(&[], &samples)
+    }
+
+    // Appends transactions, given as class and binary row, after the current ones. These move up
+    // by as many positions as there are added transactions, the bitsets being shifted word by word,
+    // and the added ones take the lowest positions
+    pub(crate) fn append(&mut self, added: &[(usize, Vec<usize>)]) {
+        let size = self.size + added.len();
+        let chunks = size.div_ceil(64).max(1);
+        for bitset in self.inputs.iter_mut().chain(self.targets.iter_mut()) {
+            shift_up(bitset, chunks, added.len());
+        }
+        (self.size, self.chunks) = (size, chunks);
+        for (offset, (class, row)) in added.iter().enumerate() {
+            let (chunk, bit) = self.bit(size - added.len() + offset);
+            for (attribute, value) in row.iter().enumerate() {
+                if *value == 1 {
+                    self.inputs[attribute][chunk] |= 1u64 << bit;
+                }
+            }
+            if let Some(bitset) = self.targets.get_mut(*class) {
+                bitset[chunk] |= 1u64 << bit;
+            }
+        }
+    }
+
+    // Removes the given transactions, sorted and without repeats, the next ones being renumbered
+    // in order. Only the positions above the lowest removed one move down, the words below it are
+    // left as they are
+    pub(crate) fn remove(&mut self, tids: &[usize]) {
+        let size = self.size - tids.len();
+        let chunks = size.div_ceil(64).max(1);
+        // Positions of the removed transactions, the last transaction being at position 0
+        let removed = tids
+            .iter()
+            .rev()
+            .map(|tid| self.size - 1 - tid)
+            .collect::<Vec<usize>>();
+        for bitset in self.inputs.iter_mut().chain(self.targets.iter_mut()) {
+            remove_positions(bitset, &removed, self.size);
+            bitset.drain(..self.chunks - chunks);
+        }
+        (self.size, self.chunks) = (size, chunks);
+    }
+
+    // Dataset made of the kept transactions, in their order, followed by the added ones, given as
+    // class and binary row
+    pub(crate) fn with_samples(
+        &self,
+        kept: &[usize],
+        added: &[(usize, Vec<usize>)],
+    ) -> BitsetStructData {
+        let size = kept.len() + added.len();
+        let chunks = size.div_ceil(64).max(1);
+        let mut data = BitsetStructData {
+            inputs: vec![vec![0u64; chunks]; self.inputs.len()],
+            targets: vec![vec![0u64; chunks]; self.targets.len()],
+            chunks,
+            size,
+        };
+        let labels = self.labels();
+        let samples = kept
+            .iter()
+            .map(|tid| (labels[*tid], self.row(*tid)))
+            .chain(added.iter().cloned());
+        for (tid, (class, row)) in samples.enumerate() {
+            let (chunk, bit) = data.bit(tid);
+            for (attribute, value) in row.iter().enumerate() {
+                if *value == 1 {
+                    data.inputs[attribute][chunk] |= 1u64 << bit;
+                }
+            }
+            if let Some(bitset) = data.targets.get_mut(class) {
+                bitset[chunk] |= 1u64 << bit;
+            }
+        }
+        data
+    }
+}
+
+// Bit of a position in a bitset whose first word holds the highest positions
+fn position_bit(bitset: &[u64], position: usize) -> (usize, usize) {
+    (bitset.len() - 1 - position / 64, position % 64)
+}
+
+// Moves the positions of a bitset up by shift, grown to the given number of words, the lowest
+// positions being cleared. Each word is made of two words of the unshifted bitset
+fn shift_up(bitset: &mut Vec<u64>, chunks: usize, shift: usize) {
+    bitset.splice(0..0, std::iter::repeat_n(0, chunks - bitset.len()));
+    let (words, bits) = (shift / 64, shift % 64);
+    for cursor in 0..chunks {
+        let word = |offset: usize| bitset.get(cursor + words + offset).copied().unwrap_or(0);
+        bitset[cursor] = match bits {
+            0 => word(0),
+            _ => (word(0) << bits) | (word(1) >> (64 - bits)),
+        };
+    }
+}
+
+// Removes the given positions, sorted, from the first size positions of a bitset. The next
+// positions move down and the freed highest ones are cleared
+fn remove_positions(bitset: &mut [u64], removed: &[usize], size: usize) {
+    let first = match removed.first() {
+        Some(first) => *first,
+        None => return,
+    };
+    let mut removed = removed.iter().peekable();
+    let mut target = first;
+    for position in first..size {
+        if removed.next_if_eq(&&position).is_some() {
+            continue;
+        }
+        let (chunk, bit) = position_bit(bitset, position);
+        let value = (bitset[chunk] >> bit) & 1;
+        let (chunk, bit) = position_bit(bitset, target);
+        bitset[chunk] = (bitset[chunk] & !(1u64 << bit)) | (value << bit);
+        target += 1;
+    }
+    for position in target..size {
+        let (chunk, bit) = position_bit(bitset, position);
+        bitset[chunk] &= !(1u64 << bit);
+    }
+}
+
 pub struct DoublePointerData {
     pub(crate) inputs: Vec<Vec<usize>>,
     pub(crate) target: Option<Vec<usize>>,
@@ -0,0 +1,171 @@
+use crate::tree::Tree;
+use serde::Serialize;
+
+// What a tree holds at a node: the tested feature of a split or the output of a leaf
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeShape {
+    Split(Option<usize>),
+    Leaf(Option<f64>),
+}
+
+// Node where two trees differ. The path gives the branches taken from the root, 0 or 1 as for the
+// tested features, and the shapes are None for an empty tree
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TreeDifference {
+    pub path: Vec<usize>,
+    pub first: Option<NodeShape>,
+    pub second: Option<NodeShape>,
+}
+
+impl Tree {
+    // Same splits and same leaf outputs, whatever the node indices or the errors
+    pub fn structural_eq(&self, other: &Tree) -> bool {
+        self.diff(other).is_empty()
+    }
+
+    // Nodes, in depth-first order, where the trees test different features or where one has a leaf
+    // the other does not predict. The subtrees below a differing node are not compared
+    pub fn diff(&self, other: &Tree) -> Vec<TreeDifference> {
+        let mut differences = vec![];
+        self.diff_recursion(
+            other,
+            Some(self.get_root_index()),
+            Some(other.get_root_index()),
+            &mut vec![],
+            &mut differences,
+        );
+        differences
+    }
+
+    fn diff_recursion(
+        &self,
+        other: &Tree,
+        first: Option<usize>,
+        second: Option<usize>,
+        path: &mut Vec<usize>,
+        differences: &mut Vec<TreeDifference>,
+    ) {
+        let (first_shape, second_shape) = (
+            first.and_then(|index| self.shape(index)),
+            second.and_then(|index| other.shape(index)),
+        );
+        if first_shape != second_shape {
+            differences.push(TreeDifference {
+                path: path.clone(),
+                first: first_shape,
+                second: second_shape,
+            });
+            return;
+        }
+        if let (Some(NodeShape::Split(_)), Some(first), Some(second)) = (first_shape, first, second)
+        {
+            let (first, second) = (self.get_node(first), other.get_node(second));
+            if let (Some(first), Some(second)) = (first, second) {
+                let children = [(first.left, second.left), (first.right, second.right)];
+                for (branch, (first, second)) in children.into_iter().enumerate() {
+                    path.push(branch);
+                    self.diff_recursion(
+                        other,
+                        Some(first).filter(|child| *child != 0),
+                        Some(second).filter(|child| *child != 0),
+                        path,
+                        differences,
+                    );
+                    path.pop();
+                }
+            }
+        }
+    }
+
+    fn shape(&self, index: usize) -> Option<NodeShape> {
+        let node = self.get_node(index)?;
+        Some(match node.left == node.right {
+            true => NodeShape::Leaf(node.value.out),
+            false => NodeShape::Split(node.value.test),
+        })
+    }
+}
+
+#[cfg(test)]
+mod compare_test {
+    use crate::tree::compare::{NodeShape, TreeDifference};
+    use crate::tree::{NodeInfos, Tree, TreeNode};
+
+    fn node(test: Option<usize>, out: Option<f64>, error: f64) -> TreeNode {
+        TreeNode::new(NodeInfos {
+            test,
+            out,
+            error,
+            ..NodeInfos::default()
+        })
+    }
+
+    // Feature 0 at the root, then the given feature on its 0 branch
+    fn tree(feature: usize, error: f64) -> Tree {
+        let mut tree = Tree::new();
+        let root = tree.add_root(node(Some(0), None, error));
+        let left = tree.add_left_node(root, node(Some(feature), None, error));
+        tree.add_right_node(root, node(None, Some(1.0), error));
+        tree.add_left_node(left, node(None, Some(0.0), error));
+        tree.add_right_node(left, node(None, Some(1.0), error));
+        tree
+    }
+
+    #[test]
+    fn equal_structures() {
+        assert!(tree(1, 2.0).structural_eq(&tree(1, 5.0)));
+        assert!(Tree::new().structural_eq(&Tree::new()));
+
+        // The nodes are added in another order, their indices differ
+        let mut other = Tree::new();
+        let root = other.add_root(node(Some(0), None, 2.0));
+        other.add_right_node(root, node(None, Some(1.0), 2.0));
+        let left = other.add_left_node(root, node(Some(1), None, 2.0));
+        other.add_left_node(left, node(None, Some(0.0), 2.0));
+        other.add_right_node(left, node(None, Some(1.0), 2.0));
+        assert!(tree(1, 2.0).structural_eq(&other));
+    }
+
+    #[test]
+    fn differences() {
+        assert_eq!(
+            tree(1, 2.0).diff(&tree(3, 2.0)),
+            vec![TreeDifference {
+                path: vec![0],
+                first: Some(NodeShape::Split(Some(1))),
+                second: Some(NodeShape::Split(Some(3))),
+            }]
+        );
+
+        let mut other = tree(1, 2.0);
+        other.get_node_mut(4).unwrap().value.out = Some(0.0);
+        let mut leaf = Tree::new();
+        leaf.add_root(node(None, Some(0.0), 6.0));
+        assert_eq!(
+            tree(1, 2.0)
+                .diff(&other)
+                .into_iter()
+                .chain(tree(1, 2.0).diff(&leaf))
+                .chain(leaf.diff(&Tree::new()))
+                .collect::<Vec<TreeDifference>>(),
+            vec![
+                TreeDifference {
+                    path: vec![0, 1],
+                    first: Some(NodeShape::Leaf(Some(1.0))),
+                    second: Some(NodeShape::Leaf(Some(0.0))),
+                },
+                TreeDifference {
+                    path: vec![],
+                    first: Some(NodeShape::Split(Some(0))),
+                    second: Some(NodeShape::Leaf(Some(0.0))),
+                },
+                TreeDifference {
+                    path: vec![],
+                    first: Some(NodeShape::Leaf(Some(0.0))),
+                    second: None,
+                },
+            ]
+        );
+    }
+}
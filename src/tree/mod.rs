@@ -1,12 +1,36 @@
+use crate::globals::deserialize_infinity;
+use crate::searches::StopReason;
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+mod compare;
+mod pruning;
+mod surgery;
+
+pub use compare::{NodeShape, TreeDifference};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct NodeInfos {
     // Specific data for decision trees
     pub(crate) test: Option<usize>,
+    #[serde(deserialize_with = "deserialize_infinity")]
     pub(crate) error: f64,
     pub(crate) metric: Option<f64>,
     pub(crate) out: Option<f64>,
+    // Why the node was not split. Only filled for leaves when explanations are requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) reason: Option<StopReason>,
+    // Number of samples of each class reaching the node
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) classes_support: Option<Vec<usize>>,
+    // Smoothed class probabilities, only for leaves once set_leaf_probabilities is called
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) probabilities: Option<Vec<f64>>,
+    // Sorted indices of the training samples reaching the node, only once fill_samples is called
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) samples: Option<Vec<usize>>,
 }
 
 impl Default for NodeInfos {
@@ -22,11 +46,15 @@ impl NodeInfos {
             error: <f64>::INFINITY,
             metric: None,
             out: None,
+            reason: None,
+            classes_support: None,
+            probabilities: None,
+            samples: None,
         }
     }
 }
 
-#[derive(Copy, Clone, Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct TreeNode {
     pub value: NodeInfos,
     pub(crate) index: usize,
@@ -43,6 +71,52 @@ impl TreeNode {
             right: 0,
         }
     }
+
+    pub fn is_leaf(&self) -> bool {
+        self.left == self.right
+    }
+
+    // Indices of the left and right children, None for a leaf
+    pub fn children(&self) -> Option<(usize, usize)> {
+        match self.is_leaf() {
+            true => None,
+            false => Some((self.left, self.right)),
+        }
+    }
+}
+
+impl NodeInfos {
+    pub fn test(&self) -> Option<usize> {
+        self.test
+    }
+
+    pub fn error(&self) -> f64 {
+        self.error
+    }
+
+    pub fn out(&self) -> Option<f64> {
+        self.out
+    }
+
+    pub fn classes_support(&self) -> Option<&[usize]> {
+        self.classes_support.as_deref()
+    }
+
+    // Number of training samples reaching the node, when its classes support is known
+    pub fn support(&self) -> Option<usize> {
+        self.classes_support
+            .as_ref()
+            .map(|supports| supports.iter().sum())
+    }
+}
+
+// Branch followed by a sample whose tested feature is missing. With `Both` the sample goes down the
+// two branches, weighted by the number of training samples of each child
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ValueEnum)]
+pub enum MissingBranch {
+    Left,
+    Right,
+    Both,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -79,6 +153,95 @@ impl Tree {
         self.count_node_recursion(self.get_root_index())
     }
 
+    // Number of edges on the longest path from the root to a leaf, 0 for a single leaf
+    pub fn depth(&self) -> usize {
+        match self.is_empty() {
+            true => 0,
+            false => self.depth_recursion(self.get_root_index()),
+        }
+    }
+
+    fn depth_recursion(&self, index: usize) -> usize {
+        match self.get_node(index) {
+            Some(node) if node.left != node.right => [node.left, node.right]
+                .iter()
+                .filter(|child| **child != 0)
+                .map(|child| self.depth_recursion(*child) + 1)
+                .max()
+                .unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    pub fn num_leaves(&self) -> usize {
+        match self.is_empty() {
+            true => 0,
+            false => self.num_leaves_recursion(self.get_root_index()),
+        }
+    }
+
+    fn num_leaves_recursion(&self, index: usize) -> usize {
+        match self.get_node(index) {
+            Some(node) if node.left != node.right => [node.left, node.right]
+                .iter()
+                .filter(|child| **child != 0)
+                .map(|child| self.num_leaves_recursion(*child))
+                .sum(),
+            _ => 1,
+        }
+    }
+
+    // Number of internal nodes testing each feature
+    pub fn feature_counts(&self) -> BTreeMap<usize, usize> {
+        let mut counts = BTreeMap::new();
+        if !self.is_empty() {
+            self.feature_counts_recursion(self.get_root_index(), &mut counts);
+        }
+        counts
+    }
+
+    fn feature_counts_recursion(&self, index: usize, counts: &mut BTreeMap<usize, usize>) {
+        if let Some(node) = self.get_node(index) {
+            if node.left == node.right {
+                return;
+            }
+            if let Some(test) = node.value.test {
+                *counts.entry(test).or_insert(0) += 1;
+            }
+            for child in [node.left, node.right] {
+                if child != 0 {
+                    self.feature_counts_recursion(child, counts);
+                }
+            }
+        }
+    }
+
+    // Number of leaves stopped for each reason, named as in the JSON tree. Leaves only have a
+    // reason when explanations are requested, the map is otherwise empty
+    pub fn leaf_reasons(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        if !self.is_empty() {
+            self.leaf_reasons_recursion(self.get_root_index(), &mut counts);
+        }
+        counts
+    }
+
+    fn leaf_reasons_recursion(&self, index: usize, counts: &mut BTreeMap<String, usize>) {
+        if let Some(node) = self.get_node(index) {
+            if node.left == node.right {
+                if let Some(reason) = node.value.reason {
+                    *counts.entry(format!("{:?}", reason)).or_insert(0) += 1;
+                }
+                return;
+            }
+            for child in [node.left, node.right] {
+                if child != 0 {
+                    self.leaf_reasons_recursion(child, counts);
+                }
+            }
+        }
+    }
+
     // ! Is it still relevant
     fn count_node_recursion(&self, node_index: usize) -> usize {
         let mut left_index = 0;
@@ -136,6 +299,97 @@ impl Tree {
         0
     }
 
+    // Indices of the nodes reachable from the root, each node before its left then right subtree.
+    // A node reached again, through a cycle of a tree read from JSON, is skipped
+    pub fn pre_order(&self) -> Vec<usize> {
+        let mut order = vec![];
+        let mut reached = vec![false; self.len()];
+        let mut stack = match self.is_empty() {
+            true => vec![],
+            false => vec![self.get_root_index()],
+        };
+        while let Some(index) = stack.pop() {
+            if let Some(node) = self.get_node(index) {
+                if std::mem::replace(&mut reached[index], true) {
+                    continue;
+                }
+                order.push(index);
+                if let Some((left, right)) = node.children() {
+                    stack.extend([right, left].into_iter().filter(|child| *child != 0));
+                }
+            }
+        }
+        order
+    }
+
+    // Checks that the tests of the reachable nodes are attributes of data with this many attributes
+    pub fn check_attributes(&self, num_attributes: usize) -> Result<(), String> {
+        let unknown = self
+            .pre_order()
+            .into_iter()
+            .filter_map(|index| self.tree[index].value.test)
+            .find(|attribute| *attribute >= num_attributes);
+        match unknown {
+            Some(attribute) => Err(format!(
+                "The tree uses the unknown attribute {}, the data has {} attributes",
+                attribute, num_attributes
+            )),
+            None => Ok(()),
+        }
+    }
+
+    // Checks that the children of the reachable nodes are nodes of the tree, each node being
+    // reached once from the root. Trees read from JSON may not hold it
+    pub fn check_children(&self) -> Result<(), String> {
+        let mut reached = vec![false; self.len()];
+        let mut stack = match self.is_empty() {
+            true => vec![],
+            false => vec![self.get_root_index()],
+        };
+        while let Some(index) = stack.pop() {
+            if std::mem::replace(&mut reached[index], true) {
+                return Err(format!("Node {} is reached twice from the root", index));
+            }
+            let children = self.tree[index].children().into_iter();
+            for child in children.flat_map(|(left, right)| [left, right]) {
+                match child {
+                    0 => {}
+                    child if child < self.len() => stack.push(child),
+                    child => {
+                        return Err(format!("Node {} has the unknown child {}", index, child));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Rebuilds the vector with only the nodes reachable from the root, numbered in pre-order, and
+    // returns the number of dropped nodes. Pruning and edits leave the replaced nodes in the
+    // vector, which would otherwise be serialized. An error is returned when the children are
+    // invalid, see check_children
+    pub fn compact(&mut self) -> Result<usize, String> {
+        self.check_children()?;
+        let order = self.pre_order();
+        let mut indices = vec![0; self.len()];
+        for (new, old) in order.iter().enumerate() {
+            indices[*old] = new;
+        }
+        let mut nodes = Vec::with_capacity(order.len());
+        for old in order {
+            let mut node = self.tree[old].clone();
+            node.index = indices[old];
+            if let Some((left, right)) = node.children() {
+                node.left = indices[left];
+                node.right = indices[right];
+            }
+            nodes.push(node);
+        }
+        let dropped = self.len() - nodes.len();
+        self.tree = nodes;
+        Ok(dropped)
+    }
+
     pub fn get_node(&self, index: usize) -> Option<&TreeNode> {
         self.tree.get(index)
     }
@@ -174,6 +428,219 @@ impl Tree {
         }
     }
 
+    /// Leaves reached by an instance with their weights. Features are 1 when the value is 1, NaN
+    /// values are missing and routed according to `branch`
+    pub fn weighted_leaves(&self, instance: &[f64], branch: MissingBranch) -> Vec<(usize, f64)> {
+        let mut leaves = vec![];
+        if !self.is_empty() {
+            self.weighted_leaves_recursion(
+                self.get_root_index(),
+                instance,
+                branch,
+                1.0,
+                &mut leaves,
+            );
+        }
+        leaves
+    }
+
+    /// Output of the instance, the weighted vote of the reached leaves
+    pub fn predict(&self, instance: &[f64], branch: MissingBranch) -> Option<f64> {
+        let mut votes: Vec<(f64, f64)> = vec![];
+        for (index, weight) in self.weighted_leaves(instance, branch) {
+            let out = self.get_node(index).and_then(|node| node.value.out)?;
+            match votes.iter_mut().find(|(vote, _)| *vote == out) {
+                Some((_, total)) => *total += weight,
+                None => votes.push((out, weight)),
+            }
+        }
+        votes
+            .into_iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(out, _)| out)
+    }
+
+    /// Leaf reached by an instance, with the (feature, branch) tests taken from the root. Missing
+    /// values follow `branch`, and with `MissingBranch::Both` the child holding the most training
+    /// samples, the left one on ties
+    pub fn decision_path(
+        &self,
+        instance: &[f64],
+        branch: MissingBranch,
+    ) -> (usize, Vec<(usize, usize)>) {
+        let mut tests = vec![];
+        if self.is_empty() {
+            return (0, tests);
+        }
+        let mut index = self.get_root_index();
+        while let Some(node) = self.get_node(index).filter(|node| node.left != node.right) {
+            let test = match node.value.test {
+                Some(test) => test,
+                None => break,
+            };
+            let value = instance.get(test).copied().unwrap_or(<f64>::NAN);
+            let taken = match (value.is_nan(), branch) {
+                (false, _) => (value == 1.0) as usize,
+                (true, MissingBranch::Left) => 0,
+                (true, MissingBranch::Right) => 1,
+                (true, MissingBranch::Both) => {
+                    let support = |child: usize| {
+                        self.get_node(child)
+                            .and_then(|child| child.value.classes_support.as_ref())
+                            .map_or(0, |supports| supports.iter().sum::<usize>())
+                    };
+                    (support(node.right) > support(node.left)) as usize
+                }
+            };
+            tests.push((test, taken));
+            index = [node.left, node.right][taken];
+        }
+        (index, tests)
+    }
+
+    /// Fewest features to flip for the instance to be predicted another output, sorted, with the
+    /// leaf reached once they are flipped. Each leaf with another output than the prediction costs
+    /// the tests of its path the instance fails, a missing value failing all the tests of its
+    /// feature. Ties go to the leftmost leaf. None when all the leaves predict the same output
+    pub fn counterfactual(
+        &self,
+        instance: &[f64],
+        branch: MissingBranch,
+    ) -> Option<(usize, Vec<usize>)> {
+        let prediction = self.predict(instance, branch)?;
+        let mut best = None;
+        self.counterfactual_recursion(
+            self.get_root_index(),
+            instance,
+            prediction,
+            &mut vec![],
+            &mut best,
+        );
+        best
+    }
+
+    fn counterfactual_recursion(
+        &self,
+        index: usize,
+        instance: &[f64],
+        prediction: f64,
+        flips: &mut Vec<usize>,
+        best: &mut Option<(usize, Vec<usize>)>,
+    ) {
+        if best
+            .as_ref()
+            .is_some_and(|(_, best_flips)| best_flips.len() <= flips.len())
+        {
+            return;
+        }
+        let node = match self.get_node(index) {
+            Some(node) => node,
+            None => return,
+        };
+        let test = match node.value.test {
+            Some(test) if node.left != node.right => test,
+            _ => {
+                if node.value.out.is_some_and(|out| out != prediction) {
+                    let mut features = flips.clone();
+                    features.sort_unstable();
+                    *best = Some((index, features));
+                }
+                return;
+            }
+        };
+        let value = instance.get(test).copied().unwrap_or(<f64>::NAN);
+        for (taken, child) in [node.left, node.right].into_iter().enumerate() {
+            let flipped = value.is_nan() || (value == 1.0) as usize != taken;
+            if flipped {
+                flips.push(test);
+            }
+            self.counterfactual_recursion(child, instance, prediction, flips, best);
+            if flipped {
+                flips.pop();
+            }
+        }
+    }
+
+    /// Sets the class probabilities of the leaves from their classes support, with Laplace
+    /// smoothing: a leaf with n_c samples of class c out of n gives (n_c + smoothing) / (n + k *
+    /// smoothing) for k classes. Leaves without classes support are left as they are
+    pub fn set_leaf_probabilities(&mut self, smoothing: f64) {
+        for node in self.tree.iter_mut() {
+            node.value.probabilities = match node.left == node.right {
+                true => node
+                    .value
+                    .classes_support
+                    .as_ref()
+                    .map(|support| laplace_probabilities(support, smoothing)),
+                false => None,
+            };
+        }
+    }
+
+    fn weighted_leaves_recursion(
+        &self,
+        index: usize,
+        instance: &[f64],
+        branch: MissingBranch,
+        weight: f64,
+        leaves: &mut Vec<(usize, f64)>,
+    ) {
+        let node = match self.get_node(index) {
+            Some(node) => node,
+            None => return,
+        };
+        if node.left == node.right {
+            leaves.push((index, weight));
+            return;
+        }
+
+        let value = node
+            .value
+            .test
+            .and_then(|test| instance.get(test).copied())
+            .unwrap_or(<f64>::NAN);
+        if !value.is_nan() {
+            let child = if value == 1.0 { node.right } else { node.left };
+            self.weighted_leaves_recursion(child, instance, branch, weight, leaves);
+            return;
+        }
+
+        match branch {
+            MissingBranch::Left => {
+                self.weighted_leaves_recursion(node.left, instance, branch, weight, leaves)
+            }
+            MissingBranch::Right => {
+                self.weighted_leaves_recursion(node.right, instance, branch, weight, leaves)
+            }
+            MissingBranch::Both => {
+                let support = |child: usize| {
+                    self.get_node(child)
+                        .and_then(|child| child.value.classes_support.as_ref())
+                        .map_or(0, |supports| supports.iter().sum::<usize>())
+                };
+                let (left, right) = (support(node.left), support(node.right));
+                let left_share = match left + right {
+                    0 => 0.5,
+                    total => left as f64 / total as f64,
+                };
+                self.weighted_leaves_recursion(
+                    node.left,
+                    instance,
+                    branch,
+                    weight * left_share,
+                    leaves,
+                );
+                self.weighted_leaves_recursion(
+                    node.right,
+                    instance,
+                    branch,
+                    weight * (1.0 - left_share),
+                    leaves,
+                );
+            }
+        }
+    }
+
     pub(crate) fn empty_tree(depth: usize) -> Tree {
         let mut tree = Tree::new();
         let value = NodeInfos::new();
@@ -190,11 +657,10 @@ impl Tree {
                 parent_node.right = 0;
             }
         } else {
-            let value = NodeInfos::new();
-            let node = TreeNode::new(value);
+            let node = TreeNode::new(NodeInfos::new());
             let left = tree.add_node(parent, true, node);
             Self::build_tree_recurse(tree, left, depth - 1);
-            let node = TreeNode::new(value);
+            let node = TreeNode::new(NodeInfos::new());
             let right = tree.add_node(parent, false, node);
             Self::build_tree_recurse(tree, right, depth - 1);
         }
@@ -219,11 +685,185 @@ impl Tree {
             }
         }
     }
+
+    // Graphviz representation of the tree. Features and classes are shown by index when no name is
+    // given for them. Left edges (feature is 0) are labelled 0 and right edges 1
+    pub fn to_dot(
+        &self,
+        feature_names: Option<&[String]>,
+        class_names: Option<&[String]>,
+    ) -> String {
+        let mut dot = String::from("digraph Tree {\ngraph [ranksep=0];\nnode [shape=record];\n");
+        if !self.is_empty() {
+            self.to_dot_recursion(self.get_root_index(), feature_names, class_names, &mut dot);
+        }
+        dot.push('}');
+        dot
+    }
+
+    fn to_dot_recursion(
+        &self,
+        index: usize,
+        feature_names: Option<&[String]>,
+        class_names: Option<&[String]>,
+        dot: &mut String,
+    ) {
+        let node = match self.get_node(index) {
+            Some(node) => node,
+            None => return,
+        };
+
+        if node.left == node.right {
+            let class = node
+                .value
+                .out
+                .map_or(String::from("None"), |out| name(class_names, out as usize));
+            let probabilities = node
+                .value
+                .probabilities
+                .as_ref()
+                .map_or(String::new(), |probabilities| {
+                    format!("|{{proba|{}}}", format_probabilities(probabilities))
+                });
+            dot.push_str(&format!(
+                "node_{} [label=\"{{{{class|{}}}|{{error|{}}}{}}}\"];\n",
+                index, class, node.value.error, probabilities
+            ));
+            return;
+        }
+
+        let feature = node
+            .value
+            .test
+            .map_or(String::from("None"), |test| name(feature_names, test));
+        dot.push_str(&format!(
+            "node_{} [label=\"{{{{feat|{}}}|{{error|{}}}}}\"];\n",
+            index, feature, node.value.error
+        ));
+        for (branch, child) in [node.left, node.right].iter().enumerate() {
+            if *child != 0 {
+                dot.push_str(&format!(
+                    "node_{} -> node_{} [label={}];\n",
+                    index, child, branch
+                ));
+                self.to_dot_recursion(*child, feature_names, class_names, dot);
+            }
+        }
+    }
+
+    // Indented text representation in the style of scikit-learn's export_text. Each line enters a
+    // branch and gives the support, error and class distribution of the node it leads to, the
+    // first line describes the root
+    pub fn to_text(
+        &self,
+        feature_names: Option<&[String]>,
+        class_names: Option<&[String]>,
+    ) -> String {
+        let mut text = String::new();
+        if let Some(root) = self.get_node(self.get_root_index()) {
+            text.push_str(&format!("root{}\n", node_summary(root)));
+            self.to_text_recursion(root.index, 0, feature_names, class_names, &mut text);
+        }
+        text
+    }
+
+    fn to_text_recursion(
+        &self,
+        index: usize,
+        depth: usize,
+        feature_names: Option<&[String]>,
+        class_names: Option<&[String]>,
+        text: &mut String,
+    ) {
+        let node = match self.get_node(index) {
+            Some(node) => node,
+            None => return,
+        };
+        let indent = "|   ".repeat(depth);
+
+        if node.left == node.right {
+            let class = node
+                .value
+                .out
+                .map_or(String::from("None"), |out| name(class_names, out as usize));
+            let probabilities = node
+                .value
+                .probabilities
+                .as_ref()
+                .map_or(String::new(), |probabilities| {
+                    format!("  (proba: {})", format_probabilities(probabilities))
+                });
+            text.push_str(&format!(
+                "{}|--- class: {}{}\n",
+                indent, class, probabilities
+            ));
+            return;
+        }
+
+        let feature = node
+            .value
+            .test
+            .map_or(String::from("None"), |test| name(feature_names, test));
+        for (branch, child) in [node.left, node.right].iter().enumerate() {
+            if let Some(child_node) = self.get_node(*child).filter(|_| *child != 0) {
+                text.push_str(&format!(
+                    "{}|--- {} == {}{}\n",
+                    indent,
+                    feature,
+                    branch,
+                    node_summary(child_node)
+                ));
+                self.to_text_recursion(*child, depth + 1, feature_names, class_names, text);
+            }
+        }
+    }
+}
+
+// Name at the given position, the position itself when no name is known
+fn name(names: Option<&[String]>, position: usize) -> String {
+    names
+        .and_then(|names| names.get(position).cloned())
+        .unwrap_or_else(|| position.to_string())
+}
+
+fn format_probabilities(probabilities: &[f64]) -> String {
+    probabilities
+        .iter()
+        .map(|probability| format!("{:.3}", probability))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+// Uniform when there is neither sample nor smoothing
+pub fn laplace_probabilities(classes_support: &[usize], smoothing: f64) -> Vec<f64> {
+    let total =
+        classes_support.iter().sum::<usize>() as f64 + smoothing * classes_support.len() as f64;
+    classes_support
+        .iter()
+        .map(|support| match total > 0.0 {
+            true => (*support as f64 + smoothing) / total,
+            false => 1.0 / classes_support.len() as f64,
+        })
+        .collect()
+}
+
+fn node_summary(node: &TreeNode) -> String {
+    match &node.value.classes_support {
+        Some(support) => format!(
+            "  (support: {}, error: {}, classes: {:?})",
+            support.iter().sum::<usize>(),
+            node.value.error,
+            support
+        ),
+        None => format!("  (error: {})", node.value.error),
+    }
 }
 
 #[cfg(test)]
 mod binary_tree_test {
-    use crate::tree::{NodeInfos, Tree, TreeNode};
+    use crate::searches::StopReason;
+    use crate::tree::{laplace_probabilities, MissingBranch, NodeInfos, Tree, TreeNode};
+    use std::collections::BTreeMap;
 
     #[test]
     fn create_node_data() {
@@ -291,6 +931,7 @@ mod binary_tree_test {
             error: 0.0,
             metric: None,
             out: None,
+            ..NodeInfos::default()
         };
         let left_node = TreeNode::new(node_infos);
         let _ = tree.add_left_node(root_index, left_node);
@@ -309,6 +950,7 @@ mod binary_tree_test {
             error: 0.0,
             metric: None,
             out: None,
+            ..NodeInfos::default()
         };
         let right_node = TreeNode::new(node_infos);
         let _ = tree.add_right_node(root_index, right_node);
@@ -325,6 +967,7 @@ mod binary_tree_test {
             error: 0.0,
             metric: None,
             out: None,
+            ..NodeInfos::default()
         };
         let root = TreeNode::new(node_infos);
         let _ = tree.add_root(root);
@@ -341,6 +984,7 @@ mod binary_tree_test {
             error: 0.0,
             metric: None,
             out: None,
+            ..NodeInfos::default()
         };
         let root = TreeNode::new(node_infos);
         let _ = tree.add_root(root);
@@ -358,6 +1002,7 @@ mod binary_tree_test {
             error: 0.0,
             metric: None,
             out: None,
+            ..NodeInfos::default()
         };
         let root = TreeNode::new(node_infos);
         let root_index = tree.add_root(root);
@@ -366,6 +1011,7 @@ mod binary_tree_test {
             error: 0.0,
             metric: None,
             out: None,
+            ..NodeInfos::default()
         };
         let left_node = TreeNode::new(node_infos);
         let _ = tree.add_left_node(root_index, left_node);
@@ -382,6 +1028,7 @@ mod binary_tree_test {
             error: 0.0,
             metric: None,
             out: None,
+            ..NodeInfos::default()
         };
         let root = TreeNode::new(node_infos);
         let root_index = tree.add_root(root);
@@ -390,6 +1037,7 @@ mod binary_tree_test {
             error: 0.0,
             metric: None,
             out: None,
+            ..NodeInfos::default()
         };
         let right_node = TreeNode::new(node_infos);
         let _ = tree.add_right_node(root_index, right_node);
@@ -397,4 +1045,360 @@ mod binary_tree_test {
         let right_node = tree.get_right_child(root).unwrap();
         assert_eq!(right_node.value.test, Some(22));
     }
+
+    #[test]
+    fn tree_complexity() {
+        let mut tree = Tree::new();
+        assert_eq!((tree.depth(), tree.num_leaves()), (0, 0));
+
+        let root = tree.add_root(TreeNode::new(NodeInfos {
+            test: Some(3),
+            ..NodeInfos::default()
+        }));
+        assert_eq!((tree.depth(), tree.num_leaves()), (0, 1));
+
+        let left = tree.add_left_node(
+            root,
+            TreeNode::new(NodeInfos {
+                test: Some(1),
+                ..NodeInfos::default()
+            }),
+        );
+        tree.add_right_node(root, TreeNode::new(NodeInfos::default()));
+        let left_left = tree.add_left_node(
+            left,
+            TreeNode::new(NodeInfos {
+                test: Some(3),
+                ..NodeInfos::default()
+            }),
+        );
+        tree.add_right_node(left, TreeNode::new(NodeInfos::default()));
+        tree.add_left_node(left_left, TreeNode::new(NodeInfos::default()));
+        tree.add_right_node(left_left, TreeNode::new(NodeInfos::default()));
+
+        assert_eq!(tree.depth(), 3);
+        assert_eq!(tree.num_leaves(), 4);
+        assert_eq!(tree.feature_counts(), BTreeMap::from([(1, 1), (3, 2)]));
+    }
+
+    #[test]
+    fn leaf_reasons_are_counted() {
+        let mut tree = Tree::new();
+        let root = tree.add_root(TreeNode::new(NodeInfos {
+            test: Some(0),
+            ..NodeInfos::default()
+        }));
+        let leaf = |reason| {
+            TreeNode::new(NodeInfos {
+                reason: Some(reason),
+                ..NodeInfos::default()
+            })
+        };
+        tree.add_left_node(root, leaf(StopReason::LeafRuleHolds));
+        tree.add_right_node(root, leaf(StopReason::PureNode));
+        assert_eq!(
+            tree.leaf_reasons(),
+            BTreeMap::from([
+                ("LeafRuleHolds".to_string(), 1),
+                ("PureNode".to_string(), 1)
+            ])
+        );
+        assert!(Tree::new().leaf_reasons().is_empty());
+    }
+
+    #[test]
+    fn tree_to_dot() {
+        let mut tree = Tree::new();
+        let root = tree.add_root(TreeNode::new(NodeInfos {
+            test: Some(1),
+            error: 3.0,
+            ..NodeInfos::default()
+        }));
+        for out in [0.0, 1.0] {
+            let leaf = TreeNode::new(NodeInfos {
+                error: 1.5,
+                out: Some(out),
+                ..NodeInfos::default()
+            });
+            if out == 0.0 {
+                tree.add_left_node(root, leaf);
+            } else {
+                tree.add_right_node(root, leaf);
+            }
+        }
+
+        let features = vec![String::from("a"), String::from("b")];
+        let classes = vec![String::from("no")];
+        let dot = tree.to_dot(Some(&features), Some(&classes));
+        assert!(dot.starts_with("digraph Tree {"));
+        assert!(dot.contains("node_0 [label=\"{{feat|b}|{error|3}}\"];"));
+        assert!(dot.contains("node_1 [label=\"{{class|no}|{error|1.5}}\"];"));
+        assert!(dot.contains("node_2 [label=\"{{class|1}|{error|1.5}}\"];"));
+        assert!(dot.contains("node_0 -> node_1 [label=0];"));
+        assert!(dot.contains("node_0 -> node_2 [label=1];"));
+        assert!(dot.ends_with('}'));
+    }
+
+    #[test]
+    fn tree_to_text() {
+        let mut tree = Tree::new();
+        let root = tree.add_root(TreeNode::new(NodeInfos {
+            test: Some(1),
+            error: 2.0,
+            classes_support: Some(vec![6, 4]),
+            ..NodeInfos::default()
+        }));
+        let left = tree.add_left_node(
+            root,
+            TreeNode::new(NodeInfos {
+                test: Some(0),
+                error: 1.0,
+                classes_support: Some(vec![5, 1]),
+                ..NodeInfos::default()
+            }),
+        );
+        for (support, out) in [(vec![5, 0], 0.0), (vec![0, 1], 1.0)] {
+            let leaf = TreeNode::new(NodeInfos {
+                error: 0.0,
+                out: Some(out),
+                classes_support: Some(support),
+                ..NodeInfos::default()
+            });
+            tree.add_node(left, out == 0.0, leaf);
+        }
+        tree.add_right_node(
+            root,
+            TreeNode::new(NodeInfos {
+                error: 1.0,
+                out: Some(1.0),
+                ..NodeInfos::default()
+            }),
+        );
+
+        let features = vec![String::from("a"), String::from("b")];
+        let classes = vec![String::from("no"), String::from("yes")];
+        let text = tree.to_text(Some(&features), Some(&classes));
+        let expected = "root  (support: 10, error: 2, classes: [6, 4])\n\
+                        |--- b == 0  (support: 6, error: 1, classes: [5, 1])\n\
+                        |   |--- a == 0  (support: 5, error: 0, classes: [5, 0])\n\
+                        |   |   |--- class: no\n\
+                        |   |--- a == 1  (support: 1, error: 0, classes: [0, 1])\n\
+                        |   |   |--- class: yes\n\
+                        |--- b == 1  (error: 1)\n\
+                        |   |--- class: yes\n";
+        assert_eq!(text, expected);
+
+        tree.set_leaf_probabilities(1.0);
+        let text = tree.to_text(Some(&features), Some(&classes));
+        assert!(text.contains("|   |   |--- class: no  (proba: 0.857, 0.143)\n"));
+        assert!(text.ends_with("|   |--- class: yes\n"));
+        assert_eq!(tree.get_node(root).unwrap().value.probabilities, None);
+    }
+
+    #[test]
+    fn laplace_smoothed_probabilities() {
+        assert_eq!(laplace_probabilities(&[3, 1], 0.0), vec![0.75, 0.25]);
+        assert_eq!(
+            laplace_probabilities(&[3, 1], 1.0),
+            vec![4.0 / 6.0, 2.0 / 6.0]
+        );
+        assert_eq!(laplace_probabilities(&[0, 0], 0.0), vec![0.5, 0.5]);
+    }
+
+    #[test]
+    fn missing_values_routing() {
+        let mut tree = Tree::new();
+        let root = tree.add_root(TreeNode::new(NodeInfos {
+            test: Some(1),
+            ..NodeInfos::default()
+        }));
+        for (supports, is_left) in [(vec![3, 0], true), (vec![0, 1], false)] {
+            let node = TreeNode::new(NodeInfos {
+                classes_support: Some(supports),
+                ..NodeInfos::default()
+            });
+            tree.add_node(root, is_left, node);
+        }
+
+        assert_eq!(
+            tree.weighted_leaves(&[0.0, 1.0], MissingBranch::Left),
+            vec![(2, 1.0)]
+        );
+        assert_eq!(
+            tree.weighted_leaves(&[0.0, f64::NAN], MissingBranch::Left),
+            vec![(1, 1.0)]
+        );
+        assert_eq!(
+            tree.weighted_leaves(&[0.0, f64::NAN], MissingBranch::Right),
+            vec![(2, 1.0)]
+        );
+        assert_eq!(
+            tree.weighted_leaves(&[0.0, f64::NAN], MissingBranch::Both),
+            vec![(1, 0.75), (2, 0.25)]
+        );
+
+        for (index, out) in [(1, 0.0), (2, 1.0)] {
+            tree.get_node_mut(index).unwrap().value.out = Some(out);
+        }
+        assert_eq!(tree.predict(&[0.0, 1.0], MissingBranch::Left), Some(1.0));
+        assert_eq!(
+            tree.predict(&[0.0, f64::NAN], MissingBranch::Both),
+            Some(0.0)
+        );
+    }
+
+    #[test]
+    fn counterfactual_flips_the_fewest_features() {
+        let mut tree = Tree::new();
+        let split = |test| {
+            TreeNode::new(NodeInfos {
+                test: Some(test),
+                ..NodeInfos::default()
+            })
+        };
+        let leaf = |out| {
+            TreeNode::new(NodeInfos {
+                out: Some(out),
+                ..NodeInfos::default()
+            })
+        };
+        // Feature 1 then, on its left, feature 0. Only the rightmost leaf predicts 1
+        let root = tree.add_root(split(1));
+        let left = tree.add_left_node(root, split(0));
+        tree.add_left_node(left, leaf(0.0));
+        tree.add_right_node(left, leaf(0.0));
+        let right = tree.add_right_node(root, leaf(1.0));
+
+        assert_eq!(
+            tree.counterfactual(&[1.0, 0.0], MissingBranch::Left),
+            Some((right, vec![1]))
+        );
+        let (leaf_index, flips) = tree
+            .counterfactual(&[0.0, 1.0], MissingBranch::Left)
+            .unwrap();
+        assert_eq!(flips, vec![1]);
+        assert_eq!(tree.get_node(leaf_index).unwrap().value.out, Some(0.0));
+        assert_eq!(
+            tree.counterfactual(&[0.0, f64::NAN], MissingBranch::Left),
+            Some((right, vec![1]))
+        );
+
+        let mut single = Tree::new();
+        single.add_root(leaf(1.0));
+        assert_eq!(single.counterfactual(&[0.0], MissingBranch::Left), None);
+    }
+
+    #[test]
+    fn pre_order_skips_the_orphaned_nodes() {
+        let mut tree = Tree::new();
+        let split = |test| {
+            TreeNode::new(NodeInfos {
+                test: Some(test),
+                ..NodeInfos::default()
+            })
+        };
+        let root = tree.add_root(split(1));
+        let left = tree.add_left_node(root, split(0));
+        let right = tree.add_right_node(root, TreeNode::new(NodeInfos::default()));
+        let left_left = tree.add_left_node(left, TreeNode::new(NodeInfos::default()));
+        let left_right = tree.add_right_node(left, TreeNode::new(NodeInfos::default()));
+        assert_eq!(
+            tree.pre_order(),
+            vec![root, left, left_left, left_right, right]
+        );
+        assert_eq!(
+            tree.get_node(left).unwrap().children(),
+            Some((left_left, left_right))
+        );
+
+        // Collapsing the left subtree leaves its children in the vector, out of the tree
+        let node = tree.get_node_mut(left).unwrap();
+        node.left = 0;
+        node.right = 0;
+        assert!(node.is_leaf());
+        assert_eq!(tree.pre_order(), vec![root, left, right]);
+    }
+
+    #[test]
+    fn compaction_drops_the_orphaned_nodes() {
+        let mut tree = Tree::new();
+        let split = |test| {
+            TreeNode::new(NodeInfos {
+                test: Some(test),
+                ..NodeInfos::default()
+            })
+        };
+        let leaf = |out| {
+            TreeNode::new(NodeInfos {
+                out: Some(out),
+                ..NodeInfos::default()
+            })
+        };
+        let root = tree.add_root(split(1));
+        let left = tree.add_left_node(root, split(0));
+        tree.add_left_node(left, leaf(0.0));
+        tree.add_right_node(left, leaf(1.0));
+        tree.add_right_node(root, leaf(1.0));
+        let node = tree.get_node_mut(left).unwrap();
+        node.left = 0;
+        node.right = 0;
+        node.value.test = None;
+        node.value.out = Some(0.0);
+
+        assert_eq!(tree.compact(), Ok(2));
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree.pre_order(), vec![0, 1, 2]);
+        assert_eq!(tree.get_node(0).unwrap().children(), Some((1, 2)));
+        assert_eq!(tree.predict(&[1.0, 0.0], MissingBranch::Left), Some(0.0));
+        assert_eq!(tree.predict(&[0.0, 1.0], MissingBranch::Left), Some(1.0));
+        assert_eq!(tree.compact(), Ok(0));
+
+        // A child out of the tree, then a cycle, as a tree read from JSON may hold
+        tree.get_node_mut(0).unwrap().right = 7;
+        assert!(tree.compact().is_err());
+        tree.get_node_mut(0).unwrap().right = 0;
+        tree.get_node_mut(0).unwrap().left = 0;
+        tree.get_node_mut(2).unwrap().left = 0;
+        tree.get_node_mut(0).unwrap().right = 2;
+        tree.get_node_mut(2).unwrap().right = 2;
+        assert!(tree.compact().is_err());
+        assert_eq!(tree.pre_order(), vec![0, 2]);
+    }
+
+    #[test]
+    fn decision_path_of_an_instance() {
+        let mut tree = Tree::new();
+        let root = tree.add_root(TreeNode::new(NodeInfos {
+            test: Some(1),
+            ..NodeInfos::default()
+        }));
+        let left = tree.add_left_node(
+            root,
+            TreeNode::new(NodeInfos {
+                test: Some(0),
+                ..NodeInfos::default()
+            }),
+        );
+        for is_left in [true, false] {
+            tree.add_node(left, is_left, TreeNode::new(NodeInfos::default()));
+        }
+        let right = tree.add_right_node(root, TreeNode::new(NodeInfos::default()));
+
+        assert_eq!(
+            tree.decision_path(&[1.0, 0.0], MissingBranch::Left),
+            (3, vec![(1, 0), (0, 1)])
+        );
+        assert_eq!(
+            tree.decision_path(&[0.0, 1.0], MissingBranch::Left),
+            (right, vec![(1, 1)])
+        );
+        assert_eq!(
+            tree.decision_path(&[f64::NAN, f64::NAN], MissingBranch::Left),
+            (2, vec![(1, 0), (0, 0)])
+        );
+        assert_eq!(
+            tree.decision_path(&[0.0, f64::NAN], MissingBranch::Right),
+            (right, vec![(1, 1)])
+        );
+    }
 }
@@ -0,0 +1,213 @@
+use crate::tree::Tree;
+
+impl Tree {
+    // Cost-complexity pruning on the training error. Going up from the leaves, a subtree is
+    // collapsed into a leaf when it does not reduce the error by more than alpha per extra leaf.
+    // Nodes need their classes support, so regression trees are left unchanged. Returns the number
+    // of collapsed subtrees
+    pub fn prune(&mut self, alpha: f64) -> usize {
+        if self.is_empty() {
+            return 0;
+        }
+        let mut collapsed = 0;
+        self.prune_recursion(self.get_root_index(), alpha, &mut collapsed);
+        collapsed
+    }
+
+    // Returns the error and the number of leaves of the pruned subtree
+    fn prune_recursion(&mut self, index: usize, alpha: f64, collapsed: &mut usize) -> (f64, usize) {
+        let (left, right, error) = match self.get_node(index) {
+            Some(node) => (node.left, node.right, node.value.error),
+            None => return (0.0, 0),
+        };
+        if left == right {
+            return (error, 1);
+        }
+
+        let (left_error, left_leaves) = self.prune_recursion(left, alpha, collapsed);
+        let (right_error, right_leaves) = self.prune_recursion(right, alpha, collapsed);
+        let (error, leaves) = (left_error + right_error, left_leaves + right_leaves);
+        match self.leaf_error(index) {
+            Some(leaf_error) if leaf_error <= error + alpha * (leaves - 1) as f64 => {
+                self.collapse(index);
+                *collapsed += 1;
+                (leaf_error, 1)
+            }
+            _ => (error, leaves),
+        }
+    }
+
+    // Reduced error pruning. The validation samples are sent down the tree, the 1 branch for a
+    // feature at 1 and the 0 branch otherwise. Going up from the leaves, a subtree is collapsed
+    // into a leaf when it does not make more than threshold fewer validation errors than the leaf
+    // predicting its majority training class. Returns the number of collapsed subtrees
+    pub fn prune_with_validation(
+        &mut self,
+        instances: &[Vec<f64>],
+        labels: &[usize],
+        threshold: f64,
+    ) -> usize {
+        if self.is_empty() {
+            return 0;
+        }
+        let num_labels = labels.iter().max().map_or(0, |label| label + 1);
+        let mut supports = vec![vec![0usize; num_labels]; self.len()];
+        for (instance, label) in instances.iter().zip(labels.iter()) {
+            let mut index = self.get_root_index();
+            while let Some(node) = self.get_node(index) {
+                supports[index][*label] += 1;
+                if node.left == node.right {
+                    break;
+                }
+                let value = node.value.test.and_then(|test| instance.get(test).copied());
+                index = match value == Some(1.0) {
+                    true => node.right,
+                    false => node.left,
+                };
+            }
+        }
+
+        let mut collapsed = 0;
+        self.validation_recursion(self.get_root_index(), &supports, threshold, &mut collapsed);
+        collapsed
+    }
+
+    // Returns the validation error of the pruned subtree
+    fn validation_recursion(
+        &mut self,
+        index: usize,
+        supports: &[Vec<usize>],
+        threshold: f64,
+        collapsed: &mut usize,
+    ) -> f64 {
+        let (left, right, out) = match self.get_node(index) {
+            Some(node) => (node.left, node.right, node.value.out),
+            None => return 0.0,
+        };
+        let misclassified = |class: Option<usize>| {
+            let support = &supports[index];
+            (support.iter().sum::<usize>() - class.and_then(|c| support.get(c)).unwrap_or(&0))
+                as f64
+        };
+        if left == right {
+            return misclassified(out.map(|out| out as usize));
+        }
+
+        let error = self.validation_recursion(left, supports, threshold, collapsed)
+            + self.validation_recursion(right, supports, threshold, collapsed);
+        match self.majority_class(index) {
+            Some(class) if misclassified(Some(class)) <= error + threshold => {
+                self.collapse(index);
+                *collapsed += 1;
+                misclassified(Some(class))
+            }
+            _ => error,
+        }
+    }
+
+    fn majority_class(&self, index: usize) -> Option<usize> {
+        let support = self.get_node(index)?.value.classes_support.as_ref()?;
+        support
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(&a.0)))
+            .map(|(class, _)| class)
+    }
+
+    // Training error of a node turned into a leaf predicting its majority class
+    fn leaf_error(&self, index: usize) -> Option<f64> {
+        let class = self.majority_class(index)?;
+        let support = self.get_node(index)?.value.classes_support.as_ref()?;
+        Some((support.iter().sum::<usize>() - support[class]) as f64)
+    }
+
+    // Turns a node into a leaf predicting its majority class. Its descendants stay in the tree but
+    // are no longer reachable
+    pub(super) fn collapse(&mut self, index: usize) {
+        let class = self.majority_class(index);
+        let error = self.leaf_error(index);
+        if let (Some(node), Some(class), Some(error)) = (self.get_node_mut(index), class, error) {
+            node.left = 0;
+            node.right = 0;
+            node.value.test = None;
+            node.value.out = Some(class as f64);
+            node.value.error = error;
+        }
+    }
+}
+
+#[cfg(test)]
+mod pruning_test {
+    use crate::tree::{NodeInfos, Tree, TreeNode};
+
+    fn node(test: Option<usize>, error: f64, support: Vec<usize>) -> TreeNode {
+        let out = match test {
+            None => support
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, count)| **count)
+                .map(|(class, _)| class as f64),
+            Some(_) => None,
+        };
+        TreeNode::new(NodeInfos {
+            test,
+            error,
+            out,
+            classes_support: Some(support),
+            ..NodeInfos::default()
+        })
+    }
+
+    // Feature 0 at the root, then feature 1 on its 0 branch. The tree makes 2 errors where a leaf
+    // would make 6, the split of the left child only removes 1 error
+    fn tree() -> Tree {
+        let mut tree = Tree::new();
+        let root = tree.add_root(node(Some(0), 1.0, vec![10, 6]));
+        let left = tree.add_left_node(root, node(Some(1), 1.0, vec![9, 2]));
+        tree.add_right_node(root, node(None, 1.0, vec![1, 4]));
+        tree.add_left_node(left, node(None, 0.0, vec![8, 0]));
+        tree.add_right_node(left, node(None, 1.0, vec![1, 2]));
+        tree
+    }
+
+    #[test]
+    fn cost_complexity_pruning() {
+        let mut tree = tree();
+        assert_eq!(tree.prune(0.5), 0);
+        assert_eq!(tree.num_leaves(), 3);
+
+        assert_eq!(tree.prune(1.0), 1);
+        assert_eq!(tree.num_leaves(), 2);
+        let left = tree.get_node(1).unwrap();
+        assert_eq!((left.value.test, left.value.out), (None, Some(0.0)));
+        assert_eq!(left.value.error, 2.0);
+
+        assert_eq!(tree.prune(4.0), 1);
+        assert_eq!(tree.num_leaves(), 1);
+        assert_eq!(tree.get_node(0).unwrap().value.error, 6.0);
+    }
+
+    #[test]
+    fn reduced_error_pruning() {
+        // All the validation samples are well classified
+        let instances = vec![
+            vec![0.0, 0.0],
+            vec![0.0, 1.0],
+            vec![0.0, 1.0],
+            vec![1.0, 0.0],
+            vec![1.0, 1.0],
+        ];
+        let labels = vec![0, 1, 1, 1, 1];
+
+        let mut tree = tree();
+        assert_eq!(tree.prune_with_validation(&instances, &labels, 0.0), 0);
+        assert_eq!(tree.num_leaves(), 3);
+
+        // The two samples reaching the right leaf of feature 1 now belong to the first class, a
+        // leaf in place of that split makes no error
+        let labels = vec![0, 0, 0, 1, 1];
+        assert_eq!(tree.prune_with_validation(&instances, &labels, 0.0), 1);
+        assert_eq!(tree.num_leaves(), 2);
+        assert_eq!(tree.depth(), 1);
+    }
+}
@@ -0,0 +1,291 @@
+use crate::tree::{Tree, TreeNode};
+
+impl Tree {
+    // Sets the output of a leaf. Its error is recomputed from its classes support when it has one,
+    // then the errors of its ancestors are updated up to the root
+    pub fn set_leaf_output(&mut self, index: usize, out: f64) -> Result<(), String> {
+        if !self.reachable_node(index)?.is_leaf() {
+            return Err(format!("Node {} is not a leaf", index));
+        }
+        if let Some(node) = self.get_node_mut(index) {
+            node.value.out = Some(out);
+            if let Some(error) = leaf_error(node) {
+                node.value.error = error;
+            }
+        }
+        self.update_ancestors_errors(index);
+        Ok(())
+    }
+
+    // Turns a node into a leaf predicting its majority class. Its descendants stay in the tree but
+    // are no longer reachable
+    pub fn collapse_to_leaf(&mut self, index: usize) -> Result<(), String> {
+        if self.reachable_node(index)?.value.classes_support.is_none() {
+            return Err(format!("Node {} has no classes support", index));
+        }
+        self.collapse(index);
+        self.update_ancestors_errors(index);
+        Ok(())
+    }
+
+    // Replaces the subtree of a node by another tree. The root of the other tree takes the place of
+    // the node and its other nodes are appended, the replaced descendants are no longer reachable
+    pub fn graft(&mut self, index: usize, subtree: &Tree) -> Result<(), String> {
+        self.reachable_node(index)?;
+        subtree
+            .check_children()
+            .map_err(|e| format!("Invalid grafted tree: {}", e))?;
+        let order = subtree.pre_order();
+        if order.is_empty() {
+            return Err("The grafted tree is empty".to_string());
+        }
+        // New index of each node of the subtree, its root replacing the node
+        let mut indices = vec![0; subtree.len()];
+        for (position, old) in order.iter().enumerate() {
+            indices[*old] = match position {
+                0 => index,
+                _ => self.len() + position - 1,
+            };
+        }
+        for old in order {
+            let source = subtree
+                .get_node(old)
+                .expect("The order comes from the tree");
+            let mut node = TreeNode::new(source.value.clone());
+            node.index = indices[old];
+            if let Some((left, right)) = source.children() {
+                node.left = indices[left];
+                node.right = indices[right];
+            }
+            match indices[old] == index {
+                true => self.tree[index] = node,
+                false => self.tree.push(node),
+            }
+        }
+        self.update_ancestors_errors(index);
+        Ok(())
+    }
+
+    // Counts the samples of each class reaching every node of the tree, replacing the classes
+    // support of the nodes. As for the validation pruning, a sample follows the 1 branch for a
+    // feature at 1 and the 0 branch otherwise. The errors are left as they are, update_errors
+    // recomputes them from the new supports
+    pub fn set_classes_support(
+        &mut self,
+        instances: &[Vec<f64>],
+        labels: &[usize],
+    ) -> Result<(), String> {
+        if instances.len() != labels.len() {
+            return Err(format!(
+                "There are {} instances but {} labels",
+                instances.len(),
+                labels.len()
+            ));
+        }
+        if self.is_empty() {
+            return Ok(());
+        }
+        let num_features = self
+            .pre_order()
+            .iter()
+            .filter_map(|index| self.get_node(*index).and_then(|node| node.value.test))
+            .max()
+            .map_or(0, |test| test + 1);
+        if let Some(row) = instances.iter().position(|row| row.len() < num_features) {
+            return Err(format!(
+                "Instance {} has {} features but the tree tests {}",
+                row,
+                instances[row].len(),
+                num_features
+            ));
+        }
+        let current = self
+            .get_node(self.get_root_index())
+            .and_then(|root| root.value.classes_support.as_ref())
+            .map_or(0, |support| support.len());
+        let num_labels = labels
+            .iter()
+            .max()
+            .map_or(0, |label| label + 1)
+            .max(current);
+        let mut supports = vec![vec![0usize; num_labels]; self.len()];
+        for (instance, label) in instances.iter().zip(labels.iter()) {
+            let mut index = self.get_root_index();
+            while let Some(node) = self.get_node(index) {
+                supports[index][*label] += 1;
+                if node.is_leaf() {
+                    break;
+                }
+                let value = node.value.test.and_then(|test| instance.get(test).copied());
+                index = match value == Some(1.0) {
+                    true => node.right,
+                    false => node.left,
+                };
+            }
+        }
+        for index in self.pre_order() {
+            if let Some(node) = self.get_node_mut(index) {
+                node.value.classes_support = Some(supports[index].clone());
+            }
+        }
+        Ok(())
+    }
+
+    // Recomputes the errors of the nodes from their classes support, the misclassified samples of
+    // the leaves summed up to the root, and returns the error of the root. Leaves without classes
+    // support or output keep their error
+    pub fn update_errors(&mut self) -> f64 {
+        match self.is_empty() {
+            true => 0.0,
+            false => self.update_errors_recursion(self.get_root_index()),
+        }
+    }
+
+    fn update_errors_recursion(&mut self, index: usize) -> f64 {
+        let node = match self.get_node(index) {
+            Some(node) => node,
+            None => return 0.0,
+        };
+        let error = match node.children() {
+            Some((left, right)) => {
+                self.update_errors_recursion(left) + self.update_errors_recursion(right)
+            }
+            None => leaf_error(node).unwrap_or(node.value.error),
+        };
+        if let Some(node) = self.get_node_mut(index) {
+            node.value.error = error;
+        }
+        error
+    }
+
+    // Sets the error of each ancestor of a node to the sum of the errors of its children, from the
+    // parent of the node up to the root
+    fn update_ancestors_errors(&mut self, index: usize) {
+        let mut parents = vec![None; self.len()];
+        for node in self.pre_order() {
+            if let Some((left, right)) = self.get_node(node).and_then(|node| node.children()) {
+                parents[left] = Some(node);
+                parents[right] = Some(node);
+            }
+        }
+        let mut current = index;
+        while let Some(parent) = parents.get(current).copied().flatten() {
+            let error = self
+                .get_node(parent)
+                .and_then(|node| node.children())
+                .map_or(0.0, |(left, right)| {
+                    self.get_node(left).map_or(0.0, |node| node.value.error)
+                        + self.get_node(right).map_or(0.0, |node| node.value.error)
+                });
+            if let Some(node) = self.get_node_mut(parent) {
+                node.value.error = error;
+            }
+            current = parent;
+        }
+    }
+
+    fn reachable_node(&self, index: usize) -> Result<&TreeNode, String> {
+        match self.pre_order().contains(&index) {
+            true => Ok(self
+                .get_node(index)
+                .expect("Reachable nodes are in the tree")),
+            false => Err(format!("Unknown node {}", index)),
+        }
+    }
+}
+
+// Samples of a leaf misclassified by its output, when it has a classes support and an output
+fn leaf_error(node: &TreeNode) -> Option<f64> {
+    let support = node.value.classes_support.as_ref()?;
+    let correct = support.get(node.value.out? as usize).copied().unwrap_or(0);
+    Some((support.iter().sum::<usize>() - correct) as f64)
+}
+
+#[cfg(test)]
+mod surgery_test {
+    use crate::tree::{NodeInfos, Tree, TreeNode};
+
+    fn node(test: Option<usize>, out: Option<f64>) -> TreeNode {
+        TreeNode::new(NodeInfos {
+            test,
+            out,
+            ..NodeInfos::default()
+        })
+    }
+
+    // Feature 0 at the root, then feature 1 on its 0 branch
+    fn tree() -> Tree {
+        let mut tree = Tree::new();
+        let root = tree.add_root(node(Some(0), None));
+        let left = tree.add_left_node(root, node(Some(1), None));
+        tree.add_right_node(root, node(None, Some(1.0)));
+        tree.add_left_node(left, node(None, Some(0.0)));
+        tree.add_right_node(left, node(None, Some(1.0)));
+        tree
+    }
+
+    fn data() -> (Vec<Vec<f64>>, Vec<usize>) {
+        let instances = vec![
+            vec![0.0, 0.0],
+            vec![0.0, 0.0],
+            vec![0.0, 1.0],
+            vec![0.0, 1.0],
+            vec![1.0, 0.0],
+            vec![1.0, 1.0],
+        ];
+        (instances, vec![0, 0, 0, 1, 1, 1])
+    }
+
+    #[test]
+    fn edits_update_the_errors() {
+        let (instances, labels) = data();
+        let mut tree = tree();
+        tree.set_classes_support(&instances, &labels).unwrap();
+        assert_eq!(tree.update_errors(), 1.0);
+
+        assert!(tree.set_leaf_output(0, 1.0).is_err());
+        tree.set_leaf_output(3, 1.0).unwrap();
+        assert_eq!(tree.get_node(3).unwrap().value.error, 2.0);
+        assert_eq!(tree.get_node(0).unwrap().value.error, 3.0);
+
+        // The left child predicts its majority class 0, the samples of class 1 are misclassified
+        tree.collapse_to_leaf(1).unwrap();
+        assert_eq!(tree.get_node(0).unwrap().value.error, 1.0);
+        assert_eq!(tree.pre_order(), vec![0, 1, 2]);
+        assert!(tree.set_leaf_output(3, 0.0).is_err());
+    }
+
+    #[test]
+    fn classes_support_checks_the_samples() {
+        let (instances, labels) = data();
+        let mut tree = tree();
+        assert!(tree.set_classes_support(&instances, &labels[1..]).is_err());
+        let narrow = instances
+            .iter()
+            .map(|row| row[..1].to_vec())
+            .collect::<Vec<_>>();
+        assert!(tree.set_classes_support(&narrow, &labels).is_err());
+        assert!(tree.get_node(0).unwrap().value.classes_support.is_none());
+    }
+
+    #[test]
+    fn grafted_subtree_replaces_the_node() {
+        let (instances, labels) = data();
+        let mut tree = tree();
+        let mut subtree = Tree::new();
+        let root = subtree.add_root(node(Some(1), None));
+        subtree.add_left_node(root, node(None, Some(1.0)));
+        subtree.add_right_node(root, node(None, Some(1.0)));
+
+        tree.graft(2, &subtree).unwrap();
+        assert_eq!(tree.pre_order(), vec![0, 1, 3, 4, 2, 5, 6]);
+        assert_eq!(tree.get_node(2).unwrap().children(), Some((5, 6)));
+        tree.set_classes_support(&instances, &labels).unwrap();
+        assert_eq!(tree.update_errors(), 1.0);
+        assert!(tree.graft(7, &subtree).is_err());
+
+        // The children of the grafted tree must be its nodes
+        subtree.get_node_mut(root).unwrap().right = 9;
+        assert!(tree.graft(2, &subtree).is_err());
+    }
+}